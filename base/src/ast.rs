@@ -24,8 +24,8 @@ use crate::{
     resolve::remove_aliases_cow,
     symbol::Symbol,
     types::{
-        self, Alias, AliasData, ArcType, ArgType, Field, Generic, NullInterner, Type, TypeEnv,
-        TypeExt, TypePtr,
+        self, Alias, AliasData, ArcType, ArgType, BuiltinType, Field, Generic, NullInterner, Type,
+        TypeEnv, TypeExt, TypePtr,
     },
 };
 
@@ -189,6 +189,12 @@ impl<'ast, Id> AstType<'ast, Id> {
     }
 }
 
+impl<'ast, Id> AsRef<SpannedAstType<'ast, Id>> for AstType<'ast, Id> {
+    fn as_ref(&self) -> &SpannedAstType<'ast, Id> {
+        &self._typ.typ
+    }
+}
+
 impl<'ast, Id> AsMut<SpannedAstType<'ast, Id>> for AstType<'ast, Id> {
     fn as_mut(&mut self) -> &mut SpannedAstType<'ast, Id> {
         &mut self._typ.typ
@@ -256,6 +262,9 @@ where
 pub enum Literal {
     Byte(u8),
     Int(i64),
+    /// An integer literal of one of the fixed width types such as `Int8` or `UInt64`. `UInt64`
+    /// values are stored as their bit pattern.
+    SizedInt(i64, BuiltinType),
     Float(NotNan<f64>),
     String(String),
     Char(char),
@@ -302,6 +311,15 @@ pub enum Pattern<'ast, Id> {
         typ: ArcType<Id>,
         elems: &'ast mut [SpannedPattern<'ast, Id>],
     },
+    /// Array pattern, eg: `[x, y]` or `[x, ..rest]`
+    Array {
+        typ: ArcType<Id>,
+        elems: &'ast mut [SpannedPattern<'ast, Id>],
+        /// The pattern matching the elements after `elems` (`..rest`), if any
+        rest: Option<&'ast mut SpannedPattern<'ast, Id>>,
+    },
+    /// Or pattern, eg: `Some x | Ok x`. Each alternative must bind the same variables
+    Or(&'ast mut [SpannedPattern<'ast, Id>]),
     /// A literal pattern
     Literal(Literal),
     /// An invalid pattern
@@ -359,6 +377,58 @@ pub fn pattern_types<'a, 'ast, Id>(
     })
 }
 
+/// Returns the variables bound by `pattern`. Since each alternative of an or pattern binds the
+/// same variables only the bindings of the first alternative are returned.
+pub fn pattern_bindings<'a, 'ast, Id>(pattern: &'a Pattern<'ast, Id>) -> Vec<&'a Id> {
+    fn gather<'a, 'ast, Id>(pattern: &'a Pattern<'ast, Id>, bindings: &mut Vec<&'a Id>) {
+        match pattern {
+            Pattern::As(id, pat) => {
+                bindings.push(&id.value);
+                gather(&pat.value, bindings);
+            }
+            Pattern::Ident(id) => bindings.push(&id.name),
+            Pattern::Constructor(_, args) => {
+                for arg in &**args {
+                    gather(&arg.value, bindings);
+                }
+            }
+            Pattern::Record {
+                fields,
+                implicit_import,
+                ..
+            } => {
+                for (name, value) in pattern_values(fields) {
+                    match value {
+                        Some(value) => gather(&value.value, bindings),
+                        None => bindings.push(&name.value),
+                    }
+                }
+                if let Some(implicit_import) = implicit_import {
+                    bindings.push(&implicit_import.value);
+                }
+            }
+            Pattern::Tuple { elems, .. } => {
+                for elem in &**elems {
+                    gather(&elem.value, bindings);
+                }
+            }
+            Pattern::Array { elems, rest, .. } => {
+                for elem in &**elems {
+                    gather(&elem.value, bindings);
+                }
+                if let Some(rest) = rest {
+                    gather(&rest.value, bindings);
+                }
+            }
+            Pattern::Or(alternatives) => gather(&alternatives[0].value, bindings),
+            Pattern::Literal(_) | Pattern::Error => (),
+        }
+    }
+    let mut bindings = Vec::new();
+    gather(pattern, &mut bindings);
+    bindings
+}
+
 impl<Id> Default for Pattern<'_, Id> {
     fn default() -> Self {
         Pattern::Error
@@ -368,6 +438,9 @@ impl<Id> Default for Pattern<'_, Id> {
 #[derive(Eq, PartialEq, Debug, AstClone)]
 pub struct Alternative<'ast, Id> {
     pub pattern: SpannedPattern<'ast, Id>,
+    /// Optional guard (`| pattern if guard -> expr`) which must evaluate to `True` for the
+    /// alternative to be selected
+    pub guard: Option<SpannedExpr<'ast, Id>>,
     pub expr: SpannedExpr<'ast, Id>,
 }
 
@@ -407,6 +480,27 @@ pub struct Do<'ast, Id> {
     pub flat_map_id: Option<&'ast mut SpannedExpr<'ast, Id>>,
 }
 
+/// A string literal with interpolated expressions, eg. `f"total: ${x} items"`
+#[derive(Eq, PartialEq, Debug, AstClone)]
+pub struct Interpolation<'ast, Id> {
+    pub parts: &'ast mut [InterpolationPart<'ast, Id>],
+    /// The `++` from `std.string` used to concatenate the parts, inserted during macro expansion
+    pub append_id: Option<&'ast mut SpannedExpr<'ast, Id>>,
+}
+
+#[derive(Eq, PartialEq, Debug, AstClone)]
+pub enum InterpolationPart<'ast, Id> {
+    /// Literal text, with escape codes already replaced
+    Text(Spanned<String, BytePos>),
+    /// An interpolated expression, `${expr}`
+    Expr {
+        expr: SpannedExpr<'ast, Id>,
+        /// The `show` from `std.show` used to convert `expr` into a string, inserted during macro
+        /// expansion. Removed by the typechecker if `expr` is a `String`
+        show_id: Option<&'ast mut SpannedExpr<'ast, Id>>,
+    },
+}
+
 /// The representation of gluon's expression syntax
 #[derive(Eq, PartialEq, Debug, AstClone)]
 pub enum Expr<'ast, Id> {
@@ -466,6 +560,8 @@ pub enum Expr<'ast, Id> {
     /// A group of sequenced expressions
     Block(&'ast mut [SpannedExpr<'ast, Id>]),
     Do(&'ast mut Do<'ast, Id>),
+    /// An interpolated string literal, eg. `f"total: ${x} items"`
+    Interpolation(&'ast mut Interpolation<'ast, Id>),
     MacroExpansion {
         original: &'ast mut SpannedExpr<'ast, Id>,
         replacement: &'ast mut SpannedExpr<'ast, Id>,
@@ -529,6 +625,7 @@ impl<'ast, Id> Expr<'ast, Id> {
             Expr::Block(..) => "Block",
 
             Expr::Do(Do { .. }) => "Do",
+            Expr::Interpolation(..) => "Interpolation",
 
             Expr::Lambda(..) => "Lambda",
             Expr::TypeBindings(..) => "TypeBindings",
@@ -811,6 +908,9 @@ pub fn walk_expr<'a, 'ast, V>(v: &mut V, e: &'a $($mut)* SpannedExpr<'ast, V::Id
             v.visit_expr(expr);
             for alt in &$($mut)* **alts {
                 v.visit_pattern(&$($mut)* alt.pattern);
+                if let Some(ref $($mut)* guard) = alt.guard {
+                    v.visit_expr(guard);
+                }
                 v.visit_expr(&$($mut)* alt.expr);
             }
         }
@@ -870,6 +970,23 @@ pub fn walk_expr<'a, 'ast, V>(v: &mut V, e: &'a $($mut)* SpannedExpr<'ast, V::Id
             }
         }
 
+        Expr::Interpolation(Interpolation {
+            ref $($mut)* parts,
+            ref $($mut)* append_id,
+        }) => {
+            for part in &$($mut)* **parts {
+                if let InterpolationPart::Expr { ref $($mut)* expr, ref $($mut)* show_id } = *part {
+                    v.visit_expr(expr);
+                    if let Some(ref $($mut)* show_id) = *show_id {
+                        v.visit_expr(show_id);
+                    }
+                }
+            }
+            if let Some(ref $($mut)* append_id) = *append_id {
+                v.visit_expr(append_id);
+            }
+        }
+
         Expr::Lambda(ref $($mut)* lambda) => {
             v.visit_ident(&$($mut)* lambda.id);
             for arg in &$($mut)* *lambda.args {
@@ -939,6 +1056,24 @@ pub fn walk_pattern<'a, 'ast,V: ?Sized + $trait_name<'a, 'ast>>(v: &mut V, p: &'
                 v.visit_pattern(elem);
             }
         }
+        Pattern::Array {
+            typ,
+            elems,
+            rest,
+        } => {
+            v.visit_typ(typ);
+            for elem in &$($mut)* **elems {
+                v.visit_pattern(elem);
+            }
+            if let Some(rest) = rest {
+                v.visit_pattern(rest);
+            }
+        }
+        Pattern::Or(alternatives) => {
+            for alternative in &$($mut)* **alternatives {
+                v.visit_pattern(alternative);
+            }
+        }
         Pattern::Ident(id) => v.visit_ident(id),
         Pattern::Literal(_) | Pattern::Error => (),
     }
@@ -1043,6 +1178,7 @@ impl Typed for Literal {
             Literal::Int(_) => Type::int(),
             Literal::Float(_) => Type::float(),
             Literal::Byte(_) => Type::byte(),
+            Literal::SizedInt(_, typ) => Type::builtin(typ),
             Literal::String(_) => Type::string(),
             Literal::Char(_) => Type::char(),
         })
@@ -1076,6 +1212,7 @@ impl Typed for Expr<'_, Symbol> {
             | Expr::Record { ref typ, .. }
             | Expr::Tuple { ref typ, .. } => Ok(typ.clone()),
             Expr::Literal(ref lit) => lit.try_type_of(env),
+            Expr::Interpolation(..) => Ok(Type::string()),
             Expr::IfElse(_, ref arm, _) => arm.try_type_of(env),
             Expr::Infix { ref op, .. } => get_return_type(env, &op.value.typ, 2),
             Expr::LetBindings(_, ref expr)
@@ -1114,6 +1251,8 @@ impl Typed for Pattern<'_, Symbol> {
             Pattern::Ident(ref id) => Ok(id.typ.clone()),
             Pattern::Record { ref typ, .. } => Ok(typ.clone()),
             Pattern::Tuple { ref typ, .. } => Ok(typ.clone()),
+            Pattern::Array { ref typ, .. } => Ok(typ.clone()),
+            Pattern::Or(ref alternatives) => alternatives[0].try_type_of(env),
             Pattern::Constructor(ref id, ref args) => get_return_type(env, &id.typ, args.len()),
             Pattern::Error => Ok(Type::hole()),
             Pattern::Literal(ref l) => l.try_type_of(env),
@@ -1277,6 +1416,8 @@ impl_ast_arena! {
     TypeBinding<'ast, Id> => type_bindings,
     ValueBinding<'ast, Id> => value_bindings,
     Do<'ast, Id> => do_exprs,
+    Interpolation<'ast, Id> => interpolations,
+    InterpolationPart<'ast, Id> => interpolation_parts,
     Alternative<'ast, Id> => alts,
     Argument<SpannedIdent<Id>> => args,
     InnerAstType<'ast, Id> => types,
@@ -1589,5 +1730,6 @@ impl_ast_clone! {
     u32,
     bool,
     BytePos,
+    String,
     Symbol,
 }
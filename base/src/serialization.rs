@@ -11,7 +11,7 @@ use crate::serde::de::{DeserializeSeed, DeserializeState, Deserializer, Error};
 use crate::serde::ser::{SerializeState, Serializer};
 
 use crate::kind::ArcKind;
-use crate::symbol::Symbol;
+use crate::symbol::{Symbol, Symbols};
 use crate::types::{AliasData, ArcType, Generic, Type, TypeExt, TypePtr};
 
 #[derive(Default)]
@@ -33,6 +33,7 @@ impl AsRef<NodeToId> for SeSeed {
 
 pub struct Seed<Id, T> {
     nodes: crate::serialization::NodeMap,
+    symbols: Arc<Symbols>,
     _marker: PhantomData<(Id, T)>,
 }
 
@@ -58,15 +59,29 @@ impl<Id, T> Seed<Id, T> {
     pub fn new(nodes: crate::serialization::NodeMap) -> Self {
         Seed {
             nodes,
+            symbols: Default::default(),
             _marker: PhantomData,
         }
     }
+
+    /// Reuses the symbols in `symbols` for any deserialized symbol with the same name
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = Arc::new(symbols);
+        self
+    }
+
+    pub(crate) fn symbol(&self, name: &str) -> Symbol {
+        self.symbols
+            .get_simple(name)
+            .unwrap_or_else(|| Symbol::from(name))
+    }
 }
 
 impl<Id, T> Clone for Seed<Id, T> {
     fn clone(&self) -> Self {
         Seed {
             nodes: self.nodes.clone(),
+            symbols: self.symbols.clone(),
             _marker: PhantomData,
         }
     }
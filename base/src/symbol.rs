@@ -58,12 +58,23 @@ mod serialization {
         where
             D: Deserializer<'de>,
         {
-            use crate::serde::de::DeserializeSeed;
-            use crate::serialization::SharedSeed;
-
-            let seed = SharedSeed::new(seed);
-            seed.deserialize(deserializer)
-                .map(|s: String| Symbol::from(&s[..]))
+            use crate::serde::de::Error;
+            use crate::serialization::{NodeMap, Variant};
+
+            // Shared symbols must deserialize to the same `Symbol` (and not just a symbol with the
+            // same name) as the type checker compares symbols by identity, for instance when
+            // instantiating the generics of a `forall`
+            match Variant::<String>::deserialize_state(seed, deserializer)? {
+                Variant::Marked(id, s) => {
+                    let symbol = seed.symbol(&s);
+                    AsMut::<NodeMap>::as_mut(seed).insert(id, symbol.clone());
+                    Ok(symbol)
+                }
+                Variant::Plain(s) => Ok(seed.symbol(&s)),
+                Variant::Reference(id) => AsMut::<NodeMap>::as_mut(seed)
+                    .get(id)
+                    .ok_or_else(|| D::Error::custom(format_args!("missing id {}", id))),
+            }
         }
     }
 
@@ -658,6 +669,31 @@ impl Symbols {
         }
     }
 
+    /// Makes `simple_symbol` return `symbol` when called with the name of `symbol`, unless a
+    /// symbol with that name has already been created
+    pub fn insert_simple(&mut self, symbol: Symbol) {
+        // The name is kept alive by the `symbol` stored in the map
+        let key = unsafe { &*(Name::new(symbol.as_pretty_str()) as *const Name) };
+        self.indexes
+            .entry(SymbolData {
+                global: false,
+                location: None,
+                name: key,
+            })
+            .or_insert(symbol);
+    }
+
+    /// Returns the symbol that `simple_symbol` would return for `name`, if it exists
+    pub fn get_simple(&self, name: &str) -> Option<Symbol> {
+        self.indexes
+            .get(&SymbolData {
+                global: false,
+                location: None,
+                name: Name::new(name),
+            })
+            .cloned()
+    }
+
     pub fn contains_name<N>(&mut self, name: N) -> bool
     where
         N: AsRef<Name>,
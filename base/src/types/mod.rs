@@ -200,7 +200,7 @@ where
 
 impl<Id, T> TypeCache<Id, T>
 where
-    T: TypeExt<Id = Id> + Clone,
+    T: TypeExt<Id = Id> + From<Type<Id, T>> + Clone,
     T::Types: Default + Extend<T> + FromIterator<T>,
 {
     pub fn builtin_type(&self, typ: BuiltinType) -> T {
@@ -209,6 +209,12 @@ where
             BuiltinType::Byte => self.byte(),
             BuiltinType::Char => self.char(),
             BuiltinType::Int => self.int(),
+            BuiltinType::Int8
+            | BuiltinType::Int16
+            | BuiltinType::Int32
+            | BuiltinType::UInt16
+            | BuiltinType::UInt32
+            | BuiltinType::UInt64 => Type::builtin(typ),
             BuiltinType::Float => self.float(),
             BuiltinType::Array => self.array_builtin(),
             BuiltinType::Function => self.function_builtin(),
@@ -228,6 +234,18 @@ pub enum BuiltinType {
     Char,
     /// Integer number
     Int,
+    /// Signed 8-bit integer
+    Int8,
+    /// Signed 16-bit integer
+    Int16,
+    /// Signed 32-bit integer
+    Int32,
+    /// Unsigned 16-bit integer
+    UInt16,
+    /// Unsigned 32-bit integer
+    UInt32,
+    /// Unsigned 64-bit integer
+    UInt64,
     /// Floating point number
     Float,
     /// Type constructor for arrays, `Array a : Type -> Type`
@@ -240,6 +258,46 @@ impl BuiltinType {
     pub fn symbol(self) -> &'static SymbolRef {
         SymbolRef::new(self.to_str())
     }
+
+    /// Returns the builtin type of integer literals ending with `suffix` (`5i8`, `5u32`, ...)
+    pub fn from_int_suffix(suffix: &str) -> Option<BuiltinType> {
+        Some(match suffix {
+            "i8" => BuiltinType::Int8,
+            "i16" => BuiltinType::Int16,
+            "i32" => BuiltinType::Int32,
+            "i64" => BuiltinType::Int,
+            "u8" => BuiltinType::Byte,
+            "u16" => BuiltinType::UInt16,
+            "u32" => BuiltinType::UInt32,
+            "u64" => BuiltinType::UInt64,
+            _ => return None,
+        })
+    }
+
+    /// The suffix which integer literals of this type are written with, if any
+    pub fn int_suffix(self) -> Option<&'static str> {
+        Some(match self {
+            BuiltinType::Int8 => "i8",
+            BuiltinType::Int16 => "i16",
+            BuiltinType::Int32 => "i32",
+            BuiltinType::Int => "i64",
+            BuiltinType::Byte => "u8",
+            BuiltinType::UInt16 => "u16",
+            BuiltinType::UInt32 => "u32",
+            BuiltinType::UInt64 => "u64",
+            _ => return None,
+        })
+    }
+
+    /// Formats `value` as an integer literal of this type, reading `UInt64` values from their bit
+    /// pattern
+    pub fn format_int(self, value: i64) -> String {
+        let suffix = self.int_suffix().unwrap_or_default();
+        match self {
+            BuiltinType::UInt64 => format!("{}{}", value as u64, suffix),
+            _ => format!("{}{}", value, suffix),
+        }
+    }
 }
 
 impl ::std::str::FromStr for BuiltinType {
@@ -247,6 +305,12 @@ impl ::std::str::FromStr for BuiltinType {
     fn from_str(x: &str) -> Result<BuiltinType, ()> {
         let t = match x {
             "Int" => BuiltinType::Int,
+            "Int8" => BuiltinType::Int8,
+            "Int16" => BuiltinType::Int16,
+            "Int32" => BuiltinType::Int32,
+            "UInt16" => BuiltinType::UInt16,
+            "UInt32" => BuiltinType::UInt32,
+            "UInt64" => BuiltinType::UInt64,
             "Byte" => BuiltinType::Byte,
             "Float" => BuiltinType::Float,
             "String" => BuiltinType::String,
@@ -266,6 +330,12 @@ impl BuiltinType {
             BuiltinType::Byte => "Byte",
             BuiltinType::Char => "Char",
             BuiltinType::Int => "Int",
+            BuiltinType::Int8 => "Int8",
+            BuiltinType::Int16 => "Int16",
+            BuiltinType::Int32 => "Int32",
+            BuiltinType::UInt16 => "UInt16",
+            BuiltinType::UInt32 => "UInt32",
+            BuiltinType::UInt64 => "UInt64",
             BuiltinType::Float => "Float",
             BuiltinType::Array => "Array",
             BuiltinType::Function => "->",
@@ -3656,6 +3726,12 @@ where
             BuiltinType::Byte => self.byte(),
             BuiltinType::Char => self.char(),
             BuiltinType::Int => self.int(),
+            BuiltinType::Int8
+            | BuiltinType::Int16
+            | BuiltinType::Int32
+            | BuiltinType::UInt16
+            | BuiltinType::UInt32
+            | BuiltinType::UInt64 => self.builtin(typ),
             BuiltinType::Float => self.float(),
             BuiltinType::Array => self.array_builtin(),
             BuiltinType::Function => self.function_builtin(),
@@ -0,0 +1,305 @@
+#ifndef GLUON_H
+#define GLUON_H
+
+/* Generated with cbindgen from c-api/src/lib.rs. Do not edit this file manually. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* The types below are defined by the gluon_vm crate */
+typedef struct Thread Thread;
+typedef int64_t VmInt;
+typedef uint32_t VmIndex;
+typedef uint32_t VmTag;
+typedef enum Status {
+  Status_Ok,
+  Status_Yield,
+  Status_Error,
+} Status;
+
+
+/**
+ * Error codes returned by the functions of the C API
+ */
+typedef enum GluError {
+  GluError_Ok = 0,
+  /**
+   * An error which is not described by any of the other codes
+   */
+  GluError_Unknown = 1,
+  /**
+   * A string passed to the API was not valid UTF-8
+   */
+  GluError_Utf8 = 2,
+  /**
+   * There is no value at the requested stack index
+   */
+  GluError_InvalidIndex = 3,
+  /**
+   * The code could not be parsed
+   */
+  GluError_Parse = 4,
+  /**
+   * The code failed to typecheck
+   */
+  GluError_Typecheck = 5,
+  /**
+   * A macro failed to expand
+   */
+  GluError_Macro = 6,
+  /**
+   * An IO error occurred, for instance when loading a module
+   */
+  GluError_IO = 7,
+  /**
+   * The gluon code panicked
+   */
+  GluError_Panic = 8,
+  /**
+   * The thread exceeded its memory limit
+   */
+  GluError_OutOfMemory = 9,
+  /**
+   * The stack exceeded its size limit
+   */
+  GluError_StackOverflow = 10,
+  /**
+   * The thread was interrupted
+   */
+  GluError_Interrupted = 11,
+  /**
+   * The thread ran out of fuel
+   */
+  GluError_OutOfFuel = 12,
+  /**
+   * The thread is dead
+   */
+  GluError_Dead = 13,
+  /**
+   * Any other error returned by the virtual machine, such as an undefined binding
+   */
+  GluError_Vm = 14,
+  /**
+   * The value does not have the type expected by the function
+   */
+  GluError_WrongType = 15,
+  /**
+   * The record does not have the requested field
+   */
+  GluError_MissingField = 16,
+} GluError;
+
+/**
+ * A userdata type defined by the C program
+ */
+typedef struct GluUserdataType GluUserdataType;
+
+/**
+ * A handle to a gluon value. The value is kept alive until the handle is freed with
+ * `glu_free_value`, even if it is removed from the stack.
+ */
+typedef struct GluValue GluValue;
+
+typedef Status (*Function)(const Thread*);
+
+/**
+ * Function which is called with the data of a userdata value when it is garbage collected, or
+ * null if the data does not need to be finalized
+ */
+typedef void (*Finalizer)(void*);
+
+/**
+ * Retrieves a short description of the last error returned by a function on the calling OS
+ * thread and returns its error code, or `Ok` and an empty string if no error has occurred.
+ *
+ * The string is nul terminated and is valid until another error occurs on the same OS thread.
+ */
+enum GluError glu_last_error_message(const uint8_t **out, uintptr_t *out_len);
+
+/**
+ * Retrieves the rendered diagnostic of the last error returned by a function on the calling OS
+ * thread and returns its error code, or `Ok` and an empty string if no error has occurred. For
+ * errors in gluon code the diagnostic points out the source code the error originated from.
+ *
+ * The string is nul terminated and is valid until another error occurs on the same OS thread.
+ */
+enum GluError glu_last_error_diagnostic(const uint8_t **out, uintptr_t *out_len);
+
+/**
+ * Creates a new vm with the standard library and the `import!` macro, the same as
+ * `gluon::new_vm`
+ */
+const Thread *glu_new_vm(void);
+
+void glu_free_vm(const Thread *vm);
+
+enum GluError glu_run_expr(const Thread *vm,
+                           const uint8_t *module,
+                           uintptr_t module_len,
+                           const uint8_t *expr,
+                           uintptr_t expr_len);
+
+enum GluError glu_load_script(const Thread *vm,
+                              const uint8_t *module,
+                              uintptr_t module_len,
+                              const uint8_t *expr,
+                              uintptr_t expr_len);
+
+enum GluError glu_call_function(const Thread *thread, VmIndex args);
+
+uintptr_t glu_len(const Thread *vm);
+
+void glu_pop(const Thread *vm, uintptr_t n);
+
+void glu_push_int(const Thread *vm, VmInt int_);
+
+void glu_push_byte(const Thread *vm, uint8_t b);
+
+void glu_push_float(const Thread *vm, double float_);
+
+void glu_push_bool(const Thread *vm, int8_t b);
+
+enum GluError glu_push_function(const Thread *vm,
+                                const uint8_t *name,
+                                uintptr_t len,
+                                Function function,
+                                VmIndex args);
+
+/**
+ * Push a string to the stack. The string must be valid utf-8 or an error will be returned
+ */
+enum GluError glu_push_string(const Thread *vm, const uint8_t *s, uintptr_t len);
+
+/**
+ * Push a string to the stack. If the string is not utf-8 this function will trigger undefined
+ * behaviour.
+ */
+enum GluError glu_push_string_unchecked(const Thread *vm, const uint8_t *s, uintptr_t len);
+
+#if !defined(GLUON_WASM32)
+void glu_push_light_userdata(const Thread *vm, void *data);
+#endif
+
+enum GluError glu_get_byte(const Thread *vm, VmIndex index, uint8_t *out);
+
+enum GluError glu_get_int(const Thread *vm, VmIndex index, VmInt *out);
+
+enum GluError glu_get_float(const Thread *vm, VmIndex index, double *out);
+
+enum GluError glu_get_bool(const Thread *vm, VmIndex index, int8_t *out);
+
+/**
+ * The returned string is garbage collected and may not be valid after the string is removed from
+ * its slot in the stack
+ */
+enum GluError glu_get_string(const Thread *vm,
+                             VmIndex index,
+                             const uint8_t **out,
+                             uintptr_t *out_len);
+
+#if !defined(GLUON_WASM32)
+enum GluError glu_get_light_userdata(const Thread *vm, VmIndex index, void **out);
+#endif
+
+/**
+ * Pops the top `fields` values of the stack and pushes a record containing them. `names` and
+ * `name_lens` point to arrays containing the name of each field, in the same order as the
+ * values were pushed.
+ */
+enum GluError glu_push_record(const Thread *vm,
+                              const uint8_t *const *names,
+                              const uintptr_t *name_lens,
+                              uintptr_t fields);
+
+/**
+ * Pops the top `fields` values of the stack and pushes a tuple containing them
+ */
+enum GluError glu_push_tuple(const Thread *vm, uintptr_t fields);
+
+/**
+ * Pops the top `fields` values of the stack and pushes a variant with the tag `tag` containing
+ * them. The tag of a variant is the index of its constructor in the declaration of its type.
+ */
+enum GluError glu_push_variant(const Thread *vm, VmTag tag, uintptr_t fields);
+
+/**
+ * Pops the top `len` values of the stack and pushes an array containing them. The values must
+ * all have the same type.
+ */
+enum GluError glu_push_array(const Thread *vm, uintptr_t len);
+
+/**
+ * Pushes the field `name` of the record at `index`
+ */
+enum GluError glu_push_field(const Thread *vm,
+                             VmIndex index,
+                             const uint8_t *name,
+                             uintptr_t name_len);
+
+/**
+ * Pushes the field at position `field` of the variant or tuple at `index`
+ */
+enum GluError glu_push_data_field(const Thread *vm, VmIndex index, uintptr_t field);
+
+/**
+ * Pushes the element at position `element` of the array at `index`
+ */
+enum GluError glu_push_element(const Thread *vm, VmIndex index, uintptr_t element);
+
+/**
+ * Retrieves the tag of the variant at `index`
+ */
+enum GluError glu_get_tag(const Thread *vm, VmIndex index, VmTag *out);
+
+/**
+ * Retrieves the number of elements of the array at `index` or the number of fields of the
+ * record, variant or tuple at `index`
+ */
+enum GluError glu_get_len(const Thread *vm, VmIndex index, uintptr_t *out);
+
+/**
+ * Pushes the global `name`, for instance a function defined by a script loaded with
+ * `glu_load_script`. Functions can then be called with `glu_call_function`.
+ */
+enum GluError glu_push_global(const Thread *vm, const uint8_t *name, uintptr_t name_len);
+
+/**
+ * Creates a handle to the value at `index`
+ */
+enum GluError glu_get_value(const Thread *vm, VmIndex index, struct GluValue **out);
+
+/**
+ * Pushes the value of `value` to the stack
+ */
+enum GluError glu_push_value(const Thread *vm, const struct GluValue *value);
+
+void glu_free_value(struct GluValue *value);
+
+/**
+ * Registers an opaque gluon type called `name` for userdata values created by the C program.
+ * When a value of the type is garbage collected, `finalizer` is called with its data. Each
+ * registration creates a new gluon type so registering a name which is already a type is an
+ * error.
+ *
+ * The returned type is valid for the rest of the process.
+ */
+enum GluError glu_register_userdata(const Thread *vm,
+                                    const uint8_t *name,
+                                    uintptr_t name_len,
+                                    Finalizer finalizer,
+                                    const struct GluUserdataType **out);
+
+/**
+ * Pushes a userdata value of type `typ` which contains `data`
+ */
+enum GluError glu_push_userdata(const Thread *vm, const struct GluUserdataType *typ, void *data);
+
+/**
+ * Retrieves the data of the userdata value at `index`, which must have the type `typ`
+ */
+enum GluError glu_get_userdata(const Thread *vm,
+                               VmIndex index,
+                               const struct GluUserdataType *typ,
+                               void **out);
+
+#endif  /* GLUON_H */
@@ -1,34 +1,223 @@
 //! A (WIP) C API allowing use of gluon in other langauges than Rust.
+//!
+//! Functions which can fail return an `Error` code. A description of the last error which
+//! occurred on the calling OS thread can then be retrieved with `glu_last_error_message` and
+//! `glu_last_error_diagnostic`.
+//!
+//! The C declarations for this API are in `include/gluon.h`, which is generated with
+//! `cbindgen --config cbindgen.toml --output include/gluon.h` (cbindgen 0.29.4) from this
+//! directory. CI checks that the header is up to date.
 #![doc(html_root_url = "https://docs.rs/gluon_c-api/0.14.1")] // # GLUON
 
-use std::{slice, str};
+use std::{cell::RefCell, ffi::c_void, fmt, ptr, slice, str};
 
 use futures::{executor::block_on, future};
 
 use gluon::{
+    base::{
+        error::InFile,
+        symbol::Symbol,
+        types::{Alias, AliasData, ArcType, Type},
+    },
     vm::{
-        api::{CPrimitive, Getable, Hole, OpaqueValue, Pushable},
-        stack,
-        thread::{RootedThread, Status, Thread, ThreadInternal},
-        types::{VmIndex, VmInt},
+        self,
+        api::{CPrimitive, Hole, OpaqueValue, Pushable, Userdata, ValueRef},
+        gc::Trace,
+        impl_trace, stack,
+        thread::{RootedThread, RootedValue, Status, Thread, ThreadInternal},
+        types::{VmIndex, VmInt, VmTag},
+        Variants,
     },
     ThreadExt,
 };
 
 pub type Function = extern "C" fn(&Thread) -> Status;
 
-// TODO What should the c api return as errors
-// TODO How should error messages be returned
+/// Function which is called with the data of a userdata value when it is garbage collected, or
+/// null if the data does not need to be finalized
+pub type Finalizer = Option<extern "C" fn(*mut c_void)>;
+
+/// Error codes returned by the functions of the C API
 #[repr(C)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Error {
-    Ok,
-    Unknown,
+    Ok = 0,
+    /// An error which is not described by any of the other codes
+    Unknown = 1,
+    /// A string passed to the API was not valid UTF-8
+    Utf8 = 2,
+    /// There is no value at the requested stack index
+    InvalidIndex = 3,
+    /// The code could not be parsed
+    Parse = 4,
+    /// The code failed to typecheck
+    Typecheck = 5,
+    /// A macro failed to expand
+    Macro = 6,
+    /// An IO error occurred, for instance when loading a module
+    IO = 7,
+    /// The gluon code panicked
+    Panic = 8,
+    /// The thread exceeded its memory limit
+    OutOfMemory = 9,
+    /// The stack exceeded its size limit
+    StackOverflow = 10,
+    /// The thread was interrupted
+    Interrupted = 11,
+    /// The thread ran out of fuel
+    OutOfFuel = 12,
+    /// The thread is dead
+    Dead = 13,
+    /// Any other error returned by the virtual machine, such as an undefined binding
+    Vm = 14,
+    /// The value does not have the type expected by the function
+    WrongType = 15,
+    /// The record does not have the requested field
+    MissingField = 16,
+}
+
+struct LastError {
+    code: Error,
+    /// Nul terminated description of the error
+    message: String,
+    /// Nul terminated diagnostic, including the source code the error originated from
+    diagnostic: String,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LastError>> = RefCell::new(None);
+}
+
+fn set_last_error(code: Error, mut message: String, mut diagnostic: String) -> Error {
+    message.push_str("\0");
+    diagnostic.push_str("\0");
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() = Some(LastError {
+            code,
+            message,
+            diagnostic,
+        })
+    });
+    code
+}
+
+fn gluon_error(err: gluon::Error) -> Error {
+    let diagnostic = err.emit_string().unwrap_or_else(|_| err.to_string());
+    set_last_error(error_code(&err), error_message(&err), diagnostic)
+}
+
+fn vm_error(err: vm::Error) -> Error {
+    gluon_error(err.into())
+}
+
+fn utf8_error() -> Error {
+    let message = "Argument was not valid UTF-8".to_string();
+    set_last_error(Error::Utf8, message.clone(), message)
 }
 
+fn invalid_index_error(index: VmIndex) -> Error {
+    let message = format!("There is no value at stack index {}", index);
+    set_last_error(Error::InvalidIndex, message.clone(), message)
+}
+
+fn missing_values_error(expected: usize) -> Error {
+    let message = format!("Expected at least {} values on the stack", expected);
+    set_last_error(Error::InvalidIndex, message.clone(), message)
+}
+
+fn wrong_type_error(expected: &str, index: VmIndex) -> Error {
+    let message = format!("Expected {} at stack index {}", expected, index);
+    set_last_error(Error::WrongType, message.clone(), message)
+}
+
+fn error_code(err: &gluon::Error) -> Error {
+    match err {
+        gluon::Error::Parse(_) => Error::Parse,
+        gluon::Error::Typecheck(_) => Error::Typecheck,
+        gluon::Error::Macro(_) => Error::Macro,
+        gluon::Error::IO(_) => Error::IO,
+        gluon::Error::VM(err) => match err {
+            vm::Error::Panic(..) => Error::Panic,
+            vm::Error::OutOfMemory { .. } => Error::OutOfMemory,
+            vm::Error::StackOverflow(_) => Error::StackOverflow,
+            vm::Error::Interrupted => Error::Interrupted,
+            vm::Error::OutOfFuel => Error::OutOfFuel,
+            vm::Error::Dead => Error::Dead,
+            _ => Error::Vm,
+        },
+        gluon::Error::Multiple(errors) => errors.iter().next().map_or(Error::Unknown, error_code),
+        gluon::Error::Other(_) => Error::Unknown,
+    }
+}
+
+/// Returns the description of `err` without the source code context of the diagnostic
+fn error_message(err: &gluon::Error) -> String {
+    fn in_file_message<E: fmt::Display>(err: &InFile<E>) -> String {
+        err.errors()
+            .iter()
+            .map(|err| err.value.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    match err {
+        gluon::Error::Parse(err) => in_file_message(err),
+        gluon::Error::Typecheck(err) => in_file_message(err),
+        gluon::Error::Macro(err) => in_file_message(err),
+        // Leave out the stacktrace
+        gluon::Error::VM(vm::Error::Panic(err, _)) => err.clone(),
+        gluon::Error::Multiple(errors) => errors
+            .iter()
+            .map(error_message)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => err.to_string(),
+    }
+}
+
+fn last_error<F>(out: &mut *const u8, out_len: &mut usize, f: F) -> Error
+where
+    F: FnOnce(&LastError) -> &str,
+{
+    LAST_ERROR.with(|last_error| match *last_error.borrow() {
+        Some(ref last_error) => {
+            let s = f(last_error);
+            *out = s.as_ptr();
+            // Exclude the nul terminator
+            *out_len = s.len() - 1;
+            last_error.code
+        }
+        None => {
+            *out = "\0".as_ptr();
+            *out_len = 0;
+            Error::Ok
+        }
+    })
+}
+
+/// Retrieves a short description of the last error returned by a function on the calling OS
+/// thread and returns its error code, or `Ok` and an empty string if no error has occurred.
+///
+/// The string is nul terminated and is valid until another error occurs on the same OS thread.
+#[no_mangle]
+pub extern "C" fn glu_last_error_message(out: &mut *const u8, out_len: &mut usize) -> Error {
+    last_error(out, out_len, |last_error| &last_error.message)
+}
+
+/// Retrieves the rendered diagnostic of the last error returned by a function on the calling OS
+/// thread and returns its error code, or `Ok` and an empty string if no error has occurred. For
+/// errors in gluon code the diagnostic points out the source code the error originated from.
+///
+/// The string is nul terminated and is valid until another error occurs on the same OS thread.
+#[no_mangle]
+pub extern "C" fn glu_last_error_diagnostic(out: &mut *const u8, out_len: &mut usize) -> Error {
+    last_error(out, out_len, |last_error| &last_error.diagnostic)
+}
+
+/// Creates a new vm with the standard library and the `import!` macro, the same as
+/// `gluon::new_vm`
 #[no_mangle]
 pub extern "C" fn glu_new_vm() -> *const Thread {
-    let vm = RootedThread::new();
+    let vm = gluon::new_vm();
     vm.into_raw()
 }
 
@@ -47,16 +236,20 @@ pub unsafe extern "C" fn glu_run_expr(
 ) -> Error {
     let module = match str::from_utf8(slice::from_raw_parts(module, module_len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
     let expr = match str::from_utf8(slice::from_raw_parts(expr, expr_len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
+    let len = glu_len(vm);
     let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>(module, expr);
     match result {
         Ok(_) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => {
+            restore_stack(vm, len);
+            gluon_error(err)
+        }
     }
 }
 
@@ -70,16 +263,20 @@ pub unsafe extern "C" fn glu_load_script(
 ) -> Error {
     let module = match str::from_utf8(slice::from_raw_parts(module, module_len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
     let expr = match str::from_utf8(slice::from_raw_parts(expr, expr_len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
+    let len = glu_len(vm);
     let result = vm.load_script(module, expr);
     match result {
         Ok(_) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => {
+            restore_stack(vm, len);
+            gluon_error(err)
+        }
     }
 }
 
@@ -90,7 +287,7 @@ pub extern "C" fn glu_call_function(thread: &Thread, args: VmIndex) -> Error {
         thread.call_function(cx, context, args)
     })) {
         Ok(_) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => vm_error(err),
     }
 }
 
@@ -140,11 +337,11 @@ pub unsafe extern "C" fn glu_push_function(
 ) -> Error {
     let s = match str::from_utf8(slice::from_raw_parts(name, len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
     match Thread::push(vm, CPrimitive::new(function, args, s)) {
         Ok(()) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => vm_error(err),
     }
 }
 
@@ -153,11 +350,11 @@ pub unsafe extern "C" fn glu_push_function(
 pub unsafe extern "C" fn glu_push_string(vm: &Thread, s: &u8, len: usize) -> Error {
     let s = match str::from_utf8(slice::from_raw_parts(s, len)) {
         Ok(s) => s,
-        Err(_) => return Error::Unknown,
+        Err(_) => return utf8_error(),
     };
     match s.push(&mut vm.current_context()) {
         Ok(()) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => vm_error(err),
     }
 }
 
@@ -168,7 +365,7 @@ pub unsafe extern "C" fn glu_push_string_unchecked(vm: &Thread, s: &u8, len: usi
     let s = str::from_utf8_unchecked(slice::from_raw_parts(s, len));
     match s.push(&mut vm.current_context()) {
         Ok(()) => Error::Ok,
-        Err(_) => Error::Unknown,
+        Err(err) => vm_error(err),
     }
 }
 
@@ -180,27 +377,34 @@ pub extern "C" fn glu_push_light_userdata(vm: &Thread, data: *mut libc::c_void)
 
 #[no_mangle]
 pub extern "C" fn glu_get_byte(vm: &Thread, index: VmIndex, out: &mut u8) -> Error {
-    get_value(vm, index, out)
+    get_value(vm, index, out, "a byte", |value| match value {
+        ValueRef::Byte(b) => Some(b),
+        _ => None,
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn glu_get_int(vm: &Thread, index: VmIndex, out: &mut VmInt) -> Error {
-    get_value(vm, index, out)
+    get_value(vm, index, out, "an integer", |value| match value {
+        ValueRef::Int(i) => Some(i),
+        _ => None,
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn glu_get_float(vm: &Thread, index: VmIndex, out: &mut f64) -> Error {
-    get_value(vm, index, out)
+    get_value(vm, index, out, "a float", |value| match value {
+        ValueRef::Float(f) => Some(f),
+        _ => None,
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn glu_get_bool(vm: &Thread, index: VmIndex, out: &mut i8) -> Error {
-    let mut b = false;
-    let err = get_value(vm, index, &mut b);
-    if err == Error::Ok {
-        *out = b as i8;
-    }
-    err
+    get_value(vm, index, out, "a boolean", |value| match value {
+        ValueRef::Data(data) if data.len() == 0 && data.tag() <= 1 => Some(data.tag() as i8),
+        _ => None,
+    })
 }
 
 /// The returned string is garbage collected and may not be valid after the string is removed from
@@ -212,18 +416,17 @@ pub unsafe extern "C" fn glu_get_string(
     out: &mut *const u8,
     out_len: &mut usize,
 ) -> Error {
-    let mut context = vm.context();
-    let stack = context.stack_frame::<stack::State>();
-    match stack
-        .get_variant(index)
-        .map(|value| <&str>::from_value(vm, value))
-    {
-        Some(value) => {
-            *out = &*value.as_ptr();
-            *out_len = value.len();
+    let string = with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::String(s) => Ok((s.as_ptr(), s.len())),
+        _ => Err(wrong_type_error("a string", index)),
+    });
+    match string {
+        Ok((ptr, len)) => {
+            *out = ptr;
+            *out_len = len;
             Error::Ok
         }
-        None => Error::Unknown,
+        Err(err) => err,
     }
 }
 
@@ -234,29 +437,379 @@ pub extern "C" fn glu_get_light_userdata(
     index: VmIndex,
     out: &mut *mut libc::c_void,
 ) -> Error {
-    let mut userdata = 0usize;
-    let err = get_value(vm, index, &mut userdata);
-    if err == Error::Ok {
-        *out = userdata as *mut libc::c_void;
+    get_value(vm, index, out, "light userdata", |value| match value {
+        ValueRef::Int(i) => Some(i as usize as *mut libc::c_void),
+        _ => None,
+    })
+}
+
+/// Returns an error if the stack does not contain at least `len` values
+fn check_stack_len(vm: &Thread, len: usize) -> Result<(), Error> {
+    if glu_len(vm) < len {
+        Err(missing_values_error(len))
+    } else {
+        Ok(())
     }
-    err
 }
 
-fn get_value<T>(vm: &Thread, index: VmIndex, out: &mut T) -> Error
-where
-    T: for<'vm, 'value> Getable<'vm, 'value>,
-{
+/// Calls `f` with the value at `index` of the stack
+fn with_value<R>(
+    vm: &Thread,
+    index: VmIndex,
+    f: impl FnOnce(Variants) -> Result<R, Error>,
+) -> Result<R, Error> {
     let mut context = vm.context();
     let stack = context.stack_frame::<stack::State>();
-    match stack
-        .get_variant(index)
-        .map(|value| T::from_value(vm, value))
+    match stack.get_variant(index) {
+        Some(value) => f(value),
+        None => Err(invalid_index_error(index)),
+    }
+}
+
+fn push_rooted(vm: &Thread, value: Result<RootedValue<RootedThread>, Error>) -> Error {
+    match value {
+        Ok(value) => match Thread::push(vm, value) {
+            Ok(()) => Error::Ok,
+            Err(err) => vm_error(err),
+        },
+        Err(err) => err,
+    }
+}
+
+unsafe fn str_arg<'a>(s: *const u8, len: usize) -> Result<&'a str, Error> {
+    let bytes = if len == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(s, len)
+    };
+    str::from_utf8(bytes).map_err(|_| utf8_error())
+}
+
+/// Pops the top `fields` values of the stack and pushes a record containing them. `names` and
+/// `name_lens` point to arrays containing the name of each field, in the same order as the
+/// values were pushed.
+#[no_mangle]
+pub unsafe extern "C" fn glu_push_record(
+    vm: &Thread,
+    names: *const *const u8,
+    name_lens: *const usize,
+    fields: usize,
+) -> Error {
+    if let Err(err) = check_stack_len(vm, fields) {
+        return err;
+    }
+    let field_names = (0..fields)
+        .map(|i| {
+            let name = str_arg(*names.add(i), *name_lens.add(i))?;
+            vm.global_env().intern(name).map_err(vm_error)
+        })
+        .collect::<Result<Vec<_>, _>>();
+    let field_names = match field_names {
+        Ok(field_names) => field_names,
+        Err(err) => return err,
+    };
+    match vm
+        .current_context()
+        .context()
+        .push_new_record(fields, &field_names)
     {
-        Some(value) => {
+        Ok(_) => Error::Ok,
+        Err(err) => vm_error(err),
+    }
+}
+
+/// Pops the top `fields` values of the stack and pushes a tuple containing them
+#[no_mangle]
+pub extern "C" fn glu_push_tuple(vm: &Thread, fields: usize) -> Error {
+    glu_push_variant(vm, 0, fields)
+}
+
+/// Pops the top `fields` values of the stack and pushes a variant with the tag `tag` containing
+/// them. The tag of a variant is the index of its constructor in the declaration of its type.
+#[no_mangle]
+pub extern "C" fn glu_push_variant(vm: &Thread, tag: VmTag, fields: usize) -> Error {
+    if let Err(err) = check_stack_len(vm, fields) {
+        return err;
+    }
+    match vm.current_context().context().push_new_data(tag, fields) {
+        Ok(_) => Error::Ok,
+        Err(err) => vm_error(err),
+    }
+}
+
+/// Pops the top `len` values of the stack and pushes an array containing them. The values must
+/// all have the same type.
+#[no_mangle]
+pub extern "C" fn glu_push_array(vm: &Thread, len: usize) -> Error {
+    match vm.current_context().context().push_new_array(len) {
+        Ok(_) => Error::Ok,
+        Err(err) => vm_error(err),
+    }
+}
+
+/// Pushes the field `name` of the record at `index`
+#[no_mangle]
+pub unsafe extern "C" fn glu_push_field(
+    vm: &Thread,
+    index: VmIndex,
+    name: *const u8,
+    name_len: usize,
+) -> Error {
+    let name = match str_arg(name, name_len) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+    let field = with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Data(data) => match data.lookup_field(vm, name) {
+            Some(field) => Ok(vm.root_value(field)),
+            None => {
+                let message = format!("The record does not have the field `{}`", name);
+                Err(set_last_error(
+                    Error::MissingField,
+                    message.clone(),
+                    message,
+                ))
+            }
+        },
+        _ => Err(wrong_type_error("a record", index)),
+    });
+    push_rooted(vm, field)
+}
+
+/// Pushes the field at position `field` of the variant or tuple at `index`
+#[no_mangle]
+pub extern "C" fn glu_push_data_field(vm: &Thread, index: VmIndex, field: usize) -> Error {
+    let field = with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Data(data) => match data.get_variant(field) {
+            Some(field) => Ok(vm.root_value(field)),
+            None => Err(invalid_index_error(index)),
+        },
+        _ => Err(wrong_type_error("a variant or a tuple", index)),
+    });
+    push_rooted(vm, field)
+}
+
+/// Pushes the element at position `element` of the array at `index`
+#[no_mangle]
+pub extern "C" fn glu_push_element(vm: &Thread, index: VmIndex, element: usize) -> Error {
+    let element = with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Array(array) => match array.get(element) {
+            Some(element) => Ok(vm.root_value(element)),
+            None => Err(invalid_index_error(index)),
+        },
+        _ => Err(wrong_type_error("an array", index)),
+    });
+    push_rooted(vm, element)
+}
+
+/// Retrieves the tag of the variant at `index`
+#[no_mangle]
+pub extern "C" fn glu_get_tag(vm: &Thread, index: VmIndex, out: &mut VmTag) -> Error {
+    match with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Data(data) => Ok(data.tag()),
+        _ => Err(wrong_type_error("a variant", index)),
+    }) {
+        Ok(tag) => {
+            *out = tag;
+            Error::Ok
+        }
+        Err(err) => err,
+    }
+}
+
+/// Retrieves the number of elements of the array at `index` or the number of fields of the
+/// record, variant or tuple at `index`
+#[no_mangle]
+pub extern "C" fn glu_get_len(vm: &Thread, index: VmIndex, out: &mut usize) -> Error {
+    match with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Array(array) => Ok(array.len()),
+        ValueRef::Data(data) => Ok(data.len()),
+        _ => Err(wrong_type_error("an array or a record", index)),
+    }) {
+        Ok(len) => {
+            *out = len;
+            Error::Ok
+        }
+        Err(err) => err,
+    }
+}
+
+/// Pushes the global `name`, for instance a function defined by a script loaded with
+/// `glu_load_script`. Functions can then be called with `glu_call_function`.
+#[no_mangle]
+pub unsafe extern "C" fn glu_push_global(vm: &Thread, name: *const u8, name_len: usize) -> Error {
+    let name = match str_arg(name, name_len) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+    match vm
+        .get_global::<OpaqueValue<RootedThread, Hole>>(name)
+        .and_then(|value| Thread::push(vm, value))
+    {
+        Ok(()) => Error::Ok,
+        Err(err) => vm_error(err),
+    }
+}
+
+/// A handle to a gluon value. The value is kept alive until the handle is freed with
+/// `glu_free_value`, even if it is removed from the stack.
+pub struct Value(RootedValue<RootedThread>);
+
+/// Creates a handle to the value at `index`
+#[no_mangle]
+pub extern "C" fn glu_get_value(vm: &Thread, index: VmIndex, out: &mut *mut Value) -> Error {
+    match with_value(vm, index, |value| Ok(vm.root_value(value))) {
+        Ok(value) => {
+            *out = Box::into_raw(Box::new(Value(value)));
+            Error::Ok
+        }
+        Err(err) => err,
+    }
+}
+
+/// Pushes the value of `value` to the stack
+#[no_mangle]
+pub extern "C" fn glu_push_value(vm: &Thread, value: &Value) -> Error {
+    push_rooted(vm, Ok(value.0.clone()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glu_free_value(value: *mut Value) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// A userdata type defined by the C program
+#[derive(Debug)]
+pub struct UserdataType {
+    name: String,
+    /// The gluon type of the values of this userdata type, distinct from every other type
+    typ: ArcType,
+    finalizer: Finalizer,
+}
+
+#[derive(Debug)]
+struct CUserdata {
+    typ: &'static UserdataType,
+    data: *mut c_void,
+}
+
+// The C program is responsible for the data being safe to use from multiple threads
+unsafe impl Send for CUserdata {}
+unsafe impl Sync for CUserdata {}
+
+impl Userdata for CUserdata {}
+
+unsafe impl Trace for CUserdata {
+    impl_trace! { self, _gc, () }
+}
+
+impl Drop for CUserdata {
+    fn drop(&mut self) {
+        if let Some(finalizer) = self.typ.finalizer {
+            finalizer(self.data);
+        }
+    }
+}
+
+/// Registers an opaque gluon type called `name` for userdata values created by the C program.
+/// When a value of the type is garbage collected, `finalizer` is called with its data. Each
+/// registration creates a new gluon type so registering a name which is already a type is an
+/// error.
+///
+/// The returned type is valid for the rest of the process.
+#[no_mangle]
+pub unsafe extern "C" fn glu_register_userdata(
+    vm: &Thread,
+    name: *const u8,
+    name_len: usize,
+    finalizer: Finalizer,
+    out: &mut *const UserdataType,
+) -> Error {
+    let name = match str_arg(name, name_len) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+    if vm.get_env().find_type_info(name).is_ok() {
+        return vm_error(vm::Error::TypeAlreadyExists(name.to_string()));
+    }
+    let opaque: ArcType = Type::opaque();
+    let gluon_type = vm.cache_alias(Alias::from(AliasData::new(
+        Symbol::from(name),
+        Vec::new(),
+        opaque,
+    )));
+    let typ = Box::leak(Box::new(UserdataType {
+        name: name.to_string(),
+        typ: gluon_type,
+        finalizer,
+    }));
+    *out = typ;
+    Error::Ok
+}
+
+/// Pushes a userdata value of type `typ` which contains `data`
+#[no_mangle]
+pub extern "C" fn glu_push_userdata(
+    vm: &Thread,
+    typ: &'static UserdataType,
+    data: *mut c_void,
+) -> Error {
+    match Thread::push(vm, CUserdata { typ, data }) {
+        Ok(()) => Error::Ok,
+        Err(err) => vm_error(err),
+    }
+}
+
+/// Retrieves the data of the userdata value at `index`, which must have the type `typ`
+#[no_mangle]
+pub extern "C" fn glu_get_userdata(
+    vm: &Thread,
+    index: VmIndex,
+    typ: &UserdataType,
+    out: &mut *mut c_void,
+) -> Error {
+    match with_value(vm, index, |value| match value.as_ref() {
+        ValueRef::Userdata(data) => match data.downcast_ref::<CUserdata>() {
+            Some(userdata) if ptr::eq(userdata.typ, typ) => Ok(userdata.data),
+            _ => Err(wrong_type_error(&format!("a `{}` value", typ.name), index)),
+        },
+        _ => Err(wrong_type_error(&format!("a `{}` value", typ.name), index)),
+    }) {
+        Ok(data) => {
+            *out = data;
+            Error::Ok
+        }
+        Err(err) => err,
+    }
+}
+
+/// Pops any values a failed evaluation left on the stack so that the indices used by the caller
+/// still refer to the same values
+fn restore_stack(vm: &Thread, len: usize) {
+    let extra = glu_len(vm).saturating_sub(len);
+    glu_pop(vm, extra);
+}
+
+/// Converts the value at `index` with `convert`, returning `Error::WrongType` if `convert` does not
+/// accept the value. The value is checked before converting it since panicking is not allowed to
+/// unwind into the C caller.
+fn get_value<T>(
+    vm: &Thread,
+    index: VmIndex,
+    out: &mut T,
+    expected: &str,
+    convert: impl FnOnce(ValueRef) -> Option<T>,
+) -> Error {
+    match with_value(vm, index, |value| {
+        convert(value.as_ref()).ok_or_else(|| wrong_type_error(expected, index))
+    }) {
+        Ok(value) => {
             *out = value;
             Error::Ok
         }
-        None => Error::Unknown,
+        Err(err) => err,
     }
 }
 
@@ -355,4 +908,345 @@ mod tests {
             glu_free_vm(vm);
         }
     }
+
+    unsafe fn last_error_message() -> &'static str {
+        let mut message = ptr::null();
+        let mut message_len = 0;
+        glu_last_error_message(&mut message, &mut message_len);
+        str::from_utf8(slice::from_raw_parts(message, message_len)).unwrap()
+    }
+
+    unsafe fn last_error_diagnostic() -> &'static str {
+        let mut diagnostic = ptr::null();
+        let mut diagnostic_len = 0;
+        glu_last_error_diagnostic(&mut diagnostic, &mut diagnostic_len);
+        str::from_utf8(slice::from_raw_parts(diagnostic, diagnostic_len)).unwrap()
+    }
+
+    unsafe fn run_expr(vm: &Thread, expr: &str) -> Error {
+        let module = "test";
+        glu_run_expr(
+            vm,
+            &module.as_bytes()[0],
+            module.len(),
+            &expr.as_bytes()[0],
+            expr.len(),
+        )
+    }
+
+    #[test]
+    fn run_expr_errors() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            assert_eq!(run_expr(vm, "1 #Int+ 2"), Error::Ok);
+
+            assert_eq!(run_expr(vm, "let x = "), Error::Parse);
+
+            assert_eq!(run_expr(vm, r#"1 #Int+ "abc""#), Error::Typecheck);
+            assert!(
+                last_error_message().starts_with("Expected the following types to be equal"),
+                "{}",
+                last_error_message()
+            );
+            let diagnostic = last_error_diagnostic();
+            assert!(diagnostic.contains("<test>:1:9"), "{}", diagnostic);
+            assert!(diagnostic.contains(r#"1 #Int+ "abc""#), "{}", diagnostic);
+
+            assert_eq!(run_expr(vm, "import! missing_module"), Error::Macro);
+
+            assert_eq!(run_expr(vm, r#"error "boom""#), Error::Panic);
+            assert_eq!(last_error_message(), "boom");
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn other_errors_are_unknown() {
+        let err = gluon::Error::Other(vm::macros::Error::message("other"));
+        assert_eq!(error_code(&err), Error::Unknown);
+    }
+
+    #[test]
+    fn last_error_code() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            let mut int = 0;
+            assert_eq!(glu_get_int(vm, 10, &mut int), Error::InvalidIndex);
+
+            let mut message = ptr::null();
+            let mut message_len = 0;
+            assert_eq!(
+                glu_last_error_message(&mut message, &mut message_len),
+                Error::InvalidIndex
+            );
+            assert_eq!(
+                str::from_utf8(slice::from_raw_parts(message, message_len)),
+                Ok("There is no value at stack index 10")
+            );
+            // The message is nul terminated
+            assert_eq!(*message.add(message_len), 0);
+
+            let invalid = [0xff, 0xfe];
+            assert_eq!(glu_push_string(vm, &invalid[0], invalid.len()), Error::Utf8);
+            assert_eq!(last_error_message(), "Argument was not valid UTF-8");
+
+            glu_free_vm(vm);
+        }
+    }
+
+    unsafe fn get_string<'a>(vm: &'a Thread, index: VmIndex) -> &'a str {
+        let mut string_ptr = ptr::null();
+        let mut string_len = 0;
+        assert_eq!(
+            glu_get_string(vm, index, &mut string_ptr, &mut string_len),
+            Error::Ok
+        );
+        str::from_utf8(slice::from_raw_parts(string_ptr, string_len)).unwrap()
+    }
+
+    #[test]
+    fn records() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            glu_push_int(vm, 1);
+            let s = "abc";
+            glu_push_string(vm, &s.as_bytes()[0], s.len());
+            let names = ["x".as_ptr(), "y".as_ptr()];
+            let name_lens = [1, 1];
+            assert_eq!(
+                glu_push_record(vm, names.as_ptr(), name_lens.as_ptr(), 2),
+                Error::Ok
+            );
+            assert_eq!(glu_len(vm), 1);
+
+            let mut len = 0;
+            assert_eq!(glu_get_len(vm, 0, &mut len), Error::Ok);
+            assert_eq!(len, 2);
+
+            assert_eq!(glu_push_field(vm, 0, "y".as_ptr(), 1), Error::Ok);
+            assert_eq!(get_string(vm, 1), "abc");
+
+            assert_eq!(glu_push_field(vm, 0, "z".as_ptr(), 1), Error::MissingField);
+            assert_eq!(glu_push_field(vm, 1, "x".as_ptr(), 1), Error::WrongType);
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn get_wrong_type() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            glu_push_int(vm, 1);
+            let s = "test";
+            glu_push_string(vm, &s.as_bytes()[0], s.len());
+
+            let mut string = ptr::null();
+            let mut len = 0;
+            assert_eq!(
+                glu_get_string(vm, 0, &mut string, &mut len),
+                Error::WrongType
+            );
+            assert_eq!(last_error_message(), "Expected a string at stack index 0");
+
+            let mut b = 0;
+            assert_eq!(glu_get_bool(vm, 0, &mut b), Error::WrongType);
+            let mut float = 0.0;
+            assert_eq!(glu_get_float(vm, 0, &mut float), Error::WrongType);
+            let mut int = 0;
+            assert_eq!(glu_get_int(vm, 1, &mut int), Error::WrongType);
+            assert_eq!(last_error_message(), "Expected an integer at stack index 1");
+            let mut byte = 0;
+            assert_eq!(glu_get_byte(vm, 1, &mut byte), Error::WrongType);
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn variants_and_tuples() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            glu_push_int(vm, 1);
+            glu_push_float(vm, 2.0);
+            assert_eq!(glu_push_variant(vm, 3, 2), Error::Ok);
+
+            let mut tag = 0;
+            assert_eq!(glu_get_tag(vm, 0, &mut tag), Error::Ok);
+            assert_eq!(tag, 3);
+            assert_eq!(glu_push_data_field(vm, 0, 1), Error::Ok);
+            let mut float = 0.0;
+            assert_eq!(glu_get_float(vm, 1, &mut float), Error::Ok);
+            assert_eq!(float, 2.0);
+            assert_eq!(glu_push_data_field(vm, 0, 2), Error::InvalidIndex);
+
+            glu_push_byte(vm, 1);
+            glu_push_bool(vm, 1);
+            assert_eq!(glu_push_tuple(vm, 2), Error::Ok);
+            assert_eq!(glu_push_data_field(vm, 2, 0), Error::Ok);
+            let mut b = 0;
+            assert_eq!(glu_get_byte(vm, 3, &mut b), Error::Ok);
+            assert_eq!(b, 1);
+
+            assert_eq!(glu_push_tuple(vm, 10), Error::InvalidIndex);
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn arrays() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            for i in 0..3 {
+                glu_push_int(vm, i * 10);
+            }
+            assert_eq!(glu_push_array(vm, 3), Error::Ok);
+            assert_eq!(glu_len(vm), 1);
+
+            let mut len = 0;
+            assert_eq!(glu_get_len(vm, 0, &mut len), Error::Ok);
+            assert_eq!(len, 3);
+            assert_eq!(glu_push_element(vm, 0, 2), Error::Ok);
+            let mut int = 0;
+            assert_eq!(glu_get_int(vm, 1, &mut int), Error::Ok);
+            assert_eq!(int, 20);
+            assert_eq!(glu_push_element(vm, 0, 3), Error::InvalidIndex);
+
+            glu_push_int(vm, 1);
+            glu_push_float(vm, 1.0);
+            assert_eq!(glu_push_array(vm, 2), Error::Vm);
+
+            assert_eq!(glu_push_array(vm, 10), Error::Vm);
+            assert_eq!(
+                last_error_message(),
+                "Expected at least 10 values on the stack"
+            );
+            assert_eq!(glu_len(vm), 4);
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn value_handles() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            let s = "handle";
+            glu_push_string(vm, &s.as_bytes()[0], s.len());
+            let mut value = ptr::null_mut();
+            assert_eq!(glu_get_value(vm, 0, &mut value), Error::Ok);
+            glu_pop(vm, 1);
+            assert_eq!(glu_len(vm), 0);
+
+            assert_eq!(glu_push_value(vm, &*value), Error::Ok);
+            assert_eq!(get_string(vm, 0), "handle");
+            glu_free_value(value);
+
+            assert_eq!(glu_get_value(vm, 1, &mut value), Error::InvalidIndex);
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn call_closure() {
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            let module = "add";
+            let script = r#"\x y -> x #Int+ y"#;
+            assert_eq!(
+                glu_load_script(
+                    vm,
+                    &module.as_bytes()[0],
+                    module.len(),
+                    &script.as_bytes()[0],
+                    script.len()
+                ),
+                Error::Ok
+            );
+            assert_eq!(
+                glu_push_global(vm, module.as_ptr(), module.len()),
+                Error::Ok
+            );
+            glu_push_int(vm, 1);
+            glu_push_int(vm, 2);
+            assert_eq!(glu_call_function(vm, 2), Error::Ok);
+            let mut int = 0;
+            assert_eq!(glu_get_int(vm, 0, &mut int), Error::Ok);
+            assert_eq!(int, 3);
+
+            let missing = "missing";
+            assert_eq!(
+                glu_push_global(vm, missing.as_ptr(), missing.len()),
+                Error::Vm
+            );
+
+            glu_free_vm(vm);
+        }
+    }
+
+    #[test]
+    fn userdata() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C" fn finalize(data: *mut c_void) {
+            FINALIZED.fetch_add(unsafe { *(data as *const usize) }, Ordering::SeqCst);
+        }
+
+        static DATA: usize = 5;
+
+        unsafe {
+            let vm = &*glu_new_vm();
+
+            let mut typ = ptr::null();
+            let name = "Data";
+            assert_eq!(
+                glu_register_userdata(vm, name.as_ptr(), name.len(), Some(finalize), &mut typ),
+                Error::Ok
+            );
+            let mut other_typ = ptr::null();
+            let name = "Other";
+            assert_eq!(
+                glu_register_userdata(vm, name.as_ptr(), name.len(), None, &mut other_typ),
+                Error::Ok
+            );
+            assert_ne!((*typ).typ, (*other_typ).typ);
+            assert_eq!(
+                vm.get_env().find_type_info("Data").unwrap().into_type(),
+                (*typ).typ
+            );
+            let mut duplicate_typ = ptr::null();
+            assert_eq!(
+                glu_register_userdata(vm, name.as_ptr(), name.len(), None, &mut duplicate_typ),
+                Error::Vm
+            );
+
+            assert_eq!(
+                glu_push_userdata(vm, &*typ, &DATA as *const usize as *mut c_void),
+                Error::Ok
+            );
+            let mut data = ptr::null_mut();
+            assert_eq!(glu_get_userdata(vm, 0, &*typ, &mut data), Error::Ok);
+            assert_eq!(*(data as *const usize), 5);
+            assert_eq!(
+                glu_get_userdata(vm, 0, &*other_typ, &mut data),
+                Error::WrongType
+            );
+
+            glu_free_vm(vm);
+            assert_eq!(FINALIZED.load(Ordering::SeqCst), 5);
+        }
+    }
 }
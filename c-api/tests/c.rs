@@ -0,0 +1,42 @@
+//! Compiles `tests/c/main.c` against `include/gluon.h` and the cdylib of this crate and runs it
+#![cfg(unix)]
+
+use std::{env, path::PathBuf, process::Command};
+
+#[test]
+fn c_harness() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Integration tests are placed in `target/<profile>/deps` while the cdylib is placed in
+    // `target/<profile>`
+    let lib_dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .to_owned();
+    let out_dir = lib_dir.join("c-api-test");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let exe = out_dir.join("main");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/c/main.c"))
+        .arg("-o")
+        .arg(&exe)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lgluon_c_api")
+        .status()
+        .expect("Could not run the C compiler");
+    assert!(status.success(), "Failed to compile the C test harness");
+
+    let status = Command::new(&exe)
+        .current_dir(&manifest_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "The C test harness failed");
+}
@@ -0,0 +1,176 @@
+/* Exercises the C API through the generated header. Run by `tests/c.rs`. */
+#include <stdio.h>
+#include <string.h>
+
+#include "gluon.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                                      \
+    do {                                                                                 \
+        if (!(cond)) {                                                                   \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond);     \
+            failures++;                                                                  \
+        }                                                                                \
+    } while (0)
+
+static GluError run_expr(const Thread *vm, const char *expr) {
+    const char *module = "test";
+    return glu_run_expr(vm, (const uint8_t *)module, strlen(module), (const uint8_t *)expr,
+                        strlen(expr));
+}
+
+static const char *last_error_message(void) {
+    const uint8_t *message;
+    uintptr_t message_len;
+    glu_last_error_message(&message, &message_len);
+    return (const char *)message;
+}
+
+static const char *last_error_diagnostic(void) {
+    const uint8_t *diagnostic;
+    uintptr_t diagnostic_len;
+    glu_last_error_diagnostic(&diagnostic, &diagnostic_len);
+    return (const char *)diagnostic;
+}
+
+static int finalized = 0;
+
+static void finalize(void *data) {
+    finalized += *(int *)data;
+}
+
+static Status mult(const Thread *vm) {
+    double l, r;
+    if (glu_get_float(vm, 0, &l) != GluError_Ok || glu_get_float(vm, 1, &r) != GluError_Ok) {
+        return Status_Error;
+    }
+    glu_push_float(vm, l * r);
+    return Status_Ok;
+}
+
+int main(void) {
+    const Thread *vm = glu_new_vm();
+
+    {
+        const uint8_t *message;
+        uintptr_t message_len;
+        CHECK(glu_last_error_message(&message, &message_len) == GluError_Ok);
+        CHECK(message_len == 0);
+    }
+
+    CHECK(run_expr(vm, "1 #Int+ 2") == GluError_Ok);
+
+    CHECK(run_expr(vm, "let x = ") == GluError_Parse);
+
+    CHECK(run_expr(vm, "1 #Int+ \"abc\"") == GluError_Typecheck);
+    CHECK(strstr(last_error_message(), "Expected the following types to be equal") != NULL);
+    CHECK(strstr(last_error_diagnostic(), "<test>:1:9") != NULL);
+
+    CHECK(run_expr(vm, "import! missing_module") == GluError_Macro);
+
+    CHECK(run_expr(vm, "error \"boom\"") == GluError_Panic);
+    CHECK(strcmp(last_error_message(), "boom") == 0);
+
+    {
+        VmInt i;
+        const uint8_t *message;
+        uintptr_t message_len;
+        CHECK(glu_get_int(vm, 10, &i) == GluError_InvalidIndex);
+        CHECK(glu_last_error_message(&message, &message_len) == GluError_InvalidIndex);
+        CHECK(message_len == strlen((const char *)message));
+    }
+
+    {
+        const uint8_t invalid[] = {0xff, 0xfe};
+        CHECK(glu_push_string(vm, invalid, sizeof(invalid)) == GluError_Utf8);
+    }
+
+    {
+        const char *name = "mult";
+        double result = 0.0;
+        CHECK(glu_push_function(vm, (const uint8_t *)name, strlen(name), mult, 2) == GluError_Ok);
+        glu_push_float(vm, 12.0);
+        glu_push_float(vm, 3.0);
+        CHECK(glu_call_function(vm, 2) == GluError_Ok);
+        CHECK(glu_get_float(vm, 0, &result) == GluError_Ok);
+        CHECK(result == 36.0);
+    }
+
+    glu_pop(vm, glu_len(vm));
+
+    {
+        const uint8_t *names[] = {(const uint8_t *)"x", (const uint8_t *)"y"};
+        const uintptr_t name_lens[] = {1, 1};
+        VmInt x = 0;
+        glu_push_int(vm, 1);
+        glu_push_int(vm, 2);
+        CHECK(glu_push_record(vm, names, name_lens, 2) == GluError_Ok);
+        CHECK(glu_push_field(vm, 0, (const uint8_t *)"x", 1) == GluError_Ok);
+        CHECK(glu_get_int(vm, 1, &x) == GluError_Ok);
+        CHECK(x == 1);
+        CHECK(glu_push_field(vm, 0, (const uint8_t *)"z", 1) == GluError_MissingField);
+        glu_pop(vm, 2);
+    }
+
+    {
+        VmTag tag = 0;
+        uintptr_t len = 0;
+        GluValue *value = NULL;
+        glu_push_float(vm, 1.5);
+        CHECK(glu_push_variant(vm, 1, 1) == GluError_Ok);
+        CHECK(glu_get_tag(vm, 0, &tag) == GluError_Ok);
+        CHECK(tag == 1);
+        CHECK(glu_push_array(vm, 1) == GluError_Ok);
+        CHECK(glu_get_len(vm, 0, &len) == GluError_Ok);
+        CHECK(len == 1);
+        CHECK(glu_get_value(vm, 0, &value) == GluError_Ok);
+        glu_pop(vm, 1);
+        CHECK(glu_push_value(vm, value) == GluError_Ok);
+        glu_free_value(value);
+        CHECK(glu_push_element(vm, 0, 0) == GluError_Ok);
+        CHECK(glu_push_data_field(vm, 1, 0) == GluError_Ok);
+        {
+            double f = 0.0;
+            CHECK(glu_get_float(vm, 2, &f) == GluError_Ok);
+            CHECK(f == 1.5);
+        }
+        glu_pop(vm, 3);
+    }
+
+    {
+        const char *module = "add";
+        const char *script = "\\x y -> x #Int+ y";
+        VmInt result = 0;
+        CHECK(glu_load_script(vm, (const uint8_t *)module, strlen(module), (const uint8_t *)script,
+                              strlen(script)) == GluError_Ok);
+        CHECK(glu_push_global(vm, (const uint8_t *)module, strlen(module)) == GluError_Ok);
+        glu_push_int(vm, 1);
+        glu_push_int(vm, 2);
+        CHECK(glu_call_function(vm, 2) == GluError_Ok);
+        CHECK(glu_get_int(vm, 0, &result) == GluError_Ok);
+        CHECK(result == 3);
+        glu_pop(vm, 1);
+    }
+
+    {
+        const GluUserdataType *type = NULL;
+        const char *name = "Data";
+        static int data = 5;
+        void *out = NULL;
+        CHECK(glu_register_userdata(vm, (const uint8_t *)name, strlen(name), finalize, &type) ==
+              GluError_Ok);
+        CHECK(glu_push_userdata(vm, type, &data) == GluError_Ok);
+        CHECK(glu_get_userdata(vm, 0, type, &out) == GluError_Ok);
+        CHECK(out == &data);
+    }
+
+    glu_free_vm(vm);
+    CHECK(finalized == 5);
+
+    if (failures != 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    return 0;
+}
@@ -0,0 +1,705 @@
+//! Checks that `match` expressions and `let` bindings handle every value that they may be given
+//! and that no alternative of a `match` expression is unreachable.
+//!
+//! The check is an implementation of the usefulness algorithm from "Warnings for pattern
+//! matching" by Luc Maranget. A pattern is useful with respect to a list of patterns if there
+//! exists a value which it matches but which none of the patterns in the list do. An alternative
+//! which is not useful with respect to the alternatives before it can never be selected, and a
+//! `match` is exhaustive exactly when a wildcard pattern is not useful with respect to all of its
+//! (unguarded) alternatives.
+use std::{fmt, iter};
+
+use crate::base::{
+    ast::{self, Alternative, Expr, Literal, Pattern, SpannedExpr, SpannedPattern, Visitor},
+    error::Errors,
+    pos::{self, BytePos, Span, Spanned},
+    resolve::remove_aliases_cow,
+    symbol::Symbol,
+    types::{arg_iter, ArcType, NullInterner, Type, TypeEnv, TypeExt},
+};
+
+use crate::lint::{Warning, Warnings};
+
+/// The maximum number of missing patterns that are reported in an error
+const MAX_MISSING_PATTERNS: usize = 3;
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum Error {
+    /// The alternatives of a `match` expression do not cover every value of the matched type.
+    /// `missing` contains examples of patterns which are not covered.
+    NonExhaustiveMatch { missing: Vec<String> },
+    /// The pattern of a `let` binding does not match every value of the bound type
+    RefutableLet { missing: Vec<String> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NonExhaustiveMatch { missing } => {
+                write!(f, "Non-exhaustive patterns in `match` expression, ")?;
+                fmt_missing(missing, f)
+            }
+            Error::RefutableLet { missing } => {
+                write!(f, "Refutable pattern in `let` binding, ")?;
+                fmt_missing(missing, f)
+            }
+        }
+    }
+}
+
+fn fmt_missing(missing: &[String], f: &mut fmt::Formatter) -> fmt::Result {
+    let shown = &missing[..missing.len().min(MAX_MISSING_PATTERNS)];
+    for (i, pattern) in shown.iter().enumerate() {
+        if i != 0 {
+            if i + 1 == shown.len() && missing.len() <= MAX_MISSING_PATTERNS {
+                write!(f, " and ")?;
+            } else {
+                write!(f, ", ")?;
+            }
+        }
+        write!(f, "`{}`", pattern)?;
+    }
+    if missing.len() > MAX_MISSING_PATTERNS {
+        write!(f, " and more")?;
+    }
+    write!(
+        f,
+        " {} not covered",
+        if missing.len() == 1 { "is" } else { "are" }
+    )
+}
+
+pub type ExhaustivenessErrors = Errors<Spanned<Error, BytePos>>;
+
+/// Checks every `match` expression and `let` binding in `expr`. Missing patterns are returned as
+/// errors while unreachable alternatives are added to `warnings`.
+///
+/// `expr` must have been successfully typechecked as the types of the constructors are used to
+/// determine which patterns that are missing.
+pub fn check_expr(
+    env: &dyn TypeEnv<Type = ArcType>,
+    expr: &SpannedExpr<Symbol>,
+    warnings: &mut Warnings,
+) -> Result<(), ExhaustivenessErrors> {
+    let mut checker = Checker {
+        env,
+        errors: Errors::new(),
+        warnings,
+    };
+    checker.visit_expr(expr);
+    if checker.errors.has_errors() {
+        Err(checker.errors)
+    } else {
+        Ok(())
+    }
+}
+
+/// A simplified pattern which only retains the information needed to determine which values it
+/// matches
+#[derive(Clone, Debug)]
+enum Pat {
+    Wildcard,
+    Constructor(Constructor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+#[derive(Clone, Debug)]
+enum Constructor {
+    /// A constructor of a variant type. `typ` is the type of the variant which is used to find
+    /// the other constructors of the variant.
+    Variant {
+        name: Symbol,
+        typ: ArcType,
+        arity: usize,
+    },
+    /// A record with the fields in `fields`. Fields which are not mentioned in a pattern are
+    /// matched by wildcards.
+    Record(Vec<Symbol>),
+    Tuple(usize),
+    /// An array with exactly `len` elements or, if `rest` is set, at least `len` elements
+    Array {
+        len: usize,
+        rest: bool,
+    },
+    Literal(Literal),
+}
+
+impl Constructor {
+    fn arity(&self) -> usize {
+        match self {
+            Constructor::Variant { arity, .. } => *arity,
+            Constructor::Record(fields) => fields.len(),
+            Constructor::Tuple(len) | Constructor::Array { len, .. } => *len,
+            Constructor::Literal(_) => 0,
+        }
+    }
+
+    fn same(&self, other: &Constructor) -> bool {
+        match (self, other) {
+            // Constructors brought into scope by destructuring a type have different symbols than the
+            // fields of the variant type so only the names are compared
+            (Constructor::Variant { name: l, .. }, Constructor::Variant { name: r, .. }) => {
+                l.declared_name() == r.declared_name()
+            }
+            (Constructor::Record(_), Constructor::Record(_)) => true,
+            (Constructor::Tuple(l), Constructor::Tuple(r)) => l == r,
+            (
+                Constructor::Array {
+                    len: l,
+                    rest: l_rest,
+                },
+                Constructor::Array {
+                    len: r,
+                    rest: r_rest,
+                },
+            ) => l == r && l_rest == r_rest,
+            (Constructor::Literal(l), Constructor::Literal(r)) => l == r,
+            _ => false,
+        }
+    }
+
+    /// Returns the arguments that the pattern `pattern_ctor args` matches on if the value were
+    /// constructed by `self`, or `None` if the pattern never matches such a value.
+    fn specialize_args(&self, pattern_ctor: &Constructor, args: &[Pat]) -> Option<Vec<Pat>> {
+        match (self, pattern_ctor) {
+            (
+                Constructor::Array { len, rest: false },
+                Constructor::Array {
+                    len: pattern_len,
+                    rest: false,
+                },
+            ) if len == pattern_len => Some(args.to_vec()),
+            // `[x, ..rest]` matches every array which has at least one element
+            (
+                Constructor::Array { len, .. },
+                Constructor::Array {
+                    len: pattern_len,
+                    rest: true,
+                },
+            ) if pattern_len <= len => Some(
+                args.iter()
+                    .cloned()
+                    .chain(wildcards(len - pattern_len))
+                    .collect(),
+            ),
+            (Constructor::Array { .. }, Constructor::Array { .. }) => None,
+            _ if self.same(pattern_ctor) => Some(args.to_vec()),
+            _ => None,
+        }
+    }
+}
+
+/// The constructors that a column of patterns needs to be checked against
+enum Signature {
+    /// Every constructor of the type
+    Complete(Vec<Constructor>),
+    /// Some constructors were not matched on by the column (if the type has infinitely many
+    /// constructors, such as `Int`, no examples of the missing constructors are given)
+    Incomplete(Vec<Constructor>),
+}
+
+fn wildcards(n: usize) -> impl Iterator<Item = Pat> {
+    iter::repeat(Pat::Wildcard).take(n)
+}
+
+/// Returns the rows that remain if the first column is known to be constructed by `ctor`, with the
+/// first column replaced by the arguments of `ctor`.
+fn specialize(row: &[Pat], ctor: &Constructor) -> Vec<Vec<Pat>> {
+    let (head, tail) = match row.split_first() {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    match head {
+        Pat::Wildcard => vec![wildcards(ctor.arity())
+            .chain(tail.iter().cloned())
+            .collect()],
+        Pat::Constructor(pattern_ctor, args) => ctor
+            .specialize_args(pattern_ctor, args)
+            .map(|args| args.into_iter().chain(tail.iter().cloned()).collect())
+            .into_iter()
+            .collect(),
+        Pat::Or(alternatives) => alternatives
+            .iter()
+            .flat_map(|alternative| {
+                let row: Vec<_> = iter::once(alternative.clone())
+                    .chain(tail.iter().cloned())
+                    .collect();
+                specialize(&row, ctor)
+            })
+            .collect(),
+    }
+}
+
+/// Returns the rows that remain if the first column is constructed by a constructor that is not
+/// matched on explicitly in the column
+fn default_rows(row: &[Pat]) -> Vec<Vec<Pat>> {
+    let (head, tail) = match row.split_first() {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    match head {
+        Pat::Wildcard => vec![tail.to_vec()],
+        Pat::Constructor(..) => Vec::new(),
+        Pat::Or(alternatives) => alternatives
+            .iter()
+            .flat_map(|alternative| {
+                let row: Vec<_> = iter::once(alternative.clone())
+                    .chain(tail.iter().cloned())
+                    .collect();
+                default_rows(&row)
+            })
+            .collect(),
+    }
+}
+
+fn head_constructors<'p>(pattern: &'p Pat, heads: &mut Vec<&'p Constructor>) {
+    match pattern {
+        Pat::Wildcard => (),
+        Pat::Constructor(ctor, _) => heads.push(ctor),
+        Pat::Or(alternatives) => {
+            for alternative in alternatives {
+                head_constructors(alternative, heads);
+            }
+        }
+    }
+}
+
+/// Rebuilds a witness for the specialized rows into a witness of the original rows
+fn unspecialize(ctor: &Constructor, witness: Vec<Pat>) -> Vec<Pat> {
+    let mut witness = witness.into_iter();
+    let args = witness.by_ref().take(ctor.arity()).collect();
+    iter::once(Pat::Constructor(ctor.clone(), args))
+        .chain(witness)
+        .collect()
+}
+
+struct Checker<'e> {
+    env: &'e dyn TypeEnv<Type = ArcType>,
+    errors: ExhaustivenessErrors,
+    warnings: &'e mut Warnings,
+}
+
+impl Checker<'_> {
+    fn check_match(&mut self, span: Span<BytePos>, alternatives: &[Alternative<Symbol>]) {
+        let mut rows = Vec::new();
+        for alternative in alternatives {
+            let pattern = self.lower(&alternative.pattern);
+            if self.usefulness(&rows, &[pattern.clone()], 1).is_empty() {
+                self.warnings.push(pos::spanned(
+                    alternative.pattern.span,
+                    Warning::UnreachablePattern,
+                ));
+            }
+            // A guard may reject the values which the pattern matches so guarded alternatives
+            // do not make any values unreachable
+            if alternative.guard.is_none() {
+                rows.push(vec![pattern]);
+            }
+        }
+
+        let missing = self.missing_patterns(&rows);
+        if !missing.is_empty() {
+            self.errors
+                .push(pos::spanned(span, Error::NonExhaustiveMatch { missing }));
+        }
+    }
+
+    fn check_let(&mut self, pattern: &SpannedPattern<Symbol>) {
+        if let Pattern::Ident(_) = pattern.value {
+            return;
+        }
+        let rows = vec![vec![self.lower(pattern)]];
+        let missing = self.missing_patterns(&rows);
+        if !missing.is_empty() {
+            self.errors
+                .push(pos::spanned(pattern.span, Error::RefutableLet { missing }));
+        }
+    }
+
+    fn missing_patterns(&self, rows: &[Vec<Pat>]) -> Vec<String> {
+        self.usefulness(rows, &[Pat::Wildcard], MAX_MISSING_PATTERNS + 1)
+            .into_iter()
+            .map(|witness| DisplayPat(&witness[0], false).to_string())
+            .collect()
+    }
+
+    fn lower(&self, pattern: &SpannedPattern<Symbol>) -> Pat {
+        match &pattern.value {
+            Pattern::As(_, pattern) => self.lower(pattern),
+            Pattern::Ident(_) | Pattern::Error => Pat::Wildcard,
+            Pattern::Literal(literal) => {
+                Pat::Constructor(Constructor::Literal(literal.clone()), Vec::new())
+            }
+            Pattern::Constructor(id, args) => Pat::Constructor(
+                Constructor::Variant {
+                    name: id.name.clone(),
+                    typ: ctor_return_type(&id.typ).clone(),
+                    arity: args.len(),
+                },
+                args.iter().map(|arg| self.lower(arg)).collect(),
+            ),
+            Pattern::Record { typ, fields, .. } => {
+                let typ = remove_aliases_cow(self.env, &mut NullInterner, typ);
+                let record_fields: Vec<_> = match **typ.remove_forall() {
+                    Type::Record(_) => typ
+                        .remove_forall()
+                        .row_iter()
+                        .map(|field| field.name.clone())
+                        .collect(),
+                    // Assume that the pattern matches everything if the type can't be resolved
+                    _ => return Pat::Wildcard,
+                };
+                let mut args: Vec<_> = wildcards(record_fields.len()).collect();
+                for (name, value) in ast::pattern_values(fields) {
+                    if let Some(value) = value {
+                        if let Some(i) = record_fields
+                            .iter()
+                            .position(|field| field.name_eq(&name.value))
+                        {
+                            args[i] = self.lower(value);
+                        }
+                    }
+                }
+                Pat::Constructor(Constructor::Record(record_fields), args)
+            }
+            Pattern::Tuple { elems, .. } => Pat::Constructor(
+                Constructor::Tuple(elems.len()),
+                elems.iter().map(|elem| self.lower(elem)).collect(),
+            ),
+            Pattern::Array { elems, rest, .. } => Pat::Constructor(
+                Constructor::Array {
+                    len: elems.len(),
+                    rest: rest.is_some(),
+                },
+                elems.iter().map(|elem| self.lower(elem)).collect(),
+            ),
+            Pattern::Or(alternatives) => Pat::Or(
+                alternatives
+                    .iter()
+                    .map(|alternative| self.lower(alternative))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns up to `limit` witnesses of values which are matched by `pattern` but not by any of
+    /// the `rows`. Each witness is a list of patterns, one for each column.
+    fn usefulness(&self, rows: &[Vec<Pat>], pattern: &[Pat], limit: usize) -> Vec<Vec<Pat>> {
+        let (head, tail) = match pattern.split_first() {
+            Some(x) => x,
+            None => {
+                return if rows.is_empty() {
+                    vec![Vec::new()]
+                } else {
+                    Vec::new()
+                };
+            }
+        };
+
+        let mut heads = Vec::new();
+        for row in rows {
+            head_constructors(&row[0], &mut heads);
+        }
+
+        let mut witnesses = Vec::new();
+        match head {
+            Pat::Or(alternatives) => {
+                for alternative in alternatives {
+                    if witnesses.len() >= limit {
+                        break;
+                    }
+                    let pattern: Vec<_> = iter::once(alternative.clone())
+                        .chain(tail.iter().cloned())
+                        .collect();
+                    witnesses.extend(self.usefulness(rows, &pattern, limit - witnesses.len()));
+                }
+            }
+            Pat::Constructor(ctor, _) => {
+                for ctor in split_constructor(ctor, &heads) {
+                    if witnesses.len() >= limit {
+                        break;
+                    }
+                    if let Some(pattern) = specialize(pattern, &ctor).pop() {
+                        witnesses.extend(self.specialized_usefulness(
+                            rows,
+                            &ctor,
+                            &pattern,
+                            limit - witnesses.len(),
+                        ));
+                    }
+                }
+            }
+            Pat::Wildcard => match self.signature(&heads) {
+                Signature::Complete(ctors) => {
+                    for ctor in ctors {
+                        if witnesses.len() >= limit {
+                            break;
+                        }
+                        let pattern: Vec<_> = wildcards(ctor.arity())
+                            .chain(tail.iter().cloned())
+                            .collect();
+                        witnesses.extend(self.specialized_usefulness(
+                            rows,
+                            &ctor,
+                            &pattern,
+                            limit - witnesses.len(),
+                        ));
+                    }
+                }
+                Signature::Incomplete(missing) => {
+                    let rows: Vec<_> = rows.iter().flat_map(|row| default_rows(row)).collect();
+                    for witness in self.usefulness(&rows, tail, limit) {
+                        if missing.is_empty() {
+                            witnesses.push(iter::once(Pat::Wildcard).chain(witness).collect());
+                        } else {
+                            witnesses.extend(missing.iter().map(|ctor| {
+                                iter::once(Pat::Constructor(
+                                    ctor.clone(),
+                                    wildcards(ctor.arity()).collect(),
+                                ))
+                                .chain(witness.iter().cloned())
+                                .collect()
+                            }));
+                        }
+                    }
+                    witnesses.truncate(limit);
+                }
+            },
+        }
+        witnesses
+    }
+
+    fn specialized_usefulness(
+        &self,
+        rows: &[Vec<Pat>],
+        ctor: &Constructor,
+        pattern: &[Pat],
+        limit: usize,
+    ) -> Vec<Vec<Pat>> {
+        let rows: Vec<_> = rows.iter().flat_map(|row| specialize(row, ctor)).collect();
+        self.usefulness(&rows, pattern, limit)
+            .into_iter()
+            .map(|witness| unspecialize(ctor, witness))
+            .collect()
+    }
+
+    fn signature(&self, heads: &[&Constructor]) -> Signature {
+        let first = match heads.first() {
+            Some(first) => *first,
+            None => return Signature::Incomplete(Vec::new()),
+        };
+        match first {
+            Constructor::Variant { typ, .. } => match self.variant_constructors(typ) {
+                Some((ctors, closed)) => {
+                    let missing: Vec<_> = ctors
+                        .iter()
+                        .filter(|ctor| heads.iter().all(|head| !head.same(ctor)))
+                        .cloned()
+                        .collect();
+                    if missing.is_empty() && closed {
+                        Signature::Complete(ctors)
+                    } else {
+                        Signature::Incomplete(missing)
+                    }
+                }
+                // Assume that the matched constructors are the only ones if the type can't be
+                // resolved
+                None => {
+                    let mut ctors: Vec<Constructor> = Vec::new();
+                    for head in heads {
+                        if ctors.iter().all(|ctor| !ctor.same(head)) {
+                            ctors.push((*head).clone());
+                        }
+                    }
+                    Signature::Complete(ctors)
+                }
+            },
+            Constructor::Record(_) | Constructor::Tuple(_) => {
+                Signature::Complete(vec![first.clone()])
+            }
+            Constructor::Array { .. } => Signature::Complete(array_lengths(heads)),
+            Constructor::Literal(_) => Signature::Incomplete(Vec::new()),
+        }
+    }
+
+    /// Returns the constructors of the variant type `typ` and whether the variant is closed (it
+    /// can't contain any other constructors)
+    fn variant_constructors(&self, typ: &ArcType) -> Option<(Vec<Constructor>, bool)> {
+        let typ = remove_aliases_cow(self.env, &mut NullInterner, typ);
+        let typ = typ.remove_forall();
+        match **typ {
+            Type::Variant(_) => (),
+            _ => return None,
+        }
+        let mut iter = typ.row_iter();
+        let ctors = iter
+            .by_ref()
+            .map(|field| Constructor::Variant {
+                name: field.name.clone(),
+                typ: typ.clone(),
+                arity: arg_iter(field.typ.remove_forall()).count(),
+            })
+            .collect();
+        let closed = match **iter.current_type() {
+            Type::Generic(_) | Type::Skolem(_) => false,
+            _ => true,
+        };
+        Some((ctors, closed))
+    }
+}
+
+/// Arrays have infinitely many lengths but every array which is longer than all the array
+/// patterns in `heads` is matched by the same patterns. Returns the constructors for all the
+/// lengths up to the longest pattern, plus one constructor for all arrays which are longer.
+fn array_lengths(heads: &[&Constructor]) -> Vec<Constructor> {
+    let max_len = heads
+        .iter()
+        .filter_map(|head| match head {
+            Constructor::Array { len, .. } => Some(*len),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    (0..=max_len)
+        .map(|len| Constructor::Array { len, rest: false })
+        .chain(iter::once(Constructor::Array {
+            len: max_len + 1,
+            rest: true,
+        }))
+        .collect()
+}
+
+/// Splits `ctor` into the constructors that must be checked separately. This is only necessary for
+/// array patterns with a rest pattern as they match arrays of several different lengths.
+fn split_constructor(ctor: &Constructor, heads: &[&Constructor]) -> Vec<Constructor> {
+    match ctor {
+        Constructor::Array { .. } => {
+            let heads: Vec<_> = heads.iter().cloned().chain(iter::once(ctor)).collect();
+            array_lengths(&heads)
+                .into_iter()
+                .filter(|array| array.specialize_args(ctor, &[]).is_some())
+                .collect()
+        }
+        _ => vec![ctor.clone()],
+    }
+}
+
+fn ctor_return_type(typ: &ArcType) -> &ArcType {
+    match &**typ {
+        Type::Forall(_, typ) | Type::Function(_, _, typ) => ctor_return_type(typ),
+        _ => typ,
+    }
+}
+
+struct DisplayPat<'a>(&'a Pat, bool);
+
+impl fmt::Display for DisplayPat<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let DisplayPat(pattern, nested) = *self;
+        match pattern {
+            Pat::Wildcard => write!(f, "_"),
+            Pat::Or(alternatives) => {
+                if nested {
+                    write!(f, "(")?;
+                }
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", DisplayPat(alternative, false))?;
+                }
+                if nested {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Pat::Constructor(ctor, args) => match ctor {
+                Constructor::Variant { name, .. } => {
+                    if nested && !args.is_empty() {
+                        write!(f, "(")?;
+                    }
+                    write!(f, "{}", name.declared_name())?;
+                    for arg in args {
+                        write!(f, " {}", DisplayPat(arg, true))?;
+                    }
+                    if nested && !args.is_empty() {
+                        write!(f, ")")?;
+                    }
+                    Ok(())
+                }
+                Constructor::Record(fields) => {
+                    let mut fields = fields
+                        .iter()
+                        .zip(args)
+                        .filter(|(_, arg)| match arg {
+                            Pat::Wildcard => false,
+                            _ => true,
+                        })
+                        .peekable();
+                    if fields.peek().is_none() {
+                        return write!(f, "_");
+                    }
+                    write!(f, "{{ ")?;
+                    for (i, (field, arg)) in fields.enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{} = {}", field.declared_name(), DisplayPat(arg, false))?;
+                    }
+                    write!(f, " }}")
+                }
+                Constructor::Tuple(_) => {
+                    write!(f, "(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", DisplayPat(arg, false))?;
+                    }
+                    write!(f, ")")
+                }
+                Constructor::Array { rest, .. } => {
+                    write!(f, "[")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", DisplayPat(arg, false))?;
+                    }
+                    if *rest {
+                        if !args.is_empty() {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "..")?;
+                    }
+                    write!(f, "]")
+                }
+                Constructor::Literal(literal) => match literal {
+                    Literal::Byte(b) => write!(f, "{}b", b),
+                    Literal::Int(i) => write!(f, "{}", i),
+                    Literal::SizedInt(i, typ) => write!(f, "{}", typ.format_int(*i)),
+                    Literal::Float(x) => write!(f, "{}", x),
+                    Literal::String(s) => write!(f, "{:?}", s),
+                    Literal::Char(c) => write!(f, "{:?}", c),
+                },
+            },
+        }
+    }
+}
+
+impl<'a> Visitor<'a, '_> for Checker<'_> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<Symbol>) {
+        match &expr.value {
+            Expr::Match(scrutinee, alternatives) => self.check_match(scrutinee.span, alternatives),
+            Expr::LetBindings(bindings, _) => {
+                for binding in bindings.iter() {
+                    self.check_let(&binding.name);
+                }
+            }
+            _ => (),
+        }
+        ast::walk_expr(self, expr);
+    }
+}
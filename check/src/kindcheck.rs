@@ -195,6 +195,12 @@ impl<'a> KindCheck<'a> {
             | BuiltinType::Byte
             | BuiltinType::Char
             | BuiltinType::Int
+            | BuiltinType::Int8
+            | BuiltinType::Int16
+            | BuiltinType::Int32
+            | BuiltinType::UInt16
+            | BuiltinType::UInt32
+            | BuiltinType::UInt64
             | BuiltinType::Float => self.type_kind(),
             BuiltinType::Array => self.function1_kind(),
             BuiltinType::Function => self.function2_kind(),
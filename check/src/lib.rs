@@ -17,7 +17,9 @@ extern crate gluon_base as base;
 #[macro_use]
 extern crate gluon_codegen;
 
+pub mod exhaustiveness;
 pub mod kindcheck;
+pub mod lint;
 pub mod metadata;
 mod recursion_check;
 pub mod rename;
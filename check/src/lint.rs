@@ -0,0 +1,477 @@
+//! Lints which are run on expressions which have passed typechecking.
+//!
+//! Every lint can be disabled for a `let` or `type` binding (and everything inside of it) by adding
+//! an `#[allow(<lint name>)]` attribute to it.
+use std::{fmt, mem};
+
+use codespan_reporting::Diagnostic;
+
+use crate::base::{
+    ast::{
+        self, Argument, AstType, Do, Expr, Pattern, PatternField, SpannedAstType, SpannedExpr,
+        SpannedIdent, SpannedPattern, TypeBinding, ValueBinding, Visitor,
+    },
+    error::{AsDiagnostic, Errors},
+    fnv::FnvSet,
+    metadata::BaseMetadata,
+    pos::{self, BytePos, Span, Spanned},
+    scoped_map::ScopedMap,
+    symbol::Symbol,
+    types::{row_iter, Type},
+};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Lint {
+    UnusedBindings,
+    UnusedImports,
+    ShadowedBindings,
+    UnusedTypeParameters,
+    UnreachablePatterns,
+    UnknownLints,
+}
+
+impl Lint {
+    /// The name used to refer to the lint in `#[allow(..)]` attributes
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedBindings => "unused_bindings",
+            Lint::UnusedImports => "unused_imports",
+            Lint::ShadowedBindings => "shadowed_bindings",
+            Lint::UnusedTypeParameters => "unused_type_parameters",
+            Lint::UnreachablePatterns => "unreachable_patterns",
+            Lint::UnknownLints => "unknown_lints",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Some(match name {
+            "unused_bindings" => Lint::UnusedBindings,
+            "unused_imports" => Lint::UnusedImports,
+            "shadowed_bindings" => Lint::ShadowedBindings,
+            "unused_type_parameters" => Lint::UnusedTypeParameters,
+            "unreachable_patterns" => Lint::UnreachablePatterns,
+            "unknown_lints" => Lint::UnknownLints,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum Warning {
+    /// Every value matched by the alternative is matched by the alternatives before it
+    UnreachablePattern,
+    /// A `let` binding which is never referred to
+    UnusedBinding(Symbol),
+    /// A binding to (a field of) an `import!` which is never referred to
+    UnusedImport(Symbol),
+    /// A `let` binding which hides an earlier binding with the same name
+    ShadowedBinding(Symbol),
+    /// A parameter of a type binding which is not used in the definition of the type
+    UnusedTypeParameter(Symbol),
+    /// An `#[allow(..)]` attribute refers to a lint which does not exist
+    UnknownLint(String),
+}
+
+impl Warning {
+    /// Returns the lint which emits this warning
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::UnreachablePattern => Lint::UnreachablePatterns,
+            Warning::UnusedBinding(_) => Lint::UnusedBindings,
+            Warning::UnusedImport(_) => Lint::UnusedImports,
+            Warning::ShadowedBinding(_) => Lint::ShadowedBindings,
+            Warning::UnusedTypeParameter(_) => Lint::UnusedTypeParameters,
+            Warning::UnknownLint(_) => Lint::UnknownLints,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnreachablePattern => write!(
+                f,
+                "Unreachable pattern, every value it matches is matched by an earlier alternative"
+            ),
+            Warning::UnusedBinding(id) => write!(f, "Unused binding `{}`", id.declared_name()),
+            Warning::UnusedImport(id) => write!(f, "Unused import `{}`", id.declared_name()),
+            Warning::ShadowedBinding(id) => write!(
+                f,
+                "`{}` shadows an earlier binding with the same name",
+                id.declared_name()
+            ),
+            Warning::UnusedTypeParameter(id) => {
+                write!(f, "Unused type parameter `{}`", id.declared_name())
+            }
+            Warning::UnknownLint(name) => write!(f, "Unknown lint `{}`", name),
+        }
+    }
+}
+
+impl AsDiagnostic for Warning {
+    fn as_diagnostic(&self) -> Diagnostic {
+        Diagnostic::new_warning(self.to_string()).with_code(self.lint().name())
+    }
+}
+
+pub type Warnings = Errors<Spanned<Warning, BytePos>>;
+
+/// Runs every lint on `expr` and adds the warnings that were found to `warnings`.
+///
+/// Only bindings inside `source_span` are linted so that code which is inserted from other
+/// sources, such as the implicit prelude, do not cause any warnings. Warnings which are already in
+/// `warnings` are removed if they are inside a binding which allows them.
+pub fn lint_expr(expr: &SpannedExpr<Symbol>, source_span: Span<BytePos>, warnings: &mut Warnings) {
+    let mut linter = Linter {
+        source_span,
+        used: FnvSet::default(),
+        unused_candidates: Vec::new(),
+        scope: ScopedMap::new(),
+        derived: Vec::new(),
+        allowed: Vec::new(),
+        warnings,
+    };
+    linter.visit_expr(expr);
+
+    let Linter {
+        used,
+        unused_candidates,
+        allowed,
+        warnings,
+        ..
+    } = linter;
+
+    warnings.extend(
+        unused_candidates
+            .into_iter()
+            .filter(|(id, _)| !used.contains(&id.value))
+            .map(|(id, lint)| {
+                let warning = match lint {
+                    Lint::UnusedImports => Warning::UnusedImport(id.value),
+                    _ => Warning::UnusedBinding(id.value),
+                };
+                pos::spanned(id.span, warning)
+            }),
+    );
+
+    let mut filtered: Vec<_> = mem::take(warnings)
+        .into_iter()
+        .filter(|warning| {
+            let lint = warning.value.lint();
+            !allowed
+                .iter()
+                .any(|&(span, allowed)| allowed == lint && span.contains(warning.span))
+        })
+        .collect();
+    filtered.sort_by_key(|warning| warning.span.start());
+    *warnings = filtered.into();
+}
+
+struct Linter<'a, 'w> {
+    source_span: Span<BytePos>,
+    /// Every variable which has been referred to
+    used: FnvSet<Symbol>,
+    /// `let` bound variables which are reported as unused unless they appear in `used`
+    unused_candidates: Vec<(Spanned<Symbol, BytePos>, Lint)>,
+    /// The names which are in scope, used to detect shadowing
+    scope: ScopedMap<&'a str, ()>,
+    /// `#[derive(..)]` generates bindings which reuse the span of the type name
+    derived: Vec<Span<BytePos>>,
+    allowed: Vec<(Span<BytePos>, Lint)>,
+    warnings: &'w mut Warnings,
+}
+
+impl<'a, 'w> Linter<'a, 'w> {
+    fn allow(&mut self, metadata: &BaseMetadata, span: Span<BytePos>) {
+        for attribute in metadata.attributes().filter(|attr| attr.name == "allow") {
+            let names = attribute
+                .arguments
+                .iter()
+                .flat_map(|arguments| arguments.split(','))
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty());
+            for name in names {
+                match Lint::from_name(name) {
+                    Some(lint) => self.allowed.push((span, lint)),
+                    None => self
+                        .warnings
+                        .push(pos::spanned(span, Warning::UnknownLint(name.into()))),
+                }
+            }
+        }
+    }
+
+    /// Brings the variables bound by `pattern` into scope. `unused_lint` is set for `let` bindings
+    /// which should be checked for being unused or shadowing another binding.
+    fn bind_pattern(&mut self, pattern: &'a SpannedPattern<Symbol>, unused_lint: Option<Lint>) {
+        let mut variables = Vec::new();
+        pattern_variables(pattern, &mut variables);
+        for (span, id) in variables {
+            self.bind(span, id, unused_lint);
+        }
+    }
+
+    fn bind_args(&mut self, args: &'a [Argument<SpannedIdent<Symbol>>]) {
+        for arg in args {
+            self.bind(arg.name.span, &arg.name.value.name, None);
+        }
+    }
+
+    fn bind(&mut self, span: Span<BytePos>, id: &'a Symbol, unused_lint: Option<Lint>) {
+        let name = id.declared_name();
+        if !self.source_span.contains(span) || name.starts_with('_') {
+            return;
+        }
+        if let Some(lint) = unused_lint {
+            if self.derived.contains(&span) {
+                return;
+            }
+            if self.scope.contains_key(name) {
+                self.warnings
+                    .push(pos::spanned(span, Warning::ShadowedBinding(id.clone())));
+            }
+            self.unused_candidates
+                .push((pos::spanned(span, id.clone()), lint));
+        }
+        self.scope.insert(name, ());
+    }
+
+    fn visit_value_binding(&mut self, bind: &'a ValueBinding<Symbol>) {
+        self.scope.enter_scope();
+        self.bind_args(&bind.args);
+        self.visit_expr(&bind.expr);
+        if let Some(typ) = &bind.typ {
+            self.visit_ast_type(typ.as_ref());
+        }
+        self.scope.exit_scope();
+    }
+
+    fn check_type_parameters(&mut self, bind: &TypeBinding<Symbol>) {
+        if !self.source_span.contains(bind.name.span) {
+            return;
+        }
+        let alias = &bind.alias.value;
+        let typ = alias.unresolved_type();
+        // The parameters of a GADT are determined by the return types of its constructors
+        if is_gadt(typ) {
+            return;
+        }
+
+        let mut generics = Generics(FnvSet::default());
+        generics.visit_ast_type(typ.as_ref());
+
+        for param in alias.params() {
+            if !param.id.declared_name().starts_with('_') && !generics.0.contains(&param.id) {
+                self.warnings.push(pos::spanned(
+                    bind.name.span,
+                    Warning::UnusedTypeParameter(param.id.clone()),
+                ));
+            }
+        }
+    }
+}
+
+impl<'a, 'ast, 'w> Visitor<'a, 'ast> for Linter<'a, 'w> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<'ast, Symbol>) {
+        match &expr.value {
+            Expr::Ident(id) => {
+                self.used.insert(id.name.clone());
+            }
+            Expr::Infix { op, .. } => {
+                self.used.insert(op.value.name.clone());
+                ast::walk_expr(self, expr);
+            }
+            Expr::Record { exprs, .. } => {
+                for field in &**exprs {
+                    if field.value.is_none() {
+                        self.used.insert(field.name.value.clone());
+                    }
+                }
+                ast::walk_expr(self, expr);
+            }
+            Expr::LetBindings(bindings, body) => {
+                self.scope.enter_scope();
+
+                for bind in bindings.iter() {
+                    self.allow(&bind.metadata, bind.span());
+                }
+
+                if bindings.is_recursive() {
+                    for bind in bindings.iter() {
+                        self.bind_pattern(&bind.name, Some(binding_lint(&bind.expr)));
+                    }
+                    for bind in bindings.iter() {
+                        self.visit_value_binding(bind);
+                    }
+                } else {
+                    for bind in bindings.iter() {
+                        self.visit_value_binding(bind);
+                        self.bind_pattern(&bind.name, Some(binding_lint(&bind.expr)));
+                    }
+                }
+
+                self.visit_expr(body);
+                self.scope.exit_scope();
+            }
+            Expr::TypeBindings(bindings, body) => {
+                for bind in &**bindings {
+                    self.allow(&bind.metadata, bind.span());
+                    if bind.metadata.get_attribute("derive").is_some() {
+                        self.derived.push(bind.name.span);
+                    }
+                    self.check_type_parameters(bind);
+                    self.visit_alias(&bind.alias);
+                }
+                self.visit_expr(body);
+            }
+            Expr::Lambda(lambda) => {
+                self.scope.enter_scope();
+                self.bind_args(&lambda.args);
+                self.visit_expr(&lambda.body);
+                self.scope.exit_scope();
+            }
+            Expr::Match(scrutinee, alts) => {
+                self.visit_expr(scrutinee);
+                for alt in &**alts {
+                    self.scope.enter_scope();
+                    self.bind_pattern(&alt.pattern, None);
+                    if let Some(guard) = &alt.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&alt.expr);
+                    self.scope.exit_scope();
+                }
+            }
+            Expr::Do(Do {
+                id,
+                bound,
+                body,
+                flat_map_id,
+            }) => {
+                self.visit_expr(bound);
+                if let Some(flat_map_id) = flat_map_id {
+                    self.visit_expr(flat_map_id);
+                }
+                self.scope.enter_scope();
+                if let Some(id) = id {
+                    self.bind_pattern(id, None);
+                }
+                self.visit_expr(body);
+                self.scope.exit_scope();
+            }
+            _ => ast::walk_expr(self, expr),
+        }
+    }
+
+    fn visit_ast_type(&mut self, typ: &'a SpannedAstType<'ast, Symbol>) {
+        // The first identifier of a projection refers to a variable
+        if let Type::Projection(ids) = &typ.value {
+            self.used.insert(ids[0].clone());
+        }
+        ast::walk_ast_type(self, typ);
+    }
+}
+
+/// Bindings of an `import!` are reported as unused imports instead of unused bindings
+fn binding_lint(expr: &SpannedExpr<Symbol>) -> Lint {
+    match &expr.value {
+        Expr::MacroExpansion { replacement, .. } => binding_lint(replacement),
+        // `import!` expands to the global variable which contains the module
+        Expr::Ident(id) if id.name.is_global() => Lint::UnusedImports,
+        _ => Lint::UnusedBindings,
+    }
+}
+
+fn pattern_variables<'a>(
+    pattern: &'a SpannedPattern<Symbol>,
+    variables: &mut Vec<(Span<BytePos>, &'a Symbol)>,
+) {
+    match &pattern.value {
+        Pattern::Ident(id) => variables.push((pattern.span, &id.name)),
+        Pattern::As(id, pattern) => {
+            variables.push((id.span, &id.value));
+            pattern_variables(pattern, variables);
+        }
+        Pattern::Record { fields, .. } => {
+            for field in &**fields {
+                match field {
+                    PatternField::Value {
+                        value: Some(pattern),
+                        ..
+                    } => pattern_variables(pattern, variables),
+                    PatternField::Value { name, value: None } => {
+                        variables.push((name.span, &name.value))
+                    }
+                    PatternField::Type { .. } => (),
+                }
+            }
+        }
+        Pattern::Tuple { elems, .. } => {
+            for elem in &**elems {
+                pattern_variables(elem, variables);
+            }
+        }
+        Pattern::Constructor(_, args) => {
+            for arg in &**args {
+                pattern_variables(arg, variables);
+            }
+        }
+        Pattern::Array { elems, rest, .. } => {
+            for elem in &**elems {
+                pattern_variables(elem, variables);
+            }
+            if let Some(rest) = rest {
+                pattern_variables(rest, variables);
+            }
+        }
+        // Every alternative binds the same variables
+        Pattern::Or(alternatives) => {
+            if let Some(first) = alternatives.first() {
+                pattern_variables(first, variables);
+            }
+        }
+        Pattern::Literal(_) | Pattern::Error => (),
+    }
+}
+
+fn is_gadt(typ: &AstType<Symbol>) -> bool {
+    let typ = match &**typ {
+        Type::Forall(_, typ) => typ,
+        _ => typ,
+    };
+    match &**typ {
+        Type::Variant(row) => row_iter(row).any(|field| {
+            let mut typ = &field.typ;
+            while let Type::Function(_, _, ret) = &**typ {
+                typ = ret;
+            }
+            match **typ {
+                Type::Opaque => false,
+                _ => true,
+            }
+        }),
+        _ => false,
+    }
+}
+
+/// Collects every type variable which is referred to in a type
+struct Generics(FnvSet<Symbol>);
+
+impl<'a, 'ast> Visitor<'a, 'ast> for Generics {
+    type Ident = Symbol;
+
+    fn visit_ast_type(&mut self, typ: &'a SpannedAstType<'ast, Symbol>) {
+        if let Type::Generic(generic) = &typ.value {
+            self.0.insert(generic.id.clone());
+        }
+        ast::walk_ast_type(self, typ);
+    }
+}
@@ -171,6 +171,8 @@ pub fn metadata(
                 Pattern::Constructor(..)
                 | Pattern::Tuple { .. }
                 | Pattern::Record { .. }
+                | Pattern::Array { .. }
+                | Pattern::Or(..)
                 | Pattern::Literal(_)
                 | Pattern::Error => self.new_pattern(metadata, &bind.name),
             }
@@ -228,7 +230,13 @@ pub fn metadata(
                     self.stack_var(id.value.clone(), metadata.clone());
                     self.new_pattern(metadata, pat);
                 }
+                Pattern::Or(ref alternatives) => {
+                    for alternative in &**alternatives {
+                        self.new_pattern(metadata.clone(), alternative);
+                    }
+                }
                 Pattern::Tuple { .. }
+                | Pattern::Array { .. }
                 | Pattern::Constructor(..)
                 | Pattern::Literal(_)
                 | Pattern::Error => (),
@@ -40,6 +40,16 @@ pub fn rename<'s, 'ast>(
 
     impl<'a, 'b, 's, 'ast> RenameVisitor<'a, 'b, 's, 'ast> {
         fn new_pattern(&mut self, pattern: &mut ast::SpannedPattern<Symbol>) {
+            self.new_pattern_(pattern, false)
+        }
+
+        /// If `reuse_bindings` is true then the variables in `pattern` refer to the variables
+        /// already bound by an earlier alternative of an or pattern
+        fn new_pattern_(
+            &mut self,
+            pattern: &mut ast::SpannedPattern<Symbol>,
+            reuse_bindings: bool,
+        ) {
             match pattern.value {
                 Pattern::Record {
                     ref mut fields,
@@ -48,42 +58,75 @@ pub fn rename<'s, 'ast>(
                 } => {
                     for (name, value) in ast::pattern_values_mut(fields) {
                         match value {
-                            Some(pat) => self.new_pattern(pat),
+                            Some(pat) => self.new_pattern_(pat, reuse_bindings),
                             None => {
                                 let id = name.value.clone();
-                                name.value = self.stack_var(id, pattern.span);
+                                name.value = self.pattern_var(reuse_bindings, id, pattern.span);
                             }
                         }
                     }
                     if let Some(ref mut implicit_import) = *implicit_import {
-                        let new_name =
-                            self.stack_var(implicit_import.value.clone(), implicit_import.span);
+                        let new_name = self.pattern_var(
+                            reuse_bindings,
+                            implicit_import.value.clone(),
+                            implicit_import.span,
+                        );
                         implicit_import.value = new_name;
                     }
                 }
                 Pattern::Ident(ref mut id) => {
-                    let new_name = self.stack_var(id.name.clone(), pattern.span);
+                    let new_name = self.pattern_var(reuse_bindings, id.name.clone(), pattern.span);
                     id.name = new_name;
                 }
                 Pattern::As(ref mut id, ref mut pat) => {
-                    let new_name = self.stack_var(id.value.clone(), pattern.span);
+                    let new_name = self.pattern_var(reuse_bindings, id.value.clone(), pattern.span);
                     id.value = new_name;
-                    self.new_pattern(pat)
+                    self.new_pattern_(pat, reuse_bindings)
                 }
                 Pattern::Tuple { ref mut elems, .. } => {
                     for elem in &mut **elems {
-                        self.new_pattern(elem);
+                        self.new_pattern_(elem, reuse_bindings);
                     }
                 }
                 Pattern::Constructor(_, ref mut args) => {
                     for arg in &mut **args {
-                        self.new_pattern(arg);
+                        self.new_pattern_(arg, reuse_bindings);
+                    }
+                }
+                Pattern::Array {
+                    ref mut elems,
+                    ref mut rest,
+                    ..
+                } => {
+                    for elem in &mut **elems {
+                        self.new_pattern_(elem, reuse_bindings);
+                    }
+                    if let Some(rest) = rest {
+                        self.new_pattern_(rest, reuse_bindings);
+                    }
+                }
+                Pattern::Or(ref mut alternatives) => {
+                    let (first, rest) = alternatives
+                        .split_first_mut()
+                        .expect("Or pattern without alternatives");
+                    self.new_pattern_(first, reuse_bindings);
+                    for alternative in rest {
+                        self.new_pattern_(alternative, true);
                     }
                 }
                 Pattern::Literal(_) | Pattern::Error => (),
             }
         }
 
+        fn pattern_var(&mut self, reuse_bindings: bool, id: Symbol, span: Span<BytePos>) -> Symbol {
+            if reuse_bindings && id.declared_name() != "_" {
+                if let Some(new_id) = self.rename(&id) {
+                    return new_id;
+                }
+            }
+            self.stack_var(id, span)
+        }
+
         // Renames the symbol to be unique in this module
         fn stack_var(&mut self, id: Symbol, span: Span<BytePos>) -> Symbol {
             let mut location = self
@@ -191,6 +234,9 @@ pub fn rename<'s, 'ast>(
                     for alt in &mut **alts {
                         self.env.stack.enter_scope();
                         self.new_pattern(&mut alt.pattern);
+                        if let Some(ref mut guard) = alt.guard {
+                            self.visit_expr(guard);
+                        }
                         self.visit_expr(&mut alt.expr);
                         self.env.stack.exit_scope();
                     }
@@ -9,9 +9,9 @@ use std::{
 
 use crate::base::{
     ast::{
-        self, Argument, AstType, DisplayEnv, Do, Expr, IdentEnv, KindedIdent, Literal, MutVisitor,
-        Pattern, PatternField, SpannedExpr, SpannedIdent, SpannedPattern, TypeBinding, Typed,
-        TypedIdent, ValueBinding, ValueBindings,
+        self, Argument, AstType, DisplayEnv, Do, Expr, IdentEnv, Interpolation, InterpolationPart,
+        KindedIdent, Literal, MutVisitor, Pattern, PatternField, SpannedExpr, SpannedIdent,
+        SpannedPattern, TypeBinding, Typed, TypedIdent, ValueBinding, ValueBindings,
     },
     error::Errors,
     fnv::{FnvMap, FnvSet},
@@ -31,6 +31,7 @@ use crate::base::{
 use crate::{
     implicits,
     kindcheck::KindCheck,
+    lint::Warnings,
     substitution::{self, Substitution},
     typ::RcType,
     unify, unify_type, TypecheckEnv,
@@ -131,6 +132,7 @@ pub struct Typecheck<'a, 'ast> {
     pub(crate) subs: Substitution<RcType>,
     named_variables: FnvMap<Symbol, RcType>,
     pub(crate) errors: Errors<SpannedTypeError<Symbol, RcType<Symbol>>>,
+    warnings: Warnings,
     /// Type variables `let test: a -> b` (`a` and `b`)
     kind_cache: KindCache,
 
@@ -173,6 +175,7 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
             symbols: symbols,
             named_variables: FnvMap::default(),
             errors: Errors::new(),
+            warnings: Errors::new(),
             kind_cache: interner.kind_cache.clone(),
             implicit_resolver: crate::implicits::ImplicitResolver::new(environment, metadata),
             unbound_variables: ScopedMap::new(),
@@ -182,6 +185,11 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
         }
     }
 
+    /// Takes the warnings that were found by the typechecker
+    pub fn take_warnings(&mut self) -> Warnings {
+        mem::replace(&mut self.warnings, Errors::new())
+    }
+
     pub(crate) fn error<E>(&mut self, span: Span<BytePos>, error: E) -> RcType
     where
         E: Into<HelpError<Symbol, RcType>>,
@@ -395,10 +403,12 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                 | UndefinedType(_)
                 | DuplicateTypeDefinition(_)
                 | DuplicateField(_)
+                | OrPatternBinding(_)
                 | UndefinedRecord { .. }
                 | EmptyCase
                 | KindError(_)
                 | RecursionCheck(_)
+                | Exhaustiveness(_)
                 | Message(_) => (),
                 NotAFunction(ref mut typ)
                 | UndefinedField(ref mut typ, _)
@@ -571,6 +581,18 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
             }
         }
 
+        // Exhaustiveness depends on the types of the patterns so only check it if typechecking
+        // succeeded
+        if !self.errors.has_errors() {
+            let env = &self.environment.environment;
+            if let Err(err) = crate::exhaustiveness::check_expr(env, expr, &mut self.warnings) {
+                self.errors.extend(
+                    err.into_iter()
+                        .map(|err| pos::spanned(err.span, TypeError::from(err.value).into())),
+                );
+            }
+        }
+
         if self.errors.has_errors() {
             let mut errors = mem::replace(&mut self.errors, Errors::new());
             let l = errors.len();
@@ -689,12 +711,57 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                 ModType::rigid(match *lit {
                     Literal::Int(_) => self.subs.int(),
                     Literal::Byte(_) => self.subs.byte(),
+                    Literal::SizedInt(_, typ) => self.subs.builtin_type(typ),
                     Literal::Float(_) => self.subs.float(),
                     Literal::String(_) => self.subs.string(),
                     Literal::Char(_) => self.subs.char(),
                 }),
                 Vec::new(),
             )),
+            Expr::Interpolation(Interpolation {
+                ref mut parts,
+                ref mut append_id,
+            }) => {
+                let string = self.subs.string();
+                let append_type = self
+                    .subs
+                    .function(vec![string.clone(), string.clone()], string.clone());
+                self.typecheck(
+                    append_id
+                        .as_mut()
+                        .expect("append inserted during macro expansion"),
+                    ModType::rigid(&append_type),
+                );
+                for part in &mut **parts {
+                    if let InterpolationPart::Expr {
+                        ref mut expr,
+                        ref mut show_id,
+                    } = *part
+                    {
+                        let expr_type = self.infer_expr(expr);
+                        let resolved_type =
+                            self.remove_aliases(self.subs.zonk(&expr_type.concrete));
+                        if let Type::Builtin(types::BuiltinType::String) = *resolved_type {
+                            // `show` would add quotes so strings are spliced in directly
+                            *show_id = None;
+                            continue;
+                        }
+                        match show_id {
+                            Some(show_id) => {
+                                // Checking `show` against `T -> String` resolves its `Show T`
+                                // implicit argument with the span of the interpolated expression
+                                let show_type =
+                                    self.subs.function(Some(expr_type.concrete), string.clone());
+                                self.typecheck(show_id, ModType::wobbly(&show_type));
+                            }
+                            None => {
+                                self.unify_span(expr.span, &string, expr_type.concrete);
+                            }
+                        }
+                    }
+                }
+                Ok((ModType::rigid(string), Vec::new()))
+            }
             Expr::App {
                 ref mut func,
                 ref mut implicit_args,
@@ -836,6 +903,12 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                         scrutinee_type.concrete.clone(),
                     );
 
+                    if let Some(ref mut guard) = alt.guard {
+                        let bool_type = self.bool();
+                        let guard_type = self.infer_expr(guard);
+                        self.unify_span(expr_check_span(guard), &bool_type, guard_type.concrete);
+                    }
+
                     let mut alt_type = self
                         .typecheck_opt(&mut alt.expr, expected_type.as_ref().map(|t| t.as_ref()));
                     alt_type.concrete = self.instantiate_generics(&alt_type);
@@ -853,11 +926,12 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                     self.exit_scope();
 
                     // The variant we matched on will not appear in any followup bindings so remove
-                    // this variant from the type we are matching on
+                    // this variant from the type we are matching on (unless the alternative is
+                    // guarded, as the variant may then fall through to the next alternative)
                     //
                     // TODO Make this more general so it can error when not matching on all the
                     // variants
-                    {
+                    if alt.guard.is_none() {
                         *unaliased_scrutinee_type = self.subs.zonk(&unaliased_scrutinee_type);
                         let replaced = match (&alt.pattern.value, &**unaliased_scrutinee_type) {
                             (Pattern::Constructor(id, _), Type::Variant(row)) => {
@@ -1739,6 +1813,65 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                 }
                 tuple_type
             }
+            Pattern::Array { typ, elems, rest } => {
+                let element_type = self.subs.new_var();
+                let array_type = self.subs.array(element_type.clone());
+                let new_type = self.unify_span(span, &array_type, match_type.concrete);
+                *typ = self.subs.bind_arc(&new_type);
+                for elem in &mut **elems {
+                    self.typecheck_pattern(
+                        elem,
+                        ModType::new(match_type.modifier, element_type.clone()),
+                        element_type.clone(),
+                    );
+                }
+                if let Some(rest) = rest {
+                    self.typecheck_pattern(
+                        rest,
+                        ModType::new(match_type.modifier, array_type.clone()),
+                        array_type.clone(),
+                    );
+                }
+                array_type
+            }
+            Pattern::Or(alternatives) => {
+                let (first, rest) = alternatives
+                    .split_first_mut()
+                    .expect("Or pattern without alternatives");
+                let typ =
+                    self.typecheck_pattern(first, match_type.clone(), partial_match_type.clone());
+
+                // Each alternative binds the same symbols (see `rename`) so the types that the
+                // first alternative gave them must agree with the types given by the others
+                let bindings: Vec<(Symbol, RcType)> = or_pattern_bindings(first)
+                    .map(|id| (id.clone(), self.stack_var_type(id)))
+                    .collect();
+
+                for alternative in rest {
+                    self.typecheck_pattern(
+                        alternative,
+                        match_type.clone(),
+                        partial_match_type.clone(),
+                    );
+
+                    let alternative_bindings: FnvSet<_> =
+                        or_pattern_bindings(alternative).collect();
+                    for (id, expected) in &bindings {
+                        if alternative_bindings.contains(id) {
+                            let actual = self.stack_var_type(id);
+                            self.unify_span(alternative.span, expected, actual);
+                        } else {
+                            self.error(alternative.span, TypeError::OrPatternBinding(id.clone()));
+                        }
+                    }
+                    for id in alternative_bindings {
+                        if bindings.iter().all(|(bound, _)| bound != id) {
+                            self.error(alternative.span, TypeError::OrPatternBinding(id.clone()));
+                        }
+                    }
+                }
+                typ
+            }
             Pattern::Ident(id) => {
                 self.stack_var(id.name.clone(), partial_match_type.clone());
                 id.typ = self.subs.bind_arc(&partial_match_type);
@@ -2243,6 +2376,14 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
         }
     }
 
+    fn stack_var_type(&self, id: &Symbol) -> RcType {
+        self.environment
+            .stack
+            .get(id)
+            .map(|bind| bind.typ.concrete.clone())
+            .unwrap_or_else(|| ice!("Pattern variable `{}` is not in scope", id))
+    }
+
     fn update_var(&mut self, id: &Symbol, typ: &RcType) {
         if let Some(bind) = self.environment.stack.get_mut(id) {
             if let Type::Variable(_) = **bind.typ {
@@ -2318,6 +2459,32 @@ impl<'a, 'ast> Typecheck<'a, 'ast> {
                     self.finish_pattern(level, elem, &field_type);
                 }
             }
+            Pattern::Array {
+                ref mut typ,
+                ref mut elems,
+                ref mut rest,
+            } => {
+                *typ = self.subs.bind_arc(final_type);
+
+                let typ = self.instantiate_generics(final_type);
+                let element_type = match *self.remove_alias(typ.clone()) {
+                    Type::App(_, ref args) if args.len() == 1 => args[0].clone(),
+                    _ => self.subs.error(),
+                };
+                for elem in &mut **elems {
+                    let mut element_type = element_type.clone();
+                    self.generalize_type(level, &mut element_type, elem.span);
+                    self.finish_pattern(level, elem, &element_type);
+                }
+                if let Some(rest) = rest {
+                    self.finish_pattern(level, rest, final_type);
+                }
+            }
+            Pattern::Or(ref mut alternatives) => {
+                for alternative in &mut **alternatives {
+                    self.finish_pattern(level, alternative, final_type);
+                }
+            }
             Pattern::Constructor(ref mut id, ref mut args) => {
                 debug!("{}: {}", self.symbols.string(&id.name), final_type);
                 let len = args.len();
@@ -3182,6 +3349,16 @@ pub fn translate_projected_type(
         .ok_or_else(|| TypeError::UndefinedField(typ, type_symbol))
 }
 
+/// The variables bound by `pattern`, ignoring `_` as it is allowed to appear in only some of the
+/// alternatives
+fn or_pattern_bindings<'a>(
+    pattern: &'a SpannedPattern<'_, Symbol>,
+) -> impl Iterator<Item = &'a Symbol> {
+    ast::pattern_bindings(&pattern.value)
+        .into_iter()
+        .filter(|id| id.declared_name() != "_")
+}
+
 fn with_pattern_types<'a: 'b, 'b, 'ast>(
     fields: &'a mut [PatternField<'ast, Symbol>],
     typ: &'b RcType,
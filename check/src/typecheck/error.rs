@@ -40,10 +40,14 @@ pub enum TypeError<I, T> {
     KindError(KindCheckError<I, T>),
     /// Error were found when checking value recursion
     RecursionCheck(crate::recursion_check::Error),
+    /// The patterns of a `match` or `let` do not cover every possible value
+    Exhaustiveness(crate::exhaustiveness::Error),
     /// Multiple types were declared with the same name in the same expression
     DuplicateTypeDefinition(I),
     /// A field was defined more than once in a record constructor or pattern match
     DuplicateField(I),
+    /// A variable was not bound in every alternative of an or pattern
+    OrPatternBinding(I),
     /// Type is not a type which has any fields
     InvalidProjection(T),
     /// Expected to find a record with the following fields
@@ -84,6 +88,12 @@ impl<I, T> From<crate::recursion_check::Error> for TypeError<I, T> {
     }
 }
 
+impl<I, T> From<crate::exhaustiveness::Error> for TypeError<I, T> {
+    fn from(e: crate::exhaustiveness::Error) -> Self {
+        TypeError::Exhaustiveness(e)
+    }
+}
+
 impl<I, T> fmt::Display for TypeError<I, T>
 where
     I: fmt::Display + AsRef<str> + Clone,
@@ -190,12 +200,18 @@ where
             }
             KindError(err) => kindcheck::fmt_kind_error(err, f),
             RecursionCheck(err) => write!(f, "{}", err),
+            Exhaustiveness(err) => write!(f, "{}", err),
             DuplicateTypeDefinition(id) => write!(
                 f,
                 "Type '{}' has been already been defined in this module",
                 id
             ),
             DuplicateField(id) => write!(f, "The record has more than one field named '{}'", id),
+            OrPatternBinding(id) => write!(
+                f,
+                "Variable `{}` is not bound in all alternatives of the or pattern",
+                id
+            ),
             InvalidProjection(typ) => write!(
                 f,
                 "Type '{}' is not a type which allows field accesses",
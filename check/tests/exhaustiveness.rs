@@ -0,0 +1,280 @@
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_parser as parser;
+
+#[macro_use]
+mod support;
+
+use crate::check::{exhaustiveness::Error, lint::Warning, typecheck::TypeError};
+
+fn missing_patterns(text: &str) -> Vec<String> {
+    let _ = env_logger::try_init();
+    let err = support::typecheck(text).unwrap_err().unwrap_check();
+    match err.errors()[0].value.error {
+        TypeError::Exhaustiveness(Error::NonExhaustiveMatch { ref missing })
+        | TypeError::Exhaustiveness(Error::RefutableLet { ref missing }) => missing.clone(),
+        _ => panic!("Expected an exhaustiveness error, got {}", err),
+    }
+}
+
+fn unreachable_patterns(text: &str) -> usize {
+    let _ = env_logger::try_init();
+    let (result, warnings) = support::typecheck_with_warnings(text);
+    result.unwrap_or_else(|err| panic!("{}", err));
+    warnings
+        .iter()
+        .filter(|warning| warning.value == Warning::UnreachablePattern)
+        .count()
+}
+
+test_check! {
+    exhaustive_variant,
+    r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x -> x
+| None -> 0
+"#,
+    "Int"
+}
+
+test_check! {
+    exhaustive_nested_variant,
+    r#"
+type Option a = | None | Some a
+match Some (Some 1) with
+| Some (Some x) -> x
+| Some None -> 1
+| None -> 0
+"#,
+    "Int"
+}
+
+test_check! {
+    exhaustive_tuple,
+    r#"
+type Option a = | None | Some a
+match (Some 1, None) with
+| (Some x, _) -> x
+| (None, Some y) -> y
+| (None, None) -> 0
+"#,
+    "Int"
+}
+
+test_check! {
+    exhaustive_or_pattern,
+    r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some 1 | None -> 0
+| Some x -> x
+"#,
+    "Int"
+}
+
+test_check! {
+    exhaustive_array,
+    r#"
+match [1, 2] with
+| [] -> 0
+| [x] -> x
+| [x, y, ..rest] -> y
+"#,
+    "Int"
+}
+
+test_check! {
+    exhaustive_record,
+    r#"
+type Option a = | None | Some a
+match { x = Some 1, y = 2 } with
+| { x = Some x } -> x
+| { x = None, y } -> y
+"#,
+    "Int"
+}
+
+test_check! {
+    irrefutable_let_pattern,
+    r#"
+let (x, { y }) = (1, { y = 2 })
+y
+"#,
+    "Int"
+}
+
+#[test]
+fn missing_constructor() {
+    let text = r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x -> x
+"#;
+    assert_eq!(missing_patterns(text), ["None"]);
+}
+
+#[test]
+fn missing_nested_constructor() {
+    let text = r#"
+type Option a = | None | Some a
+match Some (Some 1) with
+| Some (Some x) -> x
+| None -> 0
+"#;
+    assert_eq!(missing_patterns(text), ["Some None"]);
+}
+
+#[test]
+fn missing_constructor_with_arguments() {
+    let text = r#"
+type List a = | Nil | Cons a (List a)
+match Nil with
+| Nil -> 0
+"#;
+    assert_eq!(missing_patterns(text), ["Cons _ _"]);
+}
+
+#[test]
+fn missing_tuple_pattern() {
+    let text = r#"
+type Option a = | None | Some a
+match (Some 1, None) with
+| (Some x, _) -> x
+| (_, Some y) -> y
+"#;
+    assert_eq!(missing_patterns(text), ["(None, None)"]);
+}
+
+#[test]
+fn missing_record_pattern() {
+    let text = r#"
+type Option a = | None | Some a
+match { x = Some 1, y = 2 } with
+| { x = Some x } -> x
+"#;
+    assert_eq!(missing_patterns(text), ["{ x = None }"]);
+}
+
+#[test]
+fn literals_need_a_wildcard() {
+    let text = r#"
+match 1 with
+| 1 -> 0
+| 2 -> 1
+"#;
+    assert_eq!(missing_patterns(text), ["_"]);
+}
+
+#[test]
+fn missing_array_lengths() {
+    let text = r#"
+match [1] with
+| [] -> 0
+| [x, y] -> y
+"#;
+    assert_eq!(missing_patterns(text), ["[_]", "[_, _, _, ..]"]);
+}
+
+#[test]
+fn guarded_alternatives_are_not_exhaustive() {
+    let text = r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x if True -> x
+| None -> 0
+"#;
+    assert_eq!(missing_patterns(text), ["Some _"]);
+}
+
+#[test]
+fn open_variant_needs_a_wildcard() {
+    let text = r#"
+type Test r = | A | B .. r
+let f x : Test r -> Int =
+    match x with
+    | A -> 0
+    | B -> 1
+f
+"#;
+    assert_eq!(missing_patterns(text), ["_"]);
+}
+
+#[test]
+fn refutable_let_pattern() {
+    let text = r#"
+let [x, y] = [1, 2]
+x
+"#;
+    assert_eq!(missing_patterns(text), ["[]", "[_]", "[_, _, _, ..]"]);
+}
+
+#[test]
+fn many_missing_patterns() {
+    let text = r#"
+type Test = | A | B | C | D | E
+match A with
+| A -> 0
+"#;
+    let err = support::typecheck(text).unwrap_err().unwrap_check();
+    assert_eq!(
+        err.errors()[0].value.error.to_string(),
+        "Non-exhaustive patterns in `match` expression, `B`, `C`, `D` and more are not covered"
+    );
+}
+
+#[test]
+fn unreachable_alternative() {
+    let text = r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x -> x
+| None -> 0
+| Some 1 -> 1
+"#;
+    assert_eq!(unreachable_patterns(text), 1);
+}
+
+#[test]
+fn unreachable_alternative_after_wildcard() {
+    let text = r#"
+match 1 with
+| _ -> 0
+| 1 -> 1
+"#;
+    assert_eq!(unreachable_patterns(text), 1);
+}
+
+#[test]
+fn unreachable_array_alternative() {
+    let text = r#"
+match [1] with
+| [x, ..rest] -> x
+| [x, y] -> y
+| _ -> 0
+"#;
+    assert_eq!(unreachable_patterns(text), 1);
+}
+
+#[test]
+fn guarded_alternative_does_not_make_others_unreachable() {
+    let text = r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x if True -> x
+| Some x -> x
+| None -> 0
+"#;
+    assert_eq!(unreachable_patterns(text), 0);
+}
+
+#[test]
+fn reachable_literals() {
+    let text = r#"
+match "abc" with
+| "abc" -> 0
+| "def" -> 1
+| _ -> 2
+"#;
+    assert_eq!(unreachable_patterns(text), 0);
+}
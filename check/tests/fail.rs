@@ -43,6 +43,46 @@ match () with
     assert_unify_err!(result, TypeMismatch(..));
 }
 
+#[test]
+fn match_guard_must_be_bool() {
+    let _ = env_logger::try_init();
+    let text = r#"
+match 1 with
+| x if x -> x
+| _ -> 0
+"#;
+    let result = support::typecheck(text);
+
+    assert_unify_err!(result, TypeMismatch(..));
+}
+
+#[test]
+fn or_pattern_must_bind_the_same_variables() {
+    let _ = env_logger::try_init();
+    let text = r#"
+type Option a = | None | Some a
+match Some 1 with
+| Some x | None -> 1
+"#;
+    let result = support::typecheck(text);
+
+    assert_err!(result, OrPatternBinding(..));
+}
+
+#[test]
+fn or_pattern_variables_must_have_the_same_type() {
+    let _ = env_logger::try_init();
+    let text = r#"
+type Either a b = | Left a | Right b
+let e : Either Int String = Left 1
+match e with
+| Left x | Right x -> x
+"#;
+    let result = support::typecheck(text);
+
+    assert_unify_err!(result, TypeMismatch(..));
+}
+
 #[test]
 fn match_different_alt_types_expected() {
     let _ = env_logger::try_init();
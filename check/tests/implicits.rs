@@ -1173,3 +1173,50 @@ let test ?t : [Test a] -> a = t.x
 "#,
 "forall a . Array (a -> Int)"
 }
+
+test_check! {
+interpolated_string_resolves_show,
+r#"
+#[implicit]
+type Show a = { show : a -> String }
+
+let show ?s : [Show a] -> a -> String = s.show
+#[infix(left, 4)]
+let (++) x y : String -> String -> String = x
+
+let show_int : Show Int = { show = \_ -> "" }
+
+let x = 1
+f"total: ${x} items"
+"#,
+"String"
+}
+
+#[test]
+fn interpolated_string_error_span() {
+    use crate::base::pos::Span;
+
+    let _ = ::env_logger::try_init();
+    let text = r#"
+#[implicit]
+type Show a = { show : a -> String }
+
+let show ?s : [Show a] -> a -> String = s.show
+#[infix(left, 4)]
+let (++) x y : String -> String -> String = x
+
+let show_int : Show Int = { show = \_ -> "" }
+
+f"${1} ${1.0}"
+"#;
+    let (_expr, result) = support::typecheck_expr(text);
+    let errors: Vec<_> = result.unwrap_err().unwrap_check().into_errors().into();
+    assert_eq!(errors.len(), 1);
+    match errors[0].value.error {
+        TypeError::UnableToResolveImplicit(..) => (),
+        ref err => panic!("Unexpected error: {}", err),
+    }
+    // The error points at the interpolated `1.0`
+    let start = text.find("1.0").unwrap() as u32 + 1;
+    assert_eq!(errors[0].span, Span::new(start.into(), (start + 3).into()));
+}
@@ -0,0 +1,208 @@
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_parser as parser;
+
+#[macro_use]
+mod support;
+
+use crate::check::lint::Warning;
+
+fn warnings(text: &str) -> Vec<Warning> {
+    let _ = env_logger::try_init();
+    let (result, warnings) = support::typecheck_with_warnings(text);
+    result.unwrap_or_else(|err| panic!("{}", err));
+    warnings.into_iter().map(|warning| warning.value).collect()
+}
+
+fn warning_messages(text: &str) -> Vec<String> {
+    warnings(text)
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect()
+}
+
+#[test]
+fn unused_binding() {
+    let text = r#"
+let x = 1
+let y = 2
+y
+"#;
+    assert_eq!(warning_messages(text), ["Unused binding `x`"]);
+}
+
+#[test]
+fn unused_binding_in_pattern() {
+    let text = r#"
+let (x, y) = (1, 2)
+let { z, w } = { z = 1, w = 2 }
+x #Int+ w
+"#;
+    assert_eq!(
+        warning_messages(text),
+        ["Unused binding `y`", "Unused binding `z`"]
+    );
+}
+
+#[test]
+fn bindings_used_in_record_are_used() {
+    let text = r#"
+let x = 1
+let f y = y
+{ x, f }
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn recursive_binding_is_used() {
+    let text = r#"
+rec let f x = g x
+let g x = f x
+f
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn underscore_bindings_are_not_reported() {
+    let text = r#"
+let _x = 1
+let _ = 2
+3
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn shadowed_binding() {
+    let text = r#"
+let x = 1
+let x = x #Int+ 1
+x
+"#;
+    assert_eq!(
+        warning_messages(text),
+        ["`x` shadows an earlier binding with the same name"]
+    );
+}
+
+#[test]
+fn shadowed_argument() {
+    let text = r#"
+let f x =
+    let x = 1
+    x
+f
+"#;
+    assert_eq!(
+        warning_messages(text),
+        ["`x` shadows an earlier binding with the same name"]
+    );
+}
+
+#[test]
+fn bindings_in_separate_scopes_do_not_shadow() {
+    let text = r#"
+let f y =
+    let x = y
+    x
+let g y =
+    let x = y
+    x
+{ f, g }
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn unused_type_parameter() {
+    let text = r#"
+type Test a = Int
+let x : Test String = 1
+x
+"#;
+    assert_eq!(warning_messages(text), ["Unused type parameter `a`"]);
+}
+
+#[test]
+fn used_type_parameters() {
+    let text = r#"
+type Option a = | None | Some a
+type Record a b = { x : a, y : Option b }
+type Row r = | A | B .. r
+let x : Record Int Int = { x = 1, y = None }
+x
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn allow_attribute() {
+    let text = r#"
+#[allow(unused_bindings)]
+let x = 1
+2
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn allow_attribute_covers_the_binding_expression() {
+    let text = r#"
+#[allow(shadowed_bindings, unused_bindings)]
+let f x =
+    let x = 1
+    let y = 2
+    x
+f
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn allow_attribute_only_allows_the_named_lint() {
+    let text = r#"
+#[allow(shadowed_bindings)]
+let f x =
+    let x = 1
+    let y = 2
+    x
+f
+"#;
+    assert_eq!(warning_messages(text), ["Unused binding `y`"]);
+}
+
+#[test]
+fn allow_unused_type_parameters() {
+    let text = r#"
+#[allow(unused_type_parameters)]
+type Phantom a = Int
+let x : Phantom String = 1
+x
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn allow_unreachable_patterns() {
+    let text = r#"
+#[allow(unreachable_patterns)]
+let x =
+    match 1 with
+    | _ -> 0
+    | 1 -> 1
+x
+"#;
+    assert_eq!(warnings(text), []);
+}
+
+#[test]
+fn unknown_lint() {
+    let text = r#"
+#[allow(unused_things)]
+let x = 1
+x
+"#;
+    assert_eq!(warning_messages(text), ["Unknown lint `unused_things`"]);
+}
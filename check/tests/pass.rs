@@ -1135,3 +1135,40 @@ match writer with
     "#,
     "test.List String"
 }
+
+test_check! {
+    match_guard,
+    r#"
+type Option a = | None | Some a
+\x ->
+    match x with
+    | Some y if y #Int== 0 -> y
+    | Some y -> y #Int+ 1
+    | None -> 0
+"#,
+    "test.Option Int -> Int"
+}
+
+test_check! {
+    array_pattern,
+    r#"
+\x ->
+    match x with
+    | [] -> [1]
+    | [y] -> [y #Int+ 1]
+    | [_, ..rest] -> rest
+"#,
+    "Array Int -> Array Int"
+}
+
+test_check! {
+    or_pattern,
+    r#"
+type Either a b = | Left a | Right b
+\x ->
+    match x with
+    | (Left y, _) | (_, Right y) -> y #Int+ 1
+    | _ -> 0
+"#,
+    "forall a a0 . (test.Either Int a, test.Either a0 Int) -> Int"
+}
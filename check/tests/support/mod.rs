@@ -7,15 +7,19 @@ extern crate gluon_parser as parser;
 
 use self::{
     base::{
-        ast::{DisplayEnv, Expr, IdentEnv, KindedIdent, RootExpr, SpannedExpr},
+        ast::{
+            self, DisplayEnv, Expr, IdentEnv, InterpolationPart, KindedIdent, MutVisitor, RootExpr,
+            SpannedExpr, TypedIdent,
+        },
         error::{Errors, InFile},
         kind::{ArcKind, Kind, KindEnv},
         metadata::{Metadata, MetadataEnv},
-        pos::{BytePos, Spanned},
+        pos::{self, BytePos, Spanned},
         symbol::{Name, Symbol, SymbolData, SymbolModule, SymbolRef, Symbols},
         types::{self, Alias, ArcType, Field, Generic, PrimitiveEnv, Type, TypeCache, TypeEnv},
     },
     check::{
+        lint::{self, Warnings},
         metadata, rename,
         typecheck::{self, Typecheck},
     },
@@ -85,6 +89,40 @@ pub fn parse_new(s: &str) -> Result<RootExpr<Symbol>, (Option<RootExpr<Symbol>>,
     parse_partial_root_expr(&mut module, &TypeCache::new(), s)
 }
 
+/// Stands in for the macro expansion which makes interpolated strings use `show` and `++` from the
+/// standard library. As the tests do not have the standard library the `show` and `++` which are
+/// in scope are used instead.
+pub fn insert_interpolation_ids<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut SymbolModule,
+    expr: &mut SpannedExpr<'ast, Symbol>,
+) {
+    struct InterpolationVisitor<'a, 'b, 's, 'ast> {
+        arena: ast::ArenaRef<'s, 'ast, Symbol>,
+        symbols: &'b mut SymbolModule<'a>,
+    }
+
+    impl<'c, 'ast> MutVisitor<'c, 'ast> for InterpolationVisitor<'_, '_, '_, 'ast> {
+        type Ident = Symbol;
+
+        fn visit_expr(&mut self, expr: &'c mut SpannedExpr<'ast, Symbol>) {
+            if let Expr::Interpolation(interpolation) = &mut expr.value {
+                let append = Expr::Ident(TypedIdent::new(self.symbols.simple_symbol("++")));
+                interpolation.append_id = Some(self.arena.alloc(pos::spanned(expr.span, append)));
+                for part in &mut *interpolation.parts {
+                    if let InterpolationPart::Expr { expr, show_id } = part {
+                        let show = Expr::Ident(TypedIdent::new(self.symbols.simple_symbol("show")));
+                        *show_id = Some(self.arena.alloc(pos::spanned(expr.span, show)));
+                    }
+                }
+            }
+            ast::walk_mut_expr(self, expr);
+        }
+    }
+
+    InterpolationVisitor { arena, symbols }.visit_expr(expr);
+}
+
 #[allow(dead_code)]
 pub fn typecheck(text: &str) -> Result<ArcType, Error> {
     let (_, t) = typecheck_expr(text);
@@ -194,11 +232,29 @@ pub fn typecheck_expr_expected(
     text: &str,
     expected: Option<&ArcType>,
 ) -> (RootExpr<Symbol>, Result<ArcType, Error>) {
+    let (expr, result, _) = typecheck_expr_expected_with_warnings(text, expected);
+    (expr, result)
+}
+
+#[allow(dead_code)]
+pub fn typecheck_with_warnings(text: &str) -> (Result<ArcType, Error>, Warnings) {
+    let (_, result, warnings) = typecheck_expr_expected_with_warnings(text, None);
+    (result, warnings)
+}
+
+fn typecheck_expr_expected_with_warnings(
+    text: &str,
+    expected: Option<&ArcType>,
+) -> (RootExpr<Symbol>, Result<ArcType, Error>, Warnings) {
     let mut expr = match parse_new(text) {
         Ok(expr) => expr,
         Err((expr, err)) => {
             let err = in_file_error(text, err);
-            return (expr.unwrap_or_else(|| panic!("{}", err)), Err(err.into()));
+            return (
+                expr.unwrap_or_else(|| panic!("{}", err)),
+                Err(err.into()),
+                Errors::new(),
+            );
         }
     };
 
@@ -207,16 +263,15 @@ pub fn typecheck_expr_expected(
     let mut interner = interner.borrow_mut();
 
     let source = codespan::FileMap::new("test".into(), text.to_string());
-    let result = {
+    let (result, warnings) = {
         let (arena, expr) = expr.arena_expr();
         let arena = arena.borrow();
 
-        rename::rename(
-            &source,
-            &mut SymbolModule::new("test".into(), &mut interner),
-            arena,
-            expr,
-        );
+        {
+            let mut symbols = SymbolModule::new("test".into(), &mut interner);
+            insert_interpolation_ids(arena, &mut symbols, expr);
+            rename::rename(&source, &mut symbols, arena, expr);
+        }
         let (_, mut metadata) = metadata::metadata(&env, &expr);
         reparse_infix(arena, &metadata, &*interner, expr).unwrap_or_else(|err| panic!("{}", err));
 
@@ -229,10 +284,19 @@ pub fn typecheck_expr_expected(
             arena,
         );
 
-        tc.typecheck_expr_expected(expr, expected)
+        let result = tc.typecheck_expr_expected(expr, expected);
+        let mut warnings = tc.take_warnings();
+        if result.is_ok() {
+            lint::lint_expr(expr, source.span(), &mut warnings);
+        }
+        (result, warnings)
     };
 
-    (expr, result.map_err(|err| in_file_error(text, err).into()))
+    (
+        expr,
+        result.map_err(|err| in_file_error(text, err).into()),
+        warnings,
+    )
 }
 
 pub fn typecheck_expr(text: &str) -> (RootExpr<Symbol>, Result<ArcType, Error>) {
@@ -262,12 +326,11 @@ pub fn typecheck_partial_expr(
         let (arena, expr) = expr.arena_expr();
         let arena = arena.borrow();
 
-        rename::rename(
-            &source,
-            &mut SymbolModule::new("test".into(), &mut interner),
-            arena,
-            expr,
-        );
+        {
+            let mut symbols = SymbolModule::new("test".into(), &mut interner);
+            insert_interpolation_ids(arena, &mut symbols, expr);
+            rename::rename(&source, &mut symbols, arena, expr);
+        }
 
         let (_, mut metadata) = metadata::metadata(&env, &expr);
 
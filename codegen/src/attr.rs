@@ -100,6 +100,115 @@ impl Container {
     }
 }
 
+/// Options of `#[gluon::module(...)]`
+pub struct Module {
+    pub crate_name: CrateName,
+}
+
+impl Module {
+    pub fn from_args(args: &[syn::NestedMeta]) -> Module {
+        use syn::NestedMeta::*;
+
+        let mut crate_name = CrateName::None;
+
+        for meta_item in args {
+            match meta_item {
+                // Parse `#[gluon::module(crate_name = "foo")]`
+                Meta(NameValue(ref m)) if m.path.is_ident("crate_name") => {
+                    if let Ok(path) = parse_lit_into_path(&m.path, &m.lit) {
+                        crate_name = CrateName::Some(path);
+                    }
+                }
+
+                // Parse `#[gluon::module(gluon_vm)]`
+                Meta(Path(ref w)) if w.is_ident("gluon_vm") => {
+                    crate_name = CrateName::GluonVm;
+                }
+
+                _ => panic!("unexpected gluon module attribute: {:?}", meta_item),
+            }
+        }
+
+        Module { crate_name }
+    }
+}
+
+/// Returns true if `attr` is a `#[gluon::function]` attribute
+pub fn is_function_attr(attr: &syn::Attribute) -> bool {
+    let segments = &attr.path.segments;
+    matches!(segments.last(), Some(last) if last.ident == "function")
+        && (segments.len() == 1
+            || (segments.len() == 2
+                && (segments[0].ident == "gluon" || segments[0].ident == "gluon_codegen")))
+}
+
+/// Options of `#[gluon::function(...)]` on a function inside a `#[gluon::module]`
+pub struct Function {
+    /// The function was marked with `#[gluon::function]`
+    pub marked: bool,
+    pub name: Option<String>,
+    pub skip: bool,
+}
+
+impl Function {
+    pub fn from_attrs(attrs: &[syn::Attribute]) -> Function {
+        use syn::NestedMeta::*;
+
+        let mut marked = false;
+        let mut name = None;
+        let mut skip = false;
+
+        for attr in attrs.iter().filter(|attr| is_function_attr(attr)) {
+            marked = true;
+            let meta_items = match attr.parse_meta() {
+                Ok(List(meta)) => meta.nested.into_iter().collect(),
+                Ok(Path(_)) => Vec::new(),
+                _ => panic!("expected `#[gluon::function]` or `#[gluon::function(...)]`"),
+            };
+            for meta_item in meta_items {
+                match meta_item {
+                    // Parse `#[gluon::function(name = "foo")]`
+                    Meta(NameValue(ref m)) if m.path.is_ident("name") => {
+                        name = Some(get_lit_str(&m.path, &m.path, &m.lit).unwrap().value())
+                    }
+
+                    Meta(Path(ref w)) if w.is_ident("skip") => {
+                        skip = true;
+                    }
+
+                    _ => panic!("unexpected gluon function attribute: {:?}", meta_item),
+                }
+            }
+        }
+
+        Function { marked, name, skip }
+    }
+}
+
+/// Collects the doc comments in `attrs`, with the space rustdoc puts after `///` removed
+pub fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(NameValue(ref m)) => match m.lit {
+                syn::Lit::Str(ref s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| match line.chars().next() {
+            Some(' ') => line[1..].to_string(),
+            _ => line,
+        })
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
 fn get_lit_str<'a>(
     attr_name: &Path,
     _meta_item_name: &Path,
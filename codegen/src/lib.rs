@@ -149,6 +149,55 @@
 //! # fn main() {}
 //! ```
 //!
+//! ## Attribute Macros
+//!
+//! ### module
+//!
+//! Turns an inline `mod` or an inherent `impl` block into an extern module by generating a
+//! `load` function which can be passed to `add_extern_module`. Every `pub` function is exported
+//! with its arity inferred from its signature. `async fn`s and functions returning
+//! `impl Future` are run asynchronously and arguments and return values such as `WithVM`,
+//! `RuntimeResult` and `IO` work as they do with the `primitive!` macro. Doc comments on the
+//! functions, and on the module itself, are available as gluon metadata.
+//!
+//! Functions inside the module can be annotated with `#[gluon::function]` to export a private
+//! function, with `#[gluon::function(name = "...")]` to export it under another name or with
+//! `#[gluon::function(skip)]` to not export it. Functions in an `impl` block are exported as
+//! `Type::function` so functions taking `&self` need the type to implement `Userdata` and be
+//! registered before the module is loaded.
+//!
+//! #### Examples
+//!
+//! ```rust
+//! extern crate gluon;
+//!
+//! use gluon::{import::add_extern_module, new_vm, ThreadExt};
+//!
+//! /// Functions for greeting people
+//! #[gluon::module]
+//! mod greetings {
+//!     /// Greets `name`
+//!     pub fn greet(name: &str) -> String {
+//!         format!("Hello {}", name)
+//!     }
+//!
+//!     #[gluon::function(name = "shout")]
+//!     pub fn shout_(name: &str) -> String {
+//!         name.to_uppercase()
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let vm = new_vm();
+//!     add_extern_module(&vm, "greetings", greetings::load);
+//!
+//!     let (result, _) = vm
+//!         .run_expr::<String>("example", r#"(import! greetings).greet "gluon""#)
+//!         .unwrap();
+//!     assert_eq!(result, "Hello gluon");
+//! }
+//! ```
+//!
 
 #![recursion_limit = "128"]
 
@@ -158,6 +207,7 @@ extern crate quote;
 mod attr;
 mod functor;
 mod getable;
+mod module;
 mod pushable;
 mod shared;
 mod trace;
@@ -201,6 +251,24 @@ pub fn functor(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     functor::derive(input.into()).into()
 }
 
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn module(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    module::module(args.into(), input.into()).into()
+}
+
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn function(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    module::function(args.into(), input.into()).into()
+}
+
 #[doc(hidden)]
 #[proc_macro_derive(AstClone, attributes(gluon))]
 pub fn ast_clone(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
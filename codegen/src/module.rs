@@ -0,0 +1,251 @@
+use proc_macro2::{Ident, TokenStream};
+use syn::{
+    self, parse::Parser, punctuated::Punctuated, Attribute, GenericParam, Item, ItemImpl, ItemMod,
+    ReturnType, Signature, Type, TypeParamBound, Visibility,
+};
+
+use crate::attr::{self, CrateName, Function, Module};
+
+pub fn module(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = Punctuated::<syn::NestedMeta, syn::Token![,]>::parse_terminated
+        .parse2(args)
+        .expect("Expected a comma separated list of options");
+    let args: Vec<_> = args.into_iter().collect();
+    let options = Module::from_args(&args);
+
+    let item: Item = syn::parse2(input).expect("Input is checked by rustc");
+
+    match item {
+        Item::Mod(item) => gen_mod(&options, item),
+        Item::Impl(item) => gen_impl(&options, item),
+        _ => panic!("`#[gluon::module]` can only be used on a `mod` or an `impl` block"),
+    }
+}
+
+pub fn function(_args: TokenStream, _input: TokenStream) -> TokenStream {
+    panic!("`#[gluon::function]` can only be used on functions inside a `#[gluon::module]`")
+}
+
+/// A function which is exported to gluon
+struct Export {
+    ident: Ident,
+    name: String,
+    arg_count: usize,
+    is_async: bool,
+    comment: Option<String>,
+}
+
+impl Export {
+    /// Returns the export for a function with the given attributes and signature or `None` if
+    /// the function should not be exported. Removes any `#[gluon::function]` attributes.
+    fn new(vis: &Visibility, attrs: &mut Vec<Attribute>, sig: &Signature) -> Option<Export> {
+        let function = Function::from_attrs(attrs);
+        attrs.retain(|attr| !attr::is_function_attr(attr));
+
+        let is_public = matches!(vis, Visibility::Public(_));
+        if function.skip || !(is_public || function.marked) {
+            return None;
+        }
+
+        if sig
+            .generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+        {
+            panic!(
+                "`{}` can't be exported to gluon as it has type parameters",
+                sig.ident
+            );
+        }
+        if sig.variadic.is_some() {
+            panic!(
+                "`{}` can't be exported to gluon as it is variadic",
+                sig.ident
+            );
+        }
+
+        Some(Export {
+            ident: sig.ident.clone(),
+            name: function.name.unwrap_or_else(|| sig.ident.to_string()),
+            arg_count: sig.inputs.len(),
+            is_async: sig.asyncness.is_some() || returns_future(&sig.output),
+            comment: attr::doc_comment(attrs),
+        })
+    }
+
+    fn primitive(&self, vm: &TokenStream, path: TokenStream) -> TokenStream {
+        let Export {
+            ident,
+            name,
+            arg_count,
+            ..
+        } = self;
+        if *arg_count > 7 {
+            panic!(
+                "`{}` can't be exported to gluon as it takes more than 7 arguments",
+                ident
+            );
+        }
+        let arg_count = syn::LitInt::new(&arg_count.to_string(), ident.span());
+        let func = if self.is_async {
+            quote! { async fn #path }
+        } else {
+            path
+        };
+        quote! {
+            (#ident #name) => #vm::primitive!(#arg_count, #name, #func)
+        }
+    }
+
+    fn metadata(&self) -> TokenStream {
+        let name = &self.name;
+        let comment = gen_comment(&self.comment);
+        quote! {
+            metadata.module.insert(
+                String::from(#name),
+                ::std::sync::Arc::new(Metadata {
+                    comment: #comment,
+                    ..Metadata::default()
+                }),
+            );
+        }
+    }
+}
+
+/// `impl Future<...>` return types are run as `async fn`s
+fn returns_future(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, typ) => match &**typ {
+            Type::ImplTrait(impl_trait) => impl_trait.bounds.iter().any(|bound| match bound {
+                TypeParamBound::Trait(bound) => matches!(
+                    bound.path.segments.last(),
+                    Some(segment) if segment.ident == "Future"
+                ),
+                _ => false,
+            }),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+fn gen_comment(comment: &Option<String>) -> TokenStream {
+    match comment {
+        Some(comment) => quote! {
+            Some(Comment {
+                typ: CommentType::Line,
+                content: String::from(#comment),
+            })
+        },
+        None => quote! { None },
+    }
+}
+
+fn vm_crate(options: &Module) -> TokenStream {
+    match options.crate_name {
+        CrateName::Some(ref path) => quote! { #path },
+        CrateName::GluonVm => quote! { crate },
+        CrateName::None => quote! { gluon::vm },
+    }
+}
+
+fn gen_loader(
+    vm: &TokenStream,
+    comment: &Option<String>,
+    exports: &[Export],
+    fields: Vec<TokenStream>,
+) -> TokenStream {
+    let module_comment = gen_comment(comment);
+    let metadata = exports.iter().map(Export::metadata);
+    quote! {
+        /// Creates the `ExternModule` containing the functions of this module
+        pub fn load(vm: &#vm::thread::Thread) -> #vm::Result<#vm::ExternModule> {
+            use #vm::base::metadata::{Comment, CommentType, Metadata};
+
+            let mut metadata = Metadata::default();
+            metadata.comment = #module_comment;
+            #(#metadata)*
+
+            #vm::ExternModule::with_metadata(
+                vm,
+                #vm::record! {
+                    #(#fields,)*
+                },
+                metadata,
+            )
+        }
+    }
+}
+
+fn gen_mod(options: &Module, mut item: ItemMod) -> TokenStream {
+    let vm = vm_crate(options);
+
+    let content = match &mut item.content {
+        Some((_, content)) => content,
+        None => panic!("`#[gluon::module]` can only be used on modules with a body"),
+    };
+
+    let exports: Vec<_> = content
+        .iter_mut()
+        .filter_map(|item| match item {
+            Item::Fn(function) => Export::new(&function.vis, &mut function.attrs, &function.sig),
+            _ => None,
+        })
+        .collect();
+
+    let fields = exports
+        .iter()
+        .map(|export| {
+            let ident = &export.ident;
+            export.primitive(&vm, quote! { #ident })
+        })
+        .collect();
+
+    let loader = gen_loader(&vm, &attr::doc_comment(&item.attrs), &exports, fields);
+    content.push(Item::Verbatim(loader));
+
+    quote! { #item }
+}
+
+fn gen_impl(options: &Module, mut item: ItemImpl) -> TokenStream {
+    let vm = vm_crate(options);
+
+    if item.trait_.is_some() {
+        panic!("`#[gluon::module]` can only be used on inherent `impl` blocks");
+    }
+    if !item.generics.params.is_empty() {
+        panic!("`#[gluon::module]` can't be used on generic `impl` blocks");
+    }
+
+    let exports: Vec<_> = item
+        .items
+        .iter_mut()
+        .filter_map(|item| match item {
+            syn::ImplItem::Method(method) => {
+                Export::new(&method.vis, &mut method.attrs, &method.sig)
+            }
+            _ => None,
+        })
+        .collect();
+
+    // The functions are called from inside a nested function where `Self` is not available
+    let self_ty = &item.self_ty;
+    let fields = exports
+        .iter()
+        .map(|export| {
+            let ident = &export.ident;
+            export.primitive(&vm, quote! { <#self_ty>::#ident })
+        })
+        .collect();
+
+    let loader = gen_loader(&vm, &attr::doc_comment(&item.attrs), &exports, fields);
+
+    quote! {
+        #item
+
+        impl #self_ty {
+            #loader
+        }
+    }
+}
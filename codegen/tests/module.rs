@@ -0,0 +1,225 @@
+#[macro_use]
+extern crate gluon_codegen;
+extern crate gluon;
+
+mod init;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gluon::{
+    import,
+    vm::{
+        self,
+        api::{RuntimeResult, WithVM, IO},
+        ExternModule,
+    },
+    Thread, ThreadExt,
+};
+
+use init::new_vm;
+
+/// Functions for greeting people
+#[gluon::module]
+mod greetings {
+    use super::*;
+
+    /// Greets `name`
+    ///
+    /// Politely.
+    pub fn greet(name: &str) -> String {
+        format!("Hello {}", name)
+    }
+
+    #[gluon::function(name = "shout")]
+    pub fn shout_(name: &str) -> String {
+        format!("HELLO {}", name.to_uppercase())
+    }
+
+    #[gluon::function]
+    fn whisper(name: &str) -> String {
+        format!("hello {}", name.to_lowercase())
+    }
+
+    #[gluon::function(skip)]
+    pub fn not_exported() {}
+
+    fn helper() -> i32 {
+        1
+    }
+
+    pub fn add(x: i32, y: i32) -> i32 {
+        x + y + helper() - 1
+    }
+
+    pub fn checked_div(x: i32, y: i32) -> RuntimeResult<i32, String> {
+        if y == 0 {
+            RuntimeResult::Panic("Division by zero".to_string())
+        } else {
+            RuntimeResult::Return(x / y)
+        }
+    }
+
+    pub fn read_name(name: String) -> IO<String> {
+        IO::Value(name)
+    }
+
+    pub fn context_name(WithVM { vm, value: name }: WithVM<&str>) -> String {
+        let _: &Thread = vm;
+        format!("{} in a thread", name)
+    }
+
+    /// Waits for the greeting
+    pub async fn greet_later(name: String) -> String {
+        format!("Hello later {}", name)
+    }
+}
+
+#[derive(Userdata, Trace, Debug, VmType)]
+#[gluon(vm_type = "Counter")]
+#[gluon_trace(skip)]
+pub struct Counter(Arc<AtomicUsize>);
+
+/// A shared counter
+#[gluon::module]
+impl Counter {
+    pub fn new(start: usize) -> Counter {
+        Counter(Arc::new(AtomicUsize::new(start)))
+    }
+
+    /// Increments the counter, returning the previous value
+    pub fn increment(&self) -> usize {
+        self.0.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+fn load_counter(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<Counter>("Counter", &[])?;
+    Counter::load(vm)
+}
+
+#[test]
+fn module() {
+    let _ = ::env_logger::try_init();
+
+    let vm = new_vm();
+    import::add_extern_module(&vm, "greetings", greetings::load);
+
+    fn run<T>(vm: &Thread, expr: &str) -> T
+    where
+        T: for<'vm, 'value> gluon::vm::api::Getable<'vm, 'value>
+            + gluon::vm::api::VmType
+            + Send
+            + 'static,
+    {
+        let expr = format!("let greetings = import! greetings in {}", expr);
+        vm.run_expr::<T>("test", &expr)
+            .unwrap_or_else(|err| panic!("{}", err))
+            .0
+    }
+
+    assert_eq!(
+        run::<String>(&vm, r#"greetings.greet "gluon""#),
+        "Hello gluon"
+    );
+    assert_eq!(
+        run::<String>(&vm, r#"greetings.shout "gluon""#),
+        "HELLO GLUON"
+    );
+    assert_eq!(
+        run::<String>(&vm, r#"greetings.whisper "Gluon""#),
+        "hello gluon"
+    );
+    assert_eq!(run::<i32>(&vm, "greetings.add 1 2"), 3);
+    assert_eq!(run::<i32>(&vm, "greetings.checked_div 6 3"), 2);
+    assert_eq!(
+        run::<String>(&vm, r#"greetings.context_name "gluon""#),
+        "gluon in a thread"
+    );
+    assert_eq!(
+        run::<String>(&vm, r#"greetings.greet_later "gluon""#),
+        "Hello later gluon"
+    );
+
+    vm.get_database_mut().run_io(true);
+    match run::<IO<String>>(&vm, r#"greetings.read_name "gluon""#) {
+        IO::Value(name) => assert_eq!(name, "gluon"),
+        IO::Exception(err) => panic!("{}", err),
+    }
+
+    let result = vm.run_expr::<i32>("test_div", "(import! greetings).checked_div 1 0");
+    assert!(
+        result
+            .as_ref()
+            .err()
+            .map_or(false, |err| err.to_string().contains("Division by zero")),
+        "{:?}",
+        result
+    );
+
+    let env = vm.get_env();
+    assert_eq!(
+        env.get_metadata("greetings")
+            .unwrap()
+            .comment
+            .as_ref()
+            .map(|comment| &comment.content[..]),
+        Some("Functions for greeting people")
+    );
+    assert_eq!(
+        env.get_metadata("greetings.greet")
+            .unwrap()
+            .comment
+            .as_ref()
+            .map(|comment| &comment.content[..]),
+        Some("Greets `name`\n\nPolitely.")
+    );
+}
+
+#[test]
+fn skipped_functions_are_not_exported() {
+    let _ = ::env_logger::try_init();
+
+    let vm = new_vm();
+    import::add_extern_module(&vm, "greetings", greetings::load);
+
+    for field in &["shout_", "not_exported", "helper"] {
+        let script = format!("let {{ {} }} = import! greetings in ()", field);
+        assert!(
+            vm.run_expr::<()>(field, &script).is_err(),
+            "`{}` should not be exported",
+            field
+        );
+    }
+}
+
+#[test]
+fn impl_module() {
+    let _ = ::env_logger::try_init();
+
+    let vm = new_vm();
+    import::add_extern_module(&vm, "counter", load_counter);
+
+    let script = r#"
+        let { new, increment } = import! counter
+        let counter = new 10
+        let _ = increment counter
+        increment counter
+    "#;
+    let (result, _) = vm
+        .run_expr::<usize>("test", script)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 11);
+
+    assert_eq!(
+        vm.get_env()
+            .get_metadata("counter.increment")
+            .unwrap()
+            .comment
+            .as_ref()
+            .map(|comment| &comment.content[..]),
+        Some("Increments the counter, returning the previous value")
+    );
+}
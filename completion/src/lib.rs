@@ -16,8 +16,8 @@ use either::Either;
 
 use crate::base::{
     ast::{
-        self, walk_expr, walk_pattern, AstType, Expr, Pattern, PatternField, SpannedExpr,
-        SpannedIdent, SpannedPattern, Typed, TypedIdent, Visitor,
+        self, walk_expr, walk_pattern, AstType, Expr, InterpolationPart, Pattern, PatternField,
+        SpannedExpr, SpannedIdent, SpannedPattern, Typed, TypedIdent, Visitor,
     },
     filename_to_module,
     fnv::{FnvMap, FnvSet},
@@ -186,6 +186,16 @@ impl<E: TypeEnv<Type = ArcType>> OnFound for Suggest<E> {
                     self.on_pattern(arg);
                 }
             }
+            Pattern::Array { elems, rest, .. } => {
+                for elem in &**elems {
+                    self.on_pattern(elem);
+                }
+                if let Some(rest) = rest {
+                    self.on_pattern(rest);
+                }
+            }
+            // Every alternative binds the same variables so it is enough to look at the first
+            Pattern::Or(alternatives) => self.on_pattern(&alternatives[0]),
             Pattern::Literal(_) | Pattern::Error => (),
         }
     }
@@ -462,10 +472,24 @@ where
                     self.found = MatchState::Empty;
                 }
             }
-            Pattern::Tuple { ref elems, .. } => {
+            Pattern::Tuple { ref elems, .. } | Pattern::Or(ref elems) => {
                 let (_, field) = self.select_spanned(&**elems, |elem| elem.span);
                 self.visit_pattern(field.unwrap());
             }
+            Pattern::Array {
+                ref elems,
+                ref rest,
+                ..
+            } => {
+                let (_, field) = self.select_spanned(
+                    elems.iter().chain(rest.as_ref().map(|rest| &**rest)),
+                    |elem| elem.span,
+                );
+                match field {
+                    Some(field) => self.visit_pattern(field),
+                    None => self.found = MatchState::Empty,
+                }
+            }
             Pattern::Ident(_) | Pattern::Literal(_) | Pattern::Error => {
                 self.found = if current.span.containment(self.pos) == Ordering::Equal {
                     MatchState::Found(Match::Pattern(current))
@@ -519,8 +543,10 @@ where
                     }
                     Err(alt) => {
                         self.on_found.on_pattern(&alt.pattern);
-                        let iter = [Ok(&alt.pattern), Err(&alt.expr)];
-                        let (_, sel) = self.select_spanned(iter.iter().cloned(), |x| match *x {
+                        let iter = once(Ok(&alt.pattern))
+                            .chain(alt.guard.as_ref().map(Err))
+                            .chain(once(Err(&alt.expr)));
+                        let (_, sel) = self.select_spanned(iter, |x| match *x {
                             Ok(p) => p.span,
                             Err(e) => e.span,
                         });
@@ -660,6 +686,13 @@ where
                     self.visit_one(&**exprs)
                 }
             }
+            Expr::Interpolation(ref interpolation) => {
+                let holes = interpolation.parts.iter().filter_map(|part| match *part {
+                    InterpolationPart::Expr { ref expr, .. } => Some(expr),
+                    InterpolationPart::Text(_) => None,
+                });
+                self.visit_one(holes)
+            }
             Expr::Do(ref do_expr) => {
                 let iter = do_expr
                     .id
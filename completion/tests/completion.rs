@@ -266,6 +266,29 @@ None
     assert_eq!(result.map(|typ| typ.to_string()), expected);
 }
 
+#[test]
+fn find_type_in_interpolated_string() {
+    let _ = env_logger::try_init();
+
+    let result = find_type_loc(
+        r#"
+#[implicit]
+type Show a = { show : a -> String }
+let show ?s : [Show a] -> a -> String = s.show
+#[infix(left, 4)]
+let (++) x y : String -> String -> String = x
+let show_int : Show Int = { show = \_ -> "" }
+let abc = 1
+f"a ${abc} b"
+"#,
+        8,
+        8,
+    );
+    let expected = Ok("Int".to_string());
+
+    assert_eq!(result.map(|typ| typ.to_string()), expected);
+}
+
 #[test]
 fn parens_expr() {
     let _ = env_logger::try_init();
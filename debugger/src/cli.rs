@@ -0,0 +1,220 @@
+//! Interactive command line frontend for the debugger (`gluon debug FILE`)
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    sync::mpsc,
+};
+
+use gluon::{
+    base::{filename_to_module, pos::Line},
+    RootedThread,
+};
+
+use crate::{Debugger, Event, Frame, Resume, StopReason, Variable};
+
+const HELP: &str = "\
+Commands:
+    c, continue           Continue until the next breakpoint
+    s, step               Step to the next line, entering called functions
+    n, next               Step to the next line, stepping over called functions
+    o, out                Continue until the current function returns
+    b, break LINE         Set a breakpoint at LINE in the debugged file
+    b, break MODULE:LINE  Set a breakpoint at LINE in MODULE
+    b, break FUNCTION     Set a breakpoint on calls to FUNCTION
+    bt, backtrace         Print the call stack
+    l, locals [FRAME]     Print the local variables and upvars of FRAME (default 0)
+    h, help               Print this message
+    q, quit               Exit the debugger
+";
+
+/// Runs `file` under the debugger, reading commands from `input` and writing to `output`.
+/// Execution stops at the first line of the program.
+pub fn run<R, W>(thread: RootedThread, file: &str, mut input: R, mut output: W) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let source = fs::read_to_string(file)?;
+    let module = filename_to_module(file);
+
+    let (sender, events) = mpsc::channel();
+    let debugger = Debugger::attach(thread, move |event| {
+        let _ = sender.send(event);
+    });
+    debugger.launch(file, true)?;
+
+    while let Ok(event) = events.recv() {
+        match event {
+            Event::Stopped { reason, frames } => {
+                print_location(&mut output, &module, &source, reason, &frames)?;
+                match read_commands(&debugger, &module, &frames, &mut input, &mut output)? {
+                    Some(resume) => debugger.resume(resume),
+                    None => return Ok(()),
+                }
+            }
+            Event::Exited(result) => {
+                match result {
+                    Ok(()) => writeln!(output, "Program exited")?,
+                    Err(err) => writeln!(output, "Program exited with an error:\n{}", err)?,
+                }
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_location<W>(
+    output: &mut W,
+    module: &str,
+    source: &str,
+    reason: StopReason,
+    frames: &[Frame],
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let reason = match reason {
+        StopReason::Entry => "entry",
+        StopReason::Breakpoint => "breakpoint",
+        StopReason::FunctionBreakpoint => "function breakpoint",
+        StopReason::Step => "step",
+    };
+    let frame = match frames.first() {
+        Some(frame) => frame,
+        None => return writeln!(output, "Stopped ({})", reason),
+    };
+    write!(output, "Stopped ({}) in `{}`", reason, frame.function_name)?;
+    match frame.line {
+        Some(line) => {
+            writeln!(output, " at {}:{}", frame.source_name, line.to_usize() + 1)?;
+            if frame.source_name == module {
+                if let Some(text) = source.lines().nth(line.to_usize()) {
+                    writeln!(output, "{:>4} | {}", line.to_usize() + 1, text)?;
+                }
+            }
+        }
+        None => writeln!(output, " ({})", frame.source_name)?,
+    }
+    Ok(())
+}
+
+/// Reads and executes commands until one which resumes execution is given.
+/// Returns `None` if the debugger should exit.
+fn read_commands<R, W>(
+    debugger: &Debugger,
+    module: &str,
+    frames: &[Frame],
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Option<Resume>>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut line = String::new();
+    loop {
+        write!(output, "(gluon-debug) ")?;
+        output.flush()?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        let argument = words.next();
+
+        match command {
+            "c" | "continue" => return Ok(Some(Resume::Continue)),
+            "s" | "step" => return Ok(Some(Resume::StepIn)),
+            "n" | "next" => return Ok(Some(Resume::StepOver)),
+            "o" | "out" => return Ok(Some(Resume::StepOut)),
+            "q" | "quit" => return Ok(None),
+            "b" | "break" => match argument {
+                Some(argument) => set_breakpoint(debugger, module, argument, output)?,
+                None => writeln!(output, "Expected a line or function to break at")?,
+            },
+            "bt" | "backtrace" => {
+                for (i, frame) in frames.iter().enumerate() {
+                    write!(
+                        output,
+                        "#{} {} ({}",
+                        i, frame.function_name, frame.source_name
+                    )?;
+                    if let Some(line) = frame.line {
+                        write!(output, ":{}", line.to_usize() + 1)?;
+                    }
+                    writeln!(output, ")")?;
+                }
+            }
+            "l" | "locals" => {
+                let frame = match argument.map(|arg| arg.parse::<usize>()) {
+                    Some(Ok(frame)) => frame,
+                    None => 0,
+                    Some(Err(err)) => {
+                        writeln!(output, "Invalid frame: {}", err)?;
+                        continue;
+                    }
+                };
+                match debugger.variables(frame) {
+                    Some(variables) => {
+                        print_variables(output, "Locals", &variables.locals)?;
+                        print_variables(output, "Upvars", &variables.upvars)?;
+                    }
+                    None => writeln!(output, "No frame {}", frame)?,
+                }
+            }
+            "h" | "help" => write!(output, "{}", HELP)?,
+            _ => writeln!(output, "Unknown command `{}`, try `help`", command)?,
+        }
+    }
+}
+
+fn set_breakpoint<W>(
+    debugger: &Debugger,
+    module: &str,
+    argument: &str,
+    output: &mut W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let (breakpoint_module, line) = match argument.rfind(':') {
+        Some(i) => (&argument[..i], &argument[i + 1..]),
+        None => (module, argument),
+    };
+    match line.parse::<usize>() {
+        Ok(line) if line > 0 => {
+            debugger.add_line_breakpoint(breakpoint_module, Line::from((line - 1) as u32));
+            writeln!(output, "Breakpoint set at {}:{}", breakpoint_module, line)
+        }
+        Ok(_) => writeln!(output, "Lines start at 1"),
+        Err(_) => {
+            debugger.add_function_breakpoint(argument);
+            writeln!(output, "Breakpoint set on function `{}`", argument)
+        }
+    }
+}
+
+fn print_variables<W>(output: &mut W, header: &str, variables: &[Variable]) -> io::Result<()>
+where
+    W: Write,
+{
+    if variables.is_empty() {
+        return Ok(());
+    }
+    writeln!(output, "{}:", header)?;
+    for variable in variables {
+        writeln!(
+            output,
+            "    {}: {} = {}",
+            variable.name, variable.typ, variable.value
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,417 @@
+//! [Debug Adapter Protocol][dap] frontend for the debugger (`gluon debug --dap`)
+//!
+//! Only a single thread (with id `1`) is exposed to the client. Line numbers are assumed to start
+//! at 1 which is the default in the protocol.
+//!
+//! [dap]:https://microsoft.github.io/debug-adapter-protocol/
+
+use std::{
+    io::{self, BufRead, Write},
+    sync::{Arc, Mutex},
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use gluon::{
+    base::{filename_to_module, fnv::FnvMap, pos::Line},
+    RootedThread,
+};
+
+use gluon_rpc::{read_message, write_message};
+
+use crate::{Debugger, Event, Frame, Resume, StopReason};
+
+const THREAD_ID: i64 = 1;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    seq: i64,
+    command: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchArguments {
+    program: String,
+    #[serde(default)]
+    stop_on_entry: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Source {
+    path: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceBreakpoint {
+    line: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetBreakpointsArguments {
+    source: Source,
+    #[serde(default)]
+    breakpoints: Vec<SourceBreakpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionBreakpoint {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetFunctionBreakpointsArguments {
+    breakpoints: Vec<FunctionBreakpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScopesArguments {
+    frame_id: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VariablesArguments {
+    variables_reference: usize,
+}
+
+/// Writes messages to the client. Shared between the server and the thread running the program
+/// which reports when execution stops.
+struct Output<W> {
+    writer: W,
+    seq: i64,
+}
+
+impl<W> Output<W>
+where
+    W: Write,
+{
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &serde_json::to_string(&message)?)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The stack when execution last stopped
+    frames: Vec<Frame>,
+    /// Maps module names back to the paths that the client knows them by
+    paths: FnvMap<String, String>,
+}
+
+/// A Debug Adapter Protocol server debugging programs on a gluon thread
+pub struct Server {
+    thread: RootedThread,
+    debugger: Option<Debugger>,
+    state: Arc<Mutex<State>>,
+    launch: Option<LaunchArguments>,
+    configuration_done: bool,
+    launched: bool,
+}
+
+impl Server {
+    pub fn new(thread: RootedThread) -> Self {
+        Server {
+            thread,
+            debugger: None,
+            state: Default::default(),
+            launch: None,
+            configuration_done: false,
+            launched: false,
+        }
+    }
+
+    /// Processes requests from `input` until the client disconnects or closes the input
+    pub fn run<R, W>(mut self, mut input: R, output: W) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write + Send + 'static,
+    {
+        let output = Arc::new(Mutex::new(Output {
+            writer: output,
+            seq: 0,
+        }));
+
+        while let Some(content) = read_message(&mut input)? {
+            let request: Request = match serde_json::from_str(&content) {
+                Ok(request) => request,
+                Err(err) => {
+                    error!("Invalid request: {}", err);
+                    continue;
+                }
+            };
+            debug!("Request `{}`: {}", request.command, request.arguments);
+
+            let result = self.handle_request(&request, &output);
+            let mut response = json!({
+                "type": "response",
+                "request_seq": request.seq,
+                "command": request.command,
+            });
+            match result {
+                Ok(body) => {
+                    response["success"] = json!(true);
+                    response["body"] = body;
+                }
+                Err(message) => {
+                    response["success"] = json!(false);
+                    response["message"] = json!(message);
+                }
+            }
+            output.lock().unwrap().send(response)?;
+
+            match &request.command[..] {
+                "initialize" => output.lock().unwrap().event("initialized", json!({}))?,
+                "disconnect" => break,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request<W>(
+        &mut self,
+        request: &Request,
+        output: &Arc<Mutex<Output<W>>>,
+    ) -> Result<Value, String>
+    where
+        W: Write + Send + 'static,
+    {
+        let arguments = &request.arguments;
+        match &request.command[..] {
+            "initialize" => {
+                self.attach(output.clone());
+                Ok(json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsFunctionBreakpoints": true,
+                }))
+            }
+            "launch" => {
+                let launch: LaunchArguments = from_arguments(arguments)?;
+                self.state
+                    .lock()
+                    .unwrap()
+                    .paths
+                    .insert(filename_to_module(&launch.program), launch.program.clone());
+                self.launch = Some(launch);
+                self.start()?;
+                Ok(Value::Null)
+            }
+            "configurationDone" => {
+                self.configuration_done = true;
+                self.start()?;
+                Ok(Value::Null)
+            }
+            "setBreakpoints" => {
+                let args: SetBreakpointsArguments = from_arguments(arguments)?;
+                let path = args
+                    .source
+                    .path
+                    .or(args.source.name)
+                    .ok_or_else(|| "Expected a source path".to_string())?;
+                let module = filename_to_module(&path);
+                self.debugger()?.set_line_breakpoints(
+                    &module,
+                    args.breakpoints
+                        .iter()
+                        .map(|breakpoint| Line::from(breakpoint.line.saturating_sub(1))),
+                );
+                self.state.lock().unwrap().paths.insert(module, path);
+
+                Ok(json!({
+                    "breakpoints": args.breakpoints.iter().map(|breakpoint| json!({
+                        "verified": true,
+                        "line": breakpoint.line,
+                    })).collect::<Vec<_>>(),
+                }))
+            }
+            "setFunctionBreakpoints" => {
+                let args: SetFunctionBreakpointsArguments = from_arguments(arguments)?;
+                let count = args.breakpoints.len();
+                self.debugger()?.set_function_breakpoints(
+                    args.breakpoints
+                        .into_iter()
+                        .map(|breakpoint| breakpoint.name),
+                );
+                Ok(json!({ "breakpoints": vec![json!({ "verified": true }); count] }))
+            }
+            "setExceptionBreakpoints" => Ok(json!({})),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" => {
+                let state = self.state.lock().unwrap();
+                let frames = state
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .map(|(id, frame)| {
+                        let source = match state.paths.get(&frame.source_name) {
+                            Some(path) => json!({ "name": frame.source_name, "path": path }),
+                            None => json!({ "name": frame.source_name }),
+                        };
+                        json!({
+                            "id": id,
+                            "name": frame.function_name,
+                            "source": source,
+                            "line": frame.line.map_or(0, |line| line.to_usize() + 1),
+                            "column": 1,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "stackFrames": frames, "totalFrames": state.frames.len() }))
+            }
+            "scopes" => {
+                let args: ScopesArguments = from_arguments(arguments)?;
+                Ok(json!({
+                    "scopes": [
+                        {
+                            "name": "Locals",
+                            "variablesReference": args.frame_id * 2 + 1,
+                            "expensive": false,
+                        },
+                        {
+                            "name": "Upvars",
+                            "variablesReference": args.frame_id * 2 + 2,
+                            "expensive": false,
+                        },
+                    ]
+                }))
+            }
+            "variables" => {
+                let args: VariablesArguments = from_arguments(arguments)?;
+                let reference = args
+                    .variables_reference
+                    .checked_sub(1)
+                    .ok_or_else(|| "Invalid variables reference".to_string())?;
+                let variables = self
+                    .debugger()?
+                    .variables(reference / 2)
+                    .ok_or_else(|| "Execution is not stopped".to_string())?;
+                let variables = if reference % 2 == 0 {
+                    variables.locals
+                } else {
+                    variables.upvars
+                };
+                Ok(json!({
+                    "variables": variables.into_iter().map(|variable| json!({
+                        "name": variable.name,
+                        "value": variable.value,
+                        "type": variable.typ,
+                        "variablesReference": 0,
+                    })).collect::<Vec<_>>(),
+                }))
+            }
+            "continue" => {
+                self.debugger()?.resume(Resume::Continue);
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => {
+                self.debugger()?.resume(Resume::StepOver);
+                Ok(Value::Null)
+            }
+            "stepIn" => {
+                self.debugger()?.resume(Resume::StepIn);
+                Ok(Value::Null)
+            }
+            "stepOut" => {
+                self.debugger()?.resume(Resume::StepOut);
+                Ok(Value::Null)
+            }
+            "disconnect" => {
+                // Dropping the debugger lets the program run to completion
+                self.debugger = None;
+                Ok(Value::Null)
+            }
+            command => Err(format!("Unsupported request `{}`", command)),
+        }
+    }
+
+    fn attach<W>(&mut self, output: Arc<Mutex<Output<W>>>)
+    where
+        W: Write + Send + 'static,
+    {
+        if self.debugger.is_some() {
+            return;
+        }
+        let state = self.state.clone();
+        self.debugger = Some(Debugger::attach(self.thread.clone(), move |event| {
+            if let Err(err) = send_event(&state, &output, event) {
+                error!("Unable to send event: {}", err);
+            }
+        }));
+    }
+
+    fn debugger(&self) -> Result<&Debugger, String> {
+        self.debugger
+            .as_ref()
+            .ok_or_else(|| "`initialize` must be the first request".to_string())
+    }
+
+    /// Starts the program once it has been launched and all breakpoints are configured
+    fn start(&mut self) -> Result<(), String> {
+        if self.launched || !self.configuration_done {
+            return Ok(());
+        }
+        if let Some(launch) = &self.launch {
+            self.debugger()?
+                .launch(&launch.program, launch.stop_on_entry)
+                .map_err(|err| format!("Unable to launch `{}`: {}", launch.program, err))?;
+            self.launched = true;
+        }
+        Ok(())
+    }
+}
+
+fn send_event<W>(state: &Mutex<State>, output: &Mutex<Output<W>>, event: Event) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut output = output.lock().unwrap();
+    match event {
+        Event::Stopped { reason, frames } => {
+            state.lock().unwrap().frames = frames;
+            let reason = match reason {
+                StopReason::Entry => "entry",
+                StopReason::Breakpoint => "breakpoint",
+                StopReason::FunctionBreakpoint => "function breakpoint",
+                StopReason::Step => "step",
+            };
+            output.event(
+                "stopped",
+                json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+            )
+        }
+        Event::Exited(result) => {
+            state.lock().unwrap().frames.clear();
+            let exit_code = match result {
+                Ok(()) => 0,
+                Err(err) => {
+                    output.event(
+                        "output",
+                        json!({ "category": "stderr", "output": format!("{}\n", err) }),
+                    )?;
+                    1
+                }
+            };
+            output.event("exited", json!({ "exitCode": exit_code }))?;
+            output.event("terminated", json!({}))
+        }
+    }
+}
+
+fn from_arguments<T>(arguments: &Value) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_value(arguments.clone()).map_err(|err| err.to_string())
+}
@@ -0,0 +1,444 @@
+//! Step debugger for the gluon programming language.
+//!
+//! The debugger is implemented on top of the hooks provided by `gluon_vm`
+//! (`Context::set_hook`). While the debugged program runs on its own OS thread the hook checks
+//! every executed line and every function call against the registered breakpoints and the current
+//! stepping mode. Once execution stops the hook blocks, answering inspection requests (frames,
+//! locals and upvars) until it is told to resume.
+//!
+//! Two frontends are provided, an interactive command line debugger (`gluon debug FILE`) and a
+//! [Debug Adapter Protocol][dap] server (`gluon debug --dap`) which lets editors drive the debugger.
+//!
+//! [dap]:https://microsoft.github.io/debug-adapter-protocol/
+#![doc(html_root_url = "https://docs.rs/gluon_debugger/0.14.1")] // # GLUON
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+
+pub mod cli;
+pub mod dap;
+
+use std::{
+    fs, io,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use {futures::task::Poll, structopt::StructOpt};
+
+use gluon::{
+    base::{
+        filename_to_module,
+        fnv::{FnvMap, FnvSet},
+        pos::Line,
+        types::ArcType,
+    },
+    vm::{
+        internal::ValuePrinter,
+        thread::{DebugInfo, HookFlags, ThreadInternal},
+        Variants,
+    },
+    RootedThread, Thread, ThreadExt,
+};
+
+#[derive(StructOpt)]
+#[structopt(about = "Debugs a gluon program")]
+pub struct Opt {
+    #[structopt(
+        long = "dap",
+        help = "Runs a Debug Adapter Protocol server over stdin/stdout instead of the interactive debugger"
+    )]
+    pub dap: bool,
+    #[structopt(
+        name = "FILE",
+        help = "The gluon program to debug (required unless `--dap` is given)"
+    )]
+    pub input: Option<String>,
+}
+
+/// Runs the debugger frontend selected by `opt` on stdin/stdout
+pub fn run(thread: RootedThread, opt: &Opt) -> io::Result<()> {
+    let stdin = io::stdin();
+    if opt.dap {
+        dap::Server::new(thread).run(stdin.lock(), io::stdout())
+    } else {
+        let file = opt.input.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Expected a file to debug")
+        })?;
+        let stdout = io::stdout();
+        cli::run(thread, file, stdin.lock(), stdout.lock())
+    }
+}
+
+/// A frame of the call stack, with the most recently called function first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub function_name: String,
+    pub source_name: String,
+    /// The line (starting from 0) that is currently executing in this frame
+    pub line: Option<Line>,
+}
+
+/// A variable which is visible from a stack frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub typ: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameVariables {
+    pub locals: Vec<Variable>,
+    pub upvars: Vec<Variable>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// Stopped at the first line of the program
+    Entry,
+    Breakpoint,
+    FunctionBreakpoint,
+    Step,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// Execution has stopped and the debugger is ready to inspect the stack
+    Stopped {
+        reason: StopReason,
+        frames: Vec<Frame>,
+    },
+    /// The program finished, either successfully or with an error message
+    Exited(Result<(), String>),
+}
+
+/// How execution should continue after it has stopped
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resume {
+    /// Run until the next breakpoint
+    Continue,
+    /// Stop at the next line, entering any called function
+    StepIn,
+    /// Stop at the next line of the current function (or a function lower in the stack)
+    StepOver,
+    /// Stop once the current function has returned
+    StepOut,
+}
+
+enum Command {
+    Resume(Resume),
+    Variables {
+        frame: usize,
+        reply: mpsc::Sender<Option<FrameVariables>>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Run,
+    StepIn,
+    // The stack depth at which the step was started
+    StepOver(usize),
+    StepOut(usize),
+}
+
+#[derive(Default)]
+struct Breakpoints {
+    lines: FnvMap<String, FnvSet<Line>>,
+    functions: FnvSet<String>,
+    /// Stop at the first line executed in this module
+    entry: Option<String>,
+}
+
+struct Shared {
+    breakpoints: Breakpoints,
+    stopped: bool,
+    /// Set when the `Debugger` is dropped, after which the hook never stops execution
+    detached: bool,
+}
+
+type EventHandler = Box<dyn FnMut(Event) + Send>;
+
+/// Handle to a debugger attached to a gluon thread
+pub struct Debugger {
+    thread: RootedThread,
+    shared: Arc<Mutex<Shared>>,
+    commands: mpsc::Sender<Command>,
+    on_event: Arc<Mutex<EventHandler>>,
+}
+
+impl Debugger {
+    /// Attaches a debugger to `thread` by installing a line and call hook. `on_event` is called
+    /// (from the thread running the program) whenever execution stops or the program exits.
+    pub fn attach<F>(thread: RootedThread, on_event: F) -> Debugger
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            breakpoints: Breakpoints::default(),
+            stopped: false,
+            detached: false,
+        }));
+        let on_event: Arc<Mutex<EventHandler>> = Arc::new(Mutex::new(Box::new(on_event)));
+        let (commands, command_receiver) = mpsc::channel();
+
+        let mut hook = Hook {
+            shared: shared.clone(),
+            commands: Mutex::new(command_receiver),
+            on_event: on_event.clone(),
+            mode: Mode::Run,
+        };
+        {
+            let mut context = thread.context();
+            context.set_hook(Some(Box::new(move |thread, info| hook.run(thread, info))));
+            context.set_hook_mask(HookFlags::LINE_FLAG | HookFlags::CALL_FLAG);
+        }
+
+        Debugger {
+            thread,
+            shared,
+            commands,
+            on_event,
+        }
+    }
+
+    /// Replaces all line breakpoints in `module`. Lines start from 0.
+    pub fn set_line_breakpoints<I>(&self, module: &str, lines: I)
+    where
+        I: IntoIterator<Item = Line>,
+    {
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .breakpoints
+            .lines
+            .insert(module.to_string(), lines.into_iter().collect());
+    }
+
+    pub fn add_line_breakpoint(&self, module: &str, line: Line) {
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .breakpoints
+            .lines
+            .entry(module.to_string())
+            .or_default()
+            .insert(line);
+    }
+
+    /// Replaces all function breakpoints. Execution stops whenever a function with one of these
+    /// names is called.
+    pub fn set_function_breakpoints<I>(&self, names: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut shared = self.shared.lock().unwrap();
+        shared.breakpoints.functions = names.into_iter().collect();
+    }
+
+    pub fn add_function_breakpoint(&self, name: &str) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.breakpoints.functions.insert(name.to_string());
+    }
+
+    /// Starts running the gluon program in `filename` on a new OS thread.
+    /// If `stop_on_entry` is true execution stops at the first line of the program.
+    pub fn launch(
+        &self,
+        filename: &str,
+        stop_on_entry: bool,
+    ) -> io::Result<thread::JoinHandle<()>> {
+        let source = fs::read_to_string(filename)?;
+        let module = filename_to_module(filename);
+        if stop_on_entry {
+            self.shared.lock().unwrap().breakpoints.entry = Some(module);
+        }
+
+        let thread = self.thread.clone();
+        let on_event = self.on_event.clone();
+        let filename = filename.to_string();
+        Ok(thread::spawn(move || {
+            let result = futures::executor::block_on(thread.load_script_async(&filename, &source))
+                .map_err(|err| err.to_string());
+            (on_event.lock().unwrap())(Event::Exited(result));
+        }))
+    }
+
+    /// Returns true if execution is currently stopped
+    pub fn is_stopped(&self) -> bool {
+        self.shared.lock().unwrap().stopped
+    }
+
+    /// Resumes execution. Does nothing if execution is not stopped.
+    pub fn resume(&self, resume: Resume) {
+        if self.is_stopped() {
+            let _ = self.commands.send(Command::Resume(resume));
+        }
+    }
+
+    /// Returns the variables visible from `frame` (0 being the most recent call).
+    /// Returns `None` if execution is not stopped or there is no such frame.
+    pub fn variables(&self, frame: usize) -> Option<FrameVariables> {
+        if !self.is_stopped() {
+            return None;
+        }
+        let (reply, receiver) = mpsc::channel();
+        self.commands
+            .send(Command::Variables { frame, reply })
+            .ok()?;
+        receiver.recv().ok().and_then(|variables| variables)
+    }
+}
+
+impl Drop for Debugger {
+    fn drop(&mut self) {
+        // Let the program run to completion without the debugger. The hook is disabled through
+        // the shared state as the thread's context stays locked while execution is stopped so
+        // removing the hook with `set_hook` could block.
+        {
+            let mut shared = self.shared.lock().unwrap();
+            shared.breakpoints = Breakpoints::default();
+            shared.detached = true;
+        }
+        self.resume(Resume::Continue);
+    }
+}
+
+struct Hook {
+    shared: Arc<Mutex<Shared>>,
+    commands: Mutex<mpsc::Receiver<Command>>,
+    on_event: Arc<Mutex<EventHandler>>,
+    mode: Mode,
+}
+
+impl Hook {
+    fn run(&mut self, thread: &Thread, info: DebugInfo) -> Poll<gluon::vm::Result<()>> {
+        if let Some(reason) = self.stop_reason(&info) {
+            self.stop(thread, &info, reason);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn stop_reason(&mut self, info: &DebugInfo) -> Option<StopReason> {
+        let stack_info = info.stack_info(0)?;
+        let depth = info.stack_info_len();
+        let mut shared = self.shared.lock().unwrap();
+        if shared.detached {
+            return None;
+        }
+        let breakpoints = &mut shared.breakpoints;
+
+        if info.state().contains(HookFlags::CALL_FLAG) {
+            let function_name = stack_info.function_name()?;
+            return if breakpoints.functions.contains(function_name) {
+                Some(StopReason::FunctionBreakpoint)
+            } else {
+                None
+            };
+        }
+
+        let line = stack_info.line()?;
+        let source_name = stack_info.source_name();
+        if breakpoints.entry.as_ref().map(|s| &s[..]) == Some(source_name) {
+            breakpoints.entry = None;
+            return Some(StopReason::Entry);
+        }
+        if breakpoints
+            .lines
+            .get(source_name)
+            .map_or(false, |lines| lines.contains(&line))
+        {
+            return Some(StopReason::Breakpoint);
+        }
+        match self.mode {
+            Mode::StepIn => Some(StopReason::Step),
+            Mode::StepOver(start_depth) if depth <= start_depth => Some(StopReason::Step),
+            Mode::StepOut(start_depth) if depth < start_depth => Some(StopReason::Step),
+            _ => None,
+        }
+    }
+
+    fn stop(&mut self, thread: &Thread, info: &DebugInfo, reason: StopReason) {
+        debug!("Stopped: {:?}", reason);
+        self.shared.lock().unwrap().stopped = true;
+        (self.on_event.lock().unwrap())(Event::Stopped {
+            reason,
+            frames: frames(info),
+        });
+
+        let depth = info.stack_info_len();
+        let commands = self.commands.lock().unwrap();
+        self.mode = loop {
+            match commands.recv() {
+                Ok(Command::Resume(resume)) => {
+                    break match resume {
+                        Resume::Continue => Mode::Run,
+                        Resume::StepIn => Mode::StepIn,
+                        Resume::StepOver => Mode::StepOver(depth),
+                        Resume::StepOut => Mode::StepOut(depth),
+                    }
+                }
+                Ok(Command::Variables { frame, reply }) => {
+                    let _ = reply.send(variables(thread, info, frame));
+                }
+                // The debugger has been dropped
+                Err(mpsc::RecvError) => break Mode::Run,
+            }
+        };
+        self.shared.lock().unwrap().stopped = false;
+    }
+}
+
+fn frames(info: &DebugInfo) -> Vec<Frame> {
+    (0..info.stack_info_len())
+        .filter_map(|level| {
+            let stack_info = info.stack_info(level)?;
+            Some(Frame {
+                function_name: stack_info.function_name()?.to_string(),
+                source_name: stack_info.source_name().to_string(),
+                line: stack_info.line(),
+            })
+        })
+        .collect()
+}
+
+fn variables(thread: &Thread, info: &DebugInfo, frame: usize) -> Option<FrameVariables> {
+    // `frames` skips frames without a function so the same needs to be done here
+    let stack_info = (0..info.stack_info_len())
+        .filter_map(|level| info.stack_info(level))
+        .filter(|stack_info| stack_info.function_name().is_some())
+        .nth(frame)?;
+
+    let env = thread.get_env();
+    let debug_level = thread.global_env().get_debug_level();
+    let show = |typ: &ArcType, value: Option<Variants>| match value {
+        Some(value) => ValuePrinter::new(&env, typ, value, &debug_level)
+            .width(80)
+            .max_level(5)
+            .to_string(),
+        None => "<unavailable>".to_string(),
+    };
+
+    let locals = stack_info
+        .locals()
+        .map(|local| Variable {
+            name: local.name.declared_name().to_string(),
+            typ: local.typ.to_string(),
+            value: show(&local.typ, stack_info.local_value(local)),
+        })
+        .collect();
+    let upvars = stack_info
+        .upvars()
+        .iter()
+        .enumerate()
+        .map(|(i, upvar)| Variable {
+            name: upvar.name.clone(),
+            typ: upvar.typ.to_string(),
+            value: show(&upvar.typ, stack_info.upvar_value(i)),
+        })
+        .collect();
+    Some(FrameVariables { locals, upvars })
+}
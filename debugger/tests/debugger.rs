@@ -0,0 +1,329 @@
+#[macro_use]
+extern crate pretty_assertions;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use gluon::{base::pos::Line, RootedThread, ThreadExt};
+
+use gluon_debugger::{cli, dap, Debugger, Event, Frame, Resume, StopReason, Variable};
+
+const PROGRAM: &str = r#"let add x y =
+    let z = x #Int+ y
+    z
+let a = 1
+let b = add a 2
+b
+"#;
+
+fn new_vm() -> RootedThread {
+    let thread = gluon::new_vm();
+    {
+        let mut db = thread.get_database_mut();
+        db.set_optimize(false);
+        db.set_implicit_prelude(false);
+    }
+    thread
+}
+
+fn write_program(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gluon_debugger_{}.glu", name));
+    fs::write(&path, PROGRAM).unwrap();
+    path
+}
+
+struct Session {
+    debugger: Debugger,
+    events: mpsc::Receiver<Event>,
+    module: String,
+}
+
+impl Session {
+    fn new(name: &str) -> (Session, String) {
+        let path = write_program(name);
+        let file = path.to_str().unwrap().to_string();
+        let (sender, events) = mpsc::channel();
+        let debugger = Debugger::attach(new_vm(), move |event| {
+            let _ = sender.send(event);
+        });
+        let session = Session {
+            debugger,
+            events,
+            module: gluon::base::filename_to_module(&file),
+        };
+        (session, file)
+    }
+
+    fn next_event(&self) -> Event {
+        self.events
+            .recv_timeout(Duration::from_secs(30))
+            .expect("Timed out waiting for an event")
+    }
+
+    fn expect_stop(&self) -> (StopReason, Vec<Frame>) {
+        match self.next_event() {
+            Event::Stopped { reason, frames } => (reason, frames),
+            event => panic!("Expected the program to stop: {:?}", event),
+        }
+    }
+
+    fn expect_exit(&self) {
+        assert_eq!(self.next_event(), Event::Exited(Ok(())));
+    }
+}
+
+#[test]
+fn line_breakpoint_and_locals() {
+    let _ = env_logger::try_init();
+
+    let (session, file) = Session::new("line_breakpoint");
+    session
+        .debugger
+        .set_line_breakpoints(&session.module, Some(Line::from(2)));
+    session.debugger.launch(&file, false).unwrap();
+
+    let (reason, frames) = session.expect_stop();
+    assert_eq!(reason, StopReason::Breakpoint);
+    assert_eq!(
+        frames[0],
+        Frame {
+            function_name: "add".to_string(),
+            source_name: session.module.clone(),
+            line: Some(Line::from(2)),
+        }
+    );
+
+    let variables = session.debugger.variables(0).unwrap();
+    for (name, value) in &[("x", "1"), ("y", "2"), ("z", "3")] {
+        assert!(
+            variables.locals.contains(&Variable {
+                name: name.to_string(),
+                typ: "Int".to_string(),
+                value: value.to_string(),
+            }),
+            "{:?}",
+            variables.locals
+        );
+    }
+    assert!(session.debugger.variables(frames.len()).is_none());
+
+    session.debugger.resume(Resume::Continue);
+    session.expect_exit();
+    assert!(session.debugger.variables(0).is_none());
+}
+
+#[test]
+fn step_over_stays_in_the_current_function() {
+    let _ = env_logger::try_init();
+
+    let (session, file) = Session::new("step_over");
+    session.debugger.launch(&file, true).unwrap();
+
+    let (reason, frames) = session.expect_stop();
+    assert_eq!(reason, StopReason::Entry);
+    let depth = frames.len();
+
+    let mut lines = Vec::new();
+    loop {
+        session.debugger.resume(Resume::StepOver);
+        match session.next_event() {
+            Event::Stopped { reason, frames } => {
+                assert_eq!(reason, StopReason::Step);
+                assert_eq!(frames.len(), depth, "{:?}", frames);
+                lines.extend(frames[0].line.map(|line| line.to_usize()));
+            }
+            Event::Exited(result) => {
+                assert_eq!(result, Ok(()));
+                break;
+            }
+        }
+    }
+    assert_eq!(lines.last(), Some(&5));
+}
+
+#[test]
+fn function_breakpoint_and_step_out() {
+    let _ = env_logger::try_init();
+
+    let (session, file) = Session::new("function_breakpoint");
+    session.debugger.add_function_breakpoint("add");
+    session.debugger.launch(&file, false).unwrap();
+
+    let (reason, frames) = session.expect_stop();
+    assert_eq!(reason, StopReason::FunctionBreakpoint);
+    assert_eq!(frames[0].function_name, "add");
+
+    session.debugger.resume(Resume::StepOut);
+    let (reason, frames) = session.expect_stop();
+    assert_eq!(reason, StopReason::Step);
+    assert_ne!(frames[0].function_name, "add");
+    assert_eq!(frames[0].line, Some(Line::from(5)));
+
+    session.debugger.resume(Resume::Continue);
+    session.expect_exit();
+}
+
+#[test]
+fn step_in_enters_called_functions() {
+    let _ = env_logger::try_init();
+
+    let (session, file) = Session::new("step_in");
+    session
+        .debugger
+        .set_line_breakpoints(&session.module, Some(Line::from(4)));
+    session.debugger.launch(&file, false).unwrap();
+
+    let (reason, _) = session.expect_stop();
+    assert_eq!(reason, StopReason::Breakpoint);
+
+    session.debugger.resume(Resume::StepIn);
+    let (reason, frames) = session.expect_stop();
+    assert_eq!(reason, StopReason::Step);
+    assert_eq!(frames[0].function_name, "add");
+
+    session.debugger.resume(Resume::Continue);
+    session.expect_exit();
+}
+
+#[test]
+fn drop_while_stopped_runs_the_program_to_completion() {
+    let _ = env_logger::try_init();
+
+    let (session, file) = Session::new("drop_while_stopped");
+    session
+        .debugger
+        .set_line_breakpoints(&session.module, vec![Line::from(2), Line::from(5)]);
+    let handle = session.debugger.launch(&file, false).unwrap();
+
+    let (reason, _) = session.expect_stop();
+    assert_eq!(reason, StopReason::Breakpoint);
+
+    let Session {
+        debugger, events, ..
+    } = session;
+    drop(debugger);
+
+    assert_eq!(
+        events.recv_timeout(Duration::from_secs(30)),
+        Ok(Event::Exited(Ok(())))
+    );
+    handle.join().unwrap();
+}
+
+#[test]
+fn cli_session() {
+    let _ = env_logger::try_init();
+
+    let path = write_program("cli");
+    let input = "break 3\ncontinue\nlocals\nbacktrace\ncontinue\n";
+    let mut output = Vec::new();
+    cli::run(
+        new_vm(),
+        path.to_str().unwrap(),
+        input.as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Stopped (entry)"), "{}", output);
+    assert!(
+        output.contains("Stopped (breakpoint) in `add`"),
+        "{}",
+        output
+    );
+    assert!(output.contains("   3 |     z"), "{}", output);
+    assert!(output.contains("    z: Int = 3"), "{}", output);
+    assert!(output.contains("#0 add"), "{}", output);
+    assert!(output.ends_with("Program exited\n"), "{}", output);
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn dap_message(value: serde_json::Value) -> String {
+    let content = value.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+fn dap_responses(output: &SharedBuffer) -> Vec<serde_json::Value> {
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    output
+        .split("Content-Length: ")
+        .filter(|message| !message.is_empty())
+        .map(|message| {
+            let content = &message[message.find("\r\n\r\n").unwrap() + 4..];
+            serde_json::from_str(content).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn dap_initialize() {
+    let _ = env_logger::try_init();
+
+    let input = [
+        dap_message(serde_json::json!({
+            "seq": 1, "type": "request", "command": "initialize",
+            "arguments": { "adapterID": "gluon" }
+        })),
+        dap_message(serde_json::json!({ "seq": 2, "type": "request", "command": "threads" })),
+        dap_message(serde_json::json!({ "seq": 3, "type": "request", "command": "unknown" })),
+        dap_message(serde_json::json!({ "seq": 4, "type": "request", "command": "disconnect" })),
+    ]
+    .concat();
+
+    let output = SharedBuffer::default();
+    dap::Server::new(new_vm())
+        .run(input.as_bytes(), output.clone())
+        .unwrap();
+
+    let messages = dap_responses(&output);
+    let summary: Vec<_> = messages
+        .iter()
+        .map(|message| {
+            (
+                message["type"].as_str().unwrap().to_string(),
+                message["command"]
+                    .as_str()
+                    .or(message["event"].as_str())
+                    .unwrap()
+                    .to_string(),
+                message["success"].as_bool(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("response".to_string(), "initialize".to_string(), Some(true)),
+            ("event".to_string(), "initialized".to_string(), None),
+            ("response".to_string(), "threads".to_string(), Some(true)),
+            ("response".to_string(), "unknown".to_string(), Some(false)),
+            ("response".to_string(), "disconnect".to_string(), Some(true)),
+        ]
+    );
+    assert_eq!(
+        messages[0]["body"]["supportsFunctionBreakpoints"],
+        serde_json::json!(true)
+    );
+    assert_eq!(
+        messages[2]["body"]["threads"][0]["id"],
+        serde_json::json!(1)
+    );
+}
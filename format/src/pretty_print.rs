@@ -9,8 +9,8 @@ use {
 use self::types::pretty_print as pretty_types;
 use base::{
     ast::{
-        Do, Expr, Literal, Pattern, PatternField, SpannedExpr, SpannedPattern, ValueBinding,
-        ValueBindings,
+        Do, Expr, InterpolationPart, Literal, Pattern, PatternField, SpannedExpr, SpannedPattern,
+        ValueBinding, ValueBindings,
     },
     kind::Kind,
     metadata::Attribute,
@@ -292,6 +292,21 @@ where
                 ]
             }
 
+            Expr::Interpolation(ref interpolation) => chain![arena;
+                "f\"",
+                arena.concat(interpolation.parts.iter().map(|part| match *part {
+                    InterpolationPart::Text(ref text) => {
+                        arena.text(self.source.src_slice(text.span))
+                    }
+                    InterpolationPart::Expr { ref expr, .. } => chain![arena;
+                        "${",
+                        pretty(expr),
+                        "}"
+                    ],
+                })),
+                "\""
+            ],
+
             Expr::Literal(ref literal) => {
                 let text = self.source.src_slice(expr.span);
                 let literally = text.starts_with('"')
@@ -306,6 +321,7 @@ where
                     match *literal {
                         Literal::Byte(b) => arena.text(b.to_string()),
                         Literal::Int(i) => arena.text(i.to_string()),
+                        Literal::SizedInt(i, typ) => arena.text(typ.format_int(i)),
                         Literal::Float(f) => arena.text(f.to_string()),
                         Literal::String(ref s) => chain![arena;
                             "\"",
@@ -332,6 +348,13 @@ where
                     chain![arena;
                         "| ",
                         self.pretty_pattern(&alt.pattern),
+                        match alt.guard {
+                            Some(ref guard) => chain![arena;
+                                " if ",
+                                pretty(guard).group()
+                            ],
+                            None => arena.nil(),
+                        },
                         " ->",
                         self.hang(arena.nil(), (self.space_before(alt.expr.span.start()), true), &alt.expr).group()
                     ]
@@ -879,6 +902,39 @@ where
                 ")"
             ]
             .group(),
+            Pattern::Array {
+                ref elems,
+                ref rest,
+                ..
+            } => chain![arena;
+                "[",
+                arena.concat(self.comma_sep_paren(
+                    elems
+                        .iter()
+                        .map(|elem| pos::spanned(elem.span, self.pretty_pattern(elem)))
+                        .chain(rest.as_ref().map(|rest| {
+                            let doc = match rest.value {
+                                // `..` is sugar for `.._`
+                                Pattern::Ident(ref id) if id.name.as_ref() == "_" => arena.text(".."),
+                                _ => arena.text("..").append(self.pretty_pattern(rest)),
+                            };
+                            pos::spanned(rest.span, doc)
+                        })),
+                    |elem| elem.value)
+                ),
+                "]"
+            ]
+            .group(),
+            Pattern::Or(ref alternatives) => prec.enclose(
+                Prec::Function,
+                arena,
+                arena.concat(alternatives.iter().enumerate().map(|(i, alternative)| {
+                    chain![arena;
+                        if i == 0 { arena.nil() } else { arena.text(" | ") },
+                        self.pretty_pattern_(alternative, Prec::Function)
+                    ]
+                })),
+            ),
             Pattern::Error => arena.text("<error>"),
             Pattern::Literal(_) => arena.text(self.source.src_slice(pattern.span)),
         }
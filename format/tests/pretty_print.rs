@@ -167,6 +167,28 @@ match None with
     assert_eq!(&format_expr(expr).unwrap(), expr);
 }
 
+#[test]
+fn pattern_match_guard() {
+    let expr = r#"
+match None with
+| Some x if x > 0 && x < 10 -> x
+| Some x if f x -> 0
+| _ -> 123
+"#;
+    assert_diff!(&format_expr(expr).unwrap(), expr, "\n", 0);
+}
+
+#[test]
+fn array_and_or_patterns() {
+    let expr = r#"
+match xs with
+| [] -> 0
+| [Some (1 | 2), ..] -> 1
+| [x, ..rest] | [_, x, ..rest] -> 2
+"#;
+    assert_diff!(&format_expr(expr).unwrap(), expr, "\n", 0);
+}
+
 #[test]
 fn long_pattern_match() {
     let expr = r#"
@@ -766,6 +788,19 @@ let rest x =
     assert_diff!(&format_expr(expr).unwrap(), expr, "\n", 0);
 }
 
+#[test]
+fn interpolated_string() {
+    let expr = r#"
+let x = f"a ${ x+1 } \${b} ${ { y = "" }.y }"
+x
+"#;
+    let expected = r#"
+let x = f"a ${x + 1} \${b} ${{ y = "" }.y}"
+x
+"#;
+    assert_diff!(&format_expr(expr).unwrap(), expected, "\n", 0);
+}
+
 #[test]
 fn comment_in_lambda() {
     let expr = r#"
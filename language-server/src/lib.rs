@@ -0,0 +1,787 @@
+//! Language server for the gluon programming language.
+//!
+//! Speaks the [language server protocol][lsp] over any `BufRead`/`Write` pair (stdio for the
+//! `gluon_language-server` binary). Documents are compiled through the incremental compiler
+//! database of `gluon` so an edit only needs to recompile the edited module while all imported
+//! modules stay cached. Editor queries (hover, completion, etc) are answered by `gluon_completion`
+//! using the (possibly partially) typechecked expression of the document.
+//!
+//! [lsp]:https://microsoft.github.io/language-server-protocol/
+#![doc(html_root_url = "https://docs.rs/gluon_language-server/0.14.1")] // # GLUON
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+
+extern crate gluon_completion as completion;
+
+pub mod position;
+pub mod rpc;
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use {
+    codespan::FileMap,
+    codespan_reporting::{LabelStyle, Severity},
+    futures::executor::block_on,
+    lsp_types::{
+        notification::{self, Notification},
+        request::{self, Request},
+        CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams,
+        CompletionResponse, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+        DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentHighlight,
+        DocumentHighlightParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+        Documentation, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+        HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, Location,
+        MarkupContent, MarkupKind, OneOf, ParameterInformation, ParameterLabel,
+        PublishDiagnosticsParams, ReferenceParams, ServerCapabilities, ServerInfo, SignatureHelp,
+        SignatureHelpOptions, SignatureHelpParams, SignatureInformation, SymbolKind,
+        TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::Value,
+};
+
+use gluon::{
+    base::{
+        ast::{
+            walk_expr, walk_pattern, Expr, OwnedExpr, Pattern, PatternField, SpannedExpr,
+            SpannedPattern, Visitor,
+        },
+        error::{AsDiagnostic, InFile},
+        filename_to_module,
+        fnv::FnvMap,
+        metadata::Metadata,
+        pos::{BytePos, Span},
+        symbol::{Symbol, SymbolRef},
+        types::{ArcType, Type, TypeExt},
+    },
+    compiler_pipeline::TypecheckValue,
+    query::{Compilation, CompilationBase},
+    Error as GluonError, RootedThread, ThreadExt, VmBuilder,
+};
+
+use crate::{
+    position::{byte_pos_to_position, byte_span_to_range, position_to_byte_pos},
+    rpc::{error_code, Message, ResponseError},
+};
+
+type TypecheckedModule = TypecheckValue<Arc<OwnedExpr<Symbol>>>;
+
+struct Document {
+    version: i32,
+    module: String,
+    /// The last (possibly partially) typechecked version of the document
+    value: Option<TypecheckedModule>,
+}
+
+/// State of a single language server session
+#[derive(Default)]
+pub struct Server {
+    thread: Option<RootedThread>,
+    root: Option<PathBuf>,
+    documents: FnvMap<Url, Document>,
+    shutdown_requested: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    /// Returns true if the client has sent a `shutdown` request. Clients are expected to send
+    /// `shutdown` before `exit`, if they do not the process should exit with an error code.
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown_requested
+    }
+
+    /// Processes messages from `input` until the client sends `exit` or closes the input
+    pub fn run<R, W>(&mut self, mut input: R, mut output: W) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        while let Some(content) = rpc::read_message(&mut input)? {
+            if self.handle_message(&content, &mut output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a single message, writing any responses and notifications to `output`.
+    /// Returns `true` if the server should exit.
+    pub fn handle_message<W>(&mut self, content: &str, output: &mut W) -> io::Result<bool>
+    where
+        W: ?Sized + Write,
+    {
+        match rpc::parse_message(content) {
+            Ok(Message::Request { id, method, params }) => {
+                debug!("Request `{}`: {}", method, params);
+                let result = self.handle_request(&method, params);
+                rpc::write_response(output, id, result)?;
+            }
+            Ok(Message::Notification { method, params }) => {
+                debug!("Notification `{}`: {}", method, params);
+                if method == notification::Exit::METHOD {
+                    return Ok(true);
+                }
+                if let Err(err) = self.handle_notification(&method, params, output) {
+                    error!("Error when handling `{}`: {}", method, err.message);
+                }
+            }
+            Ok(Message::Response { id }) => debug!("Ignoring response to {}", id),
+            Err(err) => rpc::write_response(output, Value::Null, Err(err))?,
+        }
+        Ok(false)
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, ResponseError> {
+        if method == request::Initialize::METHOD {
+            return respond(self.initialize(from_params(params)?));
+        }
+        if self.thread.is_none() {
+            return Err(ResponseError::new(
+                error_code::SERVER_NOT_INITIALIZED,
+                "`initialize` must be the first request",
+            ));
+        }
+        match method {
+            request::Shutdown::METHOD => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            request::HoverRequest::METHOD => respond(self.hover(from_params(params)?)),
+            request::Completion::METHOD => respond(self.completion(from_params(params)?)),
+            request::SignatureHelpRequest::METHOD => {
+                respond(self.signature_help(from_params(params)?))
+            }
+            request::GotoDefinition::METHOD => respond(self.definition(from_params(params)?)),
+            request::References::METHOD => respond(self.references(from_params(params)?)),
+            request::DocumentHighlightRequest::METHOD => {
+                respond(self.document_highlight(from_params(params)?))
+            }
+            request::DocumentSymbolRequest::METHOD => {
+                respond(self.document_symbols(from_params(params)?))
+            }
+            _ => Err(ResponseError::new(
+                error_code::METHOD_NOT_FOUND,
+                format!("Unknown method `{}`", method),
+            )),
+        }
+    }
+
+    fn handle_notification<W>(
+        &mut self,
+        method: &str,
+        params: Value,
+        output: &mut W,
+    ) -> Result<(), ResponseError>
+    where
+        W: ?Sized + Write,
+    {
+        if self.thread.is_none() {
+            return Ok(());
+        }
+        let diagnostics = match method {
+            notification::DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = from_params(params)?;
+                let document = params.text_document;
+                Some(self.update_document(document.uri, document.version, document.text)?)
+            }
+            notification::DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = from_params(params)?;
+                // Only full synchronization is advertised so the last change contains the whole
+                // document
+                match params.content_changes.into_iter().last() {
+                    Some(change) => Some(self.update_document(
+                        params.text_document.uri,
+                        params.text_document.version,
+                        change.text,
+                    )?),
+                    None => None,
+                }
+            }
+            notification::DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = from_params(params)?;
+                self.documents.remove(&params.text_document.uri);
+                Some(PublishDiagnosticsParams {
+                    uri: params.text_document.uri,
+                    diagnostics: Vec::new(),
+                    version: None,
+                })
+            }
+            _ => None,
+        };
+        if let Some(diagnostics) = diagnostics {
+            rpc::write_notification(
+                output,
+                notification::PublishDiagnostics::METHOD,
+                serde_json::to_value(diagnostics).map_err(ResponseError::internal)?,
+            )
+            .map_err(ResponseError::internal)?;
+        }
+        Ok(())
+    }
+
+    fn initialize(&mut self, params: InitializeParams) -> Result<InitializeResult, ResponseError> {
+        self.root = params
+            .root_uri
+            .as_ref()
+            .and_then(|uri| uri.to_file_path().ok());
+
+        let mut import_paths = vec![PathBuf::from(".")];
+        import_paths.extend(self.root.clone());
+        self.thread = Some(VmBuilder::new().import_paths(Some(import_paths)).build());
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![".".into()]),
+                    ..CompletionOptions::default()
+                }),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec![" ".into(), "(".into()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: env!("CARGO_PKG_NAME").into(),
+                version: Some(env!("CARGO_PKG_VERSION").into()),
+            }),
+        })
+    }
+
+    fn thread(&self) -> &RootedThread {
+        self.thread
+            .as_ref()
+            .expect("Server must be initialized before use")
+    }
+
+    fn module_name(&self, uri: &Url) -> String {
+        match uri.to_file_path() {
+            Ok(path) => {
+                let path = self
+                    .root
+                    .as_ref()
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(&path);
+                filename_to_module(&path.to_string_lossy())
+            }
+            Err(()) => uri.to_string(),
+        }
+    }
+
+    /// Replaces the contents of the document and recompiles it. Only the document itself needs to
+    /// be typechecked again as every module it imports is cached in the compiler database.
+    fn update_document(
+        &mut self,
+        uri: Url,
+        version: i32,
+        text: String,
+    ) -> Result<PublishDiagnosticsParams, ResponseError> {
+        let module = self.module_name(&uri);
+        let thread = self.thread().clone();
+
+        {
+            let mut db = thread.get_database_mut();
+            // Reuse the existing file map, if any, so the code map does not grow on every edit
+            db.update_filemap(&module, &text[..]);
+            db.add_module(module.clone(), &text);
+        }
+
+        let result = {
+            let mut db = thread.get_database();
+            block_on(db.typechecked_module(module.clone(), None))
+        };
+        let (value, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err((value, err)) => (value, Some(err)),
+        };
+
+        let file_map = thread
+            .get_database()
+            .get_filemap(&module)
+            .ok_or_else(|| ResponseError::internal("Missing file map after compilation"))?;
+        let mut diagnostics = Vec::new();
+        if let Some(err) = &error {
+            error_to_diagnostics(&file_map, err, &mut diagnostics);
+        }
+        if let Some(value) = value.as_ref().filter(|value| value.warnings.has_errors()) {
+            let warnings = InFile::new(thread.get_database().code_map(), value.warnings.clone());
+            in_file_diagnostics(&file_map, &warnings, &mut diagnostics);
+        }
+
+        let document = self.documents.entry(uri.clone()).or_insert(Document {
+            version,
+            module,
+            value: None,
+        });
+        document.version = version;
+        // Keep the old expression around if the document could not be parsed at all so the
+        // editor queries still have something to work with
+        if value.is_some() {
+            document.value = value;
+        }
+
+        Ok(PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: Some(version),
+        })
+    }
+
+    fn with_document<T>(
+        &self,
+        position: &TextDocumentPositionParams,
+        f: impl FnOnce(&FileMap, &TypecheckedModule, BytePos) -> Result<T, ResponseError>,
+    ) -> Result<T, ResponseError> {
+        let uri = &position.text_document.uri;
+        let document = self.documents.get(uri).ok_or_else(|| {
+            ResponseError::invalid_params(format!("Document `{}` is not open", uri))
+        })?;
+        let value = document.value.as_ref().ok_or_else(|| {
+            ResponseError::internal(format!(
+                "Document `{}` (version {}) has not been compiled",
+                uri, document.version
+            ))
+        })?;
+        let file_map = self
+            .thread()
+            .get_database()
+            .get_filemap(&document.module)
+            .ok_or_else(|| ResponseError::internal("Missing file map"))?;
+        let pos = position_to_byte_pos(&file_map, &position.position)?;
+        f(&file_map, value, pos)
+    }
+
+    fn hover(&self, params: HoverParams) -> Result<Option<Hover>, ResponseError> {
+        let thread = self.thread();
+        self.with_document(
+            &params.text_document_position_params,
+            |file_map, value, pos| {
+                let env = thread.get_env();
+                let expr = value.expr.expr();
+                let extract = (completion::SpanAt, completion::TypeAt { env: &env });
+                let (span, typ) = match completion::completion(extract, file_map.span(), expr, pos)
+                {
+                    Ok(x) => x,
+                    Err(()) => return Ok(None),
+                };
+
+                let mut contents = format!("```gluon\n{}\n```", typ);
+                let metadata =
+                    completion::get_metadata(&value.metadata_map, file_map.span(), expr, pos);
+                if let Some(comment) = metadata.and_then(|metadata| metadata.comment.as_ref()) {
+                    contents.push_str("\n\n");
+                    contents.push_str(&comment.content);
+                }
+
+                Ok(Some(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: contents,
+                    }),
+                    range: Some(byte_span_to_range(file_map, span)?),
+                }))
+            },
+        )
+    }
+
+    fn completion(
+        &self,
+        params: CompletionParams,
+    ) -> Result<Option<CompletionResponse>, ResponseError> {
+        let thread = self.thread();
+        self.with_document(&params.text_document_position, |file_map, value, pos| {
+            let env = thread.get_env();
+            let suggestions = completion::suggest(&env, file_map.span(), value.expr.expr(), pos);
+            let items = suggestions
+                .into_iter()
+                .map(|suggestion| {
+                    let (kind, detail) = match &suggestion.typ {
+                        either::Either::Left(kind) => (CompletionItemKind::CLASS, kind.to_string()),
+                        either::Either::Right(typ) => {
+                            let kind = if is_function(typ) {
+                                CompletionItemKind::FUNCTION
+                            } else {
+                                CompletionItemKind::VARIABLE
+                            };
+                            (kind, typ.to_string())
+                        }
+                    };
+                    CompletionItem {
+                        label: suggestion.name,
+                        kind: Some(kind),
+                        detail: Some(detail),
+                        ..CompletionItem::default()
+                    }
+                })
+                .collect();
+            Ok(Some(CompletionResponse::Array(items)))
+        })
+    }
+
+    fn signature_help(
+        &self,
+        params: SignatureHelpParams,
+    ) -> Result<Option<SignatureHelp>, ResponseError> {
+        let thread = self.thread();
+        self.with_document(
+            &params.text_document_position_params,
+            |file_map, value, pos| {
+                let env = thread.get_env();
+                let expr = value.expr.expr();
+                let help = match completion::signature_help(&env, file_map.span(), expr, pos) {
+                    Some(help) => help,
+                    None => return Ok(None),
+                };
+
+                let parameters = help
+                    .typ
+                    .remove_forall_and_implicit_args()
+                    .arg_iter()
+                    .map(|arg| ParameterInformation {
+                        label: ParameterLabel::Simple(arg.to_string()),
+                        documentation: None,
+                    })
+                    .collect();
+
+                let documentation =
+                    completion::get_metadata(&value.metadata_map, file_map.span(), expr, pos)
+                        .and_then(|metadata| metadata_documentation(metadata));
+
+                Ok(Some(SignatureHelp {
+                    signatures: vec![SignatureInformation {
+                        label: format!("{}: {}", help.name, help.typ),
+                        documentation,
+                        parameters: Some(parameters),
+                        active_parameter: None,
+                    }],
+                    active_signature: Some(0),
+                    active_parameter: help.index,
+                }))
+            },
+        )
+    }
+
+    fn definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>, ResponseError> {
+        let position = &params.text_document_position_params;
+        self.with_document(position, |file_map, value, pos| {
+            let expr = value.expr.expr();
+            let symbol = match completion::symbol(file_map.span(), expr, pos) {
+                Ok(symbol) => symbol,
+                Err(()) => return Ok(None),
+            };
+            match find_definition(expr, symbol) {
+                Some(span) if file_map.span().contains(span) => {
+                    Ok(Some(GotoDefinitionResponse::Scalar(Location {
+                        uri: position.text_document.uri.clone(),
+                        range: byte_span_to_range(file_map, span)?,
+                    })))
+                }
+                _ => Ok(None),
+            }
+        })
+    }
+
+    fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>, ResponseError> {
+        let position = &params.text_document_position;
+        self.with_document(position, |file_map, value, pos| {
+            let spans = match completion::find_all_symbols(file_map.span(), value.expr.expr(), pos)
+            {
+                Ok((_, spans)) => spans,
+                Err(()) => return Ok(None),
+            };
+            spans
+                .into_iter()
+                .filter(|span| file_map.span().contains(*span))
+                .map(|span| {
+                    Ok(Location {
+                        uri: position.text_document.uri.clone(),
+                        range: byte_span_to_range(file_map, span)?,
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map(Some)
+        })
+    }
+
+    fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>, ResponseError> {
+        self.with_document(
+            &params.text_document_position_params,
+            |file_map, value, pos| {
+                let spans =
+                    match completion::find_all_symbols(file_map.span(), value.expr.expr(), pos) {
+                        Ok((_, spans)) => spans,
+                        Err(()) => return Ok(None),
+                    };
+                spans
+                    .into_iter()
+                    .filter(|span| file_map.span().contains(*span))
+                    .map(|span| {
+                        Ok(DocumentHighlight {
+                            range: byte_span_to_range(file_map, span)?,
+                            kind: None,
+                        })
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Some)
+            },
+        )
+    }
+
+    fn document_symbols(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>, ResponseError> {
+        let uri = &params.text_document.uri;
+        let document = match self.documents.get(uri) {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+        let value = match &document.value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let file_map = self
+            .thread()
+            .get_database()
+            .get_filemap(&document.module)
+            .ok_or_else(|| ResponseError::internal("Missing file map"))?;
+
+        let symbols = completion::all_symbols(file_map.span(), value.expr.expr());
+        Ok(Some(DocumentSymbolResponse::Nested(to_document_symbols(
+            &file_map, symbols,
+        )?)))
+    }
+}
+
+fn from_params<T>(params: Value) -> Result<T, ResponseError>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_value(params).map_err(ResponseError::invalid_params)
+}
+
+fn respond<T>(result: Result<T, ResponseError>) -> Result<Value, ResponseError>
+where
+    T: Serialize,
+{
+    result.and_then(|value| serde_json::to_value(value).map_err(ResponseError::internal))
+}
+
+fn is_function(typ: &ArcType) -> bool {
+    match **typ.remove_forall_and_implicit_args() {
+        Type::Function(..) => true,
+        _ => false,
+    }
+}
+
+fn metadata_documentation(metadata: &Metadata) -> Option<Documentation> {
+    metadata.comment.as_ref().map(|comment| {
+        Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: comment.content.clone(),
+        })
+    })
+}
+
+#[allow(deprecated)]
+fn to_document_symbols(
+    file_map: &FileMap,
+    symbols: Vec<completion::SpCompletionSymbol>,
+) -> Result<Vec<DocumentSymbol>, ResponseError> {
+    symbols
+        .into_iter()
+        .map(|symbol| {
+            let (kind, detail, span) = match symbol.value.content {
+                completion::CompletionSymbolContent::Value { typ, expr } => {
+                    let kind = if is_function(typ) {
+                        SymbolKind::FUNCTION
+                    } else {
+                        SymbolKind::VARIABLE
+                    };
+                    let span = if file_map.span().contains(expr.span) {
+                        symbol.span.to(expr.span)
+                    } else {
+                        symbol.span
+                    };
+                    (kind, Some(typ.to_string()), span)
+                }
+                completion::CompletionSymbolContent::Type { .. } => {
+                    (SymbolKind::STRUCT, None, symbol.span)
+                }
+            };
+            let children = to_document_symbols(file_map, symbol.value.children)?;
+            Ok(DocumentSymbol {
+                name: symbol.value.name.declared_name().to_string(),
+                detail,
+                kind,
+                tags: None,
+                deprecated: None,
+                range: byte_span_to_range(file_map, span)?,
+                selection_range: byte_span_to_range(file_map, symbol.span)?,
+                children: if children.is_empty() {
+                    None
+                } else {
+                    Some(children)
+                },
+            })
+        })
+        .collect()
+}
+
+/// Finds the span where `symbol` is bound (let binding, function argument, pattern etc)
+fn find_definition(expr: &SpannedExpr<Symbol>, symbol: &SymbolRef) -> Option<Span<BytePos>> {
+    struct FindDefinition<'s> {
+        symbol: &'s SymbolRef,
+        span: Option<Span<BytePos>>,
+    }
+
+    impl FindDefinition<'_> {
+        fn check(&mut self, name: &Symbol, span: Span<BytePos>) {
+            if self.span.is_none() && *name == *self.symbol {
+                self.span = Some(span);
+            }
+        }
+    }
+
+    impl<'a, 'ast> Visitor<'a, 'ast> for FindDefinition<'_> {
+        type Ident = Symbol;
+
+        fn visit_expr(&mut self, e: &'a SpannedExpr<'ast, Symbol>) {
+            if self.span.is_some() {
+                return;
+            }
+            match &e.value {
+                Expr::LetBindings(binds, _) => {
+                    for bind in binds {
+                        for arg in &*bind.args {
+                            self.check(&arg.name.value.name, arg.name.span);
+                        }
+                    }
+                }
+                Expr::Lambda(lambda) => {
+                    for arg in &*lambda.args {
+                        self.check(&arg.name.value.name, arg.name.span);
+                    }
+                }
+                Expr::TypeBindings(binds, _) => {
+                    for bind in &**binds {
+                        self.check(&bind.name.value, bind.name.span);
+                    }
+                }
+                _ => (),
+            }
+            walk_expr(self, e);
+        }
+
+        fn visit_pattern(&mut self, p: &'a SpannedPattern<'ast, Symbol>) {
+            match &p.value {
+                Pattern::Ident(id) => self.check(&id.name, p.span),
+                Pattern::As(id, _) => self.check(&id.value, id.span),
+                Pattern::Record { fields, .. } => {
+                    for field in &**fields {
+                        match field {
+                            PatternField::Type { name }
+                            | PatternField::Value { name, value: None } => {
+                                self.check(&name.value, name.span)
+                            }
+                            PatternField::Value { .. } => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+            walk_pattern(self, &p.value);
+        }
+    }
+
+    let mut visitor = FindDefinition { symbol, span: None };
+    visitor.visit_expr(expr);
+    visitor.span
+}
+
+fn error_to_diagnostics(file_map: &FileMap, err: &GluonError, diagnostics: &mut Vec<Diagnostic>) {
+    match err {
+        GluonError::Parse(err) => in_file_diagnostics(file_map, err, diagnostics),
+        GluonError::Typecheck(err) => in_file_diagnostics(file_map, err, diagnostics),
+        GluonError::Macro(err) => in_file_diagnostics(file_map, err, diagnostics),
+        GluonError::Multiple(errors) => {
+            for err in errors {
+                error_to_diagnostics(file_map, err, diagnostics);
+            }
+        }
+        err => diagnostics.push(Diagnostic {
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("gluon".into()),
+            message: err.to_string(),
+            ..Diagnostic::default()
+        }),
+    }
+}
+
+fn in_file_diagnostics<E>(file_map: &FileMap, err: &InFile<E>, diagnostics: &mut Vec<Diagnostic>)
+where
+    E: AsDiagnostic + fmt::Display,
+{
+    for error in err.errors() {
+        let diagnostic = error.as_diagnostic();
+
+        let mut message = diagnostic.message;
+        for label in &diagnostic.labels {
+            if let (LabelStyle::Secondary, Some(label_message)) = (label.style, &label.message) {
+                message.push('\n');
+                message.push_str(label_message);
+            }
+        }
+
+        // Errors in other modules (such as an import that fails to compile) are reported at the
+        // start of the document
+        let range = if file_map.span().contains(error.span) {
+            byte_span_to_range(file_map, error.span).unwrap_or_default()
+        } else {
+            let file_name = err.source_name().to_string();
+            message = format!("{}: {}", file_name, message);
+            Default::default()
+        };
+
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Some(match diagnostic.severity {
+                Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Note => DiagnosticSeverity::INFORMATION,
+                Severity::Help => DiagnosticSeverity::HINT,
+            }),
+            source: Some("gluon".into()),
+            message,
+            ..Diagnostic::default()
+        });
+    }
+}
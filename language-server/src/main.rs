@@ -0,0 +1,22 @@
+//! Language server for the gluon programming language, communicating over stdin/stdout
+#![doc(html_root_url = "https://docs.rs/gluon_language-server/0.14.1")] // # GLUON
+
+use std::io;
+
+use gluon_language_server::Server;
+
+fn main() {
+    #[cfg(feature = "env_logger")]
+    env_logger::init();
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let mut server = Server::new();
+    if let Err(err) = server.run(stdin.lock(), stdout.lock()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    // Exiting without a preceding `shutdown` request is treated as an error by the protocol
+    std::process::exit(if server.shutdown_requested() { 0 } else { 1 });
+}
@@ -0,0 +1,59 @@
+//! Conversions between the byte positions used by gluon and the line/character positions used by
+//! the language server protocol.
+//!
+//! Characters in the language server protocol are counted in UTF-16 code units while gluon counts
+//! bytes so every conversion needs to inspect the text of the line.
+
+use codespan::{ByteOffset, FileMap, LineIndex, RawIndex};
+
+use lsp_types::{Position, Range};
+
+use gluon::base::pos::{BytePos, Span};
+
+use crate::rpc::ResponseError;
+
+pub fn position_to_byte_pos(
+    file_map: &FileMap,
+    position: &Position,
+) -> Result<BytePos, ResponseError> {
+    let line_span = file_map
+        .line_span(LineIndex(position.line as RawIndex))
+        .map_err(ResponseError::invalid_params)?;
+    let line = file_map
+        .src_slice(line_span)
+        .map_err(ResponseError::invalid_params)?;
+
+    let mut utf16_offset = 0;
+    let mut byte_offset = 0;
+    for c in line.chars() {
+        if utf16_offset >= position.character as usize || c == '\n' || c == '\r' {
+            break;
+        }
+        utf16_offset += c.len_utf16();
+        byte_offset += c.len_utf8();
+    }
+    Ok(line_span.start() + ByteOffset::from(byte_offset as i64))
+}
+
+pub fn byte_pos_to_position(file_map: &FileMap, pos: BytePos) -> Result<Position, ResponseError> {
+    let line = file_map
+        .find_line(pos)
+        .map_err(ResponseError::invalid_params)?;
+    let line_span = file_map
+        .line_span(line)
+        .map_err(ResponseError::invalid_params)?;
+    let prefix = file_map
+        .src_slice(Span::new(line_span.start(), pos))
+        .map_err(ResponseError::invalid_params)?;
+    Ok(Position {
+        line: line.0 as u32,
+        character: prefix.encode_utf16().count() as u32,
+    })
+}
+
+pub fn byte_span_to_range(file_map: &FileMap, span: Span<BytePos>) -> Result<Range, ResponseError> {
+    Ok(Range {
+        start: byte_pos_to_position(file_map, span.start())?,
+        end: byte_pos_to_position(file_map, span.end())?,
+    })
+}
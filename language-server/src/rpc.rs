@@ -0,0 +1,130 @@
+//! Minimal JSON-RPC 2.0 transport as used by the language server protocol.
+//!
+//! Messages are framed with a `Content-Length` header by `gluon_rpc`.
+
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+pub use gluon_rpc::read_message;
+
+/// Error codes defined by JSON-RPC and the language server protocol
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMessage {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A message sent by the client
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+    /// A response to a request sent by the server. The server never sends any requests so these
+    /// are ignored
+    Response {
+        id: Value,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl ResponseError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        ResponseError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(err: impl std::fmt::Display) -> Self {
+        Self::new(error_code::INVALID_PARAMS, err.to_string())
+    }
+
+    pub fn internal(err: impl std::fmt::Display) -> Self {
+        Self::new(error_code::INTERNAL_ERROR, err.to_string())
+    }
+}
+
+/// Parses the content of a message
+pub fn parse_message(content: &str) -> Result<Message, ResponseError> {
+    let raw: RawMessage = serde_json::from_str(content)
+        .map_err(|err| ResponseError::new(error_code::PARSE_ERROR, err.to_string()))?;
+    Ok(match (raw.id, raw.method) {
+        (Some(id), Some(method)) => Message::Request {
+            id,
+            method,
+            params: raw.params,
+        },
+        (None, Some(method)) => Message::Notification {
+            method,
+            params: raw.params,
+        },
+        (Some(id), None) => Message::Response { id },
+        (None, None) => {
+            return Err(ResponseError::new(
+                error_code::INVALID_REQUEST,
+                "Message has neither an `id` nor a `method`",
+            ))
+        }
+    })
+}
+
+fn write_value<W>(output: &mut W, value: &Value) -> io::Result<()>
+where
+    W: ?Sized + Write,
+{
+    let content = serde_json::to_string(value)?;
+    gluon_rpc::write_message(output, &content)
+}
+
+/// Writes the response to the request with `id`
+pub fn write_response<W>(
+    output: &mut W,
+    id: Value,
+    result: Result<Value, ResponseError>,
+) -> io::Result<()>
+where
+    W: ?Sized + Write,
+{
+    let value = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    };
+    write_value(output, &value)
+}
+
+/// Writes a notification which is sent from the server to the client
+pub fn write_notification<W>(output: &mut W, method: &str, params: Value) -> io::Result<()>
+where
+    W: ?Sized + Write,
+{
+    write_value(
+        output,
+        &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+    )
+}
@@ -0,0 +1,254 @@
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate pretty_assertions;
+
+use serde_json::Value;
+
+use gluon_language_server::{rpc, Server};
+
+const URI: &str = "file:///test.glu";
+
+fn send(server: &mut Server, message: Value) -> Vec<Value> {
+    let mut output = Vec::new();
+    server
+        .handle_message(&message.to_string(), &mut output)
+        .unwrap();
+
+    let mut input = &output[..];
+    let mut messages = Vec::new();
+    while let Some(content) = rpc::read_message(&mut input).unwrap() {
+        messages.push(serde_json::from_str(&content).unwrap());
+    }
+    messages
+}
+
+fn request(server: &mut Server, id: i64, method: &str, params: Value) -> Value {
+    let mut messages = send(
+        server,
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+    );
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    let response = messages.pop().unwrap();
+    assert_eq!(response["id"], json!(id));
+    response
+}
+
+fn notify(server: &mut Server, method: &str, params: Value) -> Vec<Value> {
+    send(
+        server,
+        json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+    )
+}
+
+fn initialized_server() -> Server {
+    let mut server = Server::new();
+    let response = request(
+        &mut server,
+        1,
+        "initialize",
+        json!({ "processId": null, "rootUri": null, "capabilities": {} }),
+    );
+    assert!(response["result"]["capabilities"].is_object());
+    server
+}
+
+fn open(server: &mut Server, text: &str) -> Value {
+    let mut messages = notify(
+        server,
+        "textDocument/didOpen",
+        json!({
+            "textDocument": { "uri": URI, "languageId": "gluon", "version": 1, "text": text }
+        }),
+    );
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    let notification = messages.pop().unwrap();
+    assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+    notification["params"]["diagnostics"].clone()
+}
+
+fn position(line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character }
+    })
+}
+
+#[test]
+fn request_before_initialize() {
+    let mut server = Server::new();
+    let response = request(&mut server, 1, "textDocument/hover", position(0, 0));
+    assert_eq!(
+        response["error"]["code"],
+        json!(rpc::error_code::SERVER_NOT_INITIALIZED)
+    );
+}
+
+#[test]
+fn unknown_method() {
+    let mut server = initialized_server();
+    let response = request(&mut server, 2, "gluon/unknown", json!(null));
+    assert_eq!(
+        response["error"]["code"],
+        json!(rpc::error_code::METHOD_NOT_FOUND)
+    );
+}
+
+#[test]
+fn shutdown_and_exit() {
+    let mut server = initialized_server();
+    let response = request(&mut server, 2, "shutdown", json!(null));
+    assert_eq!(response["result"], Value::Null);
+    assert!(server.shutdown_requested());
+
+    let mut output = Vec::new();
+    let exit = server
+        .handle_message(
+            &json!({ "jsonrpc": "2.0", "method": "exit" }).to_string(),
+            &mut output,
+        )
+        .unwrap();
+    assert!(exit);
+}
+
+#[test]
+fn no_diagnostics_for_valid_document() {
+    let mut server = initialized_server();
+    let diagnostics = open(&mut server, "let x = 1 in x");
+    assert_eq!(diagnostics, json!([]));
+}
+
+#[test]
+fn type_error_diagnostic() {
+    let mut server = initialized_server();
+    let diagnostics = open(&mut server, "let x = 1 in\nx #Int+ \"\"");
+    let diagnostics = diagnostics.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({
+            "start": { "line": 1, "character": 8 },
+            "end": { "line": 1, "character": 10 }
+        })
+    );
+    assert_eq!(diagnostics[0]["severity"], json!(1));
+}
+
+#[test]
+fn change_clears_diagnostics() {
+    let mut server = initialized_server();
+    let diagnostics = open(&mut server, "1 #Int+ \"\"");
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+
+    let mut messages = notify(
+        &mut server,
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "1 #Int+ 2" }]
+        }),
+    );
+    let notification = messages.pop().unwrap();
+    assert_eq!(notification["params"]["version"], json!(2));
+    assert_eq!(notification["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn hover() {
+    let mut server = initialized_server();
+    open(&mut server, "let x = 1\nx");
+    let response = request(&mut server, 2, "textDocument/hover", position(1, 0));
+    assert_eq!(
+        response["result"]["contents"]["value"],
+        json!("```gluon\nInt\n```")
+    );
+    assert_eq!(
+        response["result"]["range"],
+        json!({
+            "start": { "line": 1, "character": 0 },
+            "end": { "line": 1, "character": 1 }
+        })
+    );
+}
+
+#[test]
+fn definition() {
+    let mut server = initialized_server();
+    open(&mut server, "let abc = 1\nabc");
+    let response = request(&mut server, 2, "textDocument/definition", position(1, 1));
+    assert_eq!(
+        response["result"],
+        json!({
+            "uri": URI,
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 7 }
+            }
+        })
+    );
+}
+
+#[test]
+fn references() {
+    let mut server = initialized_server();
+    open(&mut server, "let abc = 1\nabc #Int+ abc");
+    let response = request(
+        &mut server,
+        2,
+        "textDocument/references",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 1, "character": 0 },
+            "context": { "includeDeclaration": true }
+        }),
+    );
+    assert_eq!(response["result"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn document_symbols() {
+    let mut server = initialized_server();
+    open(&mut server, "let abc = 1\nlet f x = x\nf abc");
+    let response = request(
+        &mut server,
+        2,
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let names: Vec<_> = response["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].clone())
+        .collect();
+    assert_eq!(names, vec![json!("abc"), json!("f")]);
+}
+
+#[test]
+fn completion() {
+    let mut server = initialized_server();
+    open(&mut server, "let abc = 1\nlet abd = \"\"\nab");
+    let response = request(&mut server, 2, "textDocument/completion", position(2, 2));
+    let mut labels: Vec<_> = response["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].clone())
+        .collect();
+    labels.sort_by_key(|label| label.to_string());
+    assert_eq!(labels, vec![json!("abc"), json!("abd")]);
+}
+
+#[test]
+fn utf16_positions() {
+    let mut server = initialized_server();
+    open(&mut server, "let x = 1\nlet y = (\"ö😀\", x)\ny");
+    let response = request(&mut server, 2, "textDocument/hover", position(1, 16));
+    assert_eq!(
+        response["result"]["range"],
+        json!({
+            "start": { "line": 1, "character": 16 },
+            "end": { "line": 1, "character": 17 }
+        })
+    );
+}
@@ -308,6 +308,17 @@ where
                 | (&Token::Comma, Context::Paren)
                 | (&Token::Comma, Context::Bracket) => return Ok(token),
 
+                // The `if` of a guard (`| pattern if guard -> expr`) is ended by the `->` instead
+                // of an `else`
+                (&Token::RArrow, Context::If)
+                    if self.indent_levels.stack.len() >= 2
+                        && self.indent_levels.stack[self.indent_levels.stack.len() - 2].context
+                            == Context::MatchClause =>
+                {
+                    self.indent_levels.pop();
+                    continue;
+                }
+
                 // If it is closing token we remove contexts until a context for that token is found
                 (&Token::In, _)
                 | (&Token::CloseBlock, _)
@@ -315,6 +326,7 @@ where
                 | (&Token::RBrace, _)
                 | (&Token::RBracket, _)
                 | (&Token::RParen, _)
+                | (&Token::InterpolationClose, _)
                 | (&Token::Comma, _) => {
                     self.indent_levels.pop();
 
@@ -521,7 +533,7 @@ where
                 Token::Lambda => Some(Context::Lambda),
                 Token::LBrace => Some(Context::Brace),
                 Token::LBracket => Some(Context::Bracket),
-                Token::LParen => Some(Context::Paren),
+                Token::LParen | Token::InterpolationOpen => Some(Context::Paren),
                 Token::AttributeOpen => Some(Context::Attribute),
                 _ => None,
             };
@@ -610,6 +622,7 @@ fn token_closes_context(token: &BorrowedToken, context: Context) -> bool {
         | (&Token::RBrace, Context::Brace)
         | (&Token::RBracket, Context::Bracket)
         | (&Token::RParen, Context::Paren)
+        | (&Token::InterpolationClose, Context::Paren)
         | (&Token::CloseBlock, Context::Block { .. })
         | (&Token::In, Context::Rec)
         | (&Token::In, Context::Let)
@@ -101,6 +101,7 @@ fn shrink_hidden_spans<Id: std::fmt::Debug>(mut expr: SpannedExpr<Id>) -> Spanne
         | Expr::App { .. }
         | Expr::Ident(_)
         | Expr::Literal(_)
+        | Expr::Interpolation(_)
         | Expr::Projection(_, _, _)
         | Expr::Array(_)
         | Expr::Record { .. }
@@ -334,6 +335,7 @@ impl_temp_vec! {
     ast::TypeBinding<'ast, Id> => type_bindings,
     ValueBinding<'ast, Id> => value_bindings,
     ast::Do<'ast, Id> => do_exprs,
+    ast::InterpolationPart<'ast, Id> => interpolation_parts,
     ast::Alternative<'ast, Id> => alts,
     ast::Argument<ast::SpannedIdent<Id>> => args,
     FieldExpr<'ast, Id> => field_expr,
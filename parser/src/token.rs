@@ -1,4 +1,4 @@
-use std::{fmt, str};
+use std::{collections::VecDeque, fmt, str};
 
 use codespan::ByteOffset;
 
@@ -11,6 +11,7 @@ use crate::{
         ast::is_operator_byte,
         metadata::{Comment, CommentType},
         pos::{self, BytePos, Column, Line, Location, Spanned},
+        types::BuiltinType,
     },
     str_suffix::{self, StrSuffix},
 };
@@ -22,9 +23,17 @@ pub enum Token<S> {
     Operator(S),
 
     StringLiteral(StringLiteral<S>),
+    /// The text between `f"` and the first `${` of an interpolated string
+    InterpolationStart(S),
+    /// The text between a `}` and the next `${` of an interpolated string
+    InterpolationMiddle(S),
+    /// The text between the last `}` and the closing `"` of an interpolated string
+    InterpolationEnd(S),
     CharLiteral(char),
     IntLiteral(i64),
     ByteLiteral(u8),
+    /// An integer literal with a suffix such as `i8` or `u32`
+    SizedIntLiteral(i64, BuiltinType),
     FloatLiteral(NotNan<f64>),
     DocComment(Comment<S>),
 
@@ -65,6 +74,9 @@ pub enum Token<S> {
 
     AttributeOpen,
 
+    InterpolationOpen,
+    InterpolationClose,
+
     EOF, // Required for the layout algorithm
 }
 
@@ -80,9 +92,13 @@ where
             Identifier(_) => "Identifier",
             Operator(_) => "Operator",
             StringLiteral(_) => "StringLiteral",
+            InterpolationStart(_) => "InterpolationStart",
+            InterpolationMiddle(_) => "InterpolationMiddle",
+            InterpolationEnd(_) => "InterpolationEnd",
             CharLiteral(_) => "CharLiteral",
             IntLiteral(_) => "IntLiteral",
             ByteLiteral(_) => "ByteLiteral",
+            SizedIntLiteral(..) => "SizedIntLiteral",
             FloatLiteral(_) => "FloatLiteral",
             DocComment { .. } => "DocComment",
 
@@ -124,6 +140,9 @@ where
 
             AttributeOpen => "#[",
 
+            InterpolationOpen => "${",
+            InterpolationClose => "}",
+
             EOF => "EOF",
         };
         s.fmt(f)
@@ -141,9 +160,13 @@ impl<S> Token<S> {
                 self::StringLiteral::Escaped(s) => self::StringLiteral::Escaped(f(s)),
                 self::StringLiteral::Raw(s) => self::StringLiteral::Raw(f(s)),
             }),
+            InterpolationStart(s) => InterpolationStart(f(s)),
+            InterpolationMiddle(s) => InterpolationMiddle(f(s)),
+            InterpolationEnd(s) => InterpolationEnd(f(s)),
             CharLiteral(x) => CharLiteral(x),
             IntLiteral(x) => IntLiteral(x),
             ByteLiteral(x) => ByteLiteral(x),
+            SizedIntLiteral(x, typ) => SizedIntLiteral(x, typ),
             FloatLiteral(x) => FloatLiteral(x),
             DocComment(Comment { typ, content }) => DocComment(Comment {
                 typ,
@@ -188,6 +211,9 @@ impl<S> Token<S> {
 
             AttributeOpen => AttributeOpen,
 
+            InterpolationOpen => InterpolationOpen,
+            InterpolationClose => InterpolationClose,
+
             EOF => EOF,
         }
     }
@@ -216,6 +242,7 @@ fn unescape_string_literal(mut s: &str) -> String {
             b'"' => '"',
             b'\\' => '\\',
             b'/' => '/',
+            b'$' => '$',
             b'n' => '\n',
             b'r' => '\r',
             b't' => '\t',
@@ -263,6 +290,9 @@ quick_error! {
         NonParseableInt {
             display("cannot parse integer, probable overflow")
         }
+        UnknownIntSuffix(suffix: String) {
+            display("unknown integer suffix `{}`", suffix)
+        }
         HexLiteralOverflow {
             display("cannot parse hex literal, overflow")
         }
@@ -351,6 +381,10 @@ pub struct Tokenizer<'input> {
     input: &'input str,
     chars: CharLocations<'input>,
     start_index: BytePos,
+    /// The number of unclosed `{` in each interpolated expression (`${ ... }`) being tokenized
+    interpolations: Vec<u32>,
+    /// Tokens which have already been scanned but not yet returned
+    pending: VecDeque<SpannedToken<'input>>,
 }
 
 impl<'input> Tokenizer<'input> {
@@ -364,6 +398,8 @@ impl<'input> Tokenizer<'input> {
             input: input.src(),
             chars,
             start_index: input.start_index(),
+            interpolations: Vec::new(),
+            pending: VecDeque::new(),
         }
     }
 
@@ -505,6 +541,7 @@ impl<'input> Tokenizer<'input> {
             Some((_, b'"')) => Ok(b'"'),
             Some((_, b'\\')) => Ok(b'\\'),
             Some((_, b'/')) => Ok(b'/'),
+            Some((_, b'$')) => Ok(b'$'),
             Some((_, b'n')) => Ok(b'\n'),
             Some((_, b'r')) => Ok(b'\r'),
             Some((_, b't')) => Ok(b'\t'),
@@ -544,6 +581,61 @@ impl<'input> Tokenizer<'input> {
         self.error(start, UnterminatedStringLiteral)
     }
 
+    /// Scans the text of an interpolated string up to the next `${` or the closing `"`. `start` is
+    /// either the location of the `f"` that starts the string or of the `}` that ended the
+    /// previous interpolated expression.
+    fn interpolation_segment(
+        &mut self,
+        start: Location,
+        first: bool,
+    ) -> Result<SpannedToken<'input>, SpError> {
+        let content_start = self.next_loc();
+        loop {
+            let scan_start = self.next_loc();
+            self.take_until(scan_start, |b| b == b'"' || b == b'\\' || b == b'$');
+            match self.bump() {
+                Some((_, b'\\')) => {
+                    self.escape_code()?;
+                }
+                Some((dollar, b'$')) if self.test_lookahead(|b| b == b'{') => {
+                    self.bump();
+                    let content = self.slice(content_start, dollar);
+                    let token = if first {
+                        Token::InterpolationStart(content)
+                    } else {
+                        Token::InterpolationMiddle(content)
+                    };
+                    self.interpolations.push(0);
+                    self.pending.push_back(pos::spanned2(
+                        dollar,
+                        self.next_loc(),
+                        Token::InterpolationOpen,
+                    ));
+                    return Ok(pos::spanned2(start, dollar, token));
+                }
+                Some((_, b'$')) => (),
+                Some((_, b'"')) => {
+                    let end = self.next_loc();
+
+                    let mut content_end = end;
+                    content_end.absolute.0 -= 1;
+
+                    let content = self.slice(content_start, content_end);
+                    let token = if first {
+                        // No expressions were interpolated so this is just a plain string
+                        Token::StringLiteral(StringLiteral::Escaped(content))
+                    } else {
+                        Token::InterpolationEnd(content)
+                    };
+                    return Ok(pos::spanned2(start, end, token));
+                }
+                _ => break,
+            }
+        }
+
+        self.error(start, UnterminatedStringLiteral)
+    }
+
     fn raw_string_literal(&mut self, start: Location) -> Result<SpannedToken<'input>, SpError> {
         let mut delimiters = 0;
         while let Some((_, ch)) = self.bump() {
@@ -679,6 +771,17 @@ impl<'input> Tokenizer<'input> {
                     }
                 }
             }
+            Some((suffix_start, b'i')) | Some((suffix_start, b'u')) => {
+                let (end, suffix) = self.take_while(suffix_start, is_ident_continue);
+                let typ = match BuiltinType::from_int_suffix(suffix) {
+                    Some(typ) => typ,
+                    None => return self.error(suffix_start, UnknownIntSuffix(suffix.into())),
+                };
+                match parse_sized_int(int, typ) {
+                    Some(val) => (start, end, val),
+                    None => return self.error(start, NonParseableInt),
+                }
+            }
             Some((start, ch)) if is_ident_start(ch) => {
                 let ch = self.chars.chars.as_str_suffix().restore_char(&[ch]);
                 return self.error(start, UnexpectedChar(ch));
@@ -732,14 +835,38 @@ impl<'input> Iterator for Tokenizer<'input> {
     type Item = Result<SpannedToken<'input>, SpError>;
 
     fn next(&mut self) -> Option<Result<SpannedToken<'input>, SpError>> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(Ok(token));
+        }
         while let Some((start, ch)) = self.bump() {
             return match ch {
                 b',' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::Comma))),
                 b'\\' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::Lambda))),
-                b'{' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::LBrace))),
+                b'{' => {
+                    if let Some(open_braces) = self.interpolations.last_mut() {
+                        *open_braces += 1;
+                    }
+                    Some(Ok(pos::spanned2(start, self.next_loc(), Token::LBrace)))
+                }
+                b'}' if self.interpolations.last() == Some(&0) => {
+                    self.interpolations.pop();
+                    let close = pos::spanned2(start, self.next_loc(), Token::InterpolationClose);
+                    match self.interpolation_segment(self.next_loc(), false) {
+                        Ok(segment) => {
+                            self.pending.push_front(segment);
+                            Some(Ok(close))
+                        }
+                        Err(err) => Some(Err(err)),
+                    }
+                }
                 b'[' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::LBracket))),
                 b'(' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::LParen))),
-                b'}' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::RBrace))),
+                b'}' => {
+                    if let Some(open_braces) = self.interpolations.last_mut() {
+                        *open_braces -= 1;
+                    }
+                    Some(Ok(pos::spanned2(start, self.next_loc(), Token::RBrace)))
+                }
                 b']' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::RBracket))),
                 b')' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::RParen))),
                 b'?' => Some(Ok(pos::spanned2(start, self.next_loc(), Token::Question))),
@@ -747,6 +874,10 @@ impl<'input> Iterator for Tokenizer<'input> {
                 b'r' if self.test_lookahead(|ch| ch == b'"' || ch == b'#') => {
                     Some(self.raw_string_literal(start))
                 }
+                b'f' if self.test_lookahead(|ch| ch == b'"') => {
+                    self.bump();
+                    Some(self.interpolation_segment(start, true))
+                }
                 b'"' => Some(self.string_literal(start)),
                 b'\'' => Some(self.char_literal(start)),
 
@@ -798,6 +929,21 @@ impl<'input> Iterator for Tokenizer<'input> {
     }
 }
 
+/// Parses the digits of an integer literal with a suffix, checking that it fits in `typ`.
+fn parse_sized_int<S>(int: &str, typ: BuiltinType) -> Option<Token<S>> {
+    Some(match typ {
+        BuiltinType::Int => Token::IntLiteral(int.parse().ok()?),
+        BuiltinType::Byte => Token::ByteLiteral(int.parse().ok()?),
+        BuiltinType::Int8 => Token::SizedIntLiteral(int.parse::<i8>().ok()?.into(), typ),
+        BuiltinType::Int16 => Token::SizedIntLiteral(int.parse::<i16>().ok()?.into(), typ),
+        BuiltinType::Int32 => Token::SizedIntLiteral(int.parse::<i32>().ok()?.into(), typ),
+        BuiltinType::UInt16 => Token::SizedIntLiteral(int.parse::<u16>().ok()?.into(), typ),
+        BuiltinType::UInt32 => Token::SizedIntLiteral(int.parse::<u32>().ok()?.into(), typ),
+        BuiltinType::UInt64 => Token::SizedIntLiteral(int.parse::<u64>().ok()? as i64, typ),
+        _ => return None,
+    })
+}
+
 /// Converts partial hex literal (i.e. part after `0x` or `-0x`) to 64 bit signed integer.
 ///
 /// This is basically a copy and adaptation of `std::num::from_str_radix`.
@@ -988,6 +1134,52 @@ mod test {
         assert_eq!(StringLiteral::Escaped(r#"\"\""#).unescape(), r#""""#);
     }
 
+    #[test]
+    fn interpolated_string_literals() {
+        test(
+            r#"f"a${x}b${ {} }c\$""#,
+            vec![
+                (r#"~~~                "#, InterpolationStart("a")),
+                (r#"   ~~              "#, InterpolationOpen),
+                (r#"     ~             "#, Identifier("x")),
+                (r#"      ~            "#, InterpolationClose),
+                (r#"       ~           "#, InterpolationMiddle("b")),
+                (r#"        ~~         "#, InterpolationOpen),
+                (r#"           ~       "#, LBrace),
+                (r#"            ~      "#, RBrace),
+                (r#"              ~    "#, InterpolationClose),
+                (r#"               ~~~~"#, InterpolationEnd("c\\$")),
+            ],
+        );
+        test(
+            r#"f"a\${x}""#,
+            vec![(
+                r#"~~~~~~~~~"#,
+                Token::StringLiteral(StringLiteral::Escaped("a\\${x}")),
+            )],
+        );
+        assert_eq!(StringLiteral::Escaped("c\\$").unescape(), "c$");
+    }
+
+    #[test]
+    fn f_directly_before_a_string_starts_an_interpolated_string() {
+        // `f"x"` is an interpolated string literal so applying `f` to a string needs a space
+        test(
+            r#"f"x" f "x""#,
+            vec![
+                (
+                    r#"~~~~      "#,
+                    Token::StringLiteral(StringLiteral::Escaped("x")),
+                ),
+                (r#"     ~    "#, Identifier("f")),
+                (
+                    r#"       ~~~"#,
+                    Token::StringLiteral(StringLiteral::Escaped("x")),
+                ),
+            ],
+        );
+    }
+
     #[test]
     fn raw_string_literals() {
         test(
@@ -1208,6 +1400,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn sized_int_literals() {
+        test(
+            r#"3i8 -3i32 255u8 7i64 18446744073709551615u64"#,
+            vec![
+                (
+                    r#"~~~                                         "#,
+                    SizedIntLiteral(3, BuiltinType::Int8),
+                ),
+                (
+                    r#"    ~~~~~                                   "#,
+                    SizedIntLiteral(-3, BuiltinType::Int32),
+                ),
+                (
+                    r#"          ~~~~~                             "#,
+                    ByteLiteral(255),
+                ),
+                (
+                    r#"                ~~~~                        "#,
+                    IntLiteral(7),
+                ),
+                (
+                    r#"                     ~~~~~~~~~~~~~~~~~~~~~~~"#,
+                    SizedIntLiteral(-1, BuiltinType::UInt64),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn sized_int_literal_overflow() {
+        assert_eq!(
+            tokenizer(r#"128i8"#).last(),
+            Some(error(loc(0), NonParseableInt))
+        );
+        assert_eq!(
+            tokenizer(r#"-1u16"#).last(),
+            Some(error(loc(0), NonParseableInt))
+        );
+    }
+
+    #[test]
+    fn sized_int_literal_unknown_suffix() {
+        assert_eq!(
+            tokenizer(r#"3i128"#).last(),
+            Some(error(loc(1), UnknownIntSuffix("i128".into())))
+        );
+    }
+
     #[test]
     fn float_literals() {
         test(
@@ -247,6 +247,122 @@ test_parse! {
         )
 }
 
+test_parse! {
+    case_expr_guard,
+    r#"
+    match x with
+    | Some y if y > 1 ->
+        y
+    | z -> 0"#,
+    |arena| no_loc(Expr::Match(
+        arena.alloc(id("x")),
+        arena.alloc_extend(vec![
+            Alternative {
+                pattern: no_loc(Pattern::Constructor(
+                    TypedIdent::new(intern("Some")),
+                    arena.alloc_extend(vec![no_loc(Pattern::Ident(TypedIdent::new(intern("y"))))]),
+                )),
+                guard: Some(binop(arena, id("y"), ">", int(1))),
+                expr: id("y"),
+            },
+            Alternative {
+                pattern: no_loc(Pattern::Ident(TypedIdent::new(intern("z")))),
+                guard: None,
+                expr: int(0),
+            },
+        ]),
+    ))
+}
+
+test_parse! {
+    array_pattern,
+    r#"
+    match x with
+    | [] -> 0
+    | [y, _] -> y
+    | [y, ..rest] -> 1
+    | [..] -> 2"#,
+    |arena| {
+        let ident = |s: &str| no_loc(Pattern::Ident(TypedIdent::new(intern(s))));
+        let array_pattern = |elems: Vec<_>, rest: Option<&str>| {
+            no_loc(Pattern::Array {
+                typ: Type::hole(),
+                elems: arena.alloc_extend(elems),
+                rest: rest.map(|rest| arena.alloc(ident(rest))),
+            })
+        };
+        no_loc(Expr::Match(
+            arena.alloc(id("x")),
+            arena.alloc_extend(vec![
+                Alternative {
+                    pattern: array_pattern(vec![], None),
+                    guard: None,
+                    expr: int(0),
+                },
+                Alternative {
+                    pattern: array_pattern(vec![ident("y"), ident("_")], None),
+                    guard: None,
+                    expr: id("y"),
+                },
+                Alternative {
+                    pattern: array_pattern(vec![ident("y")], Some("rest")),
+                    guard: None,
+                    expr: int(1),
+                },
+                Alternative {
+                    pattern: array_pattern(vec![], Some("_")),
+                    guard: None,
+                    expr: int(2),
+                },
+            ]),
+        ))
+    }
+}
+
+test_parse! {
+    or_pattern,
+    r#"
+    match x with
+    | Some 1 | Some 2 -> 0
+    | (Left y | Right y, _) -> y"#,
+    |arena| {
+        let ctor = |name, args: Vec<_>| {
+            no_loc(Pattern::Constructor(
+                TypedIdent::new(intern(name)),
+                arena.alloc_extend(args),
+            ))
+        };
+        let y = || no_loc(Pattern::Ident(TypedIdent::new(intern("y"))));
+        no_loc(Expr::Match(
+            arena.alloc(id("x")),
+            arena.alloc_extend(vec![
+                Alternative {
+                    pattern: no_loc(Pattern::Or(arena.alloc_extend(vec![
+                        ctor("Some", vec![no_loc(Pattern::Literal(Literal::Int(1)))]),
+                        ctor("Some", vec![no_loc(Pattern::Literal(Literal::Int(2)))]),
+                    ]))),
+                    guard: None,
+                    expr: int(0),
+                },
+                Alternative {
+                    pattern: no_loc(Pattern::Tuple {
+                        typ: Type::hole(),
+                        elems: arena.alloc_extend(vec![
+                            no_loc(Pattern::Or(arena.alloc_extend(vec![
+                                ctor("Left", vec![y()]),
+                                ctor("Right", vec![y()]),
+                            ]))),
+                            no_loc(Pattern::Ident(TypedIdent::new(intern("_")))),
+                        ]),
+                    }),
+                    guard: None,
+                    expr: id("y"),
+                },
+            ]),
+        ))
+    }
+}
+
 test_parse! {
     array_expr,
     "[1, a]",
@@ -858,6 +974,44 @@ fn shebang_at_top_is_ignored() {
     )
 }
 
+#[test]
+fn interpolated_string() {
+    let _ = ::env_logger::try_init();
+    let text = r#"f"total: ${x + 1} items\n""#;
+    let e = parse_zero_index!(text);
+    mk_ast_arena!(arena);
+    let mut arena = arena.borrow();
+    let hole = pos::spanned2(
+        11.into(),
+        16.into(),
+        Expr::Infix {
+            lhs: arena.alloc(pos::spanned2(11.into(), 12.into(), id("x").value)),
+            op: pos::spanned2(13.into(), 14.into(), TypedIdent::new(intern("+"))),
+            rhs: arena.alloc(pos::spanned2(15.into(), 16.into(), int(1).value)),
+            implicit_args: &mut [],
+        },
+    );
+    let parts = arena.alloc_extend(vec![
+        InterpolationPart::Text(pos::spanned2(2.into(), 9.into(), "total: ".to_string())),
+        InterpolationPart::Expr {
+            expr: hole,
+            show_id: None,
+        },
+        InterpolationPart::Text(pos::spanned2(17.into(), 25.into(), " items\n".to_string())),
+    ]);
+    assert_eq!(
+        *e.expr(),
+        pos::spanned2(
+            0.into(),
+            26.into(),
+            Expr::Interpolation(arena.alloc(Interpolation {
+                parts,
+                append_id: None,
+            }))
+        )
+    );
+}
+
 #[test]
 fn do_in_parens() {
     let _ = ::env_logger::try_init();
@@ -160,6 +160,69 @@ match x with
     }
 }
 
+#[test]
+fn match_with_guards() {
+    let _ = ::env_logger::try_init();
+
+    let result = parse(
+        r#"
+match x with
+| Some y if y > 0 ->
+    let z = y
+    z
+| Some y if
+        y < 0 ->
+    if y < -10 then 1 else 2
+| _ -> 0
+"#,
+    );
+
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    match result.as_ref().unwrap().expr().value {
+        Expr::Match(_, ref alts) => {
+            assert_eq!(alts.len(), 3);
+            assert!(alts[0].guard.is_some());
+            assert!(alts[1].guard.is_some());
+            assert!(alts[2].guard.is_none());
+        }
+        ref x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn or_pattern_on_multiple_lines() {
+    let _ = ::env_logger::try_init();
+
+    let result = parse(
+        r#"
+match x with
+| Some 1
+| Some 2 ->
+    1
+| [_, ..] | [] -> 2
+| _ -> 0
+"#,
+    );
+
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    match result.as_ref().unwrap().expr().value {
+        Expr::Match(_, ref alts) => {
+            assert_eq!(alts.len(), 3);
+            match alts[0].pattern.value {
+                Pattern::Or(ref alternatives) => assert_eq!(alternatives.len(), 2),
+                ref x => panic!("{:?}", x),
+            }
+            match alts[1].pattern.value {
+                Pattern::Or(ref alternatives) => assert_eq!(alternatives.len(), 2),
+                ref x => panic!("{:?}", x),
+            }
+        }
+        ref x => panic!("{:?}", x),
+    }
+}
+
 #[test]
 fn allow_unindented_lambda() {
     let _ = ::env_logger::try_init();
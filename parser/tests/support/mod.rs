@@ -7,8 +7,8 @@ use std::marker::PhantomData;
 use crate::base::{
     ast::{
         self, walk_mut_alias, walk_mut_ast_type, walk_mut_expr, walk_mut_pattern, Alternative,
-        Argument, Array, AstType, DisplayEnv, Do, Expr, ExprField, IdentEnv, Lambda, Literal,
-        MutVisitor, Pattern, RootExpr, SpannedAlias, SpannedAstType, SpannedExpr,
+        Argument, Array, AstType, DisplayEnv, Do, Expr, ExprField, IdentEnv, InterpolationPart,
+        Lambda, Literal, MutVisitor, Pattern, RootExpr, SpannedAlias, SpannedAstType, SpannedExpr,
         SpannedIdent, SpannedPattern, TypeBinding, TypedIdent, ValueBinding,
     },
     error::Errors,
@@ -59,6 +59,13 @@ where
 
     fn visit_expr(&mut self, e: &mut SpannedExpr<Self::Ident>) {
         e.span = (self.0)(e.span);
+        if let Expr::Interpolation(ref mut interpolation) = e.value {
+            for part in &mut *interpolation.parts {
+                if let InterpolationPart::Text(ref mut text) = *part {
+                    text.span = (self.0)(text.span);
+                }
+            }
+        }
         walk_mut_expr(self, e);
     }
 
@@ -348,6 +355,7 @@ pub fn case<'ast>(
         arena.alloc(e),
         arena.alloc_extend(alts.into_iter().map(|(p, e)| Alternative {
             pattern: no_loc(p),
+            guard: None,
             expr: e,
         })),
     ))
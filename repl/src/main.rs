@@ -17,6 +17,7 @@ use std::{
     io::{self, Write},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use quick_error::quick_error;
@@ -29,11 +30,12 @@ use gluon::{base, parser, vm};
 use crate::base::filename_to_module;
 
 use gluon::{
-    new_vm_async, vm::thread::ThreadInternal, vm::Error as VMError, Result, Thread,
-    ThreadExt,
+    new_vm_async, vm::profiler::Profile, vm::thread::ThreadInternal, vm::Error as VMError, Result,
+    Thread, ThreadExt,
 };
 
 mod repl;
+mod test;
 
 quick_error! {
 /// Error type wrapping all possible errors that can be generated from gluon
@@ -109,12 +111,22 @@ pub enum SubOpt {
     Fmt(FmtOpt),
     #[structopt(name = "doc", about = "Documents gluon source code")]
     Doc(::gluon_doc::Opt),
+    #[structopt(name = "debug", about = "Debugs a gluon program")]
+    Debug(::gluon_debugger::Opt),
+    #[structopt(name = "test", about = "Runs the tests in gluon test files")]
+    Test(test::TestOpt),
 }
 
 const LONG_VERSION: &str = concat!(clap::crate_version!(), "\n", "commit: ", env!("GIT_HASH"));
 
+// Without `InferSubcommands` clap rejects files which are named similarly to a subcommand, such as
+// `tests/print.glu`, suggesting the subcommand instead of running the file
 #[derive(StructOpt)]
-#[structopt(about = "executes gluon programs", long_version = LONG_VERSION)]
+#[structopt(
+    about = "executes gluon programs",
+    long_version = LONG_VERSION,
+    setting = clap::AppSettings::InferSubcommands
+)]
 pub struct Opt {
     #[structopt(short = "i", long = "interactive", help = "Starts the repl")]
     interactive: bool,
@@ -147,6 +159,19 @@ pub struct Opt {
     )]
     no_std: bool,
 
+    #[structopt(
+        long = "profile",
+        parse(from_os_str),
+        help = "Profiles the executed files, writing folded stacks to FILE and a summary to stderr"
+    )]
+    profile: Option<PathBuf>,
+
+    #[structopt(
+        long = "profile-interval",
+        help = "Profiles by sampling the stack every MICROSECONDS instead of recording every call and line"
+    )]
+    profile_interval: Option<u64>,
+
     #[structopt(name = "FILE", help = "Executes each file as a gluon program")]
     input: Vec<String>,
 
@@ -154,13 +179,19 @@ pub struct Opt {
     subcommand_opt: Option<SubOpt>,
 }
 
-async fn run_files<I>(vm: &Thread, files: I) -> Result<()>
+async fn run_files<I>(vm: &Thread, color: Color, files: I) -> Result<()>
 where
     I: IntoIterator,
     I::Item: AsRef<str>,
 {
     for file in files {
-        vm.load_file_async(file.as_ref()).await?;
+        let file = file.as_ref();
+        vm.load_file_async(file).await?;
+
+        if let Some(warnings) = vm.module_warnings(&filename_to_module(file)) {
+            let mut stderr = termcolor::StandardStream::stderr(color.into());
+            warnings.emit(&mut stderr)?;
+        }
     }
     Ok(())
 }
@@ -229,6 +260,11 @@ async fn fmt_stdio(opt: &Opt) -> Result<()> {
     Ok(())
 }
 
+fn write_profile(profile: &Profile, path: &Path) -> io::Result<()> {
+    profile.write_folded(&mut fs::File::create(path)?)?;
+    profile.write_summary(&mut io::stderr())
+}
+
 async fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), Error> {
     vm.global_env().set_debug_level(opt.debug_level.clone());
     match opt.subcommand_opt {
@@ -267,6 +303,17 @@ async fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), Er
             let thread = new_vm_async().await;
             gluon_doc::generate_for_path(&thread, input, output)?;
         }
+        Some(SubOpt::Debug(ref debug_opt)) => {
+            let thread = new_vm_async().await;
+            thread
+                .get_database_mut()
+                .use_standard_lib(!opt.no_std)
+                .run_io(true);
+            gluon_debugger::run(thread, debug_opt).map_err(gluon::Error::from)?;
+        }
+        Some(SubOpt::Test(ref test_opt)) => {
+            test::run(vm, test_opt).await?;
+        }
         None => {
             if opt.interactive {
                 let prompt = opt.prompt.clone();
@@ -274,7 +321,21 @@ async fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), Er
                 let use_std_lib = !opt.no_std;
                 repl::run(color, &prompt, debug_level, use_std_lib).await?;
             } else if !opt.input.is_empty() {
-                run_files(&vm, &opt.input).await?;
+                match opt.profile {
+                    Some(ref profile_path) => {
+                        let profiler = match opt.profile_interval {
+                            Some(interval) => vm
+                                .root_thread()
+                                .start_sampling_profiler(Duration::from_micros(interval)),
+                            None => vm.root_thread().start_profiling(),
+                        };
+                        let result = run_files(&vm, color, &opt.input).await;
+                        write_profile(&profiler.finish(), profile_path)
+                            .map_err(gluon::Error::from)?;
+                        result?;
+                    }
+                    None => run_files(&vm, color, &opt.input).await?,
+                }
             } else {
                 writeln!(io::stderr(), "{}", Opt::clap().get_matches().usage())
                     .expect("Error writing help to stderr");
@@ -25,6 +25,7 @@ use crate::vm::{
 };
 
 use gluon::{
+    check::lint::{Lint, Warnings},
     compiler_pipeline::{Executable, ExecuteValue},
     import::add_extern_module,
     Error as GluonError, Result as GluonResult, RootedThread, ThreadExt,
@@ -357,7 +358,7 @@ fn eval_line(
     let vm = vm.new_thread().unwrap(); // TODO Reuse the current thread
     let line = line.to_string();
     async move {
-        eval_line_(vm.root_thread(), &line)
+        eval_line_(vm.root_thread(), &line, color)
             .map(move |result| match result {
                 Ok(x) => IO::Value(x),
                 Err(err) => {
@@ -372,10 +373,10 @@ fn eval_line(
     }
 }
 
-async fn eval_line_(vm: RootedThread, line: &str) -> gluon::Result<()> {
+async fn eval_line_(vm: RootedThread, line: &str, color: Color) -> gluon::Result<()> {
     let mut db = vm.get_database();
     let mut module_compiler = vm.module_compiler(&mut db);
-    let mut is_let_binding = false;
+    let mut let_binding_span = None;
     let mut eval_expr = {
         let eval_expr = {
             mk_ast_arena!(arena);
@@ -398,7 +399,7 @@ async fn eval_line_(vm: RootedThread, line: &str) -> gluon::Result<()> {
                 None => return Ok(()),
                 Some(ReplLine::Expr(expr)) => RootExpr::new(arena.clone(), arena.alloc(expr)),
                 Some(ReplLine::Let(let_binding)) => {
-                    is_let_binding = true;
+                    let_binding_span = Some(let_binding.name.span);
                     // We can't compile function bindings by only looking at `let_binding.expr`
                     // so rewrite `let f x y = <expr>` into `let f x y = <expr> in f`
                     // and `let { x } = <expr>` into `let repl_temp @ { x } = <expr> in repl_temp`
@@ -433,10 +434,32 @@ async fn eval_line_(vm: RootedThread, line: &str) -> gluon::Result<()> {
         eval_expr.try_into_send().unwrap()
     };
 
-    let ExecuteValue { value, typ, .. } = (&mut eval_expr)
+    let ExecuteValue {
+        value,
+        typ,
+        warnings,
+        ..
+    } = (&mut eval_expr)
         .run_expr(&mut module_compiler, vm.clone(), "line", line, None)
         .await?;
-    if is_let_binding {
+
+    // The variables bound by a `let` line can be used by later lines so they are never unused
+    let warnings: Warnings = warnings
+        .into_iter()
+        .filter(|warning| match let_binding_span {
+            Some(span) if span.contains(warning.span) => {
+                let lint = warning.value.lint();
+                lint != Lint::UnusedBindings && lint != Lint::UnusedImports
+            }
+            _ => true,
+        })
+        .collect();
+    if warnings.has_errors() {
+        let mut stderr = termcolor::StandardStream::stderr(color.into());
+        InFile::new(vm.get_database().code_map(), warnings).emit(&mut stderr)?;
+    }
+
+    if let_binding_span.is_some() {
         let mut expr = eval_expr.expr();
         let mut last_bind = None;
         loop {
@@ -538,7 +561,11 @@ fn set_globals(
             )?;
             set_globals(vm, pattern, typ, value)
         }
-        Pattern::Constructor(..) | Pattern::Literal(_) | Pattern::Error => {
+        Pattern::Constructor(..)
+        | Pattern::Literal(_)
+        | Pattern::Array { .. }
+        | Pattern::Or(_)
+        | Pattern::Error => {
             Err(VMError::Message("The repl cannot bind variables from this pattern".into()).into())
         }
     }
@@ -0,0 +1,341 @@
+//! Implementation of the `gluon test` subcommand which runs the `std.test.TestCase` values found
+//! in test files.
+
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use futures::{prelude::*, stream::FuturesOrdered};
+use structopt::StructOpt;
+use walkdir::WalkDir;
+
+use gluon::{
+    base::filename_to_module,
+    std_lib::test::{TestCase, TestEff, TestFn},
+    vm::api::{Getable, Hole, OpaqueValue, OwnedFunction, IO},
+    RootedThread, Thread, ThreadExt,
+};
+
+use crate::Error;
+
+#[derive(StructOpt)]
+pub struct TestOpt {
+    #[structopt(
+        long = "filter",
+        help = "Only runs the tests whose name contains FILTER"
+    )]
+    filter: Option<String>,
+
+    #[structopt(
+        long = "junit",
+        parse(from_os_str),
+        help = "Writes the test results as JUnit XML to FILE"
+    )]
+    junit: Option<PathBuf>,
+
+    #[structopt(
+        name = "PATH",
+        parse(from_os_str),
+        help = "Files or directories to search for tests (default: the current directory)"
+    )]
+    input: Vec<PathBuf>,
+}
+
+struct TestResult {
+    module: String,
+    name: String,
+    duration: Duration,
+    error: Option<String>,
+}
+
+/// Returns `true` for the files `gluon test` runs: `*_test.glu` files and `.glu` files in a
+/// `test` directory
+fn is_test_file(root: &Path, path: &Path) -> bool {
+    if path.extension() != Some(OsStr::new("glu")) {
+        return false;
+    }
+    let is_test_module = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map_or(false, |stem| stem.ends_with("_test"));
+    // `root` itself may be the test directory, as in `gluon test test/`
+    let in_test_dir = root.file_name() == Some(OsStr::new("test"))
+        || path
+            .strip_prefix(root)
+            .ok()
+            .and_then(|path| path.parent())
+            .map_or(false, |dir| dir.iter().any(|dir| dir == "test"));
+    is_test_module || in_test_dir
+}
+
+fn test_files(input: &[PathBuf]) -> Vec<PathBuf> {
+    let default_input = [PathBuf::from(".")];
+    let input = if input.is_empty() {
+        &default_input[..]
+    } else {
+        input
+    };
+
+    let mut files: Vec<_> = input
+        .iter()
+        .flat_map(|root| {
+            WalkDir::new(root).into_iter().filter_map(move |entry| {
+                let entry = entry.ok()?;
+                if entry.file_type().is_file()
+                    && (entry.path() == root || is_test_file(root, entry.path()))
+                {
+                    Some(entry.path().to_owned())
+                } else {
+                    None
+                }
+            })
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+async fn load_tests(vm: &Thread, module: &str, path: &Path) -> Result<TestCase, Error> {
+    let mut text = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut text))
+        .map_err(gluon::Error::from)?;
+    let (test, _) = vm.run_expr_async::<TestCase>(module, &text).await?;
+    Ok(test)
+}
+
+/// Flattens the groups of `test` into a list of tests named by their path in the groups
+fn flatten_tests(prefix: &str, test: TestCase, tests: &mut Vec<(String, TestFn)>) {
+    match test {
+        TestCase::Test(name, test) => tests.push((format!("{}/{}", prefix, name), test)),
+        TestCase::Group(name, group) => {
+            let prefix = format!("{}/{}", prefix, name);
+            for test in group {
+                flatten_tests(&prefix, test, tests);
+            }
+        }
+    }
+}
+
+async fn run_test(test: TestFn) -> gluon::Result<()> {
+    let child_thread = test.vm().new_thread()?;
+    let mut test = TestFn::from_value(&child_thread, test.get_variant());
+
+    let test = test.call_async(()).await?;
+    let mut run_io: OwnedFunction<fn(TestEff) -> IO<()>> =
+        test.vm().get_global("std.test.run_io")?;
+    match run_io.call_async(test).await? {
+        IO::Value(()) => Ok(()),
+        IO::Exception(err) => Err(err.into()),
+    }
+}
+
+fn print_result(result: &TestResult) {
+    println!(
+        "test {} ... {} ({:.3}s)",
+        result.name,
+        if result.error.is_none() {
+            "ok"
+        } else {
+            "FAILED"
+        },
+        result.duration.as_secs_f64()
+    );
+}
+
+pub async fn run(vm: &Thread, opt: &TestOpt) -> Result<(), Error> {
+    // Load `std.test` so that the `TestCase` type can be found
+    vm.run_expr_async::<OpaqueValue<RootedThread, Hole>>("gluon_test", "import! std.test")
+        .await?;
+
+    let is_selected = |name: &str| {
+        opt.filter
+            .as_ref()
+            .map_or(true, |filter| name.contains(&filter[..]))
+    };
+
+    let mut results = Vec::new();
+    let mut running = FuturesOrdered::new();
+    let mut filtered_out = 0;
+    // Load and typecheck the test files in parallel, the results are still handled in file order
+    let mut loading = FuturesOrdered::new();
+    for path in test_files(&opt.input) {
+        let module = filename_to_module(&path.display().to_string());
+        let thread = vm.new_thread().map_err(gluon::Error::from)?;
+        loading.push(
+            tokio::task::spawn_blocking(move || {
+                let start = Instant::now();
+                let test = futures::executor::block_on(load_tests(&thread, &module, &path))
+                    .map_err(|err| err.to_string());
+                (module, start.elapsed(), test)
+            })
+            .map(|result| result.expect("Test thread panicked")),
+        );
+    }
+
+    while let Some((module, duration, test)) = loading.next().await {
+        let test = match test {
+            Ok(test) => test,
+            Err(err) => {
+                if is_selected(&module) {
+                    let result = TestResult {
+                        name: module.clone(),
+                        module,
+                        duration,
+                        error: Some(err),
+                    };
+                    print_result(&result);
+                    results.push(result);
+                } else {
+                    filtered_out += 1;
+                }
+                continue;
+            }
+        };
+
+        let mut tests = Vec::new();
+        flatten_tests(&module, test, &mut tests);
+        for (name, test) in tests {
+            if !is_selected(&name) {
+                filtered_out += 1;
+                continue;
+            }
+            let module = module.clone();
+            running.push(
+                tokio::task::spawn_blocking(move || {
+                    let start = Instant::now();
+                    let result = futures::executor::block_on(run_test(test));
+                    TestResult {
+                        module,
+                        name,
+                        duration: start.elapsed(),
+                        error: result.err().map(|err| err.to_string()),
+                    }
+                })
+                .map(|result| result.expect("Test thread panicked"))
+                .inspect(print_result),
+            );
+        }
+    }
+    results.extend(running.collect::<Vec<_>>().await);
+
+    let failures: Vec<_> = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .collect();
+    if !failures.is_empty() {
+        println!("\nfailures:\n");
+        for result in &failures {
+            println!("---- {} ----", result.name);
+            println!("{}\n", result.error.as_ref().unwrap());
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {} filtered out",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        results.len() - failures.len(),
+        failures.len(),
+        filtered_out
+    );
+
+    if let Some(ref junit) = opt.junit {
+        write_junit(
+            &results,
+            &mut File::create(junit).map_err(gluon::Error::from)?,
+        )
+        .map_err(gluon::Error::from)?;
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failure::err_msg(format!(
+            "{} of {} tests failed",
+            failures.len(),
+            results.len()
+        ))
+        .into())
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes `results` as JUnit XML, with one test suite per test file
+fn write_junit(results: &[TestResult], out: &mut impl Write) -> io::Result<()> {
+    let total_time: Duration = results.iter().map(|result| result.duration).sum();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites tests="{}" failures="{}" time="{:.3}">"#,
+        results.len(),
+        results
+            .iter()
+            .filter(|result| result.error.is_some())
+            .count(),
+        total_time.as_secs_f64()
+    )?;
+
+    let mut modules: Vec<&str> = results.iter().map(|result| &result.module[..]).collect();
+    modules.sort();
+    modules.dedup();
+    for module in modules {
+        let suite: Vec<_> = results
+            .iter()
+            .filter(|result| result.module == module)
+            .collect();
+        writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+            escape_xml(module),
+            suite.len(),
+            suite.iter().filter(|result| result.error.is_some()).count(),
+            suite
+                .iter()
+                .map(|result| result.duration)
+                .sum::<Duration>()
+                .as_secs_f64()
+        )?;
+        for result in suite {
+            write!(
+                out,
+                r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                escape_xml(&result.name),
+                escape_xml(module),
+                result.duration.as_secs_f64()
+            )?;
+            match result.error {
+                Some(ref error) => {
+                    writeln!(out, ">")?;
+                    writeln!(
+                        out,
+                        r#"      <failure message="{}">{}</failure>"#,
+                        escape_xml(error.lines().next().unwrap_or("")),
+                        escape_xml(error)
+                    )?;
+                    writeln!(out, "    </testcase>")?;
+                }
+                None => writeln!(out, "/>")?,
+            }
+        }
+        writeln!(out, "  </testsuite>")?;
+    }
+    writeln!(out, "</testsuites>")
+}
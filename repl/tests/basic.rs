@@ -61,3 +61,73 @@ fn issue_365_run_io_from_command_line() {
     }
     assert_eq!(String::from_utf8_lossy(&output.stdout), "123\n");
 }
+
+fn gluon_command() -> Command {
+    let path = env::args().next().unwrap();
+    let gluon_path = Path::new(&path[..])
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("folder")
+        .join("gluon");
+    Command::new(gluon_path)
+}
+
+#[test]
+fn test_subcommand() {
+    let junit_path = env::temp_dir().join("gluon_test_subcommand.xml");
+    let output = gluon_command()
+        .args(&["test", "tests/suite", "--filter", "passing", "--junit"])
+        .arg(&junit_path)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("test tests.suite.passing_test/arithmetic/addition ... ok"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("2 passed; 0 failed; 1 filtered out"),
+        "{}",
+        stdout
+    );
+
+    let mut junit = String::new();
+    File::open(&junit_path)
+        .unwrap()
+        .read_to_string(&mut junit)
+        .unwrap();
+    assert!(
+        junit.contains(r#"<testsuite name="tests.suite.passing_test" tests="2" failures="0""#),
+        "{}",
+        junit
+    );
+
+    let output = gluon_command()
+        .args(&["test", "tests/suite"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("test tests.suite.failing_test/wrong_sum ... FAILED"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_subcommand_on_test_directory() {
+    let output = gluon_command()
+        .args(&["test", "tests/test/"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("test tests.test.arithmetic/multiplication ... ok"),
+        "{}",
+        stdout
+    );
+}
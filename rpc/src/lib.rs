@@ -0,0 +1,96 @@
+//! Message framing shared by the language server and the debug adapter of gluon.
+//!
+//! Both the [language server protocol][lsp] and the [debug adapter protocol][dap] precede every
+//! message with a `Content-Length` header followed by an empty line.
+//!
+//! [lsp]:https://microsoft.github.io/language-server-protocol/
+//! [dap]:https://microsoft.github.io/debug-adapter-protocol/
+#![doc(html_root_url = "https://docs.rs/gluon_rpc/0.14.1")] // # GLUON
+
+use std::io::{self, BufRead, Write};
+
+/// Reads the content of the next message from `input`. Returns `Ok(None)` if the input was closed.
+pub fn read_message<R>(input: &mut R) -> io::Result<Option<String>>
+where
+    R: ?Sized + BufRead,
+{
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        let mut split = header.splitn(2, ':');
+        let name = split.next().unwrap_or("").trim();
+        let value = split.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(value.parse::<usize>().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid Content-Length `{}`: {}", value, err),
+                )
+            })?);
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes `content` as a message to `output` and flushes it
+pub fn write_message<W>(output: &mut W, content: &str) -> io::Result<()>
+where
+    W: ?Sized + Write,
+{
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_written_messages() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, "{}").unwrap();
+        write_message(&mut buffer, "[1, \"å\"]").unwrap();
+
+        let mut input = &buffer[..];
+        assert_eq!(read_message(&mut input).unwrap(), Some("{}".to_string()));
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some("[1, \"å\"]".to_string())
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn ignore_other_headers() {
+        let mut input = &b"Content-Type: application/json\r\ncontent-length: 2\r\n\r\n{}"[..];
+        assert_eq!(read_message(&mut input).unwrap(), Some("{}".to_string()));
+    }
+
+    #[test]
+    fn invalid_content_length() {
+        let mut input = &b"Content-Length: abc\r\n\r\n"[..];
+        let err = read_message(&mut input).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
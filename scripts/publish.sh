@@ -14,7 +14,10 @@ declare -a PROJECTS=(
     gluon
     gluon_c-api
     gluon_doc
+    gluon_rpc
+    gluon_debugger
     gluon_repl
+    gluon_language-server
 )
 
 for PROJECT in "${PROJECTS[@]}"
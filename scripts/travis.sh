@@ -18,4 +18,8 @@ if [ -z $NO_NORMAL_TEST ]; then
     (echo $TRAVIS_RUST_VERSION | grep nightly) && cargo test --features "test nightly" -p gluon --test compiletest "$@"
 
     cargo check --all --no-default-features "$@"
+
+    # Check that the C header is up to date with the C API
+    (cd c-api && cbindgen --config cbindgen.toml --output include/gluon.h)
+    git diff --exit-code c-api/include/gluon.h
 fi
@@ -28,7 +28,7 @@ use crate::{
         symbol::{Name, NameBuf, Symbol, SymbolModule},
         types::{ArcType, NullInterner, Type, TypeCache},
     },
-    check::{metadata, rename},
+    check::{lint::Warnings, metadata, rename},
     query::{Compilation, CompilerDatabase},
     vm::{
         compiler::CompiledModule,
@@ -513,6 +513,8 @@ pub struct TypecheckValue<E> {
     pub typ: ArcType,
     pub metadata_map: FnvMap<Symbol, Arc<Metadata>>,
     pub metadata: Arc<Metadata>,
+    /// Warnings found while checking the expression
+    pub warnings: Warnings,
 }
 
 impl<E> TypecheckValue<E> {
@@ -522,12 +524,14 @@ impl<E> TypecheckValue<E> {
             typ,
             metadata_map,
             metadata,
+            warnings,
         } = self;
         TypecheckValue {
             expr: f(expr),
             typ,
             metadata_map,
             metadata,
+            warnings,
         }
     }
 }
@@ -604,8 +608,8 @@ fn typecheck_expr(
     file: &str,
     expected_type: Option<&ArcType>,
     metadata_map: &mut FnvMap<Symbol, Arc<Metadata>>,
-) -> Result<ArcType> {
-    use crate::check::typecheck::Typecheck;
+) -> Result<(ArcType, Warnings)> {
+    use crate::check::{lint, typecheck::Typecheck};
     let env = &*compiler.database;
     let (arena, expr) = expr.arena_expr();
     let mut tc = Typecheck::new(
@@ -617,8 +621,16 @@ fn typecheck_expr(
         arena.borrow(),
     );
 
-    tc.typecheck_expr_expected(expr, expected_type)
-        .map_err(|err| InFile::new(compiler.database.state().code_map.clone(), err).into())
+    let result = tc.typecheck_expr_expected(expr, expected_type);
+    let mut warnings = tc.take_warnings();
+
+    let typ = result.map_err(|err| InFile::new(compiler.database.state().code_map.clone(), err))?;
+
+    if let Some(file_map) = compiler.get_filemap(file) {
+        lint::lint_expr(expr, file_map.span(), &mut warnings);
+    }
+
+    Ok((typ, warnings))
 }
 
 #[async_trait::async_trait]
@@ -644,7 +656,7 @@ where
             metadata,
         } = self;
 
-        let typ = match typecheck_expr(
+        let (typ, warnings) = match typecheck_expr(
             expr.borrow_mut(),
             compiler,
             thread,
@@ -664,6 +676,7 @@ where
                         expr,
                         metadata_map,
                         metadata,
+                        warnings: Errors::new(),
                     }),
                     err,
                 ))
@@ -683,6 +696,7 @@ where
             typ,
             metadata_map,
             metadata,
+            warnings,
         })
     }
 }
@@ -695,6 +709,7 @@ pub struct CompileValue<E> {
     pub typ: ArcType,
     pub metadata: Arc<Metadata>,
     pub module: CompiledModule,
+    pub warnings: Warnings,
 }
 
 impl<E> CompileValue<E> {
@@ -705,6 +720,7 @@ impl<E> CompileValue<E> {
             typ,
             metadata,
             module,
+            warnings,
         } = self;
         CompileValue {
             expr: f(expr),
@@ -712,6 +728,7 @@ impl<E> CompileValue<E> {
             typ,
             metadata,
             module,
+            warnings,
         }
     }
 }
@@ -785,6 +802,7 @@ where
                      metadata,
                      module,
                      core_expr,
+                     warnings,
                      ..
                  }| CompileValue {
                     expr: self.expr,
@@ -792,6 +810,7 @@ where
                     typ,
                     metadata,
                     module,
+                    warnings,
                 },
             )
     }
@@ -833,7 +852,12 @@ where
                 debug!("Translation returned: {}", expr);
 
                 if settings.optimize {
-                    core::optimize::optimize(&translator.allocator, env, expr)
+                    core::optimize::optimize(
+                        &translator.allocator,
+                        env,
+                        expr,
+                        settings.wrapping_arithmetic,
+                    )
                 } else {
                     interpreter::Global {
                         value: core::freeze_expr(&translator.allocator, expr),
@@ -862,7 +886,8 @@ where
                 &source,
                 filename.to_string(),
                 settings.emit_debug_info,
-            );
+            )
+            .wrapping_arithmetic(settings.wrapping_arithmetic);
             compiler.compile_expr(core_expr.value.expr())?
         };
         module.function.id = Symbol::from(filename);
@@ -872,6 +897,7 @@ where
             typ: self.typ.clone(),
             metadata: self.metadata.clone(),
             module,
+            warnings: self.warnings.clone(),
         })
     }
 }
@@ -886,6 +912,7 @@ where
     pub typ: ArcType,
     pub metadata: Arc<Metadata>,
     pub value: RootedValue<T>,
+    pub warnings: Warnings,
 }
 
 #[async_trait::async_trait]
@@ -992,6 +1019,7 @@ where
             typ,
             mut module,
             metadata,
+            warnings,
         } = self;
         let run_io = compiler.database.compiler_settings().run_io;
         let module_id = Symbol::from(format!("@{}", name));
@@ -1006,6 +1034,7 @@ where
                 typ,
                 value,
                 metadata,
+                warnings,
             })
             .map_err(Error::from)
             .and_then(move |v| async move {
@@ -1112,6 +1141,7 @@ where
                 typ: typ,
                 metadata,
                 value,
+                warnings: Errors::new(),
             })
             .map_err(Error::from)
             .await
@@ -1175,6 +1205,7 @@ where
         typ,
         metadata,
         module,
+        warnings: _,
     } = self_
         .compile(compiler, thread, file, expr_str, arg)
         .await
@@ -1209,6 +1240,7 @@ where
             typ,
             value,
             metadata,
+            warnings,
         } = v;
 
         let vm1 = vm.clone();
@@ -1226,6 +1258,7 @@ where
                     value,
                     metadata,
                     typ: actual,
+                    warnings,
                 }
             })
             .map_err(Error::from)
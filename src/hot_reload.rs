@@ -0,0 +1,380 @@
+//! Hot reloading of modules in a running vm.
+//!
+//! `HotReload` watches the source files of modules and, when a file changes, recompiles its module
+//! along with every loaded module which imports it (directly or indirectly). The new values
+//! replace the old ones in the globals of the vm, so later imports and `Thread::get_global` see
+//! them, and the host is told about each reloaded module through a callback.
+//!
+//! If a changed module, or one of its dependents, fails to compile or run then nothing is
+//! replaced and the previous version of every module stays live.
+//!
+//! ```no_run
+//! # use std::{sync::Arc, time::Duration};
+//! # use gluon::{hot_reload::HotReload, new_vm, ThreadExt};
+//! let vm = new_vm();
+//! vm.load_file("scripts/game.glu").unwrap();
+//!
+//! let reload = HotReload::new(vm.clone())
+//!     .on_reload(|module, _value| println!("Reloaded `{}`", module))
+//!     .on_error(|module, err| eprintln!("Could not reload `{}`: {}", module, err));
+//! reload.watch("scripts.game", "scripts/game.glu");
+//! Arc::new(reload).watch_in_background(Duration::from_millis(500));
+//! ```
+
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::base::{
+    ast::{self, Expr, SpannedExpr, Visitor},
+    fnv::{FnvMap, FnvSet},
+    symbol::Symbol,
+};
+
+use salsa::Database;
+
+use crate::vm::{
+    thread::{RootedThread, RootedValue, ThreadInternal},
+    vm::VmEnv,
+};
+
+use crate::{
+    query::{Compilation, CompilationBase, DatabaseGlobal, ModuleTextQuery},
+    Error, Result, ThreadExt,
+};
+
+type ReloadCallback = Box<dyn FnMut(&str, RootedValue<RootedThread>) + Send>;
+type ErrorCallback = Box<dyn FnMut(&str, &Error) + Send>;
+
+struct WatchedFile {
+    path: PathBuf,
+    /// Modification time and length of the file when it was last loaded
+    stamp: Option<(SystemTime, u64)>,
+}
+
+/// Reloads modules in a running vm when their source files change
+pub struct HotReload {
+    vm: RootedThread,
+    watched: Mutex<FnvMap<String, WatchedFile>>,
+    on_reload: Mutex<Option<ReloadCallback>>,
+    on_error: Mutex<Option<ErrorCallback>>,
+}
+
+impl HotReload {
+    pub fn new(vm: RootedThread) -> HotReload {
+        HotReload {
+            vm,
+            watched: Mutex::default(),
+            on_reload: Mutex::default(),
+            on_error: Mutex::default(),
+        }
+    }
+
+    /// Called with the name and new value of each module after a successful reload (default:
+    /// None)
+    pub fn on_reload<F>(self, on_reload: F) -> Self
+    where
+        F: FnMut(&str, RootedValue<RootedThread>) + Send + 'static,
+    {
+        *self.on_reload.lock().unwrap() = Some(Box::new(on_reload));
+        self
+    }
+
+    /// Called with the name of the changed module and the error when a reload fails. If no
+    /// callback is set the error is logged instead (default: None)
+    pub fn on_error<F>(self, on_error: F) -> Self
+    where
+        F: FnMut(&str, &Error) + Send + 'static,
+    {
+        *self.on_error.lock().unwrap() = Some(Box::new(on_error));
+        self
+    }
+
+    /// Reloads `module` from `path` whenever the file at `path` changes
+    pub fn watch<P: Into<PathBuf>>(&self, module: &str, path: P) {
+        let path = path.into();
+        let stamp = file_stamp(&path);
+        self.watched
+            .lock()
+            .unwrap()
+            .insert(module.into(), WatchedFile { path, stamp });
+    }
+
+    /// Stops watching `module`
+    pub fn unwatch(&self, module: &str) {
+        self.watched.lock().unwrap().remove(module);
+    }
+
+    /// Checks the watched files once and reloads the modules whose files changed. Returns the
+    /// names of the reloaded modules, which includes the dependents of the changed modules.
+    pub fn check(&self) -> Result<Vec<String>> {
+        futures::executor::block_on(self.check_async())
+    }
+
+    pub async fn check_async(&self) -> Result<Vec<String>> {
+        let changed = {
+            let mut watched = self.watched.lock().unwrap();
+            let mut changed = Vec::new();
+            for (module, file) in watched.iter_mut() {
+                let stamp = file_stamp(&file.path);
+                if stamp != file.stamp {
+                    file.stamp = stamp;
+                    changed.push((module.clone(), file.path.clone()));
+                }
+            }
+            changed
+        };
+        if changed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut sources = Vec::new();
+        for (module, path) in changed {
+            match fs::read_to_string(&path) {
+                Ok(source) => sources.push((module, source)),
+                Err(err) => {
+                    let err = Error::from(err);
+                    self.report_error(&module, &err);
+                    return Err(err);
+                }
+            }
+        }
+        let result = self.reload_sources(&sources).await;
+        if let Err(ref err) = result {
+            for (module, _) in &sources {
+                self.report_error(module, err);
+            }
+        }
+        result
+    }
+
+    /// Polls the watched files every `interval` on a background thread. The thread stops once
+    /// the `HotReload` is dropped.
+    pub fn watch_in_background(self: Arc<Self>, interval: Duration) -> thread::JoinHandle<()> {
+        let reload = Arc::downgrade(&self);
+        drop(self);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match reload.upgrade() {
+                // Errors have already been passed to `on_error`
+                Some(reload) => drop(reload.check()),
+                None => break,
+            }
+        })
+    }
+
+    /// Replaces the source of each module in `sources` and reloads them and their dependents
+    pub fn reload(&self, sources: &[(String, String)]) -> Result<Vec<String>> {
+        futures::executor::block_on(self.reload_sources(sources))
+    }
+
+    async fn reload_sources(&self, sources: &[(String, String)]) -> Result<Vec<String>> {
+        let vm = &self.vm;
+
+        let (order, old_globals, old_sources) = {
+            let mut db = vm.get_database();
+            let loaded = db.loaded_modules();
+
+            // Modules which are not loaded are compiled from the new source once imported
+            let changed: Vec<_> = sources
+                .iter()
+                .map(|(module, _)| module.clone())
+                .filter(|module| loaded.contains(module))
+                .collect();
+
+            let order = dependents_in_load_order(vm, &loaded, &changed);
+            let old_globals: Vec<Option<DatabaseGlobal>> =
+                order.iter().map(|module| db.get_global(module)).collect();
+            let old_sources: Vec<_> = sources
+                .iter()
+                .map(|(module, _)| (module.clone(), db.module_text(module.clone()).ok()))
+                .collect();
+            (order, old_globals, old_sources)
+        };
+
+        {
+            let mut db = vm.get_database_mut();
+            for (module, source) in sources {
+                db.add_module(module.clone(), source);
+                // `add_module` only invalidates modules which were added from a string before,
+                // not modules which were read from a file
+                db.query_mut(ModuleTextQuery).invalidate(module);
+            }
+        }
+
+        // Modules are replaced one at a time, in dependency order, as the compiled code of a
+        // module refers to the values of its dependencies in the globals
+        let mut values = Vec::new();
+        for module in &order {
+            let result = async {
+                let mut db = vm.get_database();
+                let global = db.global(module.clone()).await?;
+                vm.set_global(
+                    global.id.clone(),
+                    global.typ.clone(),
+                    global.metadata.clone(),
+                    global.value.get_value(),
+                )?;
+                Ok(global.value)
+            }
+            .await;
+            match result {
+                Ok(value) => values.push(value),
+                Err(err) => {
+                    self.restore(&order, &old_globals, &old_sources);
+                    return Err(err);
+                }
+            }
+        }
+
+        if let Some(on_reload) = &mut *self.on_reload.lock().unwrap() {
+            for (module, value) in order.iter().zip(values) {
+                on_reload(module, value);
+            }
+        }
+        Ok(order)
+    }
+
+    /// Puts back the previous globals of `modules` and the previous sources of the changed
+    /// modules. Modules which were not replaced yet are set as well, as the database may only
+    /// hold the failed version of them now.
+    fn restore(
+        &self,
+        modules: &[String],
+        old_globals: &[Option<DatabaseGlobal>],
+        old_sources: &[(String, Option<Arc<Cow<'static, str>>>)],
+    ) {
+        let vm = &self.vm;
+        for (module, old) in modules.iter().zip(old_globals) {
+            if let Some(old) = old {
+                if let Err(err) = vm.set_global(
+                    old.id.clone(),
+                    old.typ.clone(),
+                    old.metadata.clone(),
+                    old.value.get_value(),
+                ) {
+                    warn!("Could not restore `{}`: {}", module, err);
+                }
+            }
+        }
+
+        let mut db = vm.get_database_mut();
+        for (module, source) in old_sources {
+            if let Some(source) = source {
+                db.add_module(module.clone(), source);
+            }
+        }
+    }
+
+    fn report_error(&self, module: &str, err: &Error) {
+        match &mut *self.on_error.lock().unwrap() {
+            Some(on_error) => on_error(module, err),
+            None => warn!("Could not reload `{}`: {}", module, err),
+        }
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Returns `changed` and every loaded module which depends on them, ordered so that each module
+/// comes after its dependencies
+fn dependents_in_load_order(
+    vm: &RootedThread,
+    loaded: &[String],
+    changed: &[String],
+) -> Vec<String> {
+    let imports: FnvMap<&str, Vec<String>> = loaded
+        .iter()
+        .map(|module| (&module[..], module_imports(vm, module)))
+        .collect();
+
+    let mut reloaded: FnvSet<&str> = changed.iter().map(|module| &module[..]).collect();
+    loop {
+        let dependents: Vec<_> = imports
+            .iter()
+            .filter(|(module, imports)| {
+                !reloaded.contains(*module)
+                    && imports.iter().any(|import| reloaded.contains(&import[..]))
+            })
+            .map(|(module, _)| *module)
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        reloaded.extend(dependents);
+    }
+
+    fn visit<'a>(
+        module: &'a str,
+        imports: &'a FnvMap<&str, Vec<String>>,
+        reloaded: &FnvSet<&str>,
+        visited: &mut FnvSet<&'a str>,
+        order: &mut Vec<String>,
+    ) {
+        if !reloaded.contains(module) || !visited.insert(module) {
+            return;
+        }
+        for import in imports.get(module).into_iter().flatten() {
+            visit(import, imports, reloaded, visited, order);
+        }
+        order.push(module.to_string());
+    }
+
+    let mut order = Vec::new();
+    let mut visited = FnvSet::default();
+    for module in loaded {
+        visit(module, &imports, &reloaded, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Returns the modules imported with `import!` in the source of `module`. Modules without a
+/// source, such as extern modules, import nothing.
+fn module_imports(vm: &RootedThread, module: &str) -> Vec<String> {
+    let text = match vm.get_database().module_text(module.into()) {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+    let expr = match vm.parse_expr(vm.global_env().type_cache(), module, &text) {
+        Ok(expr) => expr,
+        Err(_) => return Vec::new(),
+    };
+    let mut imports = Imports::default();
+    imports.visit_expr(expr.expr());
+    imports.modules
+}
+
+/// Collects the arguments of the `import!` macros in an unexpanded expression
+#[derive(Default)]
+struct Imports {
+    modules: Vec<String>,
+}
+
+impl<'a, 'ast> Visitor<'a, 'ast> for Imports {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<'ast, Symbol>) {
+        match &expr.value {
+            Expr::App { func, args, .. } => match &func.value {
+                Expr::Ident(id) if id.name.declared_name() == "import!" => {
+                    if let Ok(module) = crate::import::get_module_name(args) {
+                        let module = module.trim_start_matches('@');
+                        if !self.modules.iter().any(|m| m == module) {
+                            self.modules.push(module.to_string());
+                        }
+                    }
+                }
+                _ => ast::walk_expr(self, expr),
+            },
+            _ => ast::walk_expr(self, expr),
+        }
+    }
+}
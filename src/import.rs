@@ -12,11 +12,7 @@ use std::{
 };
 
 use async_trait::async_trait;
-use futures::{
-    future::{self, BoxFuture},
-    prelude::*,
-    task::SpawnExt,
-};
+use futures::{future, prelude::*, task::SpawnExt};
 use itertools::Itertools;
 
 use crate::base::{
@@ -32,11 +28,14 @@ use crate::vm::{
     self,
     gc::Trace,
     macros::{Error as MacroError, Macro, MacroExpander, MacroFuture},
+    sandbox::Capability,
     thread::{RootedThread, Thread, ThreadInternal},
     vm::VmEnv,
     ExternLoader, ExternModule,
 };
 
+#[cfg(feature = "serialization")]
+use crate::module_cache::ModuleCache;
 use crate::{
     query::{Compilation, CompilerDatabase},
     IoError, ModuleCompiler,
@@ -54,6 +53,14 @@ quick_error! {
                 cycle.iter().chain(Some(module)).format(" -> ")
             )
         }
+        /// The module needs a capability which the sandbox of the vm does not allow
+        Forbidden(module: String, capability: Capability) {
+            display(
+                "Module `{}` requires the `{}` capability which is not allowed by the sandbox",
+                module,
+                capability
+            )
+        }
         /// Generic message error
         String(message: String) {
             display("{}", message)
@@ -91,9 +98,21 @@ impl Importer for DefaultImporter {
     async fn import(
         &self,
         compiler: &mut ModuleCompiler<'_>,
-        _vm: &Thread,
+        vm: &Thread,
         modulename: &str,
     ) -> Result<ArcType, (Option<ArcType>, crate::Error)> {
+        #[cfg(feature = "serialization")]
+        {
+            if let Some(cache) = crate::get_import(vm).module_cache() {
+                return cache
+                    .import(compiler, vm, modulename)
+                    .await
+                    .map_err(|err| (None, err));
+            }
+        }
+        #[cfg(not(feature = "serialization"))]
+        let _ = vm;
+
         let value = compiler
             .database
             .global(modulename.to_string())
@@ -191,6 +210,8 @@ pub(crate) trait ImportApi: Send + Sync {
         forker: salsa::ForkState<CompilerDatabase>,
         thread: RootedThread,
     ) -> DatabaseFork;
+    #[cfg(feature = "serialization")]
+    fn module_cache(&self) -> Option<Arc<ModuleCache>>;
 }
 
 #[async_trait]
@@ -224,6 +245,10 @@ where
     ) -> DatabaseFork {
         Self::fork(self, forker, thread)
     }
+    #[cfg(feature = "serialization")]
+    fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        Self::module_cache(self)
+    }
 }
 
 /// Macro which rewrites occurances of `import! "filename"` to a load of that file if it is not
@@ -234,6 +259,8 @@ pub struct Import<I = DefaultImporter> {
     pub importer: I,
 
     compiler: Mutex<CompilerDatabase>,
+    #[cfg(feature = "serialization")]
+    cache: RwLock<Option<Arc<ModuleCache>>>,
 }
 
 impl<I> Import<I> {
@@ -244,6 +271,8 @@ impl<I> Import<I> {
             loaders: RwLock::default(),
             compiler: CompilerDatabase::new_base(None).into(),
             importer: importer,
+            #[cfg(feature = "serialization")]
+            cache: RwLock::default(),
         }
     }
 
@@ -256,6 +285,23 @@ impl<I> Import<I> {
         *self.paths.write().unwrap() = paths;
     }
 
+    /// Stores the compiled form of imported modules in `dir`, letting later imports of the same
+    /// modules skip typechecking and compilation as long as they and their dependencies are
+    /// unchanged.
+    ///
+    /// Only modules imported after the cache is set are cached, as the cache needs to know the
+    /// dependencies of a module to verify it. Prefer `VmBuilder::module_cache_dir` which sets the
+    /// cache before the standard library is loaded.
+    #[cfg(feature = "serialization")]
+    pub fn set_cache_dir<P: Into<PathBuf>>(&self, dir: P) {
+        *self.cache.write().unwrap() = Some(Arc::new(ModuleCache::new(dir)));
+    }
+
+    #[cfg(feature = "serialization")]
+    pub fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        self.cache.read().unwrap().clone()
+    }
+
     pub fn add_loader(&self, module: &str, loader: ExternLoader) {
         self.loaders
             .write()
@@ -377,19 +423,21 @@ impl<I> Import<I> {
     {
         assert!(module_id.is_global());
         let modulename = module_id.name().definition_name();
+
         // Retrieve the source, first looking in the standard library included in the
         // binary
         let unloaded_module = self.get_unloaded_module(&modulename);
 
         Ok(match unloaded_module {
             UnloadedModule::Extern(dependencies) => {
+                // Go through the `import` query so that dependencies which are themselves extern
+                // modules are only loaded once (and their types only registered once)
                 for dep in dependencies {
-                    let dep_id = Symbol::from(if dep.starts_with('@') {
-                        dep
-                    } else {
-                        format!("@{}", dep)
-                    });
-                    self.load_module_boxed(compiler, vm, &dep_id).await?;
+                    compiler
+                        .database
+                        .import(dep)
+                        .await
+                        .map_err(|err| (None, MacroError::new(err)))?;
                 }
 
                 let ExternModule {
@@ -412,6 +460,14 @@ impl<I> Import<I> {
                     value.get_value(),
                 )
                 .map_err(|err| (None, MacroError::new(err)))?;
+
+                #[cfg(feature = "serialization")]
+                {
+                    if let Some(cache) = self.module_cache() {
+                        cache.add_extern_module(modulename);
+                    }
+                }
+
                 typ
             }
             UnloadedModule::Source => self
@@ -421,18 +477,6 @@ impl<I> Import<I> {
                 .map_err(|(t, err)| (t, MacroError::new(err)))?,
         })
     }
-
-    fn load_module_boxed<'a, 'b>(
-        &'a self,
-        compiler: &'a mut ModuleCompiler<'b>,
-        vm: &'a Thread,
-        module_id: &'a Symbol,
-    ) -> BoxFuture<'a, Result<ArcType, (Option<ArcType>, MacroError)>>
-    where
-        I: Importer,
-    {
-        self.load_module(compiler, vm, module_id).boxed()
-    }
 }
 
 /// Adds an extern module to `thread`, letting it be loaded with `import! name` from gluon code.
@@ -551,6 +595,40 @@ macro_rules! add_extern_module_if {
     }};
 }
 
+/// Returns the name of the module imported by the arguments of `import!`
+/// Returns an error if the sandbox of `vm` does not allow `module` to be imported
+fn check_capability(vm: &Thread, module: &str) -> Result<(), Error> {
+    let module = module.trim_start_matches('@');
+    match (vm.global_env().sandbox(), Capability::of_module(module)) {
+        (Some(sandbox), Some(capability)) if !sandbox.is_allowed(capability) => {
+            Err(Error::Forbidden(module.to_string(), capability))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn get_module_name(args: &[SpannedExpr<Symbol>]) -> Result<String, Error> {
+    if args.len() != 1 {
+        return Err(Error::String("Expected import to get 1 argument".into()).into());
+    }
+
+    let modulename = match args[0].value {
+        Expr::Ident(_) | Expr::Projection(..) => {
+            let mut modulename = String::new();
+            expr_to_path(&args[0], &mut modulename)
+                .map_err(|err| Error::String(err.to_string()))?;
+            modulename
+        }
+        Expr::Literal(Literal::String(ref filename)) => {
+            format!("@{}", filename_to_module(filename))
+        }
+        _ => {
+            return Err(Error::String("Expected a string literal or path to import".into()).into());
+        }
+    };
+    Ok(modulename)
+}
+
 impl<I> Macro for Import<I>
 where
     I: Importer,
@@ -591,31 +669,6 @@ where
         _arena: &'b mut ast::OwnedArena<'ast, Symbol>,
         args: &'b mut [SpannedExpr<'ast, Symbol>],
     ) -> MacroFuture<'r, 'ast> {
-        fn get_module_name(args: &[SpannedExpr<Symbol>]) -> Result<String, Error> {
-            if args.len() != 1 {
-                return Err(Error::String("Expected import to get 1 argument".into()).into());
-            }
-
-            let modulename = match args[0].value {
-                Expr::Ident(_) | Expr::Projection(..) => {
-                    let mut modulename = String::new();
-                    expr_to_path(&args[0], &mut modulename)
-                        .map_err(|err| Error::String(err.to_string()))?;
-                    modulename
-                }
-                Expr::Literal(Literal::String(ref filename)) => {
-                    format!("@{}", filename_to_module(filename))
-                }
-                _ => {
-                    return Err(Error::String(
-                        "Expected a string literal or path to import".into(),
-                    )
-                    .into());
-                }
-            };
-            Ok(modulename)
-        }
-
         let modulename = match get_module_name(&args).map_err(MacroError::new) {
             Ok(modulename) => modulename,
             Err(err) => return Box::pin(future::err(err)),
@@ -623,6 +676,11 @@ where
 
         info!("import! {}", modulename);
 
+        // Checked on every `import!` instead of when the module is loaded, as loading is memoized
+        if let Err(err) = check_capability(macros.vm, &modulename) {
+            return Box::pin(future::err(MacroError::new(err)));
+        }
+
         let mut db = try_future!(macros
             .userdata
             .fork(macros.vm.root_thread())
@@ -44,15 +44,21 @@ macro_rules! try_future {
 }
 
 pub mod compiler_pipeline;
+pub mod hot_reload;
 #[macro_use]
 pub mod import;
 pub mod lift_io;
+#[cfg(feature = "serialization")]
+pub mod module_cache;
 #[doc(hidden)]
 pub mod query;
 pub mod std_lib;
+mod user_macros;
 
 pub use crate::vm::thread::{RootedThread, Thread};
 
+pub use gluon_codegen::{function, module};
+
 use futures::prelude::*;
 
 use either::Either;
@@ -72,12 +78,15 @@ use crate::base::{
     types::{ArcType, TypeCache},
 };
 
+use crate::check::lint::Warning;
+
 use crate::format::Formatter;
 
 use crate::vm::{
     api::{Getable, Hole, OpaqueValue, VmType},
     compiler::CompiledModule,
     macros,
+    sandbox::Sandbox,
 };
 
 use crate::{
@@ -283,6 +292,7 @@ pub struct Settings {
     pub use_standard_lib: bool,
     pub optimize: bool,
     pub run_io: bool,
+    pub wrapping_arithmetic: bool,
 }
 
 impl Default for Settings {
@@ -294,6 +304,7 @@ impl Default for Settings {
             use_standard_lib: true,
             optimize: true,
             run_io: false,
+            wrapping_arithmetic: false,
         }
     }
 }
@@ -392,6 +403,13 @@ impl import::DatabaseMut {
         /// (default: false)
         run_io set_run_io: bool
     }
+
+    runtime_option! {
+        /// Sets whether `Int` and `Byte` arithmetic wraps around on overflow. When disabled, overflow
+        /// and division by zero panic.
+        /// (default: false)
+        wrapping_arithmetic set_wrapping_arithmetic: bool
+    }
 }
 
 /// Extension trait which provides methods to load and execute gluon code
@@ -492,6 +510,19 @@ pub trait ThreadExt: Send + Sync {
         Ok((expr, typ))
     }
 
+    /// Returns the warnings that were found while typechecking `module`.
+    ///
+    /// Returns `None` if `module` has not been typechecked or if no warnings were found.
+    fn module_warnings(&self, module: &str) -> Option<InFile<Warning>> {
+        let db = self.get_database();
+        let warnings = db.peek_typechecked_module(module)?.warnings;
+        if warnings.has_errors() {
+            Some(InFile::new(db.code_map(), warnings))
+        } else {
+            None
+        }
+    }
+
     /// Compiles `expr` into a function which can be added and run by the `vm`
     async fn compile_script(
         &self,
@@ -505,6 +536,7 @@ pub trait ThreadExt: Send + Sync {
             typ: vm.global_env().type_cache().hole(),
             metadata: Default::default(),
             metadata_map: Default::default(),
+            warnings: Errors::new(),
         }
         .compile(
             &mut ModuleCompiler::new(&mut vm.get_database()),
@@ -855,6 +887,10 @@ in ()
 #[derive(Default)]
 pub struct VmBuilder {
     import_paths: Option<Vec<PathBuf>>,
+    sandbox: Option<Sandbox>,
+    memory_limit: Option<usize>,
+    #[cfg(feature = "serialization")]
+    module_cache_dir: Option<PathBuf>,
 }
 
 impl VmBuilder {
@@ -867,6 +903,29 @@ impl VmBuilder {
         import_paths set_import_paths: Option<Vec<PathBuf>>
     }
 
+    option! {
+        /// Restricts which modules scripts may import and which capabilities they may use at
+        /// runtime (default: None)
+        sandbox set_sandbox: Option<Sandbox>
+    }
+
+    option! {
+        /// Limits the memory the vm may allocate, in bytes (default: None)
+        memory_limit set_memory_limit: Option<usize>
+    }
+
+    /// Directory where compiled modules are cached between runs (default: None)
+    #[cfg(feature = "serialization")]
+    pub fn module_cache_dir(mut self, module_cache_dir: Option<PathBuf>) -> Self {
+        self.module_cache_dir = module_cache_dir;
+        self
+    }
+
+    #[cfg(feature = "serialization")]
+    pub fn set_module_cache_dir(&mut self, module_cache_dir: Option<PathBuf>) {
+        self.module_cache_dir = module_cache_dir;
+    }
+
     pub fn build(self) -> RootedThread {
         futures::executor::block_on(self.build_inner(None))
     }
@@ -893,6 +952,7 @@ impl VmBuilder {
         let vm = RootedThread::with_global_state(
             crate::vm::vm::GlobalVmStateBuilder::new()
                 .spawner(spawner)
+                .sandbox(self.sandbox)
                 .build(),
         );
 
@@ -905,6 +965,13 @@ impl VmBuilder {
                     import.set_paths(import_paths);
                 }
 
+                #[cfg(feature = "serialization")]
+                {
+                    if let Some(module_cache_dir) = self.module_cache_dir {
+                        import.set_cache_dir(module_cache_dir);
+                    }
+                }
+
                 if let Ok(gluon_path) = env::var("GLUON_PATH") {
                     import.add_path(gluon_path);
                 }
@@ -912,6 +979,8 @@ impl VmBuilder {
             }
 
             macros.insert(String::from("lift_io"), lift_io::LiftIo);
+            macros.insert(String::from("import_macros"), user_macros::ImportMacros);
+            macros.insert(String::from("quote"), user_macros::Quote);
         }
 
         add_extern_module_with_deps(
@@ -935,11 +1004,18 @@ impl VmBuilder {
         let deps: &[(_, fn(&Thread) -> _)] = &[
             ("std.byte.prim", crate::vm::primitives::load_byte),
             ("std.int.prim", crate::vm::primitives::load_int),
+            ("std.int8.prim", crate::vm::primitives::load_int8),
+            ("std.int16.prim", crate::vm::primitives::load_int16),
+            ("std.int32.prim", crate::vm::primitives::load_int32),
+            ("std.uint16.prim", crate::vm::primitives::load_uint16),
+            ("std.uint32.prim", crate::vm::primitives::load_uint32),
+            ("std.uint64.prim", crate::vm::primitives::load_uint64),
             ("std.float.prim", crate::vm::primitives::load_float),
             ("std.string.prim", crate::vm::primitives::load_string),
             ("std.fs.prim", crate::vm::primitives::load_fs),
             ("std.char.prim", crate::vm::primitives::load_char),
             ("std.char.prim", crate::vm::primitives::load_char),
+            ("std.hash.prim", crate::vm::primitives::load_hash),
             ("std.thread.prim", crate::vm::channel::load_thread),
             ("std.io.prim", crate::std_lib::io::load),
         ];
@@ -954,12 +1030,21 @@ impl VmBuilder {
             vec!["std.path.types".into()],
         );
 
+        add_extern_module_with_deps(
+            &vm,
+            "std.channel.prim",
+            crate::vm::channel::load_channel,
+            vec!["std.channel.types".into()],
+        );
+
         let deps: &[(_, fn(&Thread) -> _)] = &[
             ("std.array.prim", crate::vm::primitives::load_array),
+            ("std.array.mut.prim", crate::vm::buffer::load_array_buf),
+            ("std.byte.buf.prim", crate::vm::buffer::load_byte_buf),
             ("std.lazy.prim", crate::vm::lazy::load),
             ("std.reference.prim", crate::vm::reference::load),
-            ("std.channel.prim", crate::vm::channel::load_channel),
             ("std.debug.prim", crate::vm::debug::load),
+            ("std.io.concurrent.prim", crate::std_lib::concurrent::load),
             ("std.process.prim", crate::std_lib::process::load),
             ("std.env.prim", crate::std_lib::env::load),
         ];
@@ -987,6 +1072,20 @@ impl VmBuilder {
             args(&vm, "std.regex.prim", crate::std_lib::regex::load)
         );
 
+        add_extern_module_if!(
+            #[cfg(feature = "big_int")],
+            available_if = "gluon is compiled with the 'big_int' feature",
+            dependencies = ["std.types"],
+            args(&vm, "std.big_int.prim", crate::std_lib::big_int::load)
+        );
+
+        add_extern_module_if!(
+            #[cfg(feature = "decimal")],
+            available_if = "gluon is compiled with the 'decimal' feature",
+            dependencies = ["std.types", "std.big_int.prim"],
+            args(&vm, "std.decimal.prim", crate::std_lib::decimal::load)
+        );
+
         add_extern_module_if!(
             #[cfg(feature = "web")],
             available_if = "gluon is compiled with the 'web' feature",
@@ -1006,6 +1105,10 @@ impl VmBuilder {
             args(&vm, "std.random.prim", crate::std_lib::random::load)
         );
 
+        if let Some(memory_limit) = self.memory_limit {
+            vm.set_memory_limit(memory_limit);
+        }
+
         vm
     }
 }
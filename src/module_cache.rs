@@ -0,0 +1,447 @@
+//! On-disk cache of compiled modules.
+//!
+//! Modules loaded through `import!` are normally parsed, typechecked and compiled every time a
+//! process starts. When a cache directory is set on the `Import` macro, the type, metadata and
+//! bytecode of each imported module is written to that directory and loaded directly by later
+//! imports, as long as the source of the module, the compiler `Settings`, the compiler version,
+//! the bytecode format and the dependencies of the module are unchanged.
+
+use std::{
+    borrow::Cow,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use siphasher::sip::SipHasher13;
+
+use crate::base::{
+    ast::{self, Expr, SpannedExpr, Visitor},
+    error::Errors,
+    fnv::{FnvMap, FnvSet},
+    symbol::{Symbol, Symbols},
+    types::{self, walk_type_, Alias, AliasData, AliasRef, ArcType, Field, Type, Walker},
+};
+
+use crate::vm::{
+    serialization::{DeSeed, SeSeed},
+    thread::{Thread, ThreadInternal},
+};
+
+use crate::{
+    compiler_pipeline::{run_io, ExecuteValue, Module, TypecheckValue},
+    query::{compile_core_expr, Compilation, CompilerDatabase},
+    serde::ser::SerializeState,
+    ModuleCompiler, Result, Settings,
+};
+
+/// First line of a cache entry. Contains the key of the module source followed by the name and
+/// key of each module it depends on.
+type Header = (u64, Vec<(String, u64)>);
+
+/// Cache of compiled modules, stored as one file per module in a directory
+pub struct ModuleCache {
+    dir: PathBuf,
+    /// Keys of the modules loaded by this process. A cached module is only used if the current
+    /// keys of its dependencies are the same as when it was cached.
+    keys: Mutex<FnvMap<String, u64>>,
+    /// The source key and type of each module loaded by this process. The `import` query is
+    /// re-executed in every new revision so modules which were already loaded are not
+    /// deserialized again, as that would give their types new symbols.
+    loaded: Mutex<FnvMap<String, (u64, ArcType)>>,
+}
+
+impl ModuleCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> ModuleCache {
+        ModuleCache {
+            dir: dir.into(),
+            keys: Mutex::default(),
+            loaded: Mutex::default(),
+        }
+    }
+
+    /// Returns the directory the cached modules are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) fn add_extern_module(&self, module: &str) {
+        let key = hash(&(module, env!("CARGO_PKG_VERSION"), BYTECODE_VERSION));
+        self.keys.lock().unwrap().insert(module.into(), key);
+    }
+
+    /// Loads `module` from the cache if possible, otherwise compiles it from source and stores
+    /// the result in the cache
+    pub(crate) async fn import(
+        &self,
+        compiler: &mut ModuleCompiler<'_>,
+        vm: &Thread,
+        module: &str,
+    ) -> Result<ArcType> {
+        let db = &mut *compiler.database;
+
+        let text = db.module_text(module.into())?;
+        let source_key = source_key(&text, &db.compiler_settings());
+
+        if let Some((loaded_key, typ)) = self.loaded.lock().unwrap().get(module) {
+            if *loaded_key == source_key {
+                return Ok(typ.clone());
+            }
+        }
+
+        match self.load(db, vm, module, source_key).await {
+            Ok(Some((key, typ))) => {
+                info!("Loaded module `{}` from the cache", module);
+                self.keys.lock().unwrap().insert(module.into(), key);
+                self.loaded
+                    .lock()
+                    .unwrap()
+                    .insert(module.into(), (source_key, typ.clone()));
+                return Ok(typ);
+            }
+            Ok(None) => (),
+            Err(err) => debug!("Could not load `{}` from the cache: {}", module, err),
+        }
+
+        let (compiled, imports) = compile(db, module).await?;
+
+        match self.store(module, source_key, imports, &compiled) {
+            Ok(Some(key)) => {
+                self.keys.lock().unwrap().insert(module.into(), key);
+            }
+            Ok(None) => (),
+            Err(err) => warn!("Could not store `{}` in the cache: {}", module, err),
+        }
+
+        let typ = execute(db, vm, module, compiled).await?;
+        self.loaded
+            .lock()
+            .unwrap()
+            .insert(module.into(), (source_key, typ.clone()));
+        Ok(typ)
+    }
+
+    async fn load(
+        &self,
+        db: &mut CompilerDatabase,
+        vm: &Thread,
+        module: &str,
+        source_key: u64,
+    ) -> Result<Option<(u64, ArcType)>> {
+        let contents = match fs::read(self.entry_path(module)) {
+            Ok(contents) => contents,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let header_end = contents
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| "Missing cache entry header".to_string())?;
+        let (cached_source_key, dependencies): Header =
+            serde_json::from_slice(&contents[..header_end]).map_err(|err| err.to_string())?;
+        if cached_source_key != source_key {
+            return Ok(None);
+        }
+
+        // Share the symbols of the dependencies so that the aliases in the cached type are the
+        // same as the aliases of the already loaded modules
+        let mut collector = SymbolCollector::default();
+        for (dependency, cached_key) in &dependencies {
+            let typ = db.import(dependency.clone()).await?.typ;
+            if self.key(dependency) != Some(*cached_key) {
+                return Ok(None);
+            }
+            collector.walk(&typ);
+        }
+
+        let Module {
+            typ,
+            metadata,
+            module: compiled_module,
+        } = DeSeed::new(vm, &mut vm.current_context())
+            .with_symbols(collector.symbols)
+            .deserialize(&mut serde_json::Deserializer::from_slice(
+                &contents[header_end + 1..],
+            ))
+            .map_err(|err| err.to_string())?;
+        let typ = AliasReplacer {
+            aliases: collector.aliases,
+            replacing: FnvSet::default(),
+        }
+        .typ(&typ);
+
+        let typ = execute(
+            db,
+            vm,
+            module,
+            Module {
+                typ,
+                metadata,
+                module: compiled_module,
+            },
+        )
+        .await?;
+
+        Ok(Some((full_key(source_key, &dependencies), typ)))
+    }
+
+    /// Writes the already compiled `module` to the cache
+    fn store(
+        &self,
+        name: &str,
+        source_key: u64,
+        imports: Imports,
+        module: &Module,
+    ) -> Result<Option<u64>> {
+        let dependencies = {
+            let keys = self.keys.lock().unwrap();
+            let dependencies: Option<Vec<_>> = imports
+                .modules
+                .into_iter()
+                .map(|dependency| keys.get(&dependency).map(|&key| (dependency, key)))
+                .collect();
+            match dependencies {
+                Some(dependencies) => dependencies,
+                // A dependency was loaded before the cache was enabled
+                None => return Ok(None),
+            }
+        };
+
+        let mut contents =
+            serde_json::to_vec(&(source_key, &dependencies)).map_err(|err| err.to_string())?;
+        contents.push(b'\n');
+        module
+            .serialize_state(
+                &mut serde_json::Serializer::new(&mut contents),
+                &SeSeed::new(),
+            )
+            .map_err(|err| err.to_string())?;
+
+        // Write to a temporary file first so other processes never see a partially written entry
+        fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(name);
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, &contents)?;
+        fs::rename(&temp_path, &path)?;
+
+        Ok(Some(full_key(source_key, &dependencies)))
+    }
+
+    fn key(&self, module: &str) -> Option<u64> {
+        self.keys.lock().unwrap().get(module).cloned()
+    }
+
+    fn entry_path(&self, module: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", module))
+    }
+}
+
+/// Compiles `module` from source, returning it along with the modules it imports
+async fn compile(db: &mut CompilerDatabase, module: &str) -> Result<(Module, Imports)> {
+    let TypecheckValue {
+        expr,
+        typ,
+        metadata,
+        ..
+    } = db
+        .typechecked_module(module.into(), None)
+        .await
+        .map_err(|(_, err)| err)?;
+    let core_expr = db.core_expr(module.into(), None).await?;
+    let compiled_module = compile_core_expr(db, module, &core_expr)?;
+
+    let mut imports = Imports::default();
+    imports.visit_expr(expr.expr());
+
+    Ok((
+        Module {
+            typ,
+            metadata,
+            module: compiled_module,
+        },
+        imports,
+    ))
+}
+
+/// Runs a compiled module and sets its value as the global `module`
+async fn execute(
+    db: &mut CompilerDatabase,
+    vm: &Thread,
+    module: &str,
+    compiled: Module,
+) -> Result<ArcType> {
+    let Module {
+        typ,
+        metadata,
+        module: compiled_module,
+    } = compiled;
+
+    let closure = vm.global_env().new_global_thunk(vm, compiled_module)?;
+    let value = vm.call_thunk_top(&closure).await?;
+    let value = ExecuteValue {
+        id: Symbol::from(format!("@{}", module)),
+        expr: (),
+        typ,
+        metadata,
+        value,
+        warnings: Errors::new(),
+    };
+    let ExecuteValue {
+        id,
+        typ,
+        metadata,
+        value,
+        ..
+    } = if db.compiler_settings().run_io {
+        run_io(vm, value).await?
+    } else {
+        value
+    };
+
+    vm.set_global(id, typ.clone(), metadata, value.get_value())?;
+
+    Ok(typ)
+}
+
+/// Version of the bytecode and serialization format of the cache entries. Must be increased
+/// whenever either changes so that entries written by an older compiler are not loaded.
+const BYTECODE_VERSION: u32 = 1;
+
+/// Hashes `value` with fixed keys so that the same value gets the same key in every process
+fn hash<T: ?Sized + Hash>(value: &T) -> u64 {
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn source_key(text: &Cow<str>, settings: &Settings) -> u64 {
+    hash(&(
+        &**text,
+        settings,
+        env!("CARGO_PKG_VERSION"),
+        BYTECODE_VERSION,
+    ))
+}
+
+/// The key of a module, which changes whenever the module or any of its dependencies change
+fn full_key(source_key: u64, dependencies: &[(String, u64)]) -> u64 {
+    hash(&(source_key, dependencies))
+}
+
+/// Collects the modules imported by an expression
+#[derive(Default)]
+struct Imports {
+    modules: Vec<String>,
+}
+
+impl<'a, 'ast> Visitor<'a, 'ast> for Imports {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<'ast, Symbol>) {
+        match &expr.value {
+            Expr::Ident(id) if id.name.is_global() => {
+                let module = id.name.definition_name();
+                if !self.modules.iter().any(|m| m == module) {
+                    self.modules.push(module.to_string());
+                }
+            }
+            _ => ast::walk_expr(self, expr),
+        }
+    }
+}
+
+/// Replaces the aliases of the dependencies in a deserialized type with the already loaded
+/// aliases. Aliases are compared structurally so two copies of the same alias would be seen as
+/// different types by the type checker.
+struct AliasReplacer {
+    aliases: FnvMap<Symbol, ArcType>,
+    /// The aliases of the module itself that are being replaced
+    replacing: FnvSet<Symbol>,
+}
+
+impl AliasReplacer {
+    fn typ(&mut self, typ: &ArcType) -> ArcType {
+        types::walk_move_type(typ.clone(), &mut |typ: &ArcType| match &**typ {
+            Type::Alias(alias) => Some(self.alias(alias)),
+            Type::ExtendTypeRow { types, rest } => Some(Type::extend_type_row(
+                types
+                    .iter()
+                    .map(|field| {
+                        let typ = match &*self.alias(&field.typ) {
+                            Type::Alias(alias) => Alias::from(alias.clone()),
+                            _ => unreachable!(),
+                        };
+                        Field::new(field.name.clone(), typ)
+                    })
+                    .collect(),
+                rest.clone(),
+            )),
+            _ => None,
+        })
+    }
+
+    fn alias(&mut self, alias: &AliasRef<Symbol, ArcType>) -> ArcType {
+        if let Some(typ) = self.aliases.get(&alias.name) {
+            return typ.clone();
+        }
+        if self.replacing.contains(&alias.name) {
+            return ArcType::from(Type::Alias(alias.clone()));
+        }
+
+        // An alias defined by the deserialized module. Its group is recreated so that it refers
+        // to the loaded aliases as well.
+        self.replacing
+            .extend(alias.group.iter().map(|data| data.name.clone()));
+        let group = alias
+            .group
+            .iter()
+            .map(|data| {
+                let mut new_data = AliasData::new(
+                    data.name.clone(),
+                    data.params().to_vec(),
+                    self.typ(data.unresolved_type()),
+                );
+                new_data.is_implicit = data.is_implicit;
+                new_data
+            })
+            .collect();
+        for new_alias in Alias::group(group) {
+            self.aliases
+                .insert(new_alias.name.clone(), new_alias.into_type());
+        }
+        self.aliases[&alias.name].clone()
+    }
+}
+
+/// Collects the aliases in a type
+#[derive(Default)]
+struct SymbolCollector {
+    symbols: Symbols,
+    aliases: FnvMap<Symbol, ArcType>,
+}
+
+impl<'t> Walker<'t, ArcType> for SymbolCollector {
+    fn walk(&mut self, typ: &'t ArcType) {
+        match **typ {
+            Type::Alias(ref alias) => {
+                if !self.aliases.contains_key(&alias.name) {
+                    self.aliases.insert(alias.name.clone(), typ.clone());
+                    self.symbols.insert_simple(alias.name.clone());
+                    self.walk(alias.unresolved_type());
+                }
+            }
+            Type::Ident(ref id) => {
+                self.symbols.insert_simple(id.name.clone());
+            }
+            Type::Record(_) => {
+                for field in types::type_field_iter(typ) {
+                    self.walk(field.typ.as_type());
+                }
+                walk_type_(typ, self)
+            }
+            _ => walk_type_(typ, self),
+        }
+    }
+}
@@ -10,6 +10,7 @@ use {futures::prelude::*, salsa::Database};
 use {
     base::{
         ast::{self, OwnedExpr, TypedIdent},
+        error::Errors,
         fnv::FnvMap,
         kind::{ArcKind, KindEnv},
         metadata::{Metadata, MetadataEnv},
@@ -296,6 +297,28 @@ impl CompilerDatabase {
         self.query(CoreExprQuery).sweep(strategy);
         self.query(CompiledModuleQuery).sweep(strategy);
     }
+
+    /// Returns the names of the modules which have been loaded, either from source or as
+    /// globals of the vm
+    pub(crate) fn loaded_modules(&self) -> Vec<String> {
+        use salsa::debug::DebugQueryTable;
+
+        let mut modules: Vec<String> = self
+            .query(GlobalInnerQuery)
+            .entries::<Vec<_>>()
+            .into_iter()
+            .filter(|entry| match entry.value {
+                Some(Ok(_)) => true,
+                _ => false,
+            })
+            .map(|entry| entry.key)
+            .collect();
+        let globals = self.thread().global_env().get_globals();
+        modules.extend(globals.globals.keys().cloned());
+        modules.sort();
+        modules.dedup();
+        modules
+    }
 }
 
 pub trait CompilationBase: Send {
@@ -468,7 +491,12 @@ async fn core_expr(
         debug!("Translation returned: {}", expr);
 
         let core_expr = if settings.optimize {
-            core::optimize::optimize(&translator.allocator, env, expr)
+            core::optimize::optimize(
+                &translator.allocator,
+                env,
+                expr,
+                settings.wrapping_arithmetic,
+            )
         } else {
             interpreter::Global {
                 value: core::freeze_expr(&translator.allocator, expr),
@@ -487,15 +515,32 @@ async fn compiled_module(
     expected_type: Option<ArcType>,
 ) -> StdResult<OpaqueValue<RootedThread, GcPtr<ClosureData>>, Error> {
     let core_expr = db.core_expr(module.clone(), expected_type).await?;
+    let compiled_module = compile_core_expr(db, &module, &core_expr)?;
+
+    let env = db.compiler();
+    let closure = env
+        .thread()
+        .global_env()
+        .new_global_thunk(&env.thread(), compiled_module)?;
+
+    Ok(closure)
+}
+
+/// Compiles the translated `core_expr` of `module` into bytecode
+pub(crate) fn compile_core_expr(
+    db: &mut dyn Compilation,
+    module: &str,
+    core_expr: &interpreter::Global<CoreExpr>,
+) -> StdResult<vm::compiler::CompiledModule, Error> {
     let settings = db.compiler_settings();
 
     let mut compiler = ModuleCompiler::new(db.compiler());
 
     let source = compiler
-        .get_filemap(&module)
+        .get_filemap(module)
         .expect("Filemap does not exist");
 
-    let name = Name::new(&module);
+    let name = Name::new(module);
     let symbols = SymbolModule::new(
         String::from(AsRef::<str>::as_ref(name.module())),
         &mut compiler.symbols,
@@ -507,19 +552,14 @@ async fn compiled_module(
         env.thread().global_env(),
         symbols,
         &source,
-        module.clone(),
+        module.to_string(),
         settings.emit_debug_info,
-    );
+    )
+    .wrapping_arithmetic(settings.wrapping_arithmetic);
 
     let mut compiled_module = compiler.compile_expr(core_expr.value.expr())?;
-    let module_id = Symbol::from(format!("@{}", name));
-    compiled_module.function.id = module_id.clone();
-    let closure = env
-        .thread()
-        .global_env()
-        .new_global_thunk(&env.thread(), compiled_module)?;
-
-    Ok(closure)
+    compiled_module.function.id = Symbol::from(format!("@{}", name));
+    Ok(compiled_module)
 }
 
 async fn import(
@@ -564,6 +604,7 @@ async fn global_inner(db: &mut dyn Compilation, name: String) -> Result<Unrooted
             typ,
             value,
             metadata,
+            warnings: Errors::new(),
         })
         .map_err(Error::from)
         .await?;
@@ -669,6 +710,14 @@ impl MetadataEnv for CompilerDatabase {
         if id.is_global() {
             self.peek_typechecked_module(id.definition_name())
                 .map(|v| v.metadata.clone())
+                .or_else(|| {
+                    // Modules loaded from bytecode are only stored as globals
+                    let globals = self.thread().global_env().get_globals();
+                    globals
+                        .globals
+                        .get(id.definition_name())
+                        .map(|global| global.metadata.clone())
+                })
         } else {
             None
         }
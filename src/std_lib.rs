@@ -1,3 +1,8 @@
+#[cfg(feature = "big_int")]
+pub mod big_int;
+pub mod concurrent;
+#[cfg(feature = "decimal")]
+pub mod decimal;
 pub mod env;
 #[cfg(feature = "http")]
 pub mod http;
@@ -7,3 +12,4 @@ pub mod process;
 pub mod random;
 #[cfg(feature = "regex")]
 pub mod regex;
+pub mod test;
@@ -0,0 +1,171 @@
+//! Module containing bindings to the `num-bigint` library.
+
+pub extern crate num_bigint;
+extern crate num_traits;
+
+use self::num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::real_std::cmp::Ordering;
+
+use crate::vm::{
+    self,
+    api::{Getable, RuntimeResult},
+    thread::Thread,
+    types::VmInt,
+    ExternModule, Variants,
+};
+
+/// An arbitrary precision integer (`std.big_int.BigInt`).
+///
+/// Implements both `Pushable` and `Getable` so it can be passed to and from gluon by value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.big_int.BigInt")]
+#[gluon(crate_name = "::vm")]
+#[gluon_userdata(clone)]
+#[gluon_trace(skip)]
+pub struct BigInt(pub num_bigint::BigInt);
+
+impl<'vm, 'value> Getable<'vm, 'value> for BigInt {
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        <&BigInt>::from_value(vm, value).clone()
+    }
+}
+
+impl From<num_bigint::BigInt> for BigInt {
+    fn from(value: num_bigint::BigInt) -> Self {
+        BigInt(value)
+    }
+}
+
+fn from_int(i: VmInt) -> BigInt {
+    BigInt(i.into())
+}
+
+fn to_int(i: &BigInt) -> Option<VmInt> {
+    i.0.to_i64()
+}
+
+fn to_float(i: &BigInt) -> f64 {
+    i.0.to_f64().unwrap_or(f64::NAN)
+}
+
+fn parse(s: &str) -> Result<BigInt, ()> {
+    s.parse().map(BigInt).map_err(|_| ())
+}
+
+fn valid_radix(radix: VmInt) -> Option<u32> {
+    if radix < 2 || radix > 36 {
+        None
+    } else {
+        Some(radix as u32)
+    }
+}
+
+fn from_str_radix(s: &str, radix: VmInt) -> Result<BigInt, ()> {
+    let radix = valid_radix(radix).ok_or(())?;
+    num_bigint::BigInt::parse_bytes(s.as_bytes(), radix)
+        .map(BigInt)
+        .ok_or(())
+}
+
+fn show(i: &BigInt) -> String {
+    i.0.to_string()
+}
+
+fn to_str_radix(i: &BigInt, radix: VmInt) -> RuntimeResult<String, String> {
+    match valid_radix(radix) {
+        Some(radix) => RuntimeResult::Return(i.0.to_str_radix(radix)),
+        None => RuntimeResult::Panic(format!("Radix {} is not in the range 2 to 36", radix)),
+    }
+}
+
+fn add(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 + &r.0)
+}
+
+fn sub(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 - &r.0)
+}
+
+fn mul(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 * &r.0)
+}
+
+fn div(l: &BigInt, r: &BigInt) -> RuntimeResult<BigInt, String> {
+    if r.0.is_zero() {
+        return RuntimeResult::Panic("attempt to divide by zero".into());
+    }
+    RuntimeResult::Return(BigInt(&l.0 / &r.0))
+}
+
+fn rem(l: &BigInt, r: &BigInt) -> RuntimeResult<BigInt, String> {
+    if r.0.is_zero() {
+        return RuntimeResult::Panic("attempt to divide by zero".into());
+    }
+    RuntimeResult::Return(BigInt(&l.0 % &r.0))
+}
+
+fn negate(i: &BigInt) -> BigInt {
+    BigInt(-&i.0)
+}
+
+fn abs(i: &BigInt) -> BigInt {
+    BigInt(i.0.abs())
+}
+
+fn pow(i: &BigInt, exp: VmInt) -> RuntimeResult<BigInt, String> {
+    if exp < 0 {
+        return RuntimeResult::Panic(format!("Negative exponent {}", exp));
+    }
+    RuntimeResult::Return(BigInt(num_traits::pow(i.0.clone(), exp as usize)))
+}
+
+fn signum(i: &BigInt) -> VmInt {
+    i.0.signum().to_i64().unwrap_or(0)
+}
+
+fn eq(l: &BigInt, r: &BigInt) -> bool {
+    l == r
+}
+
+fn compare(l: &BigInt, r: &BigInt) -> Ordering {
+    l.cmp(r)
+}
+
+mod std {
+    pub mod big_int {
+        pub use crate::std_lib::big_int as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<BigInt>("std.big_int.BigInt", &[])?;
+
+    ExternModule::new(
+        vm,
+        record! {
+            type BigInt => BigInt,
+
+            from_int => primitive!(1, std::big_int::prim::from_int),
+            to_int => primitive!(1, std::big_int::prim::to_int),
+            to_float => primitive!(1, std::big_int::prim::to_float),
+            parse => primitive!(1, std::big_int::prim::parse),
+            from_str_radix => primitive!(2, std::big_int::prim::from_str_radix),
+            show => primitive!(1, std::big_int::prim::show),
+            to_str_radix => primitive!(2, std::big_int::prim::to_str_radix),
+            add => primitive!(2, std::big_int::prim::add),
+            sub => primitive!(2, std::big_int::prim::sub),
+            mul => primitive!(2, std::big_int::prim::mul),
+            div => primitive!(2, std::big_int::prim::div),
+            rem => primitive!(2, std::big_int::prim::rem),
+            negate => primitive!(1, std::big_int::prim::negate),
+            abs => primitive!(1, std::big_int::prim::abs),
+            pow => primitive!(2, std::big_int::prim::pow),
+            signum => primitive!(1, std::big_int::prim::signum),
+            eq => primitive!(2, std::big_int::prim::eq),
+            compare => primitive!(2, std::big_int::prim::compare),
+        },
+    )
+}
@@ -0,0 +1,261 @@
+//! Structured concurrency primitives for `IO` actions (`std.io.concurrent`).
+//!
+//! Every action runs on a child thread of its own so it can be stopped with `Thread::interrupt`
+//! without affecting the thread which started it.
+use crate::real_std::{
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use futures::{
+    channel::oneshot,
+    future::{self, BoxFuture, Either, Shared},
+    prelude::*,
+};
+
+use crate::vm::{
+    self,
+    api::{
+        generic::{A, B},
+        Getable, OpaqueValue, OwnedFunction, VmType, IO,
+    },
+    thread::{RootedThread, Thread},
+    ExternModule,
+};
+
+type Value<T> = OpaqueValue<RootedThread, T>;
+type Action<'vm, T> = OpaqueValue<&'vm Thread, IO<T>>;
+
+/// Interrupts the thread running an action once the future driving that action is dropped,
+/// stopping it even if it is in the middle of a computation on another OS thread.
+struct InterruptOnDrop(RootedThread);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        self.0.interrupt();
+    }
+}
+
+/// Starts `action` on a new child thread, returning the thread and a future which resolves to
+/// the result of the action.
+fn start<T>(
+    action: &Action<T>,
+) -> vm::Result<(
+    RootedThread,
+    impl Future<Output = Result<Value<T>, String>> + Send + 'static,
+)>
+where
+    T: VmType + Send + Sync + 'static,
+    Value<T>: for<'vm, 'value> Getable<'vm, 'value> + VmType + Send + Sync,
+{
+    let thread = action.vm().new_thread()?;
+    let mut function: OwnedFunction<fn(()) -> Value<T>> =
+        Getable::from_value(&thread, action.get_variant());
+    let guard = InterruptOnDrop(thread.clone());
+    Ok((thread, async move {
+        let _guard = guard;
+        function.call_async(()).await.map_err(|err| err.to_string())
+    }))
+}
+
+struct TaskState {
+    thread: RootedThread,
+    cancel: Mutex<Option<oneshot::Sender<()>>>,
+    result: Shared<BoxFuture<'static, Result<Value<A>, String>>>,
+}
+
+impl TaskState {
+    fn cancel(&self) {
+        if let Some(cancel) = self.cancel.lock().unwrap().take() {
+            let _ = cancel.send(());
+        }
+        self.thread.interrupt();
+    }
+}
+
+/// A handle to an action started with `spawn`.
+#[derive(Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.io.concurrent.Task")]
+#[gluon(crate_name = "::vm")]
+#[gluon_trace(skip)]
+pub struct Task<T> {
+    state: Arc<TaskState>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> fmt::Debug for Task<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Task")
+    }
+}
+
+/// A set of tasks which are cancelled together.
+#[derive(Default, Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.io.concurrent.TaskGroup")]
+#[gluon(crate_name = "::vm")]
+#[gluon_trace(skip)]
+pub struct TaskGroup(Mutex<Vec<Arc<TaskState>>>);
+
+impl fmt::Debug for TaskGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TaskGroup")
+    }
+}
+
+fn new_task(action: &Action<A>) -> vm::Result<Arc<TaskState>> {
+    let (thread, run) = start(action)?;
+    let (cancel_sender, cancel_receiver) = oneshot::channel();
+
+    let result = async move {
+        match future::select(cancel_receiver, run.boxed()).await {
+            Either::Left((Ok(()), _)) => Err("Task was cancelled".to_string()),
+            // The handle was dropped without cancelling the task so let it run to completion
+            Either::Left((Err(oneshot::Canceled), run)) => run.await,
+            Either::Right((result, _)) => result,
+        }
+    }
+    .boxed()
+    .shared();
+
+    // Drive the task in the background if there is an executor to do it, otherwise it runs
+    // once it is awaited
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(result.clone().map(|_| ()));
+    }
+
+    Ok(Arc::new(TaskState {
+        thread,
+        cancel: Mutex::new(Some(cancel_sender)),
+        result,
+    }))
+}
+
+fn spawn<'vm>(action: Action<'vm, A>) -> IO<Task<A>> {
+    match new_task(&action) {
+        Ok(state) => IO::Value(Task {
+            state,
+            _marker: PhantomData,
+        }),
+        Err(err) => IO::Exception(err.to_string()),
+    }
+}
+
+fn await_task(task: &Task<A>) -> impl Future<Output = IO<Value<A>>> {
+    task.state.result.clone().map(IO::from)
+}
+
+fn cancel(task: &Task<A>) -> IO<()> {
+    task.state.cancel();
+    IO::Value(())
+}
+
+fn new_group(_: ()) -> IO<TaskGroup> {
+    IO::Value(TaskGroup::default())
+}
+
+fn spawn_in<'vm>(group: &TaskGroup, action: Action<'vm, A>) -> IO<Task<A>> {
+    match new_task(&action) {
+        Ok(state) => {
+            group.0.lock().unwrap().push(state.clone());
+            IO::Value(Task {
+                state,
+                _marker: PhantomData,
+            })
+        }
+        Err(err) => IO::Exception(err.to_string()),
+    }
+}
+
+fn cancel_group(group: &TaskGroup) -> IO<()> {
+    for task in group.0.lock().unwrap().drain(..) {
+        task.cancel();
+    }
+    IO::Value(())
+}
+
+fn wait_group(group: &TaskGroup) -> impl Future<Output = IO<()>> {
+    let tasks = group.0.lock().unwrap().clone();
+    async move {
+        let result = future::try_join_all(tasks.iter().map(|task| task.result.clone())).await;
+        match result {
+            Ok(_) => IO::Value(()),
+            Err(err) => {
+                for task in &tasks {
+                    task.cancel();
+                }
+                IO::Exception(err)
+            }
+        }
+    }
+}
+
+fn par<'vm>(
+    a: Action<'vm, A>,
+    b: Action<'vm, B>,
+) -> impl Future<Output = IO<(Value<A>, Value<B>)>> {
+    let futures = match (start(&a), start(&b)) {
+        (Ok((_, a)), Ok((_, b))) => (a, b),
+        (Err(err), _) | (_, Err(err)) => {
+            return Either::Right(future::ready(IO::Exception(err.to_string())))
+        }
+    };
+    // `try_join` drops (and thereby interrupts) the other action as soon as one fails
+    Either::Left(future::try_join(futures.0, futures.1).map(IO::from))
+}
+
+fn race<'vm>(a: Action<'vm, A>, b: Action<'vm, A>) -> impl Future<Output = IO<Value<A>>> {
+    let futures = match (start(&a), start(&b)) {
+        (Ok((_, a)), Ok((_, b))) => (a, b),
+        (Err(err), _) | (_, Err(err)) => {
+            return Either::Right(future::ready(IO::Exception(err.to_string())))
+        }
+    };
+    Either::Left(
+        future::select(futures.0.boxed(), futures.1.boxed())
+            .map(|either| IO::from(either.factor_first().0)),
+    )
+}
+
+fn all<'vm>(actions: Vec<Action<'vm, A>>) -> impl Future<Output = IO<Vec<Value<A>>>> {
+    let futures = match actions
+        .iter()
+        .map(|action| start(action).map(|(_, future)| future))
+        .collect::<vm::Result<Vec<_>>>()
+    {
+        Ok(futures) => futures,
+        Err(err) => return Either::Right(future::ready(IO::Exception(err.to_string()))),
+    };
+    Either::Left(future::try_join_all(futures).map(IO::from))
+}
+
+mod std {
+    pub mod io {
+        pub mod concurrent {
+            pub use crate::std_lib::concurrent as prim;
+        }
+    }
+}
+
+pub fn load(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<Task<A>>("std.io.concurrent.Task", &["a"])?;
+    vm.register_type::<TaskGroup>("std.io.concurrent.TaskGroup", &[])?;
+
+    ExternModule::new(
+        vm,
+        record! {
+            type Task a => Task<A>,
+            type TaskGroup => TaskGroup,
+            par => primitive!(2, async fn std::io::concurrent::prim::par),
+            race => primitive!(2, async fn std::io::concurrent::prim::race),
+            all => primitive!(1, async fn std::io::concurrent::prim::all),
+            spawn => primitive!(1, std::io::concurrent::prim::spawn),
+            (await_task "await") => primitive!(1, "std.io.concurrent.prim.await", async fn std::io::concurrent::prim::await_task),
+            cancel => primitive!(1, std::io::concurrent::prim::cancel),
+            new_group => primitive!(1, std::io::concurrent::prim::new_group),
+            spawn_in => primitive!(2, std::io::concurrent::prim::spawn_in),
+            cancel_group => primitive!(1, std::io::concurrent::prim::cancel_group),
+            wait_group => primitive!(1, async fn std::io::concurrent::prim::wait_group),
+        },
+    )
+}
@@ -0,0 +1,174 @@
+//! Module containing bindings to the `bigdecimal` library.
+
+pub extern crate bigdecimal;
+extern crate num_traits;
+
+use self::bigdecimal::BigDecimal;
+use self::num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+use crate::real_std::cmp::Ordering;
+
+use crate::std_lib::big_int::BigInt;
+use crate::vm::{
+    self,
+    api::{Getable, RuntimeResult},
+    thread::Thread,
+    types::VmInt,
+    ExternModule, Variants,
+};
+
+/// An arbitrary precision decimal number (`std.decimal.Decimal`).
+///
+/// Implements both `Pushable` and `Getable` so it can be passed to and from gluon by value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.decimal.Decimal")]
+#[gluon(crate_name = "::vm")]
+#[gluon_userdata(clone)]
+#[gluon_trace(skip)]
+pub struct Decimal(pub BigDecimal);
+
+impl<'vm, 'value> Getable<'vm, 'value> for Decimal {
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        <&Decimal>::from_value(vm, value).clone()
+    }
+}
+
+impl From<BigDecimal> for Decimal {
+    fn from(value: BigDecimal) -> Self {
+        Decimal(value)
+    }
+}
+
+fn from_int(i: VmInt) -> Decimal {
+    Decimal(i.into())
+}
+
+fn from_big_int(i: &BigInt) -> Decimal {
+    Decimal(BigDecimal::new(i.0.clone(), 0))
+}
+
+fn from_float(f: f64) -> Option<Decimal> {
+    BigDecimal::from_f64(f).map(Decimal)
+}
+
+fn to_int(d: &Decimal) -> Option<VmInt> {
+    d.0.to_i64()
+}
+
+fn to_big_int(d: &Decimal) -> BigInt {
+    BigInt(d.0.with_scale(0).into_bigint_and_exponent().0)
+}
+
+fn to_float(d: &Decimal) -> f64 {
+    d.0.to_f64().unwrap_or(f64::NAN)
+}
+
+fn parse(s: &str) -> Result<Decimal, ()> {
+    s.parse().map(Decimal).map_err(|_| ())
+}
+
+fn show(d: &Decimal) -> String {
+    d.0.to_string()
+}
+
+fn add(l: &Decimal, r: &Decimal) -> Decimal {
+    Decimal(&l.0 + &r.0)
+}
+
+fn sub(l: &Decimal, r: &Decimal) -> Decimal {
+    Decimal(&l.0 - &r.0)
+}
+
+fn mul(l: &Decimal, r: &Decimal) -> Decimal {
+    Decimal(&l.0 * &r.0)
+}
+
+fn div(l: &Decimal, r: &Decimal) -> RuntimeResult<Decimal, String> {
+    if r.0.is_zero() {
+        return RuntimeResult::Panic("attempt to divide by zero".into());
+    }
+    RuntimeResult::Return(Decimal(&l.0 / &r.0))
+}
+
+fn rem(l: &Decimal, r: &Decimal) -> RuntimeResult<Decimal, String> {
+    if r.0.is_zero() {
+        return RuntimeResult::Panic("attempt to divide by zero".into());
+    }
+    RuntimeResult::Return(Decimal(&l.0 % &r.0))
+}
+
+fn negate(d: &Decimal) -> Decimal {
+    Decimal(-&d.0)
+}
+
+fn abs(d: &Decimal) -> Decimal {
+    Decimal(d.0.abs())
+}
+
+fn scale(d: &Decimal) -> VmInt {
+    d.0.as_bigint_and_exponent().1
+}
+
+fn truncate(d: &Decimal, digits: VmInt) -> Decimal {
+    Decimal(d.0.with_scale(digits))
+}
+
+fn round(d: &Decimal, digits: VmInt) -> Decimal {
+    let truncated = d.0.with_scale(digits);
+    let half = BigDecimal::new(5.into(), digits + 1);
+    if (&d.0 - &truncated).abs() < half {
+        Decimal(truncated)
+    } else if d.0.is_negative() {
+        Decimal(truncated - BigDecimal::new(1.into(), digits))
+    } else {
+        Decimal(truncated + BigDecimal::new(1.into(), digits))
+    }
+}
+
+fn eq(l: &Decimal, r: &Decimal) -> bool {
+    l == r
+}
+
+fn compare(l: &Decimal, r: &Decimal) -> Ordering {
+    l.cmp(r)
+}
+
+mod std {
+    pub mod decimal {
+        pub use crate::std_lib::decimal as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<Decimal>("std.decimal.Decimal", &[])?;
+
+    ExternModule::new(
+        vm,
+        record! {
+            type Decimal => Decimal,
+
+            from_int => primitive!(1, std::decimal::prim::from_int),
+            from_big_int => primitive!(1, std::decimal::prim::from_big_int),
+            from_float => primitive!(1, std::decimal::prim::from_float),
+            to_int => primitive!(1, std::decimal::prim::to_int),
+            to_big_int => primitive!(1, std::decimal::prim::to_big_int),
+            to_float => primitive!(1, std::decimal::prim::to_float),
+            parse => primitive!(1, std::decimal::prim::parse),
+            show => primitive!(1, std::decimal::prim::show),
+            add => primitive!(2, std::decimal::prim::add),
+            sub => primitive!(2, std::decimal::prim::sub),
+            mul => primitive!(2, std::decimal::prim::mul),
+            div => primitive!(2, std::decimal::prim::div),
+            rem => primitive!(2, std::decimal::prim::rem),
+            negate => primitive!(1, std::decimal::prim::negate),
+            abs => primitive!(1, std::decimal::prim::abs),
+            scale => primitive!(1, std::decimal::prim::scale),
+            truncate => primitive!(2, std::decimal::prim::truncate),
+            round => primitive!(2, std::decimal::prim::round),
+            eq => primitive!(2, std::decimal::prim::eq),
+            compare => primitive!(2, std::decimal::prim::compare),
+        },
+    )
+}
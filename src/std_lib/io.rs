@@ -2,6 +2,7 @@ use crate::real_std::{
     fmt,
     fs::{self, File},
     io::{self, Read, Write},
+    path::Path,
     sync::Mutex,
 };
 
@@ -16,6 +17,7 @@ use crate::vm::{
         Getable, OpaqueValue, OwnedFunction, RuntimeResult, TypedBytecode, WithVM, IO,
     },
     internal::ValuePrinter,
+    sandbox::Capability,
     stack::{self, StackFrame},
     thread::{RootedThread, Thread, ThreadInternal},
     types::*,
@@ -83,7 +85,22 @@ enum OpenOptions {
     CreateNew,
 }
 
-fn open_file_with(path: &str, opts: Vec<OpenOptions>) -> IO<GluonFile> {
+/// Returns an error if the sandbox of `vm` does not allow files to be opened at `path`
+fn check_file_access(vm: &Thread, path: &str) -> io::Result<()> {
+    match vm.global_env().sandbox() {
+        Some(sandbox) => sandbox.check_path(Capability::FileIo, Path::new(path)),
+        None => Ok(()),
+    }
+}
+
+fn open_file_with(
+    WithVM { vm, value: path }: WithVM<&str>,
+    opts: Vec<OpenOptions>,
+) -> IO<GluonFile> {
+    if let Err(err) = check_file_access(vm, path) {
+        return IO::Exception(err.to_string());
+    }
+
     let mut open_with = fs::OpenOptions::new();
 
     for opt in opts {
@@ -103,17 +120,23 @@ fn open_file_with(path: &str, opts: Vec<OpenOptions>) -> IO<GluonFile> {
         .into()
 }
 
-fn read_file_to_array(s: &str) -> IO<Vec<u8>> {
+fn read_file_to_array(WithVM { vm, value: s }: WithVM<&str>) -> IO<Vec<u8>> {
     let mut buffer = Vec::new();
-    match File::open(s).and_then(|mut file| file.read_to_end(&mut buffer)) {
+    match check_file_access(vm, s)
+        .and_then(|()| File::open(s))
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+    {
         Ok(_) => IO::Value(buffer),
         Err(err) => IO::Exception(err.to_string()),
     }
 }
 
-fn read_file_to_string(s: &str) -> IO<String> {
+fn read_file_to_string(WithVM { vm, value: s }: WithVM<&str>) -> IO<String> {
     let mut buffer = String::new();
-    match File::open(s).and_then(|mut file| file.read_to_string(&mut buffer)) {
+    match check_file_access(vm, s)
+        .and_then(|()| File::open(s))
+        .and_then(|mut file| file.read_to_string(&mut buffer))
+    {
         Ok(_) => IO::Value(buffer),
         Err(err) => {
             use crate::real_std::fmt::Write;
@@ -297,6 +320,10 @@ fn run_expr(WithVM { vm, value: expr }: WithVM<&str>) -> impl Future<Output = IO
     let vm1 = vm.clone();
     let expr = expr.to_owned(); // FIXME
     async move {
+        if let Some(Err(err)) = vm.global_env().sandbox().map(|s| s.check(Capability::Eval)) {
+            return IO::Exception(err.to_string());
+        }
+
         let mut db = vm.get_database();
         expr.run_expr(&mut ModuleCompiler::new(&mut db), vm1, "<top>", &expr, None)
             .map(|run_result| {
@@ -328,6 +355,10 @@ fn load_script(
     let expr = expr.to_owned(); // FIXME
 
     async move {
+        if let Some(Err(err)) = vm.global_env().sandbox().map(|s| s.check(Capability::Eval)) {
+            return IO::Exception(err.to_string());
+        }
+
         let mut db = vm.get_database();
         expr.load_script(&mut ModuleCompiler::new(&mut db), vm1, &name, &expr, None)
             .map(|run_result| {
@@ -0,0 +1,40 @@
+//! Rust representations of the tests defined with `std.test`, used to run gluon tests from Rust.
+use crate::base::types::{ArcType, Type};
+use crate::vm::{
+    api::{generic::A, Hole, OpaqueValue, OwnedFunction, VmType},
+    thread::{RootedThread, Thread},
+};
+
+macro_rules! define_test_type {
+    ($name:ident $($args: ident)*) => {
+        impl VmType for $name {
+            type Type = $name;
+            fn make_type(vm: &Thread) -> ArcType {
+                let typ = concat!("std.test.", stringify!($name));
+                Type::app(
+                    vm.get_env().find_type_info(typ).unwrap().into_type(),
+                    vec![$($args::make_type(vm),)* Type::unit()].into_iter().collect(),
+                )
+            }
+        }
+    };
+}
+
+/// The effect that tests run in (`std.test.TestEffIO`)
+pub struct TestEffIO;
+
+define_test_type! { TestEffIO A }
+
+pub type TestEff = OpaqueValue<RootedThread, TestEffIO>;
+pub type TestFn = OwnedFunction<fn(()) -> TestEff>;
+
+/// A single test or a named group of tests (`std.test.TestCase`). `std.test` must be loaded
+/// before a `TestCase` can be retrieved from gluon.
+#[derive(Getable)]
+#[gluon(crate_name = "::vm")]
+pub enum TestCase {
+    Test(String, TestFn),
+    Group(String, Vec<TestCase>),
+}
+
+define_test_type! { TestCase Hole }
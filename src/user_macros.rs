@@ -0,0 +1,875 @@
+//! Implementation of the `import_macros!` and `quote!` macros which let macros be written in
+//! gluon.
+//!
+//! A macro written in gluon is a function of type `Array Expr -> Result String Expr` where `Expr`
+//! is the reified syntax tree from `std.macro.types`. When such a macro is applied its arguments are
+//! converted to `Expr` values, the function is called at compile time and the `Expr` it returns is
+//! converted back into a syntax tree which replaces the application.
+use std::{convert::TryFrom, mem, sync::Arc};
+
+use gluon_codegen::Trace;
+
+use crate::{
+    base::{
+        ast::{
+            self, Alternative, Argument, AstClone, Expr, ExprField, Lambda, Pattern, SpannedExpr,
+            SpannedPattern, TypedIdent, ValueBinding,
+        },
+        fnv::FnvMap,
+        pos::{self, BytePos, Span},
+        symbol::{Symbol, Symbols},
+        types::{ArcType, ArgType, TypeExt},
+    },
+    check::check_signature,
+    import::get_module_name,
+    query::{Compilation, CompilerDatabase},
+    vm::{
+        self,
+        api::{ActiveThread, Getable, OwnedFunction, Pushable, VmType},
+        macros::{self, Macro, MacroExpander, MacroFuture},
+        thread::{RootedThread, RootedValue, Thread},
+        Variants,
+    },
+};
+
+/// `Box` which can be passed to and from gluon, used for the recursive fields of `MacroExpr`
+struct Node<T>(Box<T>);
+
+impl<T> Node<T> {
+    fn new(value: T) -> Self {
+        Node(Box::new(value))
+    }
+}
+
+impl<T: VmType> VmType for Node<T> {
+    type Type = T::Type;
+    fn make_type(vm: &Thread) -> ArcType {
+        T::make_type(vm)
+    }
+}
+
+impl<'vm, T: Pushable<'vm>> Pushable<'vm> for Node<T> {
+    fn push(self, context: &mut ActiveThread<'vm>) -> vm::Result<()> {
+        (*self.0).push(context)
+    }
+}
+
+impl<'vm, 'value, T: Getable<'vm, 'value>> Getable<'vm, 'value> for Node<T> {
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        Node::new(T::from_value(vm, value))
+    }
+}
+
+#[derive(Getable, Pushable, VmType)]
+#[gluon(vm_type = "std.macro.types.Literal")]
+#[gluon(crate_name = "::vm")]
+enum MacroLiteral {
+    Int(i64),
+    Byte(u8),
+    Float(f64),
+    String(String),
+    Char(char),
+}
+
+#[derive(Getable, Pushable, VmType)]
+#[gluon(vm_type = "std.macro.types.Pattern")]
+#[gluon(crate_name = "::vm")]
+enum MacroPattern {
+    Var(String),
+    Constructor(String, Vec<MacroPattern>),
+    TuplePattern(Vec<MacroPattern>),
+    LiteralPattern(MacroLiteral),
+}
+
+#[derive(Getable, Pushable, VmType)]
+#[gluon(vm_type = "std.macro.types.Expr")]
+#[gluon(crate_name = "::vm")]
+enum MacroExpr {
+    Ident(String),
+    Literal(MacroLiteral),
+    App(Node<MacroExpr>, Vec<MacroExpr>),
+    Infix(Node<MacroExpr>, String, Node<MacroExpr>),
+    Lambda(Vec<String>, Node<MacroExpr>),
+    Let(MacroPattern, Vec<String>, Node<MacroExpr>, Node<MacroExpr>),
+    IfElse(Node<MacroExpr>, Node<MacroExpr>, Node<MacroExpr>),
+    Match(Node<MacroExpr>, Vec<(MacroPattern, MacroExpr)>),
+    Tuple(Vec<MacroExpr>),
+    Array(Vec<MacroExpr>),
+    Record(Vec<(String, MacroExpr)>),
+    Projection(Node<MacroExpr>, String),
+    Block(Vec<MacroExpr>),
+    Opaque(i64),
+}
+
+type MacroFunction = fn(Vec<MacroExpr>) -> Result<MacroExpr, String>;
+
+/// Returns `None` for the literals which `Literal` can't represent
+fn reify_literal(literal: &ast::Literal) -> Option<MacroLiteral> {
+    Some(match *literal {
+        ast::Literal::Int(i) => MacroLiteral::Int(i),
+        ast::Literal::Byte(b) => MacroLiteral::Byte(b),
+        ast::Literal::Float(f) => MacroLiteral::Float(f.into_inner()),
+        ast::Literal::String(ref s) => MacroLiteral::String(s.clone()),
+        ast::Literal::Char(c) => MacroLiteral::Char(c),
+        ast::Literal::SizedInt(..) => return None,
+    })
+}
+
+fn is_reifiable_pattern(pattern: &SpannedPattern<Symbol>) -> bool {
+    match &pattern.value {
+        Pattern::Ident(_) => true,
+        Pattern::Constructor(_, args) => args.iter().all(is_reifiable_pattern),
+        Pattern::Tuple { elems, .. } => elems.iter().all(is_reifiable_pattern),
+        Pattern::Literal(literal) => reify_literal(literal).is_some(),
+        _ => false,
+    }
+}
+
+fn is_explicit<N>(args: &[Argument<N>]) -> bool {
+    args.iter().all(|arg| arg.arg_type == ArgType::Explicit)
+}
+
+fn is_reifiable_binding(bind: &ValueBinding<Symbol>) -> bool {
+    bind.typ.is_none()
+        && bind.metadata.attributes().next().is_none()
+        && is_explicit(bind.args)
+        && is_reifiable_pattern(&bind.name)
+}
+
+/// Converts syntax trees into `MacroExpr` values
+struct Reifier<'ast> {
+    /// Expressions referred to by `MacroExpr::Opaque`. These are the expressions which can't be
+    /// represented as a `MacroExpr` or, when quoting, the expressions inside `unquote!`
+    opaque: Vec<SpannedExpr<'ast, Symbol>>,
+    /// The symbols of the names which were reified so that the `Unreifier` can turn the names
+    /// back into the same symbols
+    names: FnvMap<String, Symbol>,
+    quote: bool,
+}
+
+impl<'ast> Reifier<'ast> {
+    fn name(&mut self, symbol: &Symbol) -> String {
+        let name = symbol.declared_name().to_string();
+        self.names
+            .entry(name.clone())
+            .or_insert_with(|| symbol.clone());
+        name
+    }
+
+    fn arg_names(&mut self, args: &[Argument<ast::SpannedIdent<Symbol>>]) -> Vec<String> {
+        args.iter()
+            .map(|arg| self.name(&arg.name.value.name))
+            .collect()
+    }
+
+    /// Reifies a pattern for which `is_reifiable_pattern` returned `true`
+    fn pattern(&mut self, pattern: &SpannedPattern<Symbol>) -> MacroPattern {
+        match &pattern.value {
+            Pattern::Ident(id) => MacroPattern::Var(self.name(&id.name)),
+            Pattern::Constructor(id, args) => MacroPattern::Constructor(
+                self.name(&id.name),
+                args.iter().map(|arg| self.pattern(arg)).collect(),
+            ),
+            Pattern::Tuple { elems, .. } => {
+                MacroPattern::TuplePattern(elems.iter().map(|elem| self.pattern(elem)).collect())
+            }
+            Pattern::Literal(literal) => MacroPattern::LiteralPattern(
+                reify_literal(literal).expect("Reifiable literal pattern"),
+            ),
+            _ => unreachable!("Pattern can't be reified"),
+        }
+    }
+
+    fn reify(&mut self, expr: &mut SpannedExpr<'ast, Symbol>) -> Result<MacroExpr, macros::Error> {
+        Ok(match &mut expr.value {
+            Expr::Ident(id) => MacroExpr::Ident(self.name(&id.name)),
+            Expr::Literal(literal) => match reify_literal(literal) {
+                Some(literal) => MacroExpr::Literal(literal),
+                None => self.opaque(expr)?,
+            },
+            Expr::App { func, args, .. } if self.quote && is_unquote(func) => {
+                if args.len() != 1 {
+                    return Err(macros::Error::message("`unquote!` expects 1 argument"));
+                }
+                self.opaque.push(mem::take(&mut args[0]));
+                MacroExpr::Opaque(self.opaque.len() as i64 - 1)
+            }
+            Expr::App {
+                func,
+                implicit_args,
+                args,
+            } if implicit_args.is_empty() => MacroExpr::App(
+                Node::new(self.reify(func)?),
+                self.reify_all(args.iter_mut())?,
+            ),
+            Expr::Infix {
+                lhs,
+                op,
+                rhs,
+                implicit_args,
+            } if implicit_args.is_empty() => MacroExpr::Infix(
+                Node::new(self.reify(lhs)?),
+                self.name(&op.value.name),
+                Node::new(self.reify(rhs)?),
+            ),
+            Expr::Lambda(lambda) if is_explicit(lambda.args) => {
+                let args = self.arg_names(lambda.args);
+                MacroExpr::Lambda(args, Node::new(self.reify(lambda.body)?))
+            }
+            Expr::LetBindings(binds, body)
+                if binds.len() == 1
+                    && is_reifiable_binding(&binds[0])
+                    && (binds.is_recursive() == !binds[0].args.is_empty()) =>
+            {
+                let bind = &mut binds[0];
+                MacroExpr::Let(
+                    self.pattern(&bind.name),
+                    self.arg_names(bind.args),
+                    Node::new(self.reify(&mut bind.expr)?),
+                    Node::new(self.reify(body)?),
+                )
+            }
+            Expr::IfElse(pred, if_true, if_false) => MacroExpr::IfElse(
+                Node::new(self.reify(pred)?),
+                Node::new(self.reify(if_true)?),
+                Node::new(self.reify(if_false)?),
+            ),
+            Expr::Match(scrutinee, alts)
+                if alts
+                    .iter()
+                    .all(|alt| alt.guard.is_none() && is_reifiable_pattern(&alt.pattern)) =>
+            {
+                let scrutinee = Node::new(self.reify(scrutinee)?);
+                let alts = alts
+                    .iter_mut()
+                    .map(|alt| Ok((self.pattern(&alt.pattern), self.reify(&mut alt.expr)?)))
+                    .collect::<Result<_, macros::Error>>()?;
+                MacroExpr::Match(scrutinee, alts)
+            }
+            // Parenthesized expressions are parsed as tuples with a single element
+            Expr::Tuple { elems, .. } if elems.len() == 1 => self.reify(&mut elems[0])?,
+            Expr::Tuple { elems, .. } => MacroExpr::Tuple(self.reify_all(elems.iter_mut())?),
+            Expr::Array(array) => MacroExpr::Array(self.reify_all(array.exprs.iter_mut())?),
+            Expr::Record {
+                types, exprs, base, ..
+            } if types.is_empty()
+                && base.is_none()
+                && exprs
+                    .iter()
+                    .all(|field| field.metadata.attributes().next().is_none()) =>
+            {
+                let fields = exprs
+                    .iter_mut()
+                    .map(|field| {
+                        let name = field.name.value.declared_name().to_string();
+                        let value = match &mut field.value {
+                            Some(value) => self.reify(value)?,
+                            None => MacroExpr::Ident(self.name(&field.name.value)),
+                        };
+                        Ok((name, value))
+                    })
+                    .collect::<Result<_, macros::Error>>()?;
+                MacroExpr::Record(fields)
+            }
+            Expr::Projection(expr, field, _) => MacroExpr::Projection(
+                Node::new(self.reify(expr)?),
+                field.declared_name().to_string(),
+            ),
+            Expr::Block(exprs) => MacroExpr::Block(self.reify_all(exprs.iter_mut())?),
+            _ => self.opaque(expr)?,
+        })
+    }
+
+    fn opaque(&mut self, expr: &mut SpannedExpr<'ast, Symbol>) -> Result<MacroExpr, macros::Error> {
+        if self.quote {
+            return Err(macros::Error::message(format!(
+                "`quote!` does not support `{}` expressions",
+                expr.value.kind()
+            )));
+        }
+        self.opaque.push(mem::take(expr));
+        Ok(MacroExpr::Opaque(self.opaque.len() as i64 - 1))
+    }
+
+    fn reify_all<'e>(
+        &mut self,
+        exprs: impl IntoIterator<Item = &'e mut SpannedExpr<'ast, Symbol>>,
+    ) -> Result<Vec<MacroExpr>, macros::Error>
+    where
+        'ast: 'e,
+    {
+        exprs.into_iter().map(|expr| self.reify(expr)).collect()
+    }
+}
+
+fn is_unquote(func: &SpannedExpr<Symbol>) -> bool {
+    match &func.value {
+        Expr::Ident(id) => id.name.declared_name() == "unquote!",
+        _ => false,
+    }
+}
+
+/// Converts `MacroExpr` values back into syntax trees
+struct Unreifier<'a, 'ast> {
+    arena: ast::ArenaRef<'a, 'ast, Symbol>,
+    symbols: &'a mut Symbols,
+    span: Span<BytePos>,
+    opaque: &'a [SpannedExpr<'ast, Symbol>],
+    /// The symbols of the names in the arguments of the macro
+    names: &'a FnvMap<String, Symbol>,
+}
+
+impl<'a, 'ast> Unreifier<'a, 'ast> {
+    fn ident(&mut self, name: &str) -> TypedIdent<Symbol> {
+        let symbol = match self.names.get(name) {
+            Some(symbol) => symbol.clone(),
+            None => self.symbols.simple_symbol(name),
+        };
+        TypedIdent::new(symbol)
+    }
+
+    fn args(&mut self, args: Vec<String>) -> &'ast mut [Argument<ast::SpannedIdent<Symbol>>] {
+        let args = args
+            .iter()
+            .map(|arg| Argument::explicit(pos::spanned(self.span, self.ident(arg))))
+            .collect::<Vec<_>>();
+        self.arena.alloc_extend(args)
+    }
+
+    fn literal(&self, literal: MacroLiteral) -> Result<ast::Literal, macros::Error> {
+        Ok(match literal {
+            MacroLiteral::Int(i) => ast::Literal::Int(i),
+            MacroLiteral::Byte(b) => ast::Literal::Byte(b),
+            MacroLiteral::Float(f) => ast::Literal::Float(
+                ordered_float::NotNan::new(f)
+                    .map_err(|_| macros::Error::message("Float literals can't be NaN"))?,
+            ),
+            MacroLiteral::String(s) => ast::Literal::String(s),
+            MacroLiteral::Char(c) => ast::Literal::Char(c),
+        })
+    }
+
+    fn pattern(
+        &mut self,
+        pattern: MacroPattern,
+    ) -> Result<SpannedPattern<'ast, Symbol>, macros::Error> {
+        let pattern = match pattern {
+            MacroPattern::Var(name) => Pattern::Ident(self.ident(&name)),
+            MacroPattern::Constructor(name, args) => {
+                let id = self.ident(&name);
+                let args = self.patterns(args)?;
+                Pattern::Constructor(id, self.arena.alloc_extend(args))
+            }
+            MacroPattern::TuplePattern(elems) => {
+                let elems = self.patterns(elems)?;
+                Pattern::Tuple {
+                    typ: Default::default(),
+                    elems: self.arena.alloc_extend(elems),
+                }
+            }
+            MacroPattern::LiteralPattern(literal) => Pattern::Literal(self.literal(literal)?),
+        };
+        Ok(pos::spanned(self.span, pattern))
+    }
+
+    fn patterns(
+        &mut self,
+        patterns: Vec<MacroPattern>,
+    ) -> Result<Vec<SpannedPattern<'ast, Symbol>>, macros::Error> {
+        patterns
+            .into_iter()
+            .map(|pattern| self.pattern(pattern))
+            .collect()
+    }
+
+    fn alloc(
+        &mut self,
+        expr: Node<MacroExpr>,
+    ) -> Result<&'ast mut SpannedExpr<'ast, Symbol>, macros::Error> {
+        let expr = self.expr(*expr.0)?;
+        Ok(self.arena.alloc(expr))
+    }
+
+    fn exprs(
+        &mut self,
+        exprs: Vec<MacroExpr>,
+    ) -> Result<Vec<SpannedExpr<'ast, Symbol>>, macros::Error> {
+        exprs.into_iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    fn expr(&mut self, expr: MacroExpr) -> Result<SpannedExpr<'ast, Symbol>, macros::Error> {
+        let arena = self.arena;
+        let expr = match expr {
+            MacroExpr::Ident(name) => Expr::Ident(self.ident(&name)),
+            MacroExpr::Literal(literal) => Expr::Literal(self.literal(literal)?),
+            MacroExpr::App(func, args) => {
+                let func = self.expr(*func.0)?;
+                let args = self.exprs(args)?;
+                Expr::app(arena, func, args)
+            }
+            MacroExpr::Infix(lhs, op, rhs) => Expr::Infix {
+                lhs: self.alloc(lhs)?,
+                op: pos::spanned(self.span, self.ident(&op)),
+                rhs: self.alloc(rhs)?,
+                implicit_args: &mut [],
+            },
+            MacroExpr::Lambda(args, body) => Expr::Lambda(Lambda {
+                id: self.ident(""),
+                args: self.args(args),
+                body: self.alloc(body)?,
+            }),
+            MacroExpr::Let(pattern, args, expr, body) => {
+                if !args.is_empty() && !matches!(pattern, MacroPattern::Var(_)) {
+                    return Err(macros::Error::message(
+                        "Only variables can be bound to functions in `Let`",
+                    ));
+                }
+                let bind = ValueBinding {
+                    metadata: Default::default(),
+                    name: self.pattern(pattern)?,
+                    typ: None,
+                    resolved_type: Default::default(),
+                    args: self.args(args),
+                    expr: self.expr(*expr.0)?,
+                };
+                let body = self.expr(*body.0)?;
+                Expr::let_binding(arena, bind, body)
+            }
+            MacroExpr::IfElse(pred, if_true, if_false) => Expr::IfElse(
+                self.alloc(pred)?,
+                self.alloc(if_true)?,
+                self.alloc(if_false)?,
+            ),
+            MacroExpr::Match(scrutinee, alts) => {
+                let scrutinee = self.alloc(scrutinee)?;
+                let alts = alts
+                    .into_iter()
+                    .map(|(pattern, expr)| {
+                        Ok(Alternative {
+                            pattern: self.pattern(pattern)?,
+                            guard: None,
+                            expr: self.expr(expr)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, macros::Error>>()?;
+                Expr::Match(scrutinee, arena.alloc_extend(alts))
+            }
+            MacroExpr::Tuple(elems) => Expr::Tuple {
+                typ: Default::default(),
+                elems: arena.alloc_extend(self.exprs(elems)?),
+            },
+            MacroExpr::Array(exprs) => Expr::Array(ast::Array {
+                typ: Default::default(),
+                exprs: arena.alloc_extend(self.exprs(exprs)?),
+            }),
+            MacroExpr::Record(fields) => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(name, value)| {
+                        Ok(ExprField {
+                            metadata: Default::default(),
+                            name: pos::spanned(self.span, self.symbols.simple_symbol(name)),
+                            value: Some(self.expr(value)?),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, macros::Error>>()?;
+                Expr::Record {
+                    typ: Default::default(),
+                    types: &mut [],
+                    exprs: arena.alloc_extend(fields),
+                    base: None,
+                }
+            }
+            MacroExpr::Projection(expr, field) => Expr::Projection(
+                self.alloc(expr)?,
+                self.symbols.simple_symbol(field),
+                Default::default(),
+            ),
+            MacroExpr::Block(exprs) => Expr::Block(arena.alloc_extend(self.exprs(exprs)?)),
+            MacroExpr::Opaque(index) => {
+                let expr = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.opaque.get(index))
+                    .ok_or_else(|| {
+                        macros::Error::message(format!(
+                            "`Opaque {}` does not refer to an argument of the macro",
+                            index
+                        ))
+                    })?;
+                return Ok(expr.ast_clone(arena));
+            }
+        };
+        Ok(pos::spanned(self.span, expr))
+    }
+}
+
+/// Converts `MacroExpr` values into expressions which construct them
+struct Quoter<'a, 'ast> {
+    arena: ast::ArenaRef<'a, 'ast, Symbol>,
+    span: Span<BytePos>,
+    splices: Vec<SpannedExpr<'ast, Symbol>>,
+    /// The variable which `std.macro.types` is bound to
+    types: Symbol,
+}
+
+impl<'a, 'ast> Quoter<'a, 'ast> {
+    fn spanned(&self, expr: Expr<'ast, Symbol>) -> SpannedExpr<'ast, Symbol> {
+        pos::spanned(self.span, expr)
+    }
+
+    /// Applies `types.<type_field>.<name>` to `args`
+    fn constructor(
+        &mut self,
+        type_field: &str,
+        name: &str,
+        args: Vec<SpannedExpr<'ast, Symbol>>,
+    ) -> SpannedExpr<'ast, Symbol> {
+        let types = self.spanned(Expr::Ident(TypedIdent::new(self.types.clone())));
+        let constructors = self.spanned(Expr::Projection(
+            self.arena.alloc(types),
+            Symbol::from(type_field),
+            Default::default(),
+        ));
+        let func = self.spanned(Expr::Projection(
+            self.arena.alloc(constructors),
+            Symbol::from(name),
+            Default::default(),
+        ));
+        self.spanned(Expr::app(self.arena, func, args))
+    }
+
+    fn string(&self, s: String) -> SpannedExpr<'ast, Symbol> {
+        self.spanned(Expr::Literal(ast::Literal::String(s)))
+    }
+
+    fn array(&self, exprs: Vec<SpannedExpr<'ast, Symbol>>) -> SpannedExpr<'ast, Symbol> {
+        self.spanned(Expr::Array(ast::Array {
+            typ: Default::default(),
+            exprs: self.arena.alloc_extend(exprs),
+        }))
+    }
+
+    fn tuple(
+        &self,
+        first: SpannedExpr<'ast, Symbol>,
+        second: SpannedExpr<'ast, Symbol>,
+    ) -> SpannedExpr<'ast, Symbol> {
+        self.spanned(Expr::Tuple {
+            typ: Default::default(),
+            elems: self.arena.alloc_extend(vec![first, second]),
+        })
+    }
+
+    fn strings(&self, strings: Vec<String>) -> SpannedExpr<'ast, Symbol> {
+        let strings = strings.into_iter().map(|s| self.string(s)).collect();
+        self.array(strings)
+    }
+
+    fn literal(&mut self, literal: MacroLiteral) -> SpannedExpr<'ast, Symbol> {
+        let (name, literal) = match literal {
+            MacroLiteral::Int(i) => ("Int", ast::Literal::Int(i)),
+            MacroLiteral::Byte(b) => ("Byte", ast::Literal::Byte(b)),
+            MacroLiteral::Float(f) => (
+                "Float",
+                ast::Literal::Float(ordered_float::NotNan::new(f).unwrap()),
+            ),
+            MacroLiteral::String(s) => ("String", ast::Literal::String(s)),
+            MacroLiteral::Char(c) => ("Char", ast::Literal::Char(c)),
+        };
+        let literal = self.spanned(Expr::Literal(literal));
+        self.constructor("literal", name, vec![literal])
+    }
+
+    fn pattern(&mut self, pattern: MacroPattern) -> SpannedExpr<'ast, Symbol> {
+        match pattern {
+            MacroPattern::Var(name) => {
+                let name = self.string(name);
+                self.constructor("pattern", "Var", vec![name])
+            }
+            MacroPattern::Constructor(name, args) => {
+                let name = self.string(name);
+                let args = self.patterns(args);
+                self.constructor("pattern", "Constructor", vec![name, args])
+            }
+            MacroPattern::TuplePattern(elems) => {
+                let elems = self.patterns(elems);
+                self.constructor("pattern", "TuplePattern", vec![elems])
+            }
+            MacroPattern::LiteralPattern(literal) => {
+                let literal = self.literal(literal);
+                self.constructor("pattern", "LiteralPattern", vec![literal])
+            }
+        }
+    }
+
+    fn patterns(&mut self, patterns: Vec<MacroPattern>) -> SpannedExpr<'ast, Symbol> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| self.pattern(pattern))
+            .collect();
+        self.array(patterns)
+    }
+
+    fn exprs(&mut self, exprs: Vec<MacroExpr>) -> SpannedExpr<'ast, Symbol> {
+        let exprs = exprs.into_iter().map(|expr| self.expr(expr)).collect();
+        self.array(exprs)
+    }
+
+    fn expr(&mut self, expr: MacroExpr) -> SpannedExpr<'ast, Symbol> {
+        let (name, args) = match expr {
+            MacroExpr::Ident(name) => ("Ident", vec![self.string(name)]),
+            MacroExpr::Literal(literal) => ("Literal", vec![self.literal(literal)]),
+            MacroExpr::App(func, args) => ("App", vec![self.expr(*func.0), self.exprs(args)]),
+            MacroExpr::Infix(lhs, op, rhs) => (
+                "Infix",
+                vec![self.expr(*lhs.0), self.string(op), self.expr(*rhs.0)],
+            ),
+            MacroExpr::Lambda(args, body) => {
+                ("Lambda", vec![self.strings(args), self.expr(*body.0)])
+            }
+            MacroExpr::Let(pattern, args, expr, body) => (
+                "Let",
+                vec![
+                    self.pattern(pattern),
+                    self.strings(args),
+                    self.expr(*expr.0),
+                    self.expr(*body.0),
+                ],
+            ),
+            MacroExpr::IfElse(pred, if_true, if_false) => (
+                "IfElse",
+                vec![
+                    self.expr(*pred.0),
+                    self.expr(*if_true.0),
+                    self.expr(*if_false.0),
+                ],
+            ),
+            MacroExpr::Match(scrutinee, alts) => {
+                let scrutinee = self.expr(*scrutinee.0);
+                let alts = alts
+                    .into_iter()
+                    .map(|(pattern, expr)| {
+                        let pattern = self.pattern(pattern);
+                        let expr = self.expr(expr);
+                        self.tuple(pattern, expr)
+                    })
+                    .collect();
+                ("Match", vec![scrutinee, self.array(alts)])
+            }
+            MacroExpr::Tuple(elems) => ("Tuple", vec![self.exprs(elems)]),
+            MacroExpr::Array(exprs) => ("Array", vec![self.exprs(exprs)]),
+            MacroExpr::Record(fields) => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let name = self.string(name);
+                        let value = self.expr(value);
+                        self.tuple(name, value)
+                    })
+                    .collect();
+                ("Record", vec![self.array(fields)])
+            }
+            MacroExpr::Projection(expr, field) => {
+                ("Projection", vec![self.expr(*expr.0), self.string(field)])
+            }
+            MacroExpr::Block(exprs) => ("Block", vec![self.exprs(exprs)]),
+            // Every splice is only referred to once
+            MacroExpr::Opaque(index) => return mem::take(&mut self.splices[index as usize]),
+        };
+        self.constructor("expr", name, args)
+    }
+}
+
+/// Macro implemented by a gluon function of type `Array Expr -> Result String Expr`
+#[derive(Trace)]
+#[gluon(crate_name = "vm")]
+struct UserMacro {
+    function: RootedValue<RootedThread>,
+}
+
+impl Macro for UserMacro {
+    fn expand<'r, 'a: 'r, 'b: 'r, 'ast: 'r>(
+        &self,
+        env: &'b mut MacroExpander<'a>,
+        arena: &'b mut ast::OwnedArena<'ast, Symbol>,
+        args: &'b mut [SpannedExpr<'ast, Symbol>],
+    ) -> MacroFuture<'r, 'ast> {
+        let function = self.function.clone();
+        Box::pin(async move {
+            let span = match (args.first(), args.last()) {
+                (Some(first), Some(last)) => Span::new(first.span.start(), last.span.end()),
+                _ => Span::default(),
+            };
+
+            let mut reifier = Reifier {
+                opaque: Vec::new(),
+                names: FnvMap::default(),
+                quote: false,
+            };
+            let args = reifier.reify_all(args.iter_mut())?;
+
+            let thread = env
+                .vm
+                .new_thread()
+                .map_err(|err| macros::Error::message(err.to_string()))?;
+            let mut function: OwnedFunction<MacroFunction> =
+                Getable::from_value(&thread, function.get_variant());
+            let result = function
+                .call_async(args)
+                .await
+                .map_err(|err| macros::Error::message(err.to_string()))?
+                .map_err(macros::Error::message)?;
+
+            let mut expr = Unreifier {
+                arena: arena.borrow(),
+                symbols: &mut env.symbols,
+                span,
+                opaque: &reifier.opaque,
+                names: &reifier.names,
+            }
+            .expr(result)?;
+
+            // The macro may have returned expressions which contain further macros
+            env.run_nested(arena, &mut expr).await;
+
+            Ok(expr.into())
+        })
+    }
+}
+
+/// `import_macros! module` imports `module` just like `import!` and also makes every field of the
+/// module which has the type `Array Expr -> Result String Expr` available as a macro in the rest
+/// of the expression being expanded.
+#[derive(Trace)]
+#[gluon(crate_name = "vm")]
+pub(crate) struct ImportMacros;
+
+impl Macro for ImportMacros {
+    fn expand<'r, 'a: 'r, 'b: 'r, 'ast: 'r>(
+        &self,
+        env: &'b mut MacroExpander<'a>,
+        arena: &'b mut ast::OwnedArena<'ast, Symbol>,
+        args: &'b mut [SpannedExpr<'ast, Symbol>],
+    ) -> MacroFuture<'r, 'ast> {
+        Box::pin(async move {
+            let modulename = get_module_name(args).map_err(macros::Error::new)?;
+            let modulename = modulename.trim_start_matches('@').to_string();
+
+            let import = env.get("import").ok_or_else(|| {
+                macros::Error::message("`import_macros!` requires the `import!` macro")
+            })?;
+
+            {
+                let mut db = env
+                    .userdata
+                    .fork(env.vm.root_thread())
+                    .downcast::<salsa::Snapshot<CompilerDatabase>>()
+                    .map_err(|_| {
+                        macros::Error::message(
+                            "`import_macros` requires a `CompilerDatabase` as user data during \
+                             macro expansion",
+                        )
+                    })?;
+
+                // The types of the macros must be loaded before their signature can be checked
+                db.import("std.macro.types".into())
+                    .await
+                    .map_err(|err| macros::Error::message(err.to_string()))?;
+                let module = db
+                    .global(modulename)
+                    .await
+                    .map_err(|err| macros::Error::message(err.to_string()))?;
+
+                let signature = MacroFunction::make_type(env.vm);
+                for field in module.typ.remove_forall().row_iter() {
+                    if !check_signature(&env.vm.get_env(), &signature, &field.typ) {
+                        continue;
+                    }
+                    let name = field.name.declared_name();
+                    if let Some(function) = module.value.get_field(name) {
+                        env.insert_local(name.to_string(), Arc::new(UserMacro { function }));
+                    }
+                }
+            }
+
+            import.expand(env, arena, args).await
+        })
+    }
+}
+
+/// `quote!(expr)` evaluates to the `Expr` value which represents `expr`. `unquote!(e)` can be used
+/// inside `expr` to splice in the `Expr` that `e` evaluates to.
+#[derive(Trace)]
+#[gluon(crate_name = "vm")]
+pub(crate) struct Quote;
+
+impl Macro for Quote {
+    fn expand<'r, 'a: 'r, 'b: 'r, 'ast: 'r>(
+        &self,
+        env: &'b mut MacroExpander<'a>,
+        arena: &'b mut ast::OwnedArena<'ast, Symbol>,
+        args: &'b mut [SpannedExpr<'ast, Symbol>],
+    ) -> MacroFuture<'r, 'ast> {
+        Box::pin(async move {
+            if args.len() != 1 {
+                return Err(macros::Error::message("`quote!` expects 1 argument"));
+            }
+            let span = args[0].span;
+
+            let mut reifier = Reifier {
+                opaque: Vec::new(),
+                names: FnvMap::default(),
+                quote: true,
+            };
+            let expr = reifier.reify(&mut args[0])?;
+
+            // A fresh symbol can't be referred to by the spliced expressions so they can't be
+            // captured by the binding of `std.macro.types`
+            let types = Symbol::from("quote_types");
+            let body = Quoter {
+                arena: arena.borrow(),
+                span,
+                splices: reifier.opaque,
+                types: types.clone(),
+            }
+            .expr(expr);
+
+            // let <types> = import! std.macro.types in ...
+            let symbols = &mut env.symbols;
+            let sp = |e| pos::spanned(span, e);
+            let import_path = ["macro", "types"].iter().fold(
+                sp(Expr::Ident(TypedIdent::new(symbols.simple_symbol("std")))),
+                |expr, name| {
+                    sp(Expr::Projection(
+                        arena.alloc(expr),
+                        symbols.simple_symbol(*name),
+                        Default::default(),
+                    ))
+                },
+            );
+            let import = sp(Expr::app(
+                arena.borrow(),
+                sp(Expr::Ident(TypedIdent::new(
+                    symbols.simple_symbol("import!"),
+                ))),
+                Some(import_path),
+            ));
+            let bind = ValueBinding {
+                metadata: Default::default(),
+                name: pos::spanned(span, Pattern::Ident(TypedIdent::new(types))),
+                typ: None,
+                resolved_type: Default::default(),
+                args: &mut [],
+                expr: import,
+            };
+            let mut expr = sp(Expr::let_binding(arena.borrow(), bind, body));
+
+            // Expand the `import!` as well as any macros in the spliced expressions
+            env.run_nested(arena, &mut expr).await;
+
+            Ok(expr.into())
+        })
+    }
+}
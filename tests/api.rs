@@ -6,19 +6,23 @@ extern crate gluon_vm;
 #[macro_use]
 extern crate gluon_codegen;
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 use futures::prelude::*;
 
 use gluon::{
-    base::types::{Alias, ArcType, Type},
-    import::{add_extern_module, Import},
+    base::types::{Alias, ArcType, BuiltinType, Type},
+    import::{add_extern_module, add_extern_module_with_deps, Import},
     query::Compilation,
     vm::{
         api::{
             de::De,
             scoped::{Ref, RefMut},
-            FunctionRef, FutureResult, Hole, OpaqueValue, OwnedFunction, RuntimeResult, VmType, IO,
+            FunctionRef, FutureResult, Hole, OpaqueValue, OwnedFunction, RuntimeResult, UInt64,
+            VmType, IO,
         },
         gc,
         thread::{RootedThread, Thread},
@@ -146,6 +150,160 @@ fn array() {
     assert_eq!(result, expected);
 }
 
+#[test]
+fn sized_ints() {
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let half = import! half
+        half 18446744073709551614u64
+    "#;
+    fn half(x: UInt64) -> UInt64 {
+        UInt64(x.0 / 2)
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "half", |thread| {
+        ExternModule::new(thread, primitive!(1, half))
+    });
+
+    let result = vm
+        .run_expr::<UInt64>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let expected = (
+        UInt64(9223372036854775807),
+        Type::builtin(BuiltinType::UInt64),
+    );
+
+    assert_eq!(result, expected);
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn big_int_and_decimal() {
+    use gluon::std_lib::{big_int::BigInt, decimal::Decimal};
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let big_int = import! std.big_int
+        let decimal = import! std.decimal
+        let square = import! square
+        let x = big_int.from_int 9223372036854775807
+        decimal.from_big_int (square x)
+    "#;
+    fn square(x: BigInt) -> BigInt {
+        BigInt(&x.0 * &x.0)
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "square", |thread| {
+        ExternModule::new(thread, primitive!(1, square))
+    });
+
+    vm.run_expr::<()>("<top>", "let _ = import! std.decimal in ()")
+        .unwrap();
+
+    let (result, _) = vm
+        .run_expr::<Decimal>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(
+        result.0.to_string(),
+        "85070591730234615847396907784232501249"
+    );
+}
+
+#[cfg(feature = "big_int")]
+#[test]
+fn big_int_rejects_out_of_range_exponents_and_radixes() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+
+    let result = vm.run_expr::<String>(
+        "<top>",
+        r#"
+        let big_int = import! std.big_int
+        big_int.show (big_int.pow (big_int.from_int 2) (0 - 1))
+        "#,
+    );
+    match result {
+        Err(gluon::Error::VM(Error::Panic(ref err, _))) => {
+            assert_eq!(err, "Negative exponent -1")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected a panic"),
+    }
+
+    // 4294967298 is 2 when truncated to 32 bits
+    let result = vm.run_expr::<String>(
+        "<top>",
+        r#"
+        let big_int = import! std.big_int
+        big_int.to_str_radix (big_int.from_int 3) 4294967298
+        "#,
+    );
+    match result {
+        Err(gluon::Error::VM(Error::Panic(ref err, _))) => {
+            assert_eq!(err, "Radix 4294967298 is not in the range 2 to 36")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected a panic"),
+    }
+
+    let (result, _) = vm
+        .run_expr::<bool>(
+            "<top>",
+            r#"
+            let big_int = import! std.big_int
+            match big_int.from_str_radix "11" 4294967298 with
+            | Ok _ -> False
+            | Err _ -> True
+            "#,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(result);
+}
+
+#[test]
+fn extern_module_dependencies_are_only_loaded_once() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    {
+        let loads = loads.clone();
+        add_extern_module(&vm, "shared", move |thread| {
+            loads.fetch_add(1, Ordering::SeqCst);
+            ExternModule::new(thread, 1)
+        });
+    }
+    for name in &["dependent_a", "dependent_b"] {
+        add_extern_module_with_deps(
+            &vm,
+            name,
+            |thread| ExternModule::new(thread, 2),
+            vec!["shared".into()],
+        );
+    }
+
+    let (result, _) = vm
+        .run_expr::<i32>(
+            "<top>",
+            r#"
+            let a = import! dependent_a
+            let b = import! dependent_b
+            let shared = import! shared
+            a + b + shared
+            "#,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 5);
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+}
+
 #[test]
 fn return_finished_future() {
     let _ = ::env_logger::try_init();
@@ -730,3 +888,194 @@ fn clone_userdata() {
 
     assert_eq!(*result, Test(123));
 }
+
+#[test]
+fn hash_map() {
+    use std::collections::HashMap;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let hash_map = import! std.hash_map
+        let { ? } = import! std.hash
+        let lengths = import! lengths
+        hash_map.map_with_key (\_ v -> v + 1) (lengths ["a", "bc", "def"])
+    "#;
+    fn lengths(words: Vec<String>) -> HashMap<String, VmInt> {
+        words
+            .into_iter()
+            .map(|word| {
+                let len = word.len() as VmInt;
+                (word, len)
+            })
+            .collect()
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "lengths", |thread| {
+        ExternModule::new(thread, primitive!(1, lengths))
+    });
+    // The `HashMap` type must be loaded before it can be used from Rust
+    vm.load_file("std/hash_map.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (result, _) = vm
+        .run_expr::<HashMap<String, VmInt>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let expected: HashMap<_, _> = vec![("a".to_string(), 2), ("bc".into(), 3), ("def".into(), 4)]
+        .into_iter()
+        .collect();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn hash_set() {
+    use std::collections::HashSet;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let hash_set = import! std.hash_set
+        let { ? } = import! std.hash
+        let words = import! words
+        hash_set.insert "c" (hash_set.remove "a" (words ["a", "b", "a"]))
+    "#;
+    fn words(words: Vec<String>) -> HashSet<String> {
+        words.into_iter().collect()
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "words", |thread| {
+        ExternModule::new(thread, primitive!(1, words))
+    });
+    // The `HashSet` type must be loaded before it can be used from Rust
+    vm.load_file("std/hash_set.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (result, _) = vm
+        .run_expr::<HashSet<String>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let expected: HashSet<_> = vec!["b".to_string(), "c".into()].into_iter().collect();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn hash_set_with_int_elements() {
+    use std::collections::HashSet;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let hash_set = import! std.hash_set
+        let { ? } = import! std.hash
+        let numbers = import! numbers
+        hash_set.insert 4 (hash_set.remove 1 (numbers 3))
+    "#;
+    fn numbers(n: VmInt) -> HashSet<VmInt> {
+        (1..=n).collect()
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "numbers", |thread| {
+        ExternModule::new(thread, primitive!(1, numbers))
+    });
+    vm.load_file("std/hash_set.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (result, _) = vm
+        .run_expr::<HashSet<VmInt>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let expected: HashSet<_> = vec![2, 3, 4].into_iter().collect();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn array_buf() {
+    use gluon::vm::{
+        api::{generic::A, WithVM},
+        buffer::{ArrayBuf, IntoArrayBuf},
+    };
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let io @ { ? } = import! std.io
+        let { wrap } = import! std.applicative
+        let array_buf = import! std.array.mut
+        let { numbers, sum } = import! buffers
+        do buf = numbers ()
+        seq array_buf.push buf 4
+        wrap (sum buf)
+    "#;
+    fn numbers(_: ()) -> IO<IntoArrayBuf<VmInt>> {
+        IO::Value(IntoArrayBuf(vec![1, 2, 3]))
+    }
+    fn sum(WithVM { vm, value: buf }: WithVM<&ArrayBuf<A>>) -> VmInt {
+        buf.to_vec::<VmInt>(vm).into_iter().sum()
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "buffers", |thread| {
+        ExternModule::new(
+            thread,
+            record! {
+                numbers => primitive!(1, numbers),
+                sum => primitive!(1, sum),
+            },
+        )
+    });
+    vm.get_database_mut().run_io(true);
+    // The `ArrayBuf` type must be loaded before it can be used from Rust
+    vm.load_file("std/array/mut.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (result, _) = vm
+        .run_expr::<IO<VmInt>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(result, IO::Value(10));
+}
+
+#[test]
+fn byte_buf() {
+    use gluon::vm::buffer::ByteBuf;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let io @ { ? } = import! std.io
+        let { wrap } = import! std.applicative
+        let byte_buf = import! std.byte.buf
+        let { header, encode } = import! buffers
+        let buf = header
+        seq byte_buf.push_str buf "abc"
+        wrap (encode buf)
+    "#;
+    fn encode(buf: &ByteBuf) -> Vec<u8> {
+        buf.to_vec()
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "buffers", |thread| {
+        ExternModule::new(
+            thread,
+            record! {
+                header => ByteBuf::from(vec![0xFF, 0]),
+                encode => primitive!(1, encode),
+            },
+        )
+    });
+    vm.get_database_mut().run_io(true);
+    // The `ByteBuf` type must be loaded before it can be used from Rust
+    vm.load_file("std/byte/buf.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (result, _) = vm
+        .run_expr::<IO<Vec<u8>>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(result, IO::Value(vec![0xFF, 0, b'a', b'b', b'c']));
+}
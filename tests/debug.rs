@@ -14,6 +14,7 @@ use gluon::{
         types::{ArcType, Type, TypeExt},
     },
     vm::{
+        api::ValueRef,
         compiler::UpvarInfo,
         thread::{HookFlags, ThreadInternal},
     },
@@ -446,6 +447,111 @@ fn upvars() {
     );
 }
 
+#[test]
+fn local_and_upvar_values() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let result = Arc::new(Mutex::new(Vec::new()));
+    {
+        let result = result.clone();
+        let mut context = thread.context();
+        context.set_hook(Some(Box::new(move |_, debug_info| {
+            let stack_info = debug_info.stack_info(0).unwrap();
+            if stack_info.function_name() == Some("f") {
+                let locals = stack_info
+                    .locals()
+                    .map(
+                        |local| match stack_info.local_value(local).map(|v| v.as_ref()) {
+                            Some(ValueRef::Int(i)) => (local.name.declared_name().to_string(), i),
+                            value => panic!("Unexpected value {:?}", value),
+                        },
+                    )
+                    .collect::<Vec<_>>();
+                let upvars = (0..stack_info.upvars().len())
+                    .map(|i| match stack_info.upvar_value(i).map(|v| v.as_ref()) {
+                        Some(ValueRef::Int(i)) => i,
+                        value => panic!("Unexpected value {:?}", value),
+                    })
+                    .collect::<Vec<_>>();
+                result.lock().unwrap().push((locals, upvars));
+            }
+            Poll::Ready(Ok(()))
+        })));
+        context.set_hook_mask(HookFlags::LINE_FLAG);
+    }
+    let expr = r#"
+    let x = 10
+    let f y =
+        let z = y #Int+ 1
+        z #Int+ x
+    f 3
+    "#;
+
+    thread.get_database_mut().implicit_prelude(false);
+
+    assert_eq!(thread.run_expr::<i32>("test", expr).unwrap().0, 14);
+
+    assert_eq!(
+        *result.lock().unwrap(),
+        vec![
+            (vec![("y".to_string(), 3)], vec![10]),
+            (vec![("y".to_string(), 3), ("z".to_string(), 4)], vec![10]),
+        ]
+    );
+}
+
+#[test]
+fn local_value_does_not_read_past_the_frame() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let result = Arc::new(Mutex::new(Vec::new()));
+    {
+        let result = result.clone();
+        let mut context = thread.context();
+        context.set_hook(Some(Box::new(move |_, debug_info| {
+            let stack_info = debug_info.stack_info(0).unwrap();
+            if stack_info.function_name() == Some("f") {
+                let caller = debug_info.stack_info(1).unwrap();
+                let x = caller.locals().next().expect("local `x`").clone();
+                // Only `f` has the argument `12345` in its frame so `g` must not be able to see it
+                let values = (0..64)
+                    .filter_map(|index| {
+                        let mut local = x.clone();
+                        local.index = x.index + index;
+                        caller.local_value(&local).map(|v| match v.as_ref() {
+                            ValueRef::Int(i) => Some(i),
+                            _ => None,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                result.lock().unwrap().push(values);
+            }
+            Poll::Ready(Ok(()))
+        })));
+        context.set_hook_mask(HookFlags::LINE_FLAG);
+    }
+    let expr = r#"
+    let f y =
+        y
+    let g x =
+        f (x #Int+ 12344)
+    g 1
+    "#;
+
+    thread.get_database_mut().implicit_prelude(false);
+
+    assert_eq!(thread.run_expr::<i32>("test", expr).unwrap().0, 12345);
+
+    let result = result.lock().unwrap();
+    assert!(!result.is_empty());
+    for values in &*result {
+        assert!(values.contains(&Some(1)), "{:?}", values);
+        assert!(!values.contains(&Some(12345)), "{:?}", values);
+    }
+}
+
 #[test]
 fn implicit_prelude_variable_names() {
     let _ = env_logger::try_init();
@@ -0,0 +1,96 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use gluon::{hot_reload::HotReload, RootedThread, ThreadExt, VmBuilder};
+
+fn new_vm(dir: &Path) -> RootedThread {
+    let vm = VmBuilder::new()
+        .import_paths(Some(vec![dir.into()]))
+        .build();
+    vm.get_database_mut().implicit_prelude(false);
+    vm
+}
+
+fn get_int(vm: &RootedThread, name: &str) -> i32 {
+    vm.get_global(name).unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[test]
+fn reload_changed_module_and_dependents() {
+    let _ = env_logger::try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.glu");
+    fs::write(&config_path, "{ speed = 1 }").unwrap();
+    fs::write(
+        dir.path().join("logic.glu"),
+        "let config = import! config in { doubled_speed = config.speed #Int* 2 }",
+    )
+    .unwrap();
+
+    let vm = new_vm(dir.path());
+    vm.run_expr::<i32>("test", "let logic = import! logic in logic.doubled_speed")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let reloaded = Arc::new(Mutex::new(Vec::new()));
+    let reload = HotReload::new(vm.clone()).on_reload({
+        let reloaded = reloaded.clone();
+        move |module, _| reloaded.lock().unwrap().push(module.to_string())
+    });
+    reload.watch("config", &config_path);
+
+    assert_eq!(reload.check().unwrap(), Vec::<String>::new());
+
+    fs::write(&config_path, "{ speed = 10 }").unwrap();
+    assert_eq!(
+        reload.check().unwrap_or_else(|err| panic!("{}", err)),
+        ["config", "logic"]
+    );
+    assert_eq!(*reloaded.lock().unwrap(), ["config", "logic"]);
+    assert_eq!(get_int(&vm, "config.speed"), 10);
+    assert_eq!(get_int(&vm, "logic.doubled_speed"), 20);
+
+    let (result, _) = vm
+        .run_expr::<i32>("test2", "let logic = import! logic in logic.doubled_speed")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 20);
+}
+
+#[test]
+fn failed_reload_keeps_the_old_version() {
+    let _ = env_logger::try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.glu");
+    fs::write(&config_path, "{ speed = 1 }").unwrap();
+    fs::write(
+        dir.path().join("logic.glu"),
+        "let config = import! config in { doubled_speed = config.speed #Int* 2 }",
+    )
+    .unwrap();
+
+    let vm = new_vm(dir.path());
+    vm.run_expr::<i32>("test", "let logic = import! logic in logic.doubled_speed")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let reload = HotReload::new(vm.clone()).on_error({
+        let errors = errors.clone();
+        move |module, _| errors.lock().unwrap().push(module.to_string())
+    });
+    reload.watch("config", &config_path);
+
+    // `logic` fails to typecheck against the new `config`
+    fs::write(&config_path, r#"{ speed = "fast" }"#).unwrap();
+    assert!(reload.check().is_err());
+    assert_eq!(*errors.lock().unwrap(), ["config"]);
+    assert_eq!(get_int(&vm, "config.speed"), 1);
+    assert_eq!(get_int(&vm, "logic.doubled_speed"), 2);
+
+    fs::write(&config_path, "{ speed = 3 }").unwrap();
+    reload.check().unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(get_int(&vm, "logic.doubled_speed"), 6);
+}
@@ -386,3 +386,214 @@ fn io_error_in_catch3() {
 
     assert_eq!(result, expected);
 }
+
+#[test]
+fn bounded_channel_send_async_waits_for_receiver() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let { ? } = import! std.io
+        let { wrap, (*>) } = import! std.applicative
+        let { Result } = import! std.result
+        let channel = import! std.channel
+        let thread = import! std.thread
+
+        let { sender, receiver } = channel.bounded_channel 1 0
+
+        let producer =
+            channel.send_async sender 1
+                *> channel.send_async sender 2
+                *> channel.send_async sender 3
+                *> channel.close sender
+
+        rec let sum acc =
+            do x = channel.recv_async receiver
+            match x with
+            | Ok x -> sum (acc + x)
+            | Err _ -> wrap acc
+
+        do child = thread.new_thread ()
+        do producer = thread.spawn_on child (\_ -> producer)
+        do result = thread.join producer (sum 0)
+        wrap result._1
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<i32>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value(6));
+}
+
+#[test]
+fn channel_recv_timeout_and_select() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let { ? } = import! std.io
+        let { wrap, (*>) } = import! std.applicative
+        let { Result } = import! std.result
+        let channel @ { RecvError } = import! std.channel
+
+        let first = channel.bounded_channel 1 ""
+        let second = channel.bounded_channel 1 ""
+
+        do timeout = channel.recv_timeout first.receiver 10
+        seq channel.send_async second.sender "second"
+        do selected = channel.select [first.receiver, second.receiver]
+        seq channel.close first.sender
+        seq channel.close second.sender
+        do closed = channel.select [first.receiver, second.receiver]
+
+        match (timeout, selected, closed) with
+        | (Err Timeout, Ok { index = 1, value }, Err Closed) -> wrap value
+        | _ -> wrap "unexpected result"
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<String>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value("second".to_string()));
+}
+
+#[test]
+fn select_repeatedly_with_a_channel_which_never_receives() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let { ? } = import! std.io
+        let { wrap } = import! std.applicative
+        let { Result } = import! std.result
+        let channel = import! std.channel
+
+        let never = channel.bounded_channel 1 0
+        let ready = channel.bounded_channel 1 0
+
+        rec let loop n acc =
+            if n == 0 then
+                wrap acc
+            else
+                seq channel.send_async ready.sender n
+                do selected = channel.select [never.receiver, ready.receiver]
+                match selected with
+                | Ok { index = 1, value } -> loop (n - 1) (acc + value)
+                | _ -> wrap 0
+
+        loop 1000 0
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<i32>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value(500500));
+}
+
+#[test]
+fn concurrent_combinators() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let io @ { ? } = import! std.io
+        let { wrap, flat_map } = io
+        let array = import! std.array
+        let { Option } = import! std.option
+        let { sleep } = import! std.thread
+        let { par, race, all, timeout } = import! std.io.concurrent
+
+        let after ms x = flat_map (\_ -> wrap x) (sleep ms)
+
+        do pair = par (after 10 1) (wrap 2)
+        do winner = race (after 1000 10) (after 10 20)
+        do xs = all [wrap 1, after 10 2, wrap 3]
+        do timed_out = timeout 10 (after 1000 0)
+        do finished = timeout 1000 (wrap 4)
+        do failed = io.catch (flat_map (\_ -> wrap 0) (par (io.throw "error") (after 1000 0))) (\_ -> wrap 5)
+
+        match (timed_out, finished) with
+        | (None, Some x) -> wrap (pair._0 + pair._1 + winner + array.len xs + x + failed)
+        | _ -> wrap 0
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<i32>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value(35));
+}
+
+#[test]
+fn concurrent_cancel_interrupts_task() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let io @ { ? } = import! std.io
+        let { wrap, flat_map } = io
+        let { sleep } = import! std.thread
+        let { Reference, ref, load, (<-) } = import! std.reference
+        let { spawn, await, cancel } = import! std.io.concurrent
+
+        let counter = ref 0
+        let spin _ : () -> IO () =
+            seq sleep 1
+            seq wrap (counter <- (load counter + 1))
+            spin ()
+
+        do task = spawn (spin ())
+        seq sleep 20
+        seq cancel task
+        do result = io.catch (flat_map (\_ -> wrap "not cancelled") (await task)) wrap
+        let count = load counter
+        seq sleep 20
+        wrap (if count == load counter then result else "still running")
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<String>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value("Task was cancelled".to_string()));
+}
+
+#[test]
+fn task_group_cancels_children_when_body_fails() {
+    let _ = ::env_logger::try_init();
+
+    let text = r#"
+        let io @ { ? } = import! std.io
+        let { wrap, flat_map } = io
+        let { sleep } = import! std.thread
+        let { Reference, ref, load, (<-) } = import! std.reference
+        let { task_group, spawn_in, await } = import! std.io.concurrent
+
+        let finished = ref False
+        let child = flat_map (\_ -> wrap (finished <- True)) (sleep 50)
+
+        let body group =
+            do task = spawn_in group child
+            io.throw "body failed"
+
+        do r = io.catch (flat_map (\_ -> wrap "no error") (task_group body)) wrap
+        seq sleep 100
+        wrap (if load finished then "child was not cancelled" else "cancelled")
+    "#;
+
+    let mut runtime = Runtime::new().unwrap();
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+    let (result, _) = runtime
+        .block_on(vm.run_expr_async::<IO<String>>("<top>", text))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, IO::Value("cancelled".to_string()));
+}
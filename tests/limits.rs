@@ -2,14 +2,14 @@ mod support;
 
 use gluon::{
     vm::{
-        api::{Hole, OpaqueValue},
-        thread::ThreadInternal,
+        api::{FunctionRef, Hole, OpaqueValue, ValueRef},
+        thread::{Execute, ThreadInternal},
         Error as VMError,
     },
     Error, Thread, ThreadExt,
 };
 
-use crate::support::make_vm;
+use crate::support::{futures::executor::block_on, make_vm};
 
 #[test]
 fn out_of_memory() {
@@ -47,3 +47,85 @@ fn stack_overflow() {
         Ok(_) => panic!("Expected an error"),
     }
 }
+
+#[test]
+fn out_of_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.set_fuel(Some(1000));
+    vm.get_database_mut().implicit_prelude(false);
+
+    let expr = r#"
+        let loop x : Int -> Int = loop x
+        loop 0
+    "#;
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("example", expr);
+
+    match result {
+        Err(Error::VM(VMError::OutOfFuel)) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+    assert_eq!(vm.fuel(), Some(0));
+}
+
+#[test]
+fn resume_after_adding_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.get_database_mut().implicit_prelude(false);
+
+    let expr = r#"
+        let sum n : Int -> Int = if n #Int== 0 then 0 else n #Int+ sum (n #Int- 1)
+        sum
+    "#;
+    let (mut sum, _) = vm
+        .run_expr::<FunctionRef<fn(i32) -> i32>>("example", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    vm.set_fuel(Some(100));
+    match sum.call(100) {
+        Err(VMError::OutOfFuel) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+
+    vm.add_fuel(100_000);
+    let value = block_on(Execute::new(vm.root_thread())).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value.get_variant().as_ref(), ValueRef::Int(5050));
+    assert!(vm.fuel().unwrap() < 100_000);
+}
+
+#[test]
+fn spawned_threads_share_the_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.get_database_mut().implicit_prelude(false);
+    vm.run_expr::<OpaqueValue<&Thread, Hole>>("load", "import! std.thread")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    vm.set_fuel(Some(10_000));
+    let expr = r#"
+        let thread = import! std.thread
+        let loop x : Int -> Int = loop x
+        let child = thread.spawn (\_ ->
+            let _ = loop 0
+            ())
+        thread.resume child
+    "#;
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("example", expr);
+
+    match result {
+        Err(Error::VM(VMError::OutOfFuel)) => (),
+        Err(err) => assert!(
+            err.to_string().contains("Thread ran out of fuel"),
+            "Unexpected error `{}`",
+            err
+        ),
+        Ok(_) => panic!("Expected the child thread to run out of fuel"),
+    }
+    assert_eq!(vm.fuel(), Some(0));
+}
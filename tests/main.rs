@@ -5,10 +5,8 @@ use std::{
 };
 
 use {
-    collect_mac::collect,
     failure_derive::Fail,
     futures::{join, prelude::*, stream, task::SpawnExt},
-    serde_derive::Deserialize,
     structopt::StructOpt,
 };
 
@@ -17,10 +15,11 @@ use gluon::{
         ast::{Expr, Pattern, SpannedExpr},
         filename_to_module,
         symbol::Symbol,
-        types::{ArcType, Type},
+        types::ArcType,
     },
     new_vm_async,
-    vm::api::{de::De, generic::A, Getable, Hole, OpaqueValue, OwnedFunction, VmType, IO},
+    std_lib::test::{TestCase, TestEff, TestEffIO, TestFn},
+    vm::api::{Getable, Hole, OpaqueValue, OwnedFunction, IO},
     RootedThread, Thread, ThreadExt,
 };
 
@@ -121,36 +120,6 @@ fn test_files(path: &str) -> Result<Vec<PathBuf>, Error> {
     Ok(paths)
 }
 
-macro_rules! define_test_type {
-    ($name:ident $($args: ident)*) => {
-        impl VmType for $name {
-            type Type = $name;
-            fn make_type(vm: &Thread) -> ArcType {
-                let typ = concat!("std.test.", stringify!($name));
-                Type::app(
-                    vm.get_env().find_type_info(typ).unwrap().into_type(),
-                    collect![$($args::make_type(vm),)* Type::unit()],
-                )
-            }
-        }
-    };
-}
-
-type TestEff = OpaqueValue<RootedThread, TestEffIO>;
-type TestFn = OwnedFunction<fn(()) -> TestEff>;
-
-#[derive(Deserialize)]
-enum TestCase {
-    Test(String, TestFn),
-    Group(String, Vec<TestCase>),
-}
-
-define_test_type! { TestCase Hole }
-
-struct TestEffIO;
-
-define_test_type! { TestEffIO A }
-
 fn make_tensile_test(name: String, test: TestFn) -> tensile::Test<Error> {
     let mut test = ::std::panic::AssertUnwindSafe(test);
     tensile::test(name, {
@@ -165,19 +134,17 @@ fn make_tensile_test(name: String, test: TestFn) -> tensile::Test<Error> {
     })
 }
 
-impl TestCase {
-    fn into_tensile_test(self) -> tensile::Test<Error> {
-        match self {
-            TestCase::Test(name, test) => {
-                let child_thread = test.vm().new_thread().unwrap();
-                let test = TestFn::from_value(&child_thread, test.get_variant());
-                make_tensile_test(name, test)
-            }
-            TestCase::Group(name, tests) => tensile::Test::Group {
-                name,
-                tests: tests.into_iter().map(TestCase::into_tensile_test).collect(),
-            },
+fn into_tensile_test(test: TestCase) -> tensile::Test<Error> {
+    match test {
+        TestCase::Test(name, test) => {
+            let child_thread = test.vm().new_thread().unwrap();
+            let test = TestFn::from_value(&child_thread, test.get_variant());
+            make_tensile_test(name, test)
         }
+        TestCase::Group(name, tests) => tensile::Test::Group {
+            name,
+            tests: tests.into_iter().map(into_tensile_test).collect(),
+        },
     }
 }
 
@@ -185,7 +152,7 @@ async fn make_test<'t>(vm: &'t Thread, name: &str, filename: &Path) -> Result<Te
     let mut file = File::open(&filename)?;
     let mut text = String::new();
     file.read_to_string(&mut text)?;
-    let (De(test), _) = vm.run_expr_async(&name, &text).await?;
+    let (test, _) = vm.run_expr_async::<TestCase>(&name, &text).await?;
     Ok(test)
 }
 
@@ -359,7 +326,7 @@ async fn main_(options: &Opt) -> Result<(), Error> {
             let name2 = name.clone();
             pool.spawn_with_handle(async move {
                 match make_test(&vm, &name, &filename).await {
-                    Ok(test) => test.into_tensile_test(),
+                    Ok(test) => into_tensile_test(test),
                     Err(err) => {
                         let err = ::std::panic::AssertUnwindSafe(err);
                         tensile::test(name2, || Err(err.0))
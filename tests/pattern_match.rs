@@ -15,6 +15,17 @@ match True with
 11i32
 }
 
+test_expr! { prelude match_on_imported_variant,
+r#"
+let { Ordering } = import! std.types
+match GT with
+| LT -> 1
+| EQ -> 2
+| GT -> 3
+"#,
+3i32
+}
+
 #[test]
 fn non_exhaustive_pattern() {
     let _ = ::env_logger::try_init();
@@ -199,3 +210,122 @@ getName ("abc", 123)
 "#,
 "abc".to_string()
 }
+
+test_expr! { match_guard,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some y if y #Int< 0 -> 0
+    | Some y if y #Int< 10 -> y
+    | Some y -> 10
+    | None -> 11
+f (Some (0 #Int- 5)) #Int+ f (Some 3) #Int* 100 #Int+ f (Some 20) #Int* 10000 #Int+ f None #Int* 1000000
+"#,
+11100300
+}
+
+test_expr! { match_guard_falls_through_to_variable,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some y if y #Int== 1 -> 10
+    | z -> 20
+f (Some 1) #Int+ f (Some 2) #Int+ f None
+"#,
+50
+}
+
+test_expr! { match_guard_on_nested_and_literal_patterns,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | (Some 1, y) if 5 #Int< y -> y
+    | (Some a, 2) if 5 #Int< a -> a
+    | (Some a, b) -> 1
+    | (None, b) -> b
+f (Some 1, 6) #Int+ f (Some 7, 2) #Int* 10 #Int+ f (Some 1, 2) #Int* 100 #Int+ f (None, 4) #Int* 1000
+"#,
+4176
+}
+
+#[test]
+fn match_guard_without_matching_alternative() {
+    let _ = ::env_logger::try_init();
+    let text = r"
+match 1 with
+| x if x #Int== 0 -> True
+";
+    let vm = make_vm();
+    let result = vm.run_expr::<bool>("<top>", text);
+    assert!(result.is_err());
+}
+
+test_expr! { match_array_pattern,
+r#"
+let f xs : Array Int -> Int =
+    match xs with
+    | [] -> 0
+    | [x] -> x
+    | [x, y] -> x #Int+ y
+    | [x, ..rest] -> x #Int* 100
+f [] #Int+ f [1] #Int+ f [2, 3] #Int* 10 #Int+ f [4, 5, 6] #Int* 1000
+"#,
+400051
+}
+
+test_expr! { match_array_rest_pattern,
+r#"
+let array = import! std.array.prim
+type Option a = | None | Some a
+let f xs : Array (Option Int) -> Int =
+    match xs with
+    | [Some x, None, ..rest] -> x #Int+ array.len rest
+    | [_, ..rest] -> 100 #Int* array.len rest
+    | [..] -> 0
+f [Some 1, None, None, None] #Int+ f [None, None] #Int* 10 #Int+ f []
+"#,
+1003
+}
+
+test_expr! { match_or_pattern,
+r#"
+type Option a = | None | Some a
+type Either e a = | Left e | Right a
+let f x =
+    match x with
+    | (Left y, _) | (_, Some y) if y #Int< 10 -> y
+    | (Right 1, None) | (Left _, None) -> 20
+    | _ -> 30
+f (Left 1, None) #Int+ f (Right 2, Some 3) #Int* 10 #Int+ f (Left 11, Some 4) #Int* 100 #Int+ f (Right 1, None) #Int* 1000 #Int+ f (Right 2, Some 15) #Int* 10000
+"#,
+320431
+}
+
+test_expr! { match_nested_or_pattern,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some (1 | 2) -> 1
+    | Some _ | None -> 0
+f (Some 1) #Int+ f (Some 2) #Int+ f (Some 3) #Int+ f None
+"#,
+2
+}
+
+test_expr! { match_nested_or_pattern_with_bindings,
+r#"
+type Option a = | None | Some a
+type Either e a = | Left e | Right a
+let f x =
+    match x with
+    | (Some (Left y | Right y), Some (Left z | Right z)) if y #Int< z -> y #Int* 10 #Int+ z
+    | (Some (Left y | Right y), _) | (None, Some (Left y | Right y)) -> y
+    | _ -> 0
+f (Some (Left 1), Some (Right 2)) #Int+ f (Some (Right 3), Some (Left 2)) #Int* 100 #Int+ f (None, Some (Right 4)) #Int* 1000 #Int+ f (None, None)
+"#,
+4312
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use gluon::{
+    vm::{
+        profiler::Profile,
+        thread::{HookFlags, ThreadInternal},
+    },
+    RootedThread, ThreadExt,
+};
+
+const PROGRAM: &str = r#"
+let add x y = x #Int+ y
+let replicate x = [x, x, x]
+let sum n acc : Int -> Int -> Int =
+    if n #Int== 0 then
+        acc
+    else
+        let _ = replicate n
+        sum (n #Int- 1) (add acc n)
+sum 100 0
+"#;
+
+fn new_vm() -> RootedThread {
+    let thread = gluon::new_vm();
+    {
+        let mut db = thread.get_database_mut();
+        db.set_optimize(false);
+        db.set_implicit_prelude(false);
+    }
+    thread
+}
+
+fn profile(thread: &RootedThread) -> Profile {
+    let profiler = thread.start_profiling();
+    let (value, _) = thread
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 5050);
+    profiler.finish()
+}
+
+#[test]
+fn call_counts() {
+    let _ = env_logger::try_init();
+
+    let profile = profile(&new_vm());
+
+    assert_eq!(profile.function("add").map(|f| f.calls), Some(100));
+    assert_eq!(profile.function("replicate").map(|f| f.calls), Some(100));
+    assert_eq!(profile.function("sum").map(|f| f.calls), Some(101));
+}
+
+#[test]
+fn time_and_allocations() {
+    let _ = env_logger::try_init();
+
+    let profile = profile(&new_vm());
+
+    let sum = profile.function("sum").unwrap();
+    let add = profile.function("add").unwrap();
+    assert!(sum.inclusive_time >= sum.exclusive_time);
+    assert!(sum.inclusive_time >= add.inclusive_time);
+    assert!(profile.total_time() >= sum.inclusive_time);
+
+    let replicate = profile.function("replicate").unwrap();
+    assert!(replicate.allocated_bytes > 0);
+    assert!(profile.total_allocated_bytes() >= replicate.allocated_bytes);
+}
+
+#[test]
+fn folded_stacks_and_summary() {
+    let _ = env_logger::try_init();
+
+    let profile = profile(&new_vm());
+
+    assert!(profile
+        .stacks()
+        .iter()
+        .any(|(stack, _)| stack.ends_with(&["sum".to_string(), "add".to_string()])));
+
+    let mut folded = Vec::new();
+    profile.write_folded(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    for line in folded.lines() {
+        let (stack, time) = line.split_at(line.rfind(' ').unwrap());
+        assert!(!stack.is_empty(), "{}", line);
+        assert!(time.trim().parse::<u64>().is_ok(), "{}", line);
+    }
+
+    let summary = profile.to_string();
+    assert!(summary.contains("Exclusive (ms)"), "{}", summary);
+    assert!(summary.contains("  replicate\n"), "{}", summary);
+}
+
+#[test]
+fn finish_restores_the_previous_hook() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    thread.context().set_hook_mask(HookFlags::LINE_FLAG);
+
+    profile(&thread);
+
+    assert_eq!(thread.context().hook_mask(), HookFlags::LINE_FLAG);
+}
+
+#[test]
+fn dropping_the_profiler_restores_the_previous_hook() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    thread.context().set_hook_mask(HookFlags::LINE_FLAG);
+
+    let profiler = thread.start_profiling();
+    assert_eq!(
+        thread.context().hook_mask(),
+        HookFlags::CALL_FLAG | HookFlags::LINE_FLAG
+    );
+    drop(profiler);
+
+    assert_eq!(thread.context().hook_mask(), HookFlags::LINE_FLAG);
+}
+
+#[test]
+fn sampling() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let profiler = thread.start_sampling_profiler(Duration::from_micros(10));
+    let (value, _) = thread
+        .run_expr::<i32>("test", &PROGRAM.replace("sum 100 0", "sum 20000 0"))
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 200010000);
+    let profile = profiler.finish();
+
+    assert!(!profile.stacks().is_empty());
+    assert!(profile
+        .functions()
+        .iter()
+        .all(|function| function.calls == 0));
+    assert!(profile.total_time() > Duration::from_secs(0));
+}
@@ -0,0 +1,221 @@
+use std::fs;
+
+use gluon::{
+    vm::{
+        api::{Hole, OpaqueValue, IO},
+        sandbox::{Capability, Sandbox},
+        Error as VMError,
+    },
+    Error, RootedThread, Thread, ThreadExt, VmBuilder,
+};
+
+fn sandboxed_vm(sandbox: Sandbox) -> RootedThread {
+    let vm = VmBuilder::new().sandbox(Some(sandbox)).build();
+    vm.get_database_mut().run_io(true);
+    vm
+}
+
+/// Runs an `IO String` action, returning the exception it throws as an error
+fn run_io_string(vm: &Thread, expr: &str) -> Result<String, String> {
+    match vm.run_expr::<IO<String>>("test", expr) {
+        Ok((IO::Value(value), _)) => Ok(value),
+        Ok((IO::Exception(err), _)) => Err(err),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[test]
+fn forbidden_import_fails_at_compile_time() {
+    let _ = ::env_logger::try_init();
+
+    let vm = sandboxed_vm(Sandbox::new());
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", "import! std.process");
+    match result {
+        Err(err) => {
+            let message = err.to_string();
+            assert!(
+                message.contains(
+                    "Module `std.process` requires the `process` capability which is not allowed \
+                     by the sandbox"
+                ),
+                "{}",
+                message
+            );
+        }
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn forbidden_import_fails_every_time() {
+    let _ = ::env_logger::try_init();
+
+    let vm = sandboxed_vm(Sandbox::new());
+    for _ in 0..2 {
+        let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", "import! std.process");
+        match result {
+            Err(err) => {
+                let message = err.to_string();
+                assert!(
+                    message.contains("not allowed by the sandbox"),
+                    "{}",
+                    message
+                );
+            }
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+}
+
+#[test]
+fn forbidden_submodule_import_fails_at_compile_time() {
+    let _ = ::env_logger::try_init();
+
+    let vm = sandboxed_vm(Sandbox::new());
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", "import! std.fs.prim");
+    match result {
+        Err(err) => {
+            let message = err.to_string();
+            assert!(
+                message.contains(
+                    "Module `std.fs.prim` requires the `fs` capability which is not allowed by \
+                     the sandbox"
+                ),
+                "{}",
+                message
+            );
+        }
+        Ok(_) => panic!("Expected an error"),
+    }
+
+    // `std.path` does not need the `fs` capability to be imported
+    vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", "import! std.path")
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[test]
+fn allowed_import() {
+    let _ = ::env_logger::try_init();
+
+    let vm = sandboxed_vm(Sandbox::new().allow(Capability::Env));
+    vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", "import! std.env")
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[test]
+fn file_access_is_restricted_to_the_roots() {
+    let _ = ::env_logger::try_init();
+
+    let root = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    fs::write(root.path().join("inside.txt"), "inside").unwrap();
+    fs::write(outside.path().join("outside.txt"), "outside").unwrap();
+
+    let vm = sandboxed_vm(
+        Sandbox::new()
+            .allow(Capability::FileIo)
+            .fs_root(root.path()),
+    );
+
+    let read = |path: &std::path::Path| {
+        run_io_string(
+            &vm,
+            &format!(
+                "let io = import! std.io in io.read_file_to_string {:?}",
+                path.display().to_string()
+            ),
+        )
+    };
+
+    match read(&root.path().join("inside.txt")) {
+        Ok(contents) => assert_eq!(contents, "inside"),
+        Err(err) => panic!("{}", err),
+    }
+    match read(&outside.path().join("outside.txt")) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => assert!(err.contains("not allowed by the sandbox"), "{}", err),
+    }
+    match read(&root.path().join("..").join("outside.txt")) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => assert!(err.contains("not allowed by the sandbox"), "{}", err),
+    }
+}
+
+#[test]
+fn run_expr_needs_eval() {
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let io = import! std.io
+        let { flat_map } = io.monad
+        do result = io.run_expr "123"
+        io.applicative.wrap result.value
+    "#;
+
+    let vm = sandboxed_vm(Sandbox::new());
+    match run_io_string(&vm, expr) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => assert!(
+            err.contains("The `eval` capability is not allowed by the sandbox"),
+            "{}",
+            err
+        ),
+    }
+
+    let vm = sandboxed_vm(Sandbox::new().allow(Capability::Eval));
+    match run_io_string(&vm, expr) {
+        Ok(value) => assert_eq!(value, "123"),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[test]
+fn sandbox_with_memory_limit() {
+    let _ = ::env_logger::try_init();
+
+    let vm = VmBuilder::new()
+        .sandbox(Some(Sandbox::new()))
+        .memory_limit(Some(10))
+        .build();
+    vm.get_database_mut().implicit_prelude(false);
+
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("test", " [1, 2, 3, 4] ");
+    match result {
+        Err(Error::VM(VMError::OutOfMemory { limit: 10, .. })) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn path_queries_outside_the_roots_return_false() {
+    let _ = ::env_logger::try_init();
+
+    let root = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    fs::write(root.path().join("inside.txt"), "inside").unwrap();
+    fs::write(outside.path().join("outside.txt"), "outside").unwrap();
+
+    let vm = sandboxed_vm(Sandbox::new().allow(Capability::Fs).fs_root(root.path()));
+
+    let query = |function: &str, path: &std::path::Path| {
+        let expr = format!(
+            "let path = import! std.path in path.{} {:?}",
+            function,
+            path.display().to_string()
+        );
+        match vm.run_expr::<IO<bool>>("test", &expr) {
+            Ok((IO::Value(value), _)) => value,
+            Ok((IO::Exception(err), _)) => panic!("{}", err),
+            Err(err) => panic!("{}", err),
+        }
+    };
+
+    assert!(query("exists", &root.path().join("inside.txt")));
+    assert!(query("is_file", &root.path().join("inside.txt")));
+    assert!(query("is_dir", root.path()));
+
+    assert!(!query("exists", &outside.path().join("outside.txt")));
+    assert!(!query("is_file", &outside.path().join("outside.txt")));
+    assert!(!query("is_dir", outside.path()));
+}
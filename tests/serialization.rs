@@ -1,7 +1,11 @@
 #![cfg(feature = "serialization")]
 extern crate serde_state as serde;
 
-use std::{fs::File, io::Read};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use crate::serde::ser::SerializeState;
 
@@ -13,7 +17,7 @@ use gluon::{
         thread::{RootedThread, RootedValue, Thread},
         Variants,
     },
-    ThreadExt,
+    ThreadExt, VmBuilder,
 };
 
 fn serialize_value(value: Variants) {
@@ -282,3 +286,62 @@ fn issue_805_no_deadlock_in_deserialize() {
         .to_string()
         .contains("is not defined"));
 }
+
+fn new_cached_vm(cache_dir: &Path, import_paths: Vec<PathBuf>) -> RootedThread {
+    VmBuilder::new()
+        .import_paths(Some(import_paths))
+        .module_cache_dir(Some(cache_dir.into()))
+        .build()
+}
+
+#[test]
+fn module_cache() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let list = import! std.list
+let { List } = list
+
+let eq_list: Eq (List Int) = list.eq
+in Cons 1 Nil == Cons 1 Nil
+"#;
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    let vm = new_cached_vm(cache_dir.path(), vec![".".into()]);
+    let (result, _) = vm
+        .run_expr::<bool>("test", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(result);
+    assert!(cache_dir.path().join("std.list.json").exists());
+
+    // The second vm loads `std.list` and its dependencies from the cache
+    let vm = new_cached_vm(cache_dir.path(), vec![".".into()]);
+    let (result, _) = vm
+        .run_expr::<bool>("test", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(result);
+}
+
+#[test]
+fn module_cache_is_invalidated_when_the_source_changes() {
+    let _ = env_logger::try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let module_path = dir.path().join("cached_module.glu");
+    let text = "let m = import! cached_module in m.value";
+
+    fs::write(&module_path, "{ value = 1 }").unwrap();
+    let vm = new_cached_vm(&cache_dir, vec![dir.path().into()]);
+    let (result, _) = vm
+        .run_expr::<i32>("test", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 1);
+
+    fs::write(&module_path, "{ value = 2 }").unwrap();
+    let vm = new_cached_vm(&cache_dir, vec![dir.path().into()]);
+    let (result, _) = vm
+        .run_expr::<i32>("test", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 2);
+}
@@ -15,3 +15,22 @@ fn macro_error_with_line_column_info() {
 "#
     );
 }
+
+#[test]
+fn unused_import_warning() {
+    let thread = new_vm();
+    thread
+        .load_script("test", "let string = import! std.string\n1")
+        .unwrap_or_else(|err| panic!("{}", err));
+    let warnings = thread.module_warnings("test").expect("Expected a warning");
+    assert_eq!(
+        warnings.emit_string().unwrap(),
+        r#"warning[unused_imports]: Unused import `string`
+- <test>:1:5
+  |
+1 | let string = import! std.string
+  |     ^^^^^^
+  |
+"#
+    );
+}
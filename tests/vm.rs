@@ -12,7 +12,7 @@ use gluon::{
     base::{pos::BytePos, types::Type},
     vm,
     vm::{
-        api::{FunctionRef, Hole, OpaqueValue, ValueRef, IO},
+        api::{FunctionRef, Hole, Int32, Int8, OpaqueValue, UInt16, ValueRef, IO},
         channel::Sender,
         thread::{RootedThread, Thread, ThreadInternal},
     },
@@ -132,6 +132,99 @@ in add { x = 10, y = 5 } { x = 1, y = 2 }
         _ => panic!(),
     }
 }
+
+#[test]
+fn user_defined_macro() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let { Expr } = import! std.macro
+let { Result } = import! std.result
+
+let twice args : Array Expr -> Result String Expr =
+    match args with
+    | [e] -> Ok (quote!(unquote!(e) #Int+ unquote!(e)))
+    | _ -> Err "`twice!` expects 1 argument"
+
+let flip args : Array Expr -> Result String Expr =
+    match args with
+    | [App f [x, y]] -> Ok (App f [y, x])
+    | _ -> Err "`flip!` expects an application with 2 arguments"
+
+let helper x : Int -> Int = x
+{ twice, flip, helper }
+"#;
+    let vm = make_vm();
+    load_script(&vm, "test_macros", text).unwrap_or_else(|err| panic!("{}", err));
+
+    let script = r#"
+let { helper } = import_macros! test_macros
+let sub l r = l #Int- r
+let x = twice!(let y = 3 in y #Int* 2)
+// Type bindings are passed to the macro as `Opaque` expressions
+let z = flip!(sub 1 (type T = Int in 10))
+helper x #Int+ z
+"#;
+    let value = run_expr::<i32>(&vm, script);
+    assert_eq!(value, 21);
+}
+
+#[test]
+fn quote_does_not_capture_spliced_names() {
+    let _ = ::env_logger::try_init();
+    // `App` and `String` are also constructors of the types in `std.macro.types`
+    let text = r#"
+let macro = import! std.macro
+let { Result } = import! std.result
+
+type Wrapped = | App macro.Expr | String macro.Expr
+
+let unwrap w : Wrapped -> macro.Expr =
+    match w with
+    | App e -> e
+    | String e -> e
+
+let twice args : Array macro.Expr -> Result String macro.Expr =
+    match args with
+    | [e] -> Ok (quote!(unquote!(unwrap (App e)) #Int+ unquote!(unwrap (String e))))
+    | _ -> Err "`twice!` expects 1 argument"
+
+{ twice }
+"#;
+    let vm = make_vm();
+    load_script(&vm, "test_macros", text).unwrap_or_else(|err| panic!("{}", err));
+
+    let script = r#"
+let _ = import_macros! test_macros
+twice!(1 #Int+ 2)
+"#;
+    let value = run_expr::<i32>(&vm, script);
+    assert_eq!(value, 6);
+}
+
+#[test]
+fn user_defined_macro_error() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let { Expr } = import! std.macro
+let { Result } = import! std.result
+
+let fail args : Array Expr -> Result String Expr = Err "Macro failed"
+{ fail }
+"#;
+    let vm = make_vm();
+    load_script(&vm, "test_macros", text).unwrap_or_else(|err| panic!("{}", err));
+
+    let result = vm.run_expr::<i32>(
+        "test",
+        r#"
+let _ = import_macros! test_macros
+fail!(1)
+"#,
+    );
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("Macro failed"), "{}", err);
+}
+
 #[test]
 fn adt() {
     let _ = ::env_logger::try_init();
@@ -215,6 +308,48 @@ r"
 false
 }
 
+test_expr! { prelude sized_int_arithmetic,
+r"
+let { ? } = import! std.int32
+1000i32 * 3i32 - 7i32
+",
+Int32(2993)
+}
+
+test_expr! { sized_int_overflow_variants,
+r"
+let uint16 = import! std.uint16
+(uint16.wrapping_add 65535u16 2u16, uint16.saturating_add 65535u16 2u16, uint16.checked_add 65535u16 2u16)
+",
+(UInt16(1), UInt16(65535), None::<UInt16>)
+}
+
+test_expr! { sized_int_literal_pattern,
+r"
+match 18446744073709551615u64 with
+| 18446744073709551615u64 -> 1
+| _ -> 2
+",
+1i32
+}
+
+#[test]
+fn sized_int_overflow_panics() {
+    let _ = ::env_logger::try_init();
+    let text = r"
+let { ? } = import! std.int8
+127i8 + 1i8
+";
+    let vm = make_vm();
+    let result = vm.run_expr::<Int8>("<top>", text);
+    match result {
+        Err(Error::VM(vm::Error::Panic(err, Some(_)))) => {
+            assert_eq!(err, "attempt to add with overflow")
+        }
+        _ => panic!("Expected an overflow panic, got {:?}", result),
+    }
+}
+
 test_expr! { prelude overloaded_compare_int,
 r"
 99 < 100
@@ -432,6 +567,43 @@ r#"
 String::from("x")
 }
 
+test_expr! { prelude interpolated_string,
+r#"
+let x = 3
+f"total: ${x} items, ${1.5 + 1.0}${ { a = 2 }.a }\${x}"
+"#,
+String::from("total: 3 items, 2.52${x}")
+}
+
+test_expr! { interpolated_string_without_prelude,
+r#"
+let { ? } = import! std.int
+let { ? } = import! std.float
+f"${1} and ${2.5}"
+"#,
+String::from("1 and 2.5")
+}
+
+test_expr! { prelude interpolated_string_with_string_parts,
+r#"
+let name = "gluon"
+let greeting = "Hello"
+f"${greeting} ${name}! ${'x'}"
+"#,
+String::from("Hello gluon! 'x'")
+}
+
+test_expr! { prelude interpolated_string_ignores_local_show_and_append,
+r#"
+let show x : Int -> String = "hijacked"
+#[infix(left, 4)]
+let (++) x y : String -> String -> String = x
+let x = 3
+f"x = ${x}!"
+"#,
+String::from("x = 3!")
+}
+
 test_expr! { load_simple,
 r#"
 let _ = import! std.foldable
@@ -693,12 +865,113 @@ int.max_value * 2
     let vm = make_vm();
     let result = vm.run_expr::<i32>("<top>", text);
     match result {
-        Err(Error::VM(vm::Error::Message(ref err))) if err.contains("overflow") => (),
+        Err(Error::VM(vm::Error::Panic(ref err, Some(_)))) => {
+            assert_eq!(err, "attempt to multiply with overflow")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn constant_arithmetic_overflow_panics() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+9223372036854775807 + 1
+"#;
+    let vm = make_vm();
+    let result = vm.run_expr::<i64>("<top>", text);
+    match result {
+        Err(Error::VM(vm::Error::Panic(ref err, Some(_)))) => {
+            assert_eq!(err, "attempt to add with overflow")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn division_by_zero_panics_with_stacktrace() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let f x =
+    10 / x
+f 0
+"#;
+    let vm = make_vm();
+    let result = vm.run_expr::<i64>("<top>", text);
+    match result {
+        Err(Error::VM(vm::Error::Panic(ref err, Some(ref trace)))) => {
+            assert_eq!(err, "attempt to divide by zero");
+            let frame = trace.frames.last().cloned().flatten();
+            assert!(
+                frame.map_or(false, |frame| frame.line.is_some()),
+                "Expected the stacktrace to point at the division: {:?}",
+                trace
+            );
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let int = import! std.int
+(int.max_value + 1, 9223372036854775807 * 2)
+"#;
+    let vm = make_vm();
+    vm.get_database_mut().set_wrapping_arithmetic(true);
+    let (result, _) = vm
+        .run_expr::<(i64, i64)>("<top>", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, (i64::min_value(), -2));
+
+    let result = vm.run_expr::<i64>("<top>", "1 / 0");
+    match result {
+        Err(Error::VM(vm::Error::Panic(ref err, Some(_)))) => {
+            assert_eq!(err, "attempt to divide by zero")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn wrapping_byte_arithmetic() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+((#Byte+) 255b 2b, (#Byte-) 0b 1b, (#Byte*) 16b 17b)
+"#;
+    let vm = make_vm();
+    vm.get_database_mut().set_wrapping_arithmetic(true);
+    let (result, _) = vm
+        .run_expr::<(u8, u8, u8)>("<top>", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, (1, 255, 16));
+
+    let result = vm.run_expr::<u8>("<top>", "(#Byte/) 1b 0b");
+    match result {
+        Err(Error::VM(vm::Error::Panic(ref err, Some(_)))) => {
+            assert_eq!(err, "attempt to divide by zero")
+        }
         Err(err) => panic!("Unexpected error `{}`", err),
         Ok(_) => panic!("Expected an error"),
     }
 }
 
+test_expr! { prelude checked_int_arithmetic,
+r#"
+let int = import! std.int
+match (int.checked_add int.max_value 1, int.checked_sub 3 1, int.checked_mul 2 3, int.checked_div 1 0) with
+| (None, Some 2, Some 6, None) -> True
+| _ -> False
+"#,
+true
+}
+
 #[test]
 fn partially_applied_constructor_is_lambda() {
     let _ = ::env_logger::try_init();
@@ -4,9 +4,10 @@ use std::{
     borrow::Borrow,
     cell::Ref,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, HashSet},
     ffi::{OsStr, OsString},
     fmt,
+    hash::{BuildHasher, Hash},
     marker::PhantomData,
     ops::Deref,
     path::{Path, PathBuf},
@@ -885,7 +886,64 @@ macro_rules! int_impls {
     };
 }
 
-int_impls! { i16 i32 i64 u16 u32 u64 usize isize }
+int_impls! { i8 i16 i32 i64 u16 u32 u64 usize isize }
+
+macro_rules! sized_int_impls {
+    ($($(#[$attr: meta])* $name: ident($id: ident),)*) => {
+        $(
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub $id);
+
+        impl VmType for $name {
+            type Type = Self;
+        }
+        impl<'vm> Pushable<'vm> for $name {
+            #[inline]
+            fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+                context.push(ValueRepr::Int(self.0 as VmInt));
+                Ok(())
+            }
+        }
+        impl<'vm, 'value> Getable<'vm, 'value> for $name {
+            impl_getable_simple!();
+
+            #[inline]
+            fn from_value(_: &'vm Thread, value: Variants<'value>) -> Self {
+                match value.as_ref() {
+                    ValueRef::Int(i) => $name(i as $id),
+                    _ => ice!("expected ValueRef to be an Int, got {:?}", value.as_ref()),
+                }
+            }
+        }
+        impl From<$id> for $name {
+            fn from(value: $id) -> Self {
+                $name(value)
+            }
+        }
+        impl From<$name> for $id {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+        )*
+    };
+}
+
+sized_int_impls! {
+    /// Marshals an `i8` as gluon's `Int8` type instead of `Int`
+    Int8(i8),
+    /// Marshals an `i16` as gluon's `Int16` type instead of `Int`
+    Int16(i16),
+    /// Marshals an `i32` as gluon's `Int32` type instead of `Int`
+    Int32(i32),
+    /// Marshals a `u16` as gluon's `UInt16` type instead of `Int`
+    UInt16(u16),
+    /// Marshals a `u32` as gluon's `UInt32` type instead of `Int`
+    UInt32(u32),
+    /// Marshals a `u64` as gluon's `UInt64` type instead of `Int`
+    UInt64(u64),
+}
 
 impl VmType for f64 {
     type Type = Self;
@@ -1351,6 +1409,207 @@ where
     }
 }
 
+impl<K, V, S> VmType for HashMap<K, V, S>
+where
+    K: VmType,
+    K::Type: Sized,
+    V: VmType,
+    V::Type: Sized,
+{
+    type Type = HashMap<K::Type, V::Type>;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        let map_alias = vm
+            .find_type_info("std.hash_map.HashMap")
+            .unwrap()
+            .clone()
+            .into_type();
+        Type::app(map_alias, collect![K::make_type(vm), V::make_type(vm)])
+    }
+}
+
+/// A type which can be used as the key of a `std.hash_map.HashMap` or the element of a
+/// `std.hash_set.HashSet` pushed from Rust. Entries are inserted with the `std.hash.Hash` and
+/// `std.cmp.Eq` instances stored in the globals `HASH` and `EQ`, which must already be loaded.
+pub trait HashKey: for<'vm> Pushable<'vm> + VmType + Hash + Eq {
+    /// The name of the global containing the `Hash` instance of the type
+    const HASH: &'static str;
+    /// The name of the global containing the `Eq` instance of the type
+    const EQ: &'static str;
+}
+
+macro_rules! hash_keys {
+    ($($typ: ty => $hash: expr, $eq: expr;)*) => {
+        $(
+            impl HashKey for $typ {
+                const HASH: &'static str = $hash;
+                const EQ: &'static str = $eq;
+            }
+        )*
+    };
+}
+
+hash_keys! {
+    String => "std.hash.string", "std.string.eq";
+    VmInt => "std.hash.int", "std.int.eq";
+    u8 => "std.hash.byte", "std.byte.eq";
+    char => "std.hash.char", "std.char.eq";
+    bool => "std.hash.bool", "std.bool.eq";
+}
+
+impl<'s> HashKey for &'s str {
+    const HASH: &'static str = "std.hash.string";
+    const EQ: &'static str = "std.string.eq";
+}
+
+impl<'vm, K, V, S> Pushable<'vm> for HashMap<K, V, S>
+where
+    K: HashKey,
+    K::Type: Sized,
+    V: for<'vm2> Pushable<'vm2> + VmType,
+    V::Type: Sized,
+{
+    fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+        to_gluon_hash_map(self, context)
+    }
+}
+
+/// Builds a `std.hash_map.HashMap` by inserting each entry with `std.hash_map.insert_with`
+fn to_gluon_hash_map<'vm, K, V>(
+    entries: impl IntoIterator<Item = (K, V)>,
+    context: &mut ActiveThread<'vm>,
+) -> Result<()>
+where
+    K: HashKey,
+    K::Type: Sized,
+    V: for<'vm2> Pushable<'vm2> + VmType,
+    V::Type: Sized,
+{
+    type Instance = OpaqueValue<RootedThread, Hole>;
+    type Map<K2, V2> = OpaqueValue<RootedThread, HashMap<K2, V2>>;
+
+    let thread = context.thread();
+    let hash: Instance = thread.get_global(K::HASH)?;
+    let eq: Instance = thread.get_global(K::EQ)?;
+    let mut map: Map<K, V> = thread.get_global("std.hash_map.empty")?;
+    let mut insert: OwnedFunction<fn(Instance, Instance, K, V, Map<K, V>) -> Map<K, V>> =
+        thread.get_global("std.hash_map.insert_with")?;
+
+    context.drop();
+    for (key, value) in entries {
+        map = insert.call(hash.clone(), eq.clone(), key, value, map)?;
+    }
+    context.restore();
+
+    map.push(context)
+}
+
+impl<'vm, 'value, K, V, S> Getable<'vm, 'value> for HashMap<K, V, S>
+where
+    K: Getable<'vm, 'value> + Eq + Hash,
+    V: Getable<'vm, 'value>,
+    S: BuildHasher + Default,
+{
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        let mut map = HashMap::default();
+        for_each_hash_map_entry(vm, value, &mut |key, value| {
+            map.insert(K::from_value(vm, key), V::from_value(vm, value));
+        });
+        map
+    }
+}
+
+impl<T, S> VmType for HashSet<T, S>
+where
+    T: VmType,
+    T::Type: Sized,
+{
+    type Type = HashSet<T::Type>;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        let set_alias = vm
+            .find_type_info("std.hash_set.HashSet")
+            .unwrap()
+            .clone()
+            .into_type();
+        Type::app(set_alias, collect![T::make_type(vm)])
+    }
+}
+
+impl<'vm, T, S> Pushable<'vm> for HashSet<T, S>
+where
+    T: HashKey,
+    T::Type: Sized,
+{
+    fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+        to_gluon_hash_map(self.into_iter().map(|key| (key, ())), context)?;
+        context.context().push_new_data(0, 1)?;
+        Ok(())
+    }
+}
+
+impl<'vm, 'value, T, S> Getable<'vm, 'value> for HashSet<T, S>
+where
+    T: Getable<'vm, 'value> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        let mut set = HashSet::default();
+        match value.as_ref() {
+            ValueRef::Data(data) => {
+                let map = data.get_variant(0).expect("map");
+                for_each_hash_map_entry(vm, map, &mut |key, _| {
+                    set.insert(T::from_value(vm, key));
+                });
+            }
+            _ => ice!("ValueRef is not a HashSet"),
+        }
+        set
+    }
+}
+
+/// Calls `f` with the key and value of every entry in the `std.hash_map.HashMap` in `value`
+fn for_each_hash_map_entry<'value>(
+    vm: &Thread,
+    value: Variants<'value>,
+    f: &mut dyn FnMut(Variants<'value>, Variants<'value>),
+) {
+    match value.as_ref() {
+        ValueRef::Data(data) => match data.tag() {
+            // Leaf hash (Array { key, value })
+            1 => match data.get_variant(1).expect("entries").as_ref() {
+                ValueRef::Array(entries) => {
+                    for entry in entries.as_ref().iter() {
+                        match entry.as_ref() {
+                            ValueRef::Data(entry) => f(
+                                entry.lookup_field(vm, "key").expect("key"),
+                                entry.lookup_field(vm, "value").expect("value"),
+                            ),
+                            _ => ice!("ValueRef is not a HashMap entry"),
+                        }
+                    }
+                }
+                _ => ice!("ValueRef is not an Array"),
+            },
+            // Branch bitmap (Array (HashMap k v))
+            2 => match data.get_variant(1).expect("children").as_ref() {
+                ValueRef::Array(children) => {
+                    for child in children.as_ref().iter() {
+                        for_each_hash_map_entry(vm, child, f);
+                    }
+                }
+                _ => ice!("ValueRef is not an Array"),
+            },
+            _ => (),
+        },
+        _ => ice!("ValueRef is not a HashMap"),
+    }
+}
+
 impl<T: VmType> VmType for Option<T>
 where
     T::Type: Sized,
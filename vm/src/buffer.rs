@@ -0,0 +1,424 @@
+//! Mutable, growable buffers of values (`std.array.mut`) and bytes (`std.byte.buf`).
+use crate::real_std::{any::Any, fmt, marker::PhantomData, sync::Mutex};
+
+use crate::{
+    api::{
+        generic::A, ActiveThread, Array, Generic, Getable, Pushable, Unrooted, Userdata, VmType,
+        WithVM, IO,
+    },
+    base::types::ArcType,
+    gc::{CloneUnrooted, GcPtr, GcRef, Move, Trace},
+    thread::ThreadInternal,
+    types::VmIndex,
+    value::{Cloner, Value},
+    vm::Thread,
+    ExternModule, Result, Variants,
+};
+
+/// A mutable, growable array of gluon values with O(1) indexing and amortized O(1) `push`.
+#[derive(VmType)]
+#[gluon(gluon_vm)]
+#[gluon(vm_type = "std.array.mut.ArrayBuf")]
+pub struct ArrayBuf<T> {
+    values: Mutex<Vec<Value>>,
+    thread: GcPtr<Thread>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Userdata for ArrayBuf<T>
+where
+    T: Any + Send + Sync,
+{
+    fn deep_clone<'gc>(
+        &self,
+        deep_cloner: &'gc mut Cloner,
+    ) -> Result<GcRef<'gc, Box<dyn Userdata>>> {
+        let values = self.values.lock().unwrap();
+        // SAFETY During the `alloc` call the unrooted values are scanned through the `DataDef`
+        unsafe {
+            let cloned_values = values
+                .iter()
+                .map(|value| Ok(deep_cloner.deep_clone(value)?.unrooted()))
+                .collect::<Result<Vec<_>>>()?;
+            let data: Box<dyn Userdata> = Box::new(ArrayBuf {
+                values: Mutex::new(cloned_values),
+                thread: GcPtr::from_raw(deep_cloner.thread()),
+                _marker: PhantomData::<A>,
+            });
+            deep_cloner.gc().alloc(Move(data))
+        }
+    }
+}
+
+impl<T> fmt::Debug for ArrayBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ArrayBuf({:?})", *self.values.lock().unwrap())
+    }
+}
+
+unsafe impl<T> Trace for ArrayBuf<T> {
+    impl_trace_fields! { self, gc; values }
+}
+
+impl<T> ArrayBuf<T> {
+    /// Returns the number of values in the buffer.
+    pub fn len(&self) -> usize {
+        self.values.lock().unwrap().len()
+    }
+
+    /// Returns `true` if the buffer does not contain any values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Converts the values currently in the buffer into a `Vec`.
+    ///
+    /// Buffers created from gluon are always `ArrayBuf<A>` so the element type `U` is given
+    /// separately.
+    pub fn to_vec<'vm, U>(&self, vm: &'vm Thread) -> Vec<U>
+    where
+        U: for<'value> Getable<'vm, 'value>,
+    {
+        self.values
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|value| U::from_value(vm, Variants::new(value)))
+            .collect()
+    }
+
+    fn store(&self, value: &Value) -> Result<Value> {
+        let value = self.thread.deep_clone_value(&self.thread, value)?;
+        // SAFETY Rooted when stored in the buffer
+        unsafe { Ok(value.get_value().clone_unrooted()) }
+    }
+}
+
+/// Wrapper which pushes a `Vec<T>` to gluon as an `ArrayBuf` instead of as an immutable `Array`.
+pub struct IntoArrayBuf<T>(pub Vec<T>);
+
+impl<T> VmType for IntoArrayBuf<T>
+where
+    T: VmType,
+    ArrayBuf<T>: VmType,
+{
+    type Type = <ArrayBuf<T> as VmType>::Type;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        ArrayBuf::<T>::make_type(vm)
+    }
+}
+
+impl<'vm, T> Pushable<'vm> for IntoArrayBuf<T>
+where
+    T: Pushable<'vm>,
+{
+    fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+        let len = self.0.len() as VmIndex;
+        for value in self.0 {
+            value.push(context)?;
+        }
+        let thread = context.thread();
+        let values = {
+            let mut context = context.context();
+            let stack = &context.stack[context.stack.len() - len..];
+            // SAFETY The values stay rooted on the stack until they are moved into the buffer
+            // which roots them during the allocation
+            let values = stack.iter().map(|value| unsafe { value.clone_unrooted() });
+            let values = values.collect();
+            context.stack.pop_many(len);
+            values
+        };
+        // SAFETY `thread` is the thread which we push the buffer to
+        let buf = unsafe {
+            ArrayBuf {
+                values: Mutex::new(values),
+                thread: GcPtr::from_raw(thread),
+                _marker: PhantomData::<A>,
+            }
+        };
+        buf.push(context)
+    }
+}
+
+fn unrooted(value: &Value) -> Unrooted<A> {
+    // SAFETY The returned, unrooted value gets pushed immediately to the stack
+    unsafe { Unrooted::from(value.clone_unrooted()) }
+}
+
+fn out_of_range<T>(index: usize, len: usize) -> IO<T> {
+    IO::Exception(format!(
+        "index {} is out of range for buffer of length {}",
+        index, len
+    ))
+}
+
+fn check_slice<T>(start: usize, end: usize, len: usize) -> Option<IO<T>> {
+    if start > end {
+        Some(IO::Exception(format!(
+            "slice index starts at {} but ends at {}",
+            start, end
+        )))
+    } else if end > len {
+        Some(out_of_range(end, len))
+    } else {
+        None
+    }
+}
+
+mod array_buf {
+    use super::*;
+
+    pub(crate) fn new(WithVM { vm, .. }: WithVM<()>) -> IO<ArrayBuf<A>> {
+        // SAFETY `vm` is the thread which we return the buffer to
+        unsafe {
+            IO::Value(ArrayBuf {
+                values: Mutex::new(Vec::new()),
+                thread: GcPtr::from_raw(vm),
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    pub(crate) fn from_array(WithVM { vm, value: array }: WithVM<Array<A>>) -> IO<ArrayBuf<A>> {
+        // SAFETY The values are rooted by `array` until the buffer is allocated and `vm` is the
+        // thread which we return the buffer to
+        unsafe {
+            IO::Value(ArrayBuf {
+                values: Mutex::new(array.get_array().iter().map(|v| v.unrooted()).collect()),
+                thread: GcPtr::from_raw(vm),
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    pub(crate) fn len(buf: &ArrayBuf<A>) -> IO<usize> {
+        IO::Value(buf.len())
+    }
+
+    pub(crate) fn push(buf: &ArrayBuf<A>, value: Generic<A>) -> IO<()> {
+        match buf.store(value.get_value()) {
+            Ok(value) => IO::Value(buf.values.lock().unwrap().push(value)),
+            Err(err) => IO::Exception(err.to_string()),
+        }
+    }
+
+    pub(crate) fn append(buf: &ArrayBuf<A>, array: Array<A>) -> IO<()> {
+        let values = array
+            .get_array()
+            .iter()
+            .map(|value| buf.store(value.get_value()))
+            .collect::<Result<Vec<_>>>();
+        match values {
+            Ok(values) => IO::Value(buf.values.lock().unwrap().extend(values)),
+            Err(err) => IO::Exception(err.to_string()),
+        }
+    }
+
+    pub(crate) fn pop(buf: &ArrayBuf<A>) -> IO<Option<Unrooted<A>>> {
+        // SAFETY The returned, unrooted value gets pushed immediately to the stack
+        IO::Value(buf.values.lock().unwrap().pop().map(Unrooted::from))
+    }
+
+    pub(crate) fn get(buf: &ArrayBuf<A>, index: usize) -> IO<Option<Unrooted<A>>> {
+        IO::Value(buf.values.lock().unwrap().get(index).map(unrooted))
+    }
+
+    pub(crate) fn set(buf: &ArrayBuf<A>, index: usize, value: Generic<A>) -> IO<()> {
+        let value = match buf.store(value.get_value()) {
+            Ok(value) => value,
+            Err(err) => return IO::Exception(err.to_string()),
+        };
+        let mut values = buf.values.lock().unwrap();
+        let len = values.len();
+        match values.get_mut(index) {
+            Some(slot) => {
+                *slot = value;
+                IO::Value(())
+            }
+            None => out_of_range(index, len),
+        }
+    }
+
+    pub(crate) fn slice(buf: &ArrayBuf<A>, start: usize, end: usize) -> IO<Vec<Unrooted<A>>> {
+        let values = buf.values.lock().unwrap();
+        if let Some(err) = check_slice(start, end, values.len()) {
+            return err;
+        }
+        IO::Value(values[start..end].iter().map(unrooted).collect())
+    }
+
+    pub(crate) fn freeze(buf: &ArrayBuf<A>) -> IO<Vec<Unrooted<A>>> {
+        IO::Value(buf.values.lock().unwrap().iter().map(unrooted).collect())
+    }
+
+    pub(crate) fn clear(buf: &ArrayBuf<A>) -> IO<()> {
+        buf.values.lock().unwrap().clear();
+        IO::Value(())
+    }
+}
+
+/// A mutable, growable array of bytes.
+#[derive(Default, VmType)]
+#[gluon(gluon_vm)]
+#[gluon(vm_type = "std.byte.buf.ByteBuf")]
+pub struct ByteBuf(Mutex<Vec<u8>>);
+
+impl Userdata for ByteBuf {
+    fn deep_clone<'gc>(
+        &self,
+        deep_cloner: &'gc mut Cloner,
+    ) -> Result<GcRef<'gc, Box<dyn Userdata>>> {
+        let data: Box<dyn Userdata> = Box::new(ByteBuf::from(self.to_vec()));
+        deep_cloner.gc().alloc(Move(data))
+    }
+}
+
+impl fmt::Debug for ByteBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ByteBuf({:?})", *self.0.lock().unwrap())
+    }
+}
+
+unsafe impl Trace for ByteBuf {
+    impl_trace! { self, _gc, {} }
+}
+
+impl From<Vec<u8>> for ByteBuf {
+    fn from(bytes: Vec<u8>) -> Self {
+        ByteBuf(Mutex::new(bytes))
+    }
+}
+
+impl ByteBuf {
+    /// Returns the number of bytes in the buffer.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    /// Returns `true` if the buffer does not contain any bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies the bytes currently in the buffer into a `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Returns the bytes in the buffer.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0.into_inner().unwrap()
+    }
+}
+
+mod byte_buf {
+    use super::{check_slice, out_of_range, ByteBuf, IO};
+
+    pub(crate) fn new(_: ()) -> IO<ByteBuf> {
+        IO::Value(ByteBuf::default())
+    }
+
+    pub(crate) fn from_array(bytes: &[u8]) -> IO<ByteBuf> {
+        IO::Value(ByteBuf::from(bytes.to_vec()))
+    }
+
+    pub(crate) fn len(buf: &ByteBuf) -> IO<usize> {
+        IO::Value(buf.len())
+    }
+
+    pub(crate) fn push(buf: &ByteBuf, byte: u8) -> IO<()> {
+        buf.0.lock().unwrap().push(byte);
+        IO::Value(())
+    }
+
+    pub(crate) fn append(buf: &ByteBuf, bytes: &[u8]) -> IO<()> {
+        buf.0.lock().unwrap().extend_from_slice(bytes);
+        IO::Value(())
+    }
+
+    pub(crate) fn push_str(buf: &ByteBuf, s: &str) -> IO<()> {
+        buf.0.lock().unwrap().extend_from_slice(s.as_bytes());
+        IO::Value(())
+    }
+
+    pub(crate) fn pop(buf: &ByteBuf) -> IO<Option<u8>> {
+        IO::Value(buf.0.lock().unwrap().pop())
+    }
+
+    pub(crate) fn get(buf: &ByteBuf, index: usize) -> IO<Option<u8>> {
+        IO::Value(buf.0.lock().unwrap().get(index).cloned())
+    }
+
+    pub(crate) fn set(buf: &ByteBuf, index: usize, byte: u8) -> IO<()> {
+        let mut bytes = buf.0.lock().unwrap();
+        let len = bytes.len();
+        match bytes.get_mut(index) {
+            Some(slot) => {
+                *slot = byte;
+                IO::Value(())
+            }
+            None => out_of_range(index, len),
+        }
+    }
+
+    pub(crate) fn slice(buf: &ByteBuf, start: usize, end: usize) -> IO<Vec<u8>> {
+        let bytes = buf.0.lock().unwrap();
+        if let Some(err) = check_slice(start, end, bytes.len()) {
+            return err;
+        }
+        IO::Value(bytes[start..end].to_vec())
+    }
+
+    pub(crate) fn freeze(buf: &ByteBuf) -> IO<Vec<u8>> {
+        IO::Value(buf.to_vec())
+    }
+
+    pub(crate) fn clear(buf: &ByteBuf) -> IO<()> {
+        buf.0.lock().unwrap().clear();
+        IO::Value(())
+    }
+}
+
+pub fn load_array_buf(vm: &Thread) -> Result<ExternModule> {
+    let _ = vm.register_type::<ArrayBuf<A>>("std.array.mut.ArrayBuf", &["a"]);
+    ExternModule::new(
+        vm,
+        record! {
+            type ArrayBuf a => ArrayBuf<A>,
+            new => primitive!(1, "std.array.mut.prim.new", array_buf::new),
+            from_array => primitive!(1, "std.array.mut.prim.from_array", array_buf::from_array),
+            len => primitive!(1, "std.array.mut.prim.len", array_buf::len),
+            push => primitive!(2, "std.array.mut.prim.push", array_buf::push),
+            append => primitive!(2, "std.array.mut.prim.append", array_buf::append),
+            pop => primitive!(1, "std.array.mut.prim.pop", array_buf::pop),
+            get => primitive!(2, "std.array.mut.prim.get", array_buf::get),
+            set => primitive!(3, "std.array.mut.prim.set", array_buf::set),
+            slice => primitive!(3, "std.array.mut.prim.slice", array_buf::slice),
+            freeze => primitive!(1, "std.array.mut.prim.freeze", array_buf::freeze),
+            clear => primitive!(1, "std.array.mut.prim.clear", array_buf::clear),
+        },
+    )
+}
+
+pub fn load_byte_buf(vm: &Thread) -> Result<ExternModule> {
+    let _ = vm.register_type::<ByteBuf>("std.byte.buf.ByteBuf", &[]);
+    ExternModule::new(
+        vm,
+        record! {
+            type ByteBuf => ByteBuf,
+            new => primitive!(1, "std.byte.buf.prim.new", byte_buf::new),
+            from_array => primitive!(1, "std.byte.buf.prim.from_array", byte_buf::from_array),
+            len => primitive!(1, "std.byte.buf.prim.len", byte_buf::len),
+            push => primitive!(2, "std.byte.buf.prim.push", byte_buf::push),
+            append => primitive!(2, "std.byte.buf.prim.append", byte_buf::append),
+            push_str => primitive!(2, "std.byte.buf.prim.push_str", byte_buf::push_str),
+            pop => primitive!(1, "std.byte.buf.prim.pop", byte_buf::pop),
+            get => primitive!(2, "std.byte.buf.prim.get", byte_buf::get),
+            set => primitive!(3, "std.byte.buf.prim.set", byte_buf::set),
+            slice => primitive!(3, "std.byte.buf.prim.slice", byte_buf::slice),
+            freeze => primitive!(1, "std.byte.buf.prim.freeze", byte_buf::freeze),
+            clear => primitive!(1, "std.byte.buf.prim.clear", byte_buf::clear),
+        },
+    )
+}
@@ -1,20 +1,26 @@
 use crate::real_std::{
     any::Any,
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     fmt,
     marker::PhantomData,
+    pin::Pin,
     slice,
-    sync::{Arc, Mutex},
-    time::Duration,
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use futures::{
     future::{self, Either},
     prelude::*,
-    task::Poll,
+    task::{Context, Poll, Waker},
     try_join,
 };
 
+use slab::Slab;
+
 use crate::base::{
     kind::Kind,
     types::{ArcType, KindedIdent, Type},
@@ -35,11 +41,174 @@ use crate::{
     Error, ExternModule, Result as VmResult, Variants,
 };
 
+/// The state shared between the `Sender`s and `Receiver`s of a channel
+struct Channel {
+    values: VecDeque<Value>,
+    /// The maximum number of values which can be buffered, `None` for unbounded channels
+    capacity: Option<usize>,
+    closed: bool,
+    /// Tasks waiting for a value to be sent or for the channel to be closed
+    recv_wakers: WakerSet,
+    /// Tasks waiting for space in a bounded channel or for the channel to be closed
+    send_wakers: WakerSet,
+}
+
+unsafe impl Trace for Channel {
+    impl_trace_fields! { self, gc; values }
+}
+
+impl Channel {
+    fn new(capacity: Option<usize>) -> Self {
+        Channel {
+            values: VecDeque::new(),
+            capacity,
+            closed: false,
+            recv_wakers: WakerSet::default(),
+            send_wakers: WakerSet::default(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.capacity
+            .map_or(false, |capacity| self.values.len() >= capacity)
+    }
+
+    fn push(&mut self, value: Value) {
+        self.values.push_back(value);
+        self.recv_wakers.wake_all();
+    }
+
+    fn pop(&mut self) -> Option<Value> {
+        let value = self.values.pop_front();
+        if value.is_some() {
+            self.send_wakers.wake_all();
+        }
+        value
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.recv_wakers.wake_all();
+        self.send_wakers.wake_all();
+    }
+
+    fn poll_recv(&mut self) -> Poll<Result<Value, RecvError>> {
+        match self.pop() {
+            Some(value) => Poll::Ready(Ok(value)),
+            None if self.closed => Poll::Ready(Err(RecvError::Closed)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+fn recv_wakers(channel: &mut Channel) -> &mut WakerSet {
+    &mut channel.recv_wakers
+}
+
+fn send_wakers(channel: &mut Channel) -> &mut WakerSet {
+    &mut channel.send_wakers
+}
+
+/// The wakers of the tasks waiting on a channel. A waker stays registered until the task stops
+/// waiting (see `Waiter`) so that it is woken by every change of the channel until then.
+#[derive(Default)]
+struct WakerSet(Slab<Waker>);
+
+impl WakerSet {
+    fn register(&mut self, key: &mut Option<usize>, waker: &Waker) {
+        match *key {
+            Some(key) => {
+                if !self.0[key].will_wake(waker) {
+                    self.0[key] = waker.clone();
+                }
+            }
+            None => *key = Some(self.0.insert(waker.clone())),
+        }
+    }
+
+    fn remove(&mut self, key: &mut Option<usize>) {
+        if let Some(key) = key.take() {
+            self.0.remove(key);
+        }
+    }
+
+    fn wake_all(&self) {
+        for (_, waker) in &self.0 {
+            waker.wake_by_ref();
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A task waiting on a channel. The waker of the task is removed from the channel once the task
+/// is done waiting or the `Waiter` is dropped.
+struct Waiter {
+    channel: Arc<Mutex<Channel>>,
+    wakers: fn(&mut Channel) -> &mut WakerSet,
+    key: Option<usize>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl Waiter {
+    fn new(channel: Arc<Mutex<Channel>>, wakers: fn(&mut Channel) -> &mut WakerSet) -> Self {
+        Waiter {
+            channel,
+            wakers,
+            key: None,
+        }
+    }
+
+    /// Polls `f` with the locked channel, registering the current task to be woken if `f` is not
+    /// ready
+    fn poll<T>(&mut self, cx: &mut Context, f: impl FnOnce(&mut Channel) -> Poll<T>) -> Poll<T> {
+        let mut channel = self.channel.lock().unwrap();
+        let poll = f(&mut channel);
+        let wakers = (self.wakers)(&mut channel);
+        match poll {
+            Poll::Ready(_) => wakers.remove(&mut self.key),
+            Poll::Pending => wakers.register(&mut self.key, cx.waker()),
+        }
+        poll
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context) -> Poll<Result<Value, RecvError>> {
+        self.poll(cx, Channel::poll_recv)
+    }
+
+    fn cancel(&mut self) {
+        if self.key.is_some() {
+            if let Ok(mut channel) = self.channel.lock() {
+                (self.wakers)(&mut channel).remove(&mut self.key);
+            }
+        }
+    }
+}
+
+/// The reason a value could not be received from a channel
+#[derive(Clone, Copy, Debug, PartialEq, Pushable, VmType)]
+#[gluon(vm_type = "std.channel.types.RecvError")]
+#[gluon(gluon_vm)]
+pub enum RecvError {
+    /// The channel is closed and all values sent before it was closed have been received
+    Closed,
+    /// No value was sent before the timeout elapsed
+    Timeout,
+}
+
 pub struct Sender<T> {
     // No need to traverse this thread reference as any thread having a reference to this `Sender`
     // would also directly own a reference to the `Thread`
     thread: GcPtr<Thread>,
-    queue: Arc<Mutex<VecDeque<Value>>>,
+    channel: Arc<Mutex<Channel>>,
     _element_type: PhantomData<T>,
 }
 
@@ -50,7 +219,7 @@ where
     T: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", *self.queue.lock().unwrap())
+        write!(f, "{:?}", self.channel.lock().unwrap().values)
     }
 }
 
@@ -61,21 +230,26 @@ unsafe impl<T> Trace for Sender<T> {
     }
 }
 
-impl<T> Sender<T> {
-    fn send(&self, value: &Value) {
-        // SAFETY Rooted when stored in `queue`
-        unsafe {
-            self.queue.lock().unwrap().push_back(value.clone_unrooted());
-        }
+/// Sends `value` unless the channel is closed or full. `value` is cloned into `thread` which is
+/// the thread that owns the channel.
+fn try_send(thread: &Thread, channel: &mut Channel, value: &Value) -> Result<(), ()> {
+    if channel.closed || channel.is_full() {
+        return Err(());
     }
+    let value = thread.deep_clone_value(thread, value).map_err(|_| ())?;
+    // SAFETY Rooted when stored in `values`
+    unsafe {
+        channel.push(value.get_value().clone_unrooted());
+    }
+    Ok(())
 }
 
 unsafe impl<T> Trace for Receiver<T> {
-    impl_trace_fields! { self, gc; queue }
+    impl_trace_fields! { self, gc; channel }
 }
 
 pub struct Receiver<T> {
-    queue: Arc<Mutex<VecDeque<Value>>>,
+    channel: Arc<Mutex<Channel>>,
     _element_type: PhantomData<T>,
 }
 
@@ -86,13 +260,13 @@ where
     T: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", *self.queue.lock().unwrap())
+        write!(f, "{:?}", self.channel.lock().unwrap().values)
     }
 }
 
 impl<T> Receiver<T> {
     fn try_recv(&self) -> Result<Value, ()> {
-        self.queue.lock().unwrap().pop_front().ok_or(())
+        self.channel.lock().unwrap().pop().ok_or(())
     }
 }
 
@@ -143,35 +317,255 @@ where
     }
 }
 
-field_decl! { sender, receiver }
+field_decl! { sender, receiver, index, value }
 
 pub type ChannelRecord<S, R> = record_type!(sender => S, receiver => R);
 
-/// FIXME The dummy `a` argument should not be needed to ensure that the channel can only be used
-/// with a single type
-fn channel(WithVM { vm, .. }: WithVM<Generic<A>>) -> ChannelRecord<Sender<A>, Receiver<A>> {
+type Selected<T> = record_type!(index => VmInt, value => T);
+
+fn new_channel(vm: &Thread, capacity: Option<usize>) -> ChannelRecord<Sender<A>, Receiver<A>> {
     let sender = Sender {
         thread: unsafe { GcPtr::from_raw(vm) },
-        queue: Arc::new(Mutex::new(VecDeque::new())),
+        channel: Arc::new(Mutex::new(Channel::new(capacity))),
         _element_type: PhantomData,
     };
     let receiver = Receiver {
-        queue: sender.queue.clone(),
+        channel: sender.channel.clone(),
         _element_type: PhantomData,
     };
     record_no_decl!(sender => sender, receiver => receiver)
 }
 
+/// FIXME The dummy `a` argument should not be needed to ensure that the channel can only be used
+/// with a single type
+fn channel(WithVM { vm, .. }: WithVM<Generic<A>>) -> ChannelRecord<Sender<A>, Receiver<A>> {
+    new_channel(vm, None)
+}
+
+/// Like `channel` the dummy `a` argument fixes the element type of the channel
+fn bounded_channel(
+    WithVM {
+        vm,
+        value: capacity,
+    }: WithVM<VmInt>,
+    _: Generic<A>,
+) -> RuntimeResult<ChannelRecord<Sender<A>, Receiver<A>>, String> {
+    if capacity < 1 {
+        return RuntimeResult::Panic(format!(
+            "The capacity of a bounded channel must be at least 1, got {}",
+            capacity
+        ));
+    }
+    RuntimeResult::Return(new_channel(vm, Some(capacity as usize)))
+}
+
 fn recv(receiver: &Receiver<A>) -> Result<Unrooted<A>, ()> {
     receiver.try_recv().map_err(|_| ()).map(Unrooted::from)
 }
 
 fn send(sender: &Sender<A>, value: Generic<A>) -> Result<(), ()> {
-    let value = sender
-        .thread
-        .deep_clone_value(&sender.thread, value.get_value())
-        .map_err(|_| ())?;
-    Ok(sender.send(value.get_value()))
+    try_send(
+        &sender.thread,
+        &mut sender.channel.lock().unwrap(),
+        value.get_value(),
+    )
+}
+
+fn send_async(sender: &Sender<A>, value: Generic<A>) -> impl Future<Output = IO<Result<(), ()>>> {
+    let thread = sender.thread.root_thread();
+    let mut waiter = Waiter::new(sender.channel.clone(), send_wakers);
+    future::poll_fn(move |cx| {
+        waiter.poll(cx, |channel| {
+            if !channel.closed && channel.is_full() {
+                return Poll::Pending;
+            }
+            Poll::Ready(IO::Value(try_send(&thread, channel, value.get_value())))
+        })
+    })
+}
+
+fn recv_async(receiver: &Receiver<A>) -> impl Future<Output = IO<Result<Unrooted<A>, RecvError>>> {
+    let mut waiter = Waiter::new(receiver.channel.clone(), recv_wakers);
+    future::poll_fn(move |cx| {
+        waiter
+            .poll_recv(cx)
+            .map(|result| IO::Value(result.map(Unrooted::from)))
+    })
+}
+
+fn recv_timeout(
+    receiver: &Receiver<A>,
+    ms: VmInt,
+) -> impl Future<Output = IO<Result<Unrooted<A>, RecvError>>> {
+    let mut waiter = Waiter::new(receiver.channel.clone(), recv_wakers);
+    let mut timeout = delay(Duration::from_millis(ms.max(0) as u64));
+    future::poll_fn(move |cx| {
+        let timeout = match timeout {
+            Ok(ref mut timeout) => timeout,
+            Err(ref err) => return Poll::Ready(IO::Exception(err.clone())),
+        };
+        if let Poll::Ready(result) = waiter.poll_recv(cx) {
+            return Poll::Ready(IO::Value(result.map(Unrooted::from)));
+        }
+        timeout.poll_unpin(cx).map(|()| {
+            waiter.cancel();
+            IO::Value(Err(RecvError::Timeout))
+        })
+    })
+}
+
+fn select(
+    receivers: Vec<&Receiver<A>>,
+) -> impl Future<Output = IO<Result<Selected<Unrooted<A>>, RecvError>>> {
+    let mut waiters: Vec<_> = receivers
+        .into_iter()
+        .map(|receiver| Waiter::new(receiver.channel.clone(), recv_wakers))
+        .collect();
+    future::poll_fn(move |cx| {
+        let mut all_closed = true;
+        for i in 0..waiters.len() {
+            match waiters[i].poll_recv(cx) {
+                Poll::Ready(Ok(value)) => {
+                    // Stop waiting on the channels which did not receive a value
+                    for waiter in &mut waiters {
+                        waiter.cancel();
+                    }
+                    let selected =
+                        record_no_decl!(index => i as VmInt, value => Unrooted::from(value));
+                    return Poll::Ready(IO::Value(Ok(selected)));
+                }
+                Poll::Ready(Err(_)) => (),
+                Poll::Pending => all_closed = false,
+            }
+        }
+        if all_closed {
+            Poll::Ready(IO::Value(Err(RecvError::Closed)))
+        } else {
+            Poll::Pending
+        }
+    })
+}
+
+fn close(sender: &Sender<A>) -> IO<()> {
+    sender.channel.lock().unwrap().close();
+    IO::Value(())
+}
+
+fn is_closed(receiver: &Receiver<A>) -> IO<bool> {
+    IO::Value(receiver.channel.lock().unwrap().closed)
+}
+
+lazy_static! {
+    static ref TIMER: Result<Arc<Timer>, String> = Timer::start();
+}
+
+/// A timer which completes every `Delay` from a single thread, shared by all vms. The timer does
+/// not use the timer of a specific runtime so that it works regardless of which executor drives
+/// the vm.
+struct Timer {
+    /// The pending delays, ordered by their deadline
+    delays: Mutex<BTreeMap<(Instant, u64), Arc<Mutex<DelayState>>>>,
+    next_id: AtomicU64,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct DelayState {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+impl Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start() -> Result<Arc<Timer>, String> {
+        let timer = Arc::new(Timer {
+            delays: Mutex::default(),
+            next_id: AtomicU64::new(0),
+            condvar: Condvar::new(),
+        });
+        let thread_timer = timer.clone();
+        crate::real_std::thread::Builder::new()
+            .name("gluon-timer".into())
+            .spawn(move || thread_timer.run())
+            .map_err(|err| format!("Unable to spawn the timer thread: {}", err))?;
+        Ok(timer)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start() -> Result<Arc<Timer>, String> {
+        Err("Timers are not supported on wasm32 as they need a thread".to_string())
+    }
+
+    fn add(&'static self, deadline: Instant) -> Delay {
+        let id = self.next_id.fetch_add(1, atomic::Ordering::Relaxed);
+        let key = (deadline, id);
+        let state = Arc::new(Mutex::new(DelayState::default()));
+        self.delays.lock().unwrap().insert(key, state.clone());
+        self.condvar.notify_one();
+        Delay {
+            timer: self,
+            key,
+            state,
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    fn run(&self) {
+        let mut delays = self.delays.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            while let Some(&key) = delays.keys().next() {
+                if key.0 > now {
+                    break;
+                }
+                let state = delays.remove(&key).unwrap();
+                let mut state = state.lock().unwrap();
+                state.done = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+            let next_deadline = delays.keys().next().map(|&(deadline, _)| deadline);
+            delays = match next_deadline {
+                Some(deadline) => self.condvar.wait_timeout(delays, deadline - now).unwrap().0,
+                None => self.condvar.wait(delays).unwrap(),
+            };
+        }
+    }
+}
+
+struct Delay {
+    timer: &'static Timer,
+    key: (Instant, u64),
+    state: Arc<Mutex<DelayState>>,
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.done {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        // Delays which are dropped before they complete, such as the timeout of a `recv_timeout`
+        // which received a value, must not stay in the timer until their deadline
+        self.timer.delays.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Completes once `duration` has passed. Returns an error if timers are not supported.
+fn delay(duration: Duration) -> Result<Delay, String> {
+    let timer = TIMER.as_ref().map_err(|err| err.clone())?;
+    Ok(timer.add(Instant::now() + duration))
 }
 
 async fn resume(child: RootedThread) -> RuntimeResult<Result<(), String>, String> {
@@ -369,9 +763,11 @@ fn new_thread(WithVM { vm, .. }: WithVM<()>) -> IO<RootedThread> {
     }
 }
 
-fn sleep(ms: VmInt) -> IO<()> {
-    ::std::thread::sleep(Duration::from_millis(ms as u64));
-    IO::Value(())
+fn sleep(ms: VmInt) -> impl Future<Output = IO<()>> {
+    match delay(Duration::from_millis(ms.max(0) as u64)) {
+        Ok(delay) => Either::Left(delay.map(IO::Value)),
+        Err(err) => Either::Right(future::ready(IO::Exception(err))),
+    }
 }
 
 fn interrupt(thread: RootedThread) -> IO<()> {
@@ -394,10 +790,17 @@ pub fn load_channel<'vm>(vm: &'vm Thread) -> VmResult<ExternModule> {
         vm,
         record! {
             type Sender a => Sender<A>,
-            type Receiver a => Sender<A>,
+            type Receiver a => Receiver<A>,
             channel => primitive!(1, std::channel::channel),
+            bounded_channel => primitive!(2, std::channel::bounded_channel),
             recv => primitive!(1, std::channel::recv),
             send => primitive!(2, std::channel::send),
+            recv_async => primitive!(1, async fn std::channel::recv_async),
+            send_async => primitive!(2, async fn std::channel::send_async),
+            recv_timeout => primitive!(2, async fn std::channel::recv_timeout),
+            select => primitive!(1, async fn std::channel::select),
+            close => primitive!(1, std::channel::close),
+            is_closed => primitive!(1, std::channel::is_closed),
         },
     )
 }
@@ -412,8 +815,72 @@ pub fn load_thread<'vm>(vm: &'vm Thread) -> VmResult<ExternModule> {
             spawn_on => primitive!(2, std::thread::prim::spawn_on),
             new_thread => primitive!(1, std::thread::prim::new_thread),
             interrupt => primitive!(1, std::thread::prim::interrupt),
-            sleep => primitive!(1, std::thread::prim::sleep),
+            sleep => primitive!(1, async fn std::thread::prim::sleep),
             join => primitive!(2, async fn std::thread::prim::join),
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::task::noop_waker;
+
+    fn receiver(channel: &Arc<Mutex<Channel>>) -> Receiver<A> {
+        Receiver {
+            channel: channel.clone(),
+            _element_type: PhantomData,
+        }
+    }
+
+    #[test]
+    fn select_removes_wakers_from_channels_which_did_not_receive_a_value() {
+        let never = Arc::new(Mutex::new(Channel::new(None)));
+        let ready = Arc::new(Mutex::new(Channel::new(None)));
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        for _ in 0..100 {
+            let mut selected = select(vec![&receiver(&never), &receiver(&ready)]);
+            assert!(selected.poll_unpin(&mut cx).is_pending());
+            assert_eq!(never.lock().unwrap().recv_wakers.len(), 1);
+
+            ready.lock().unwrap().push(Value::int(1));
+            assert!(selected.poll_unpin(&mut cx).is_ready());
+            assert_eq!(never.lock().unwrap().recv_wakers.len(), 0);
+            assert_eq!(ready.lock().unwrap().recv_wakers.len(), 0);
+        }
+    }
+
+    #[test]
+    fn dropping_recv_async_removes_its_waker() {
+        let channel = Arc::new(Mutex::new(Channel::new(None)));
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut recv = recv_async(&receiver(&channel));
+        assert!(recv.poll_unpin(&mut cx).is_pending());
+        assert_eq!(channel.lock().unwrap().recv_wakers.len(), 1);
+        drop(recv);
+        assert_eq!(channel.lock().unwrap().recv_wakers.len(), 0);
+    }
+
+    #[test]
+    fn delays_complete_in_order() {
+        let long = delay(Duration::from_millis(50)).unwrap();
+        let short = delay(Duration::from_millis(10)).unwrap();
+        match futures::executor::block_on(future::select(long, short)) {
+            Either::Left(_) => panic!("The longer delay completed first"),
+            Either::Right(_) => (),
+        }
+    }
+    #[test]
+    fn dropping_a_delay_removes_it_from_the_timer() {
+        let delay = delay(Duration::from_secs(60)).unwrap();
+        let (timer, key) = (delay.timer, delay.key);
+        assert!(timer.delays.lock().unwrap().contains_key(&key));
+        drop(delay);
+        assert!(!timer.delays.lock().unwrap().contains_key(&key));
+    }
+}
@@ -452,6 +452,7 @@ pub struct Compiler<'a> {
     source: &'a ::codespan::FileMap,
     source_name: String,
     emit_debug_info: bool,
+    wrapping_arithmetic: bool,
     empty_symbol: Symbol,
     hole: ArcType,
 }
@@ -498,10 +499,17 @@ impl<'a> Compiler<'a> {
             source: source,
             source_name: source_name,
             emit_debug_info: emit_debug_info,
+            wrapping_arithmetic: false,
             hole: Type::hole(),
         }
     }
 
+    /// Sets whether integer arithmetic wraps around on overflow instead of panicking
+    pub fn wrapping_arithmetic(mut self, wrapping_arithmetic: bool) -> Self {
+        self.wrapping_arithmetic = wrapping_arithmetic;
+        self
+    }
+
     fn intern(&mut self, s: &str) -> Result<InternedStr> {
         self.vm.intern(s)
     }
@@ -985,12 +993,19 @@ impl<'a> Compiler<'a> {
             function.function.instructions[end - 2] = Jump(end as VmIndex);
         } else {
             let instr = match self.symbols.string(op) {
+                "#Int+" if self.wrapping_arithmetic => WrappingAddInt,
+                "#Int-" if self.wrapping_arithmetic => WrappingSubtractInt,
+                "#Int*" if self.wrapping_arithmetic => WrappingMultiplyInt,
+                "#Int/" if self.wrapping_arithmetic => WrappingDivideInt,
                 "#Int+" => AddInt,
                 "#Int-" => SubtractInt,
                 "#Int*" => MultiplyInt,
                 "#Int/" => DivideInt,
                 "#Int<" | "#Char<" => IntLT,
                 "#Int==" | "#Char==" => IntEQ,
+                "#Byte+" if self.wrapping_arithmetic => WrappingAddByte,
+                "#Byte-" if self.wrapping_arithmetic => WrappingSubtractByte,
+                "#Byte*" if self.wrapping_arithmetic => WrappingMultiplyByte,
                 "#Byte+" => AddByte,
                 "#Byte-" => SubtractByte,
                 "#Byte*" => MultiplyByte,
@@ -1163,6 +1178,14 @@ mod tests {
     }
 
     fn assert_instructions(source: &str, instructions: &[&[Instruction]]) {
+        assert_instructions_(source, false, instructions)
+    }
+
+    fn assert_instructions_(
+        source: &str,
+        wrapping_arithmetic: bool,
+        instructions: &[&[Instruction]],
+    ) {
         let mut symbols = Symbols::new();
         let global_allocator = Allocator::new();
         let global = ExprParser::new()
@@ -1179,12 +1202,74 @@ mod tests {
             &source,
             "test".into(),
             false,
-        );
+        )
+        .wrapping_arithmetic(wrapping_arithmetic);
         let module = compiler.compile_expr(&global).unwrap();
 
         verify_instructions(&module.function, &mut instructions.iter().cloned());
     }
 
+    #[test]
+    fn wrapping_arithmetic() {
+        let _ = ::env_logger::try_init();
+
+        let source = "(#Int*) ((#Int+) 1 2) ((#Int/) 3 ((#Int-) 4 5))";
+        assert_instructions(
+            source,
+            &[&[
+                PushInt(1),
+                PushInt(2),
+                AddInt,
+                PushInt(3),
+                PushInt(4),
+                PushInt(5),
+                SubtractInt,
+                DivideInt,
+                MultiplyInt,
+                Return,
+            ]],
+        );
+        assert_instructions_(
+            source,
+            true,
+            &[&[
+                PushInt(1),
+                PushInt(2),
+                WrappingAddInt,
+                PushInt(3),
+                PushInt(4),
+                PushInt(5),
+                WrappingSubtractInt,
+                WrappingDivideInt,
+                WrappingMultiplyInt,
+                Return,
+            ]],
+        );
+    }
+
+    #[test]
+    fn wrapping_byte_arithmetic() {
+        let _ = ::env_logger::try_init();
+
+        let source = "(#Byte*) ((#Byte+) 1b 2b) ((#Byte/) 3b ((#Byte-) 4b 5b))";
+        assert_instructions_(
+            source,
+            true,
+            &[&[
+                PushByte(1),
+                PushByte(2),
+                WrappingAddByte,
+                PushByte(3),
+                PushByte(4),
+                PushByte(5),
+                WrappingSubtractByte,
+                DivideByte,
+                WrappingMultiplyByte,
+                Return,
+            ]],
+        );
+    }
+
     #[test]
     fn recursive_record() {
         let _ = ::env_logger::try_init();
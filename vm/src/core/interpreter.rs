@@ -442,6 +442,7 @@ pub struct Compiler<'a, 'e> {
     costs: core::costs::Costs,
     pure_symbols: Option<&'a PurityMap>,
     bindings: Vec<Tail<'e>>,
+    wrapping_arithmetic: bool,
 }
 
 impl<'a, 'e> KindEnv for Compiler<'a, 'e> {
@@ -477,6 +478,7 @@ impl<'a, 'e> Compiler<'a, 'e> {
             costs: Default::default(),
             pure_symbols: Default::default(),
             bindings: Vec::new(),
+            wrapping_arithmetic: false,
         }
     }
 
@@ -520,6 +522,13 @@ impl<'a, 'e> Compiler<'a, 'e> {
         self
     }
 
+    /// Sets whether integer arithmetic wraps on overflow. When it doesn't, operations which
+    /// would overflow or divide by zero are left unfolded so that they panic at runtime.
+    pub fn wrapping_arithmetic(mut self, wrapping_arithmetic: bool) -> Self {
+        self.wrapping_arithmetic = wrapping_arithmetic;
+        self
+    }
+
     fn push_unknown_stack_var(&mut self, s: Symbol) {
         self.bindings_in_scope.insert(s.clone(), ());
         self.local_bindings.insert(s, None);
@@ -1369,33 +1378,39 @@ impl<'a, 'e> Compiler<'a, 'e> {
         l: ReducedExpr<'e>,
         r: ReducedExpr<'e>,
     ) -> Option<CExpr<'e>> {
-        macro_rules! binop {
-            () => {{
-                let f: fn(_, _) -> _ = match id.name.as_ref().chars().last().unwrap() {
-                    '+' => |l, r| l + r,
-                    '-' => |l, r| l - r,
-                    '*' => |l, r| l * r,
-                    '/' => |l, r| l / r,
-                    _ => return None,
-                };
-                f
-            }};
-        }
+        let op = id.name.as_ref().chars().last().unwrap();
 
         trace!("Fold binop {} {} {}", l, id.name, r);
         let l = self.peek_reduced_expr(l);
         let r = self.peek_reduced_expr(r);
         match (l.as_ref(), r.as_ref()) {
             (&Expr::Const(Literal::Int(l), ..), &Expr::Const(Literal::Int(r), ..)) => {
-                let f = binop!();
+                let value = match op {
+                    '/' if r == 0 => None,
+                    '+' if self.wrapping_arithmetic => Some(l.wrapping_add(r)),
+                    '-' if self.wrapping_arithmetic => Some(l.wrapping_sub(r)),
+                    '*' if self.wrapping_arithmetic => Some(l.wrapping_mul(r)),
+                    '/' if self.wrapping_arithmetic => Some(l.wrapping_div(r)),
+                    '+' => l.checked_add(r),
+                    '-' => l.checked_sub(r),
+                    '*' => l.checked_mul(r),
+                    '/' => l.checked_div(r),
+                    _ => None,
+                }?;
                 Some(
                     self.allocator
                         .arena
-                        .alloc(Expr::Const(Literal::Int(f(l, r)), expr.span())),
+                        .alloc(Expr::Const(Literal::Int(value), expr.span())),
                 )
             }
             (&Expr::Const(Literal::Float(l), ..), &Expr::Const(Literal::Float(r), ..)) => {
-                let f = binop!();
+                let f: fn(_, _) -> _ = match op {
+                    '+' => |l, r| l + r,
+                    '-' => |l, r| l - r,
+                    '*' => |l, r| l * r,
+                    '/' => |l, r| l / r,
+                    _ => return None,
+                };
                 Some(
                     self.allocator
                         .arena
@@ -1541,7 +1556,10 @@ pub(crate) mod tests {
         ($actual:expr, $expected:expr) => {
             assert_eq_expr!($actual, $expected, |_: &Symbol| None)
         };
-        ($actual:expr, $expected:expr, $globals:expr) => {{
+        ($actual:expr, $expected:expr, $globals:expr) => {
+            assert_eq_expr!($actual, $expected, $globals, false)
+        };
+        ($actual:expr, $expected:expr, $globals:expr, $wrapping_arithmetic:expr) => {{
             let mut symbols = Symbols::new();
             let globals = $globals;
 
@@ -1569,6 +1587,7 @@ pub(crate) mod tests {
                 Compiler::new(&allocator, &globals)
                     .costs(costs)
                     .pure_symbols(&pure_symbols)
+                    .wrapping_arithmetic($wrapping_arithmetic)
                     .compile_expr(actual_expr)
                     .unwrap()
             };
@@ -1650,6 +1669,37 @@ pub(crate) mod tests {
         assert_eq_expr!(expr, "3");
     }
 
+    #[test]
+    fn dont_fold_overflowing_primitive_op() {
+        let _ = ::env_logger::try_init();
+
+        let expr = r#"
+            (#Int*) 4611686018427387904 4
+        "#;
+        assert_eq_expr!(expr, expr);
+    }
+
+    #[test]
+    fn fold_wrapping_primitive_op() {
+        let _ = ::env_logger::try_init();
+
+        let expr = r#"
+            (#Int*) 4611686018427387904 4
+        "#;
+        assert_eq_expr!(expr, "0", |_: &Symbol| None, true);
+    }
+
+    #[test]
+    fn dont_fold_division_by_zero() {
+        let _ = ::env_logger::try_init();
+
+        let expr = r#"
+            (#Int/) 1 0
+        "#;
+        assert_eq_expr!(expr, expr);
+        assert_eq_expr!(expr, expr, |_: &Symbol| None, true);
+    }
+
     #[test]
     fn fold_function_call_basic() {
         let _ = ::env_logger::try_init();
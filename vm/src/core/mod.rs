@@ -178,6 +178,8 @@ impl Literal {
         match literal {
             ast::Literal::Byte(x) => Literal::Byte(*x),
             ast::Literal::Int(x) => Literal::Int(*x),
+            // All the fixed width integers are represented as `Int` at runtime
+            ast::Literal::SizedInt(x, _) => Literal::Int(*x),
             ast::Literal::Float(x) => Literal::Float(*x),
             ast::Literal::String(x) => Literal::String(Box::from(&x[..])),
             ast::Literal::Char(x) => Literal::Char(*x),
@@ -230,6 +232,16 @@ impl<'a> Binder<'a> {
         ident_expr
     }
 
+    fn bind_closure(&mut self, closure: Closure<'a>) -> Expr<'a> {
+        let ident_expr = Expr::Ident(closure.name.clone(), closure.expr.span());
+        self.bindings.push(LetBinding {
+            name: closure.name.clone(),
+            span_start: closure.pos,
+            expr: Named::Recursive(vec![closure]),
+        });
+        ident_expr
+    }
+
     fn into_expr(self, allocator: &'a Allocator<'a>, expr: Expr<'a>) -> Expr<'a> {
         self.bindings.into_iter().rev().fold(expr, |expr, bind| {
             Expr::Let(
@@ -926,19 +938,11 @@ impl<'a, 'e> Translator<'a, 'e> {
                 }
             }
 
-            ast::Expr::IfElse(ref pred, ref if_true, ref if_false) => {
-                let alts = self.allocator.alternative_arena.alloc_fixed(iterator!(
-                    Alternative {
-                        pattern: Pattern::Constructor(self.bool_constructor(true), vec![]),
-                        expr: self.translate_alloc(if_true),
-                    },
-                    Alternative {
-                        pattern: Pattern::Constructor(self.bool_constructor(false), vec![]),
-                        expr: self.translate_alloc(if_false),
-                    },
-                ));
-                Expr::Match(self.translate_alloc(pred), alts)
-            }
+            ast::Expr::IfElse(ref pred, ref if_true, ref if_false) => self.if_then_else(
+                self.translate_alloc(pred),
+                self.translate_alloc(if_true),
+                self.translate_alloc(if_false),
+            ),
 
             ast::Expr::Infix {
                 ref lhs,
@@ -974,12 +978,61 @@ impl<'a, 'e> Translator<'a, 'e> {
 
             ast::Expr::Literal(ref literal) => Expr::Const(Literal::from_ast(literal), expr.span),
 
+            ast::Expr::Interpolation(ast::Interpolation {
+                ref parts,
+                ref append_id,
+            }) => {
+                let append_id = append_id
+                    .as_ref()
+                    .unwrap_or_else(|| ice!("append_id must be set when translating to core"));
+
+                let mut parts = parts
+                    .iter()
+                    .filter_map(|part| match *part {
+                        ast::InterpolationPart::Text(ref text) if text.value.is_empty() => None,
+                        ast::InterpolationPart::Text(ref text) => Some(Expr::Const(
+                            Literal::String(Box::from(&text.value[..])),
+                            text.span,
+                        )),
+                        ast::InterpolationPart::Expr {
+                            ref expr,
+                            ref show_id,
+                        } => Some(match show_id {
+                            Some(show_id) => Expr::Call(
+                                self.translate_alloc(show_id),
+                                arena.alloc_fixed(Some(self.translate(expr))),
+                            ),
+                            // `String` parts are used as is
+                            None => self.translate(expr),
+                        }),
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter();
+
+                match parts.next() {
+                    None => Expr::Const(Literal::String(Box::from("")), expr.span),
+                    Some(first) => {
+                        let mut binder = Binder::default();
+                        let append = binder.bind(
+                            self.translate_alloc(append_id),
+                            append_id.env_type_of(&self.env),
+                        );
+                        let append = &*arena.alloc(append);
+                        let result = parts.fold(first, |acc, part| {
+                            Expr::Call(append, arena.alloc_fixed(vec![acc, part]))
+                        });
+                        binder.into_expr(&self.allocator, result)
+                    }
+                }
+            }
+
             ast::Expr::Match(ref expr, ref alts) => {
                 let expr = self.translate_alloc(&**expr);
                 let alts: Vec<_> = alts
                     .iter()
                     .map(|alt| Equation {
                         patterns: vec![&alt.pattern],
+                        guard: alt.guard.as_ref().map(|guard| self.translate_alloc(guard)),
                         result: self.translate_alloc(&alt.expr),
                     })
                     .collect();
@@ -1150,6 +1203,7 @@ impl<'a, 'e> Translator<'a, 'e> {
                             id_expr,
                             &[Equation {
                                 patterns: vec![&pat],
+                                guard: None,
                                 result: core_body,
                             }],
                         );
@@ -1259,6 +1313,7 @@ impl<'a, 'e> Translator<'a, 'e> {
                             bind_expr,
                             &[Equation {
                                 patterns: vec![&bind.name],
+                                guard: None,
                                 result: tail,
                             }],
                         );
@@ -1286,6 +1341,25 @@ impl<'a, 'e> Translator<'a, 'e> {
         }
     }
 
+    fn if_then_else(
+        &'a self,
+        pred: &'a Expr<'a>,
+        if_true: &'a Expr<'a>,
+        if_false: &'a Expr<'a>,
+    ) -> Expr<'a> {
+        let alts = self.allocator.alternative_arena.alloc_fixed(iterator!(
+            Alternative {
+                pattern: Pattern::Constructor(self.bool_constructor(true), vec![]),
+                expr: if_true,
+            },
+            Alternative {
+                pattern: Pattern::Constructor(self.bool_constructor(false), vec![]),
+                expr: if_false,
+            },
+        ));
+        Expr::Match(pred, alts)
+    }
+
     fn bool_constructor(&self, variant: bool) -> TypedIdent<Symbol> {
         let b = self.env.get_bool();
         match *b {
@@ -1447,17 +1521,18 @@ pub struct PatternTranslator<'a, 'e: 'a>(&'a Translator<'a, 'e>);
 #[derive(Clone, PartialEq, Debug)]
 struct Equation<'a, 'p, 'ast> {
     patterns: Vec<&'p SpannedPattern<'ast, Symbol>>,
+    /// Guard which must evaluate to `True` for `result` to be selected
+    guard: Option<&'a Expr<'a>>,
     result: &'a Expr<'a>,
 }
 
 impl<'a, 'p, 'ast> fmt::Display for Equation<'a, 'p, 'ast> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[({:?},{})]",
-            self.patterns.iter().format(", "),
-            self.result
-        )
+        write!(f, "[({:?}", self.patterns.iter().format(", "))?;
+        if let Some(guard) = self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, ",{})]", self.result)
     }
 }
 
@@ -1467,6 +1542,7 @@ enum CType {
     Record,
     Variable,
     Literal,
+    Array,
 }
 
 /// `PatternTranslator` translated nested (AST) patterns into non-nested (core) patterns.
@@ -1500,6 +1576,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             CType::Record => self.compile_record(default, variables, equations),
             CType::Variable => self.compile_variable(default, variables, equations),
             CType::Literal => self.compile_literal(default, variables, equations),
+            CType::Array => self.compile_array(default, variables, equations),
         }
     }
 
@@ -1597,15 +1674,16 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             // (since those need to be solved first) and then the remaining_patterns
             let new_equations = equations
                 .iter()
-                .map(|equation| (&equation.patterns[1..], equation.result))
+                .map(|equation| (equation, &equation.patterns[1..]))
                 .zip(&temp)
-                .map(|((remaining_equations, result), first)| Equation {
+                .map(|((equation, remaining_equations), first)| Equation {
                     patterns: first
                         .iter()
                         .map(|pattern| &**pattern)
                         .chain(remaining_equations.iter().cloned())
                         .collect(),
-                    result,
+                    guard: equation.guard,
+                    result: equation.result,
                 })
                 .collect::<Vec<_>>();
 
@@ -1651,6 +1729,8 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 ast::Pattern::As(_, _)
                 | ast::Pattern::Tuple { .. }
                 | ast::Pattern::Record { .. }
+                | ast::Pattern::Array { .. }
+                | ast::Pattern::Or(_)
                 | ast::Pattern::Ident(_)
                 | ast::Pattern::Literal(_)
                 | ast::Pattern::Error => unreachable!(),
@@ -1701,6 +1781,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                                 .iter()
                                 .chain(equation.patterns.iter().cloned().skip(1))
                                 .collect(),
+                            guard: equation.guard,
                             result: equation.result,
                         }
                     })
@@ -1740,6 +1821,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 .iter()
                 .map(|equation| Equation {
                     patterns: equation.patterns[1..].to_owned(),
+                    guard: equation.guard,
                     result: equation.result,
                 })
                 .collect::<Vec<_>>(),
@@ -1789,6 +1871,8 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 | ast::Pattern::As(_, _)
                 | ast::Pattern::Tuple { .. }
                 | ast::Pattern::Record { .. }
+                | ast::Pattern::Array { .. }
+                | ast::Pattern::Or(_)
                 | ast::Pattern::Ident(_)
                 | ast::Pattern::Error => unreachable!(),
             }
@@ -1809,6 +1893,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                     .iter()
                     .map(|equation| Equation {
                         patterns: equation.patterns.iter().cloned().skip(1).collect(),
+                        guard: equation.guard,
                         result: equation.result,
                     })
                     .collect::<Vec<_>>();
@@ -1833,6 +1918,189 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
         self.0.allocator.arena.alloc(expr)
     }
 
+    fn compile_array<'p>(
+        &mut self,
+        default: &'a Expr<'a>,
+        variables: &[&'a Expr<'a>],
+        equations: &[Equation<'a, 'p, '_>],
+    ) -> &'a Expr<'a> {
+        fn array_shape(pattern: &ast::Pattern<Symbol>) -> (usize, bool) {
+            match *unwrap_as(pattern) {
+                ast::Pattern::Array {
+                    ref elems,
+                    ref rest,
+                    ..
+                } => (elems.len(), rest.is_some()),
+                _ => unreachable!(),
+            }
+        }
+
+        // Only adjacent equations which match arrays of the same shape can be compiled together
+        // since `[x, ..]` and `[x, y]` may both match the same array
+        let groups = equations
+            .iter()
+            .group_by(|equation| array_shape(&equation.patterns.first().unwrap().value));
+        let groups = (&groups)
+            .into_iter()
+            .map(|(shape, group)| (shape, group.cloned().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        groups
+            .into_iter()
+            .rev()
+            .fold(default, |default, ((len, has_rest), equations)| {
+                self.compile_array_shape(default, variables, len, has_rest, &equations)
+            })
+    }
+
+    // Matches arrays of length `len` (or at least `len` if `has_rest` is set) by binding each
+    // element (and the rest of the array) to a new variable
+    //
+    // | [x, Some y] -> ..
+    // // Turns into
+    // let len = @array_length array
+    // match len with
+    // | 2 ->
+    //     let e0 = @array_index array 0
+    //     let e1 = @array_index array 1
+    //     match e0, e1 with ..
+    // | _ -> default
+    fn compile_array_shape<'p>(
+        &mut self,
+        default: &'a Expr<'a>,
+        variables: &[&'a Expr<'a>],
+        len: usize,
+        has_rest: bool,
+        equations: &[Equation<'a, 'p, '_>],
+    ) -> &'a Expr<'a> {
+        let translator = self.0;
+        let arena = &translator.allocator.arena;
+
+        let array = variables[0];
+        let array_type = array.env_type_of(&translator.env);
+        let int_type: ArcType = Type::int();
+
+        let primitive = |name: &str, args: Vec<ArcType>, ret: ArcType| -> &'a Expr<'a> {
+            arena.alloc(Expr::Ident(
+                TypedIdent {
+                    name: Symbol::from(name),
+                    typ: Type::function(args, ret),
+                },
+                Span::default(),
+            ))
+        };
+        let int = |i: usize| Expr::Const(Literal::Int(i as i64), Span::default());
+
+        let mut length_binder = Binder::default();
+        let array_length = length_binder.bind(
+            arena.alloc(Expr::Call(
+                primitive("@array_length", vec![array_type.clone()], int_type.clone()),
+                arena.alloc_fixed(once(array.clone())),
+            )),
+            int_type.clone(),
+        );
+
+        let elem_types = match *unwrap_as(&equations[0].patterns.first().unwrap().value) {
+            ast::Pattern::Array { ref elems, .. } => elems
+                .iter()
+                .map(|elem| elem.env_type_of(&translator.env))
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+
+        let mut binder = Binder::default();
+        let mut new_variables = elem_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, elem_type)| {
+                let index = Expr::Call(
+                    primitive(
+                        "@array_index",
+                        vec![array_type.clone(), int_type.clone()],
+                        elem_type.clone(),
+                    ),
+                    arena.alloc_fixed(iterator!(array.clone(), int(i))),
+                );
+                &*arena.alloc(binder.bind(arena.alloc(index), elem_type))
+            })
+            .collect::<Vec<_>>();
+        if has_rest {
+            let rest = if len == 0 {
+                array
+            } else {
+                let slice = Expr::Call(
+                    primitive(
+                        "@array_slice",
+                        vec![array_type.clone(), int_type.clone(), int_type.clone()],
+                        array_type.clone(),
+                    ),
+                    arena.alloc_fixed(iterator!(array.clone(), int(len), array_length.clone())),
+                );
+                arena.alloc(binder.bind(arena.alloc(slice), array_type.clone()))
+            };
+            new_variables.push(rest);
+        }
+        new_variables.extend(variables[1..].iter().cloned());
+
+        // Replace the array pattern with the patterns of its elements (and the rest pattern)
+        let new_equations = equations
+            .iter()
+            .map(|equation| {
+                let (elems, rest) = match *unwrap_as(&equation.patterns.first().unwrap().value) {
+                    ast::Pattern::Array {
+                        ref elems,
+                        ref rest,
+                        ..
+                    } => (elems, rest),
+                    _ => unreachable!(),
+                };
+                Equation {
+                    patterns: elems
+                        .iter()
+                        .chain(rest.as_deref())
+                        .chain(equation.patterns.iter().cloned().skip(1))
+                        .collect(),
+                    guard: equation.guard,
+                    result: equation.result,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let expr = self.translate(default, &new_variables, &new_equations);
+        let expr = binder.into_expr_ref(&translator.allocator, expr);
+
+        let expr = if !has_rest {
+            let alts = translator
+                .allocator
+                .alternative_arena
+                .alloc_fixed(iterator!(
+                    Alternative {
+                        pattern: Pattern::Literal(Literal::Int(len as i64)),
+                        expr,
+                    },
+                    Alternative {
+                        pattern: Pattern::Ident(translator.dummy_symbol.clone()),
+                        expr: default,
+                    },
+                ));
+            arena.alloc(Expr::Match(arena.alloc(array_length), alts))
+        } else if len != 0 {
+            let less = primitive(
+                "#Int<",
+                vec![int_type.clone(), int_type.clone()],
+                translator.env.get_bool(),
+            );
+            let too_short = arena.alloc(Expr::Call(
+                less,
+                arena.alloc_fixed(iterator!(array_length, int(len))),
+            ));
+            arena.alloc(translator.if_then_else(too_short, default, expr))
+        } else {
+            // `[..rest]` matches any array so the length is not needed
+            return expr;
+        };
+        length_binder.into_expr_ref(&translator.allocator, expr)
+    }
+
     // Generates a variable for each of the new equations we inserted
     // This variable is what we `match` the expression(s) on
     fn insert_new_variables(
@@ -1857,6 +2125,19 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
         expr: &'a Expr<'a>,
         equations: &[Equation<'a, 'p, '_>],
     ) -> Expr<'a> {
+        if equations.iter().any(|equation| {
+            equation
+                .patterns
+                .iter()
+                .any(|pattern| or_pattern_expansions(&pattern.value) > 1)
+        }) {
+            mk_ast_arena!(arena);
+            let mut binder = Binder::default();
+            let equations = self.expand_or_patterns((*arena).borrow(), &mut binder, equations);
+            let expr = self.translate_top(expr, &equations);
+            return binder.into_expr(&self.0.allocator, expr);
+        }
+
         let arena = &self.0.allocator.arena;
         let default = arena.alloc(self.0.error_expr("Unmatched pattern"));
         match *expr {
@@ -1883,6 +2164,130 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
         }
     }
 
+    /// Replaces each equation which contains or patterns with an equation for every combination
+    /// of alternatives. The variables of each new equation are replaced by fresh variables (so
+    /// that the bindings of one equation do not interfere with the others). `guard` and `result`
+    /// are bound once in `binder` as functions of the original variables, which the new equations
+    /// call with their fresh variables so that the arm is not duplicated for every combination.
+    fn expand_or_patterns<'p, 'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        binder: &mut Binder<'a>,
+        equations: &[Equation<'a, 'p, '_>],
+    ) -> Vec<Equation<'a, 'ast, 'ast>> {
+        let translator = self.0;
+        let arena_expr = &translator.allocator.arena;
+        let mut new_equations = Vec::new();
+        for equation in equations {
+            let expansions: usize = equation
+                .patterns
+                .iter()
+                .map(|pattern| or_pattern_expansions(&pattern.value))
+                .product();
+            let mut expanded = (0..expansions)
+                .map(|index| {
+                    let mut expander = OrPatternExpander {
+                        env: translator.env,
+                        arena,
+                        renamed: if expansions > 1 {
+                            Some(Vec::new())
+                        } else {
+                            None
+                        },
+                    };
+                    let indices = split_expansion_index(equation.patterns.iter().cloned(), index);
+                    let patterns = equation
+                        .patterns
+                        .iter()
+                        .zip(indices)
+                        .map(|(pattern, index)| &*arena.alloc(expander.expand(pattern, index)))
+                        .collect::<Vec<_>>();
+                    (patterns, expander.renamed.unwrap_or_default())
+                })
+                .collect::<Vec<_>>();
+
+            if expansions == 1 {
+                let (patterns, _) = expanded.pop().unwrap();
+                new_equations.push(Equation {
+                    patterns,
+                    guard: equation.guard,
+                    result: equation.result,
+                });
+                continue;
+            }
+
+            // Every alternative binds the same variables so they become the arguments of the
+            // shared functions. A dummy argument is used if no variables are bound.
+            let (args, dummy_arg) = if expanded[0].1.is_empty() {
+                (
+                    vec![TypedIdent {
+                        name: Symbol::from("unused"),
+                        typ: Type::int(),
+                    }],
+                    Some(&*arena_expr.alloc(Expr::Const(Literal::Int(0), Span::default()))),
+                )
+            } else {
+                let args = expanded[0]
+                    .1
+                    .iter()
+                    .map(|(original, _)| original.clone())
+                    .collect::<Vec<_>>();
+                (args, None)
+            };
+            let mut bind_arm = |name: &str, expr: &'a Expr<'a>, typ: ArcType| {
+                let name = TypedIdent {
+                    name: Symbol::from(name),
+                    typ: Type::function(args.iter().map(|arg| arg.typ.clone()), typ),
+                };
+                binder.bind_closure(Closure {
+                    pos: expr.span().start(),
+                    name,
+                    args: args.clone(),
+                    expr,
+                })
+            };
+            let guard = equation
+                .guard
+                .map(|guard| bind_arm("or_guard", guard, translator.env.get_bool()));
+            let result = bind_arm(
+                "or_arm",
+                equation.result,
+                equation.result.env_type_of(&translator.env),
+            );
+
+            for (patterns, renamed) in expanded {
+                let call = |function: &Expr<'a>| -> &'a Expr<'a> {
+                    let call_args = match dummy_arg {
+                        Some(dummy_arg) => arena_expr.alloc_fixed(once(dummy_arg.clone())),
+                        None => arena_expr.alloc_fixed(args.iter().map(|arg| {
+                            let fresh = renamed
+                                .iter()
+                                .find(|(original, _)| original.name == arg.name)
+                                .map(|(_, fresh)| fresh.clone())
+                                .unwrap_or_else(|| {
+                                    ice!("ICE: Or pattern alternative does not bind {}", arg.name)
+                                });
+                            Expr::Ident(
+                                TypedIdent {
+                                    name: fresh,
+                                    typ: arg.typ.clone(),
+                                },
+                                Span::default(),
+                            )
+                        })),
+                    };
+                    arena_expr.alloc(Expr::Call(arena_expr.alloc(function.clone()), call_args))
+                };
+                new_equations.push(Equation {
+                    patterns,
+                    guard: guard.as_ref().map(|guard| call(guard)),
+                    result: call(&result),
+                });
+            }
+        }
+        new_equations
+    }
+
     fn translate<'p>(
         &mut self,
         default: &'a Expr<'a>,
@@ -1896,6 +2301,8 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 ast::Pattern::Record { .. } | ast::Pattern::Tuple { .. } => CType::Record,
                 ast::Pattern::Constructor(_, _) => CType::Constructor,
                 ast::Pattern::Literal(_) => CType::Literal,
+                ast::Pattern::Array { .. } => CType::Array,
+                ast::Pattern::Or(_) => ice!("ICE: Or pattern survived expansion"),
                 ast::Pattern::Error => ice!("ICE: Error pattern survived typechecking"),
             }
         }
@@ -1912,10 +2319,20 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             .group_by(|equation| varcon(&equation.patterns.first().expect("Pattern").value));
 
         let expr = match variables.first() {
+            // All patterns have been matched so the first equation is selected unless it has a
+            // guard, in which case the following equations (and finally `default`) are tried if
+            // the guard fails
             None => equations
-                .first()
-                .map(|equation| equation.result)
-                .unwrap_or(default),
+                .iter()
+                .rev()
+                .fold(default, |next, equation| match equation.guard {
+                    Some(guard) => self.0.allocator.arena.alloc(self.0.if_then_else(
+                        guard,
+                        equation.result,
+                        next,
+                    )),
+                    None => equation.result,
+                }),
             Some(_) => {
                 fn bind_variables<'b>(
                     env: &dyn PrimitiveEnv<Type = ArcType>,
@@ -2114,7 +2531,10 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                         }
                     }
                 }
-                ast::Pattern::Literal(_) | ast::Pattern::Error => (),
+                ast::Pattern::Literal(_)
+                | ast::Pattern::Array { .. }
+                | ast::Pattern::Or(_)
+                | ast::Pattern::Error => (),
             }
         }
 
@@ -2130,6 +2550,228 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
     }
 }
 
+/// Returns the number of patterns without or patterns that `pattern` expands into
+fn or_pattern_expansions(pattern: &ast::Pattern<Symbol>) -> usize {
+    match *pattern {
+        ast::Pattern::As(_, ref pattern) => or_pattern_expansions(&pattern.value),
+        ast::Pattern::Constructor(_, ref args)
+        | ast::Pattern::Tuple {
+            elems: ref args, ..
+        } => args
+            .iter()
+            .map(|arg| or_pattern_expansions(&arg.value))
+            .product(),
+        ast::Pattern::Record { ref fields, .. } => ast::pattern_values(fields)
+            .filter_map(|(_, value)| value.as_ref())
+            .map(|value| or_pattern_expansions(&value.value))
+            .product(),
+        ast::Pattern::Array {
+            ref elems,
+            ref rest,
+            ..
+        } => elems
+            .iter()
+            .chain(rest.as_deref())
+            .map(|elem| or_pattern_expansions(&elem.value))
+            .product(),
+        ast::Pattern::Or(ref alternatives) => alternatives
+            .iter()
+            .map(|alternative| or_pattern_expansions(&alternative.value))
+            .sum(),
+        ast::Pattern::Ident(_) | ast::Pattern::Literal(_) | ast::Pattern::Error => 1,
+    }
+}
+
+/// Splits `index` into the index of the expansion to use for each of `patterns`. The expansions
+/// of the last pattern are iterated first so the alternatives are tried from left to right.
+fn split_expansion_index<'p, 'ast: 'p>(
+    patterns: impl DoubleEndedIterator<Item = &'p SpannedPattern<'ast, Symbol>>,
+    mut index: usize,
+) -> Vec<usize> {
+    let mut indices = patterns
+        .rev()
+        .map(|pattern| {
+            let expansions = or_pattern_expansions(&pattern.value);
+            let i = index % expansions;
+            index /= expansions;
+            i
+        })
+        .collect::<Vec<_>>();
+    indices.reverse();
+    indices
+}
+
+/// Copies patterns into a new arena, replacing each or pattern with one of its alternatives
+struct OrPatternExpander<'e, 't, 'ast> {
+    env: &'e dyn PrimitiveEnv<Type = ArcType>,
+    arena: ast::ArenaRef<'t, 'ast, Symbol>,
+    /// If set, each variable is replaced by a fresh variable and recorded as
+    /// `(original, fresh)`
+    renamed: Option<Vec<(TypedIdent<Symbol>, Symbol)>>,
+}
+
+impl<'e, 't, 'ast> OrPatternExpander<'e, 't, 'ast> {
+    fn bind(&mut self, id: TypedIdent<Symbol>) -> TypedIdent<Symbol> {
+        match self.renamed {
+            Some(ref mut renamed) if id.name.declared_name() != "_" => {
+                let fresh = TypedIdent {
+                    name: Symbol::from(id.name.declared_name()),
+                    typ: id.typ.clone(),
+                };
+                renamed.push((id, fresh.name.clone()));
+                fresh
+            }
+            _ => id,
+        }
+    }
+
+    fn expand_slice(
+        &mut self,
+        patterns: &[SpannedPattern<'_, Symbol>],
+        index: usize,
+    ) -> &'ast mut [SpannedPattern<'ast, Symbol>] {
+        let indices = split_expansion_index(patterns.iter(), index);
+        let patterns = patterns
+            .iter()
+            .zip(indices)
+            .map(|(pattern, index)| self.expand(pattern, index))
+            .collect::<Vec<_>>();
+        self.arena.alloc_extend(patterns)
+    }
+
+    /// Returns the `index`th pattern that `pattern` expands into
+    fn expand(
+        &mut self,
+        pattern: &SpannedPattern<'_, Symbol>,
+        mut index: usize,
+    ) -> SpannedPattern<'ast, Symbol> {
+        let value = match pattern.value {
+            ast::Pattern::As(ref id, ref pattern) => {
+                let typ = pattern.env_type_of(&self.env);
+                let id = spanned(
+                    id.span,
+                    self.bind(TypedIdent {
+                        name: id.value.clone(),
+                        typ,
+                    })
+                    .name,
+                );
+                let pattern = self.expand(pattern, index);
+                ast::Pattern::As(id, self.arena.alloc(pattern))
+            }
+            ast::Pattern::Constructor(ref id, ref args) => {
+                ast::Pattern::Constructor(id.clone(), self.expand_slice(args, index))
+            }
+            ast::Pattern::Ident(ref id) => ast::Pattern::Ident(self.bind(id.clone())),
+            ast::Pattern::Record {
+                ref typ,
+                ref fields,
+                ref implicit_import,
+            } => {
+                let values = ast::pattern_values(fields)
+                    .filter_map(|(_, value)| value.as_ref())
+                    .collect::<Vec<_>>();
+                let mut indices = split_expansion_index(values.into_iter(), index).into_iter();
+                let mut new_fields = Vec::with_capacity(fields.len());
+                for field in &**fields {
+                    new_fields.push(match *field {
+                        ast::PatternField::Type { ref name } => {
+                            ast::PatternField::Type { name: name.clone() }
+                        }
+                        ast::PatternField::Value {
+                            ref name,
+                            value: Some(ref value),
+                        } => ast::PatternField::Value {
+                            name: name.clone(),
+                            value: Some(self.expand(value, indices.next().unwrap())),
+                        },
+                        // `{ x }` binds the field to `x` so it must become `{ x = x1 }` if `x` is
+                        // renamed
+                        ast::PatternField::Value {
+                            ref name,
+                            value: None,
+                        } => {
+                            let value = if self.renamed.is_some() {
+                                let field_type =
+                                    remove_aliases_cow(&self.env, &mut NullInterner, typ)
+                                        .row_iter()
+                                        .find(|f| f.name.name_eq(&name.value))
+                                        .map(|f| f.typ.clone())
+                                        .unwrap_or_else(Type::hole);
+                                let id = self.bind(TypedIdent {
+                                    name: name.value.clone(),
+                                    typ: field_type,
+                                });
+                                Some(spanned(name.span, ast::Pattern::Ident(id)))
+                            } else {
+                                None
+                            };
+                            ast::PatternField::Value {
+                                name: name.clone(),
+                                value,
+                            }
+                        }
+                    });
+                }
+                let implicit_import = implicit_import.as_ref().map(|id| {
+                    spanned(
+                        id.span,
+                        self.bind(TypedIdent {
+                            name: id.value.clone(),
+                            typ: typ.clone(),
+                        })
+                        .name,
+                    )
+                });
+                ast::Pattern::Record {
+                    typ: typ.clone(),
+                    fields: self.arena.alloc_extend(new_fields),
+                    implicit_import,
+                }
+            }
+            ast::Pattern::Tuple { ref typ, ref elems } => ast::Pattern::Tuple {
+                typ: typ.clone(),
+                elems: self.expand_slice(elems, index),
+            },
+            ast::Pattern::Array {
+                ref typ,
+                ref elems,
+                ref rest,
+            } => {
+                let mut indices =
+                    split_expansion_index(elems.iter().chain(rest.as_deref()), index).into_iter();
+                let elems = elems
+                    .iter()
+                    .zip(indices.by_ref())
+                    .map(|(elem, index)| self.expand(elem, index))
+                    .collect::<Vec<_>>();
+                let rest = rest.as_deref().map(|rest| {
+                    let rest = self.expand(rest, indices.next().unwrap());
+                    self.arena.alloc(rest)
+                });
+                ast::Pattern::Array {
+                    typ: typ.clone(),
+                    elems: self.arena.alloc_extend(elems),
+                    rest,
+                }
+            }
+            ast::Pattern::Or(ref alternatives) => {
+                for alternative in &**alternatives {
+                    let expansions = or_pattern_expansions(&alternative.value);
+                    if index < expansions {
+                        return self.expand(alternative, index);
+                    }
+                    index -= expansions;
+                }
+                unreachable!()
+            }
+            ast::Pattern::Literal(ref literal) => ast::Pattern::Literal(literal.clone()),
+            ast::Pattern::Error => ast::Pattern::Error,
+        };
+        spanned(pattern.span, value)
+    }
+}
+
 fn get_ident(pattern: &ast::Pattern<Symbol>) -> Option<TypedIdent<Symbol>> {
     match *pattern {
         ast::Pattern::Ident(ref id) => Some(id.clone()),
@@ -2788,6 +3430,100 @@ pub mod tests {
         check_translation(expr_str, expected_str);
     }
 
+    #[test]
+    fn match_or_pattern() {
+        let expr_str = r#"
+            match x with
+            | Some 1 | Some 2 -> 1
+            | _ -> 0
+        "#;
+
+        let expected_str = r#"
+            rec let or_arm unused = 1
+            in
+            match x with
+            | Some p ->
+                match p with
+                | 1 -> or_arm 0
+                | 2 -> or_arm 0
+                | _ -> 0
+                end
+            | _ -> 0
+            end
+        "#;
+        check_translation(expr_str, expected_str);
+    }
+
+    #[test]
+    fn match_or_pattern_with_bindings() {
+        let expr_str = r#"
+            match x with
+            | Left y | Right y -> y
+        "#;
+
+        let expected_str = r#"
+            rec let or_arm y = y
+            in
+            match x with
+            | Left y1 -> or_arm y1
+            | Right y2 -> or_arm y2
+            | _ -> @error "Unmatched pattern"
+            end
+        "#;
+        check_translation(expr_str, expected_str);
+    }
+
+    #[test]
+    fn match_nested_or_pattern() {
+        let expr_str = r#"
+            match x with
+            | Some (Left y | Right y) -> y
+            | _ -> 0
+        "#;
+
+        let expected_str = r#"
+            rec let or_arm y = y
+            in
+            match x with
+            | Some p ->
+                match p with
+                | Left y1 -> or_arm y1
+                | Right y2 -> or_arm y2
+                | _ -> 0
+                end
+            | _ -> 0
+            end
+        "#;
+        check_translation(expr_str, expected_str);
+    }
+
+    #[test]
+    fn match_array_pattern() {
+        let expr_str = r#"
+            match xs with
+            | [] -> 0
+            | [x, y] -> x
+            | [..rest] -> 1
+        "#;
+
+        let expected_str = r#"
+            let len = @array_length xs in
+            match len with
+            | 0 -> 0
+            | _ ->
+                let len2 = @array_length xs in
+                match len2 with
+                | 2 ->
+                    let x = @array_index xs 0 in
+                    let y = @array_index xs 1 in
+                    x
+                | _ -> 1
+                end
+            end
+        "#;
+        check_translation(expr_str, expected_str);
+    }
+
     #[test]
     fn expr_size() {
         let s = std::mem::size_of::<Expr>();
@@ -256,10 +256,13 @@ fn optimize_unnecessary_allocation<'a>(
     optimizer.visit_expr(expr).unwrap_or(expr)
 }
 
+/// Optimizes `expr`. `wrapping_arithmetic` must match the setting the expression is compiled with
+/// so that constant folding of integer arithmetic has the same semantics as the runtime.
 pub fn optimize<'a>(
     allocator: &'a Arc<Allocator<'a>>,
     env: &'a dyn OptimizeEnv<Type = ArcType>,
     expr: &'a Expr<'a>,
+    wrapping_arithmetic: bool,
 ) -> Global<CoreExpr> {
     let expr = optimize_unnecessary_allocation(allocator, expr);
 
@@ -279,7 +282,8 @@ pub fn optimize<'a>(
     };
     let mut interpreter = crate::core::interpreter::Compiler::new(allocator, &f)
         .costs(costs)
-        .pure_symbols(&pure_symbols);
+        .pure_symbols(&pure_symbols)
+        .wrapping_arithmetic(wrapping_arithmetic);
     let expr = interpreter.compile_expr(expr).ok().unwrap_or(expr);
 
     let mut dep_graph = dead_code::DepGraph::default();
@@ -0,0 +1,68 @@
+use crate::base::{
+    ast::{self, Expr, ExprField, TypeBinding, ValueBinding},
+    pos,
+    symbol::{Symbol, Symbols},
+    types::{remove_forall, row_iter, Type},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate<'ast>(
+    mut arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let default = symbols.simple_symbol("default");
+
+    let default_value_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Record(ref row) => {
+            if let Some(field) =
+                row_iter(row).find(|field| is_self_type(&bind.alias.value.name, &field.typ))
+            {
+                return Err(Error::message(format!(
+                    "Unable to derive Default as the field `{}` contains the record itself",
+                    field.name.declared_name()
+                )));
+            }
+            pos::spanned(
+                span,
+                Expr::Record {
+                    typ: Type::hole(),
+                    types: &mut [],
+                    exprs: arena.alloc_extend(row_iter(row).map(|field| ExprField {
+                        metadata: Default::default(),
+                        name: pos::spanned(span, field.name.clone()),
+                        value: Some(ident(span, default.clone())),
+                    })),
+                    base: None,
+                },
+            )
+        }
+        _ => return Err(Error::message("Default can only be derived for records")),
+    };
+
+    let default_import =
+        arena.generate_import_(span, symbols, &[], &["default"], true, "std.default");
+
+    let self_type = bind.alias.value.self_type(&mut arena);
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "default", bind))),
+        ),
+        args: &mut [],
+        expr: let_bindings(
+            arena,
+            span,
+            vec![default_import],
+            record(arena, symbols, span, vec![("default", default_value_expr)]),
+        ),
+        metadata: Default::default(),
+        typ: Some(binding_type(arena, symbols, "Default", self_type, bind)),
+        resolved_type: Type::hole(),
+    })
+}
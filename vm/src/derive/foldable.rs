@@ -0,0 +1,218 @@
+use crate::base::{
+    ast::{self, AstType, Expr, SpannedExpr, TypeBinding, ValueBinding},
+    pos::{self, BytePos, Span},
+    symbol::{Symbol, Symbols},
+    types::Type,
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let (param, self_type) = last_param_type(arena, "Foldable", bind)?;
+    let foldable_expr = foldable_expr(arena, symbols, bind, &param)?;
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "foldable", bind))),
+        ),
+        args: &mut [],
+        expr: foldable_expr,
+        metadata: Default::default(),
+        typ: Some(constructor_binding_type(
+            arena, symbols, "Foldable", self_type,
+        )),
+        resolved_type: Type::hole(),
+    })
+}
+
+/// Generates the `Foldable` record which folds over `param`
+pub(crate) fn foldable_expr<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+    param: &Symbol,
+) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let f = Symbol::from("f");
+    let z = Symbol::from("z");
+    let x = Symbol::from("x");
+    let foldr_ = symbols.simple_symbol("foldr_");
+    let foldl_ = symbols.simple_symbol("foldl_");
+
+    let folder = Folder {
+        span,
+        param,
+        self_name: &bind.alias.value.name,
+        f: f.clone(),
+        foldr: symbols.simple_symbol("foldr"),
+        foldr_: foldr_.clone(),
+        foldl: symbols.simple_symbol("foldl"),
+        foldl_: foldl_.clone(),
+    };
+
+    // `foldr` visits the fields from right to left and `foldl` from left to right
+    let foldr_expr = match_fields(
+        arena,
+        span,
+        bind,
+        ident(span, x.clone()),
+        "Foldable",
+        &mut |_, fields| {
+            fields
+                .iter()
+                .rev()
+                .try_fold(ident(span, z.clone()), |acc, field| {
+                    folder.foldr_field(
+                        arena,
+                        symbols,
+                        0,
+                        ident(span, field.var.clone()),
+                        field.typ,
+                        acc,
+                    )
+                })
+        },
+    )?;
+    let foldl_expr = match_fields(
+        arena,
+        span,
+        bind,
+        ident(span, x.clone()),
+        "Foldable",
+        &mut |_, fields| {
+            fields
+                .iter()
+                .try_fold(ident(span, z.clone()), |acc, field| {
+                    folder.foldl_field(
+                        arena,
+                        symbols,
+                        0,
+                        ident(span, field.var.clone()),
+                        field.typ,
+                        acc,
+                    )
+                })
+        },
+    )?;
+
+    let foldable_import =
+        arena.generate_import(span, symbols, &[], &["foldr", "foldl"], "std.foldable");
+
+    let args = [f, z, x];
+    let foldable_record_expr = Expr::rec_let_bindings(
+        arena,
+        vec![
+            function_binding(arena, span, &foldr_, &args, None, foldr_expr),
+            function_binding(arena, span, &foldl_, &args, None, foldl_expr),
+        ],
+        record(
+            arena,
+            symbols,
+            span,
+            vec![
+                ("foldr", ident(span, foldr_.clone())),
+                ("foldl", ident(span, foldl_.clone())),
+            ],
+        ),
+    );
+
+    Ok(let_bindings(
+        arena,
+        span,
+        vec![foldable_import],
+        pos::spanned(span, foldable_record_expr),
+    ))
+}
+
+struct Folder<'a> {
+    span: Span<BytePos>,
+    param: &'a Symbol,
+    self_name: &'a Symbol,
+    f: Symbol,
+    foldr: Symbol,
+    foldr_: Symbol,
+    foldl: Symbol,
+    foldl_: Symbol,
+}
+
+impl Folder<'_> {
+    /// Folds every `param` inside `value` into `acc`, from right to left
+    fn foldr_field<'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        symbols: &mut Symbols,
+        depth: usize,
+        value: SpannedExpr<'ast, Symbol>,
+        typ: &AstType<'ast, Symbol>,
+        acc: SpannedExpr<'ast, Symbol>,
+    ) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+        let span = self.span;
+        match ParamUse::new(typ, self.param, self.self_name) {
+            ParamUse::Unused => Ok(acc),
+            ParamUse::Param => Ok(arena.app(span, self.f.clone(), vec![value, acc])),
+            ParamUse::Applied { is_self, arg } => {
+                let foldr = if is_self { &self.foldr_ } else { &self.foldr };
+                let y = Symbol::from(format!("y_{}", depth));
+                let inner_acc = Symbol::from(format!("acc_{}", depth));
+                let inner = self.foldr_field(
+                    arena,
+                    symbols,
+                    depth + 1,
+                    ident(span, y.clone()),
+                    arg,
+                    ident(span, inner_acc.clone()),
+                )?;
+                let inner = lambda(arena, symbols, span, vec![y, inner_acc], inner);
+                Ok(arena.app(span, foldr.clone(), vec![inner, acc, value]))
+            }
+            ParamUse::Function { .. } | ParamUse::Unsupported => {
+                Err(unsupported_param_use("Foldable", self.param))
+            }
+        }
+    }
+
+    /// Folds every `param` inside `value` into `acc`, from left to right
+    fn foldl_field<'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        symbols: &mut Symbols,
+        depth: usize,
+        value: SpannedExpr<'ast, Symbol>,
+        typ: &AstType<'ast, Symbol>,
+        acc: SpannedExpr<'ast, Symbol>,
+    ) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+        let span = self.span;
+        match ParamUse::new(typ, self.param, self.self_name) {
+            ParamUse::Unused => Ok(acc),
+            ParamUse::Param => Ok(arena.app(span, self.f.clone(), vec![acc, value])),
+            ParamUse::Applied { is_self, arg } => {
+                let foldl = if is_self { &self.foldl_ } else { &self.foldl };
+                let y = Symbol::from(format!("y_{}", depth));
+                let inner_acc = Symbol::from(format!("acc_{}", depth));
+                let inner = self.foldl_field(
+                    arena,
+                    symbols,
+                    depth + 1,
+                    ident(span, y.clone()),
+                    arg,
+                    ident(span, inner_acc.clone()),
+                )?;
+                let inner = lambda(arena, symbols, span, vec![inner_acc, y], inner);
+                Ok(arena.app(span, foldl.clone(), vec![inner, acc, value]))
+            }
+            ParamUse::Function { .. } | ParamUse::Unsupported => {
+                Err(unsupported_param_use("Foldable", self.param))
+            }
+        }
+    }
+}
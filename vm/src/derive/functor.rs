@@ -0,0 +1,150 @@
+use crate::base::{
+    ast::{self, AstType, Expr, SpannedExpr, TypeBinding, ValueBinding},
+    pos::{self, BytePos, Span},
+    symbol::{Symbol, Symbols},
+    types::Type,
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let (param, self_type) = last_param_type(arena, "Functor", bind)?;
+    let functor_expr = functor_expr(arena, symbols, bind, &param)?;
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "functor", bind))),
+        ),
+        args: &mut [],
+        expr: functor_expr,
+        metadata: Default::default(),
+        typ: Some(constructor_binding_type(
+            arena, symbols, "Functor", self_type,
+        )),
+        resolved_type: Type::hole(),
+    })
+}
+
+/// Generates the `Functor` record which maps over `param`
+pub(crate) fn functor_expr<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+    param: &Symbol,
+) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let f = Symbol::from("f");
+    let x = Symbol::from("x");
+    let map_ = symbols.simple_symbol("map_");
+
+    let mapper = Mapper {
+        span,
+        param,
+        self_name: &bind.alias.value.name,
+        f: f.clone(),
+        map: symbols.simple_symbol("map"),
+        map_: map_.clone(),
+    };
+    let map_expr = match_fields(
+        arena,
+        span,
+        bind,
+        ident(span, x.clone()),
+        "Functor",
+        &mut |ctor, fields| {
+            let values = fields
+                .iter()
+                .map(|field| {
+                    mapper.map_field(arena, symbols, 0, ident(span, field.var.clone()), field.typ)
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(construct(arena, span, ctor, fields, values))
+        },
+    )?;
+
+    let functor_import = arena.generate_import(span, symbols, &[], &["map"], "std.functor");
+
+    let functor_record_expr = Expr::rec_let_bindings(
+        arena,
+        vec![function_binding(
+            arena,
+            span,
+            &map_,
+            &[f, x],
+            None,
+            map_expr,
+        )],
+        record(
+            arena,
+            symbols,
+            span,
+            vec![("map", ident(span, map_.clone()))],
+        ),
+    );
+
+    Ok(let_bindings(
+        arena,
+        span,
+        vec![functor_import],
+        pos::spanned(span, functor_record_expr),
+    ))
+}
+
+struct Mapper<'a> {
+    span: Span<BytePos>,
+    param: &'a Symbol,
+    self_name: &'a Symbol,
+    f: Symbol,
+    map: Symbol,
+    map_: Symbol,
+}
+
+impl Mapper<'_> {
+    /// Applies `f` to every `param` inside `value`
+    fn map_field<'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        symbols: &mut Symbols,
+        depth: usize,
+        value: SpannedExpr<'ast, Symbol>,
+        typ: &AstType<'ast, Symbol>,
+    ) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+        let span = self.span;
+        let y = Symbol::from(format!("y_{}", depth));
+        match ParamUse::new(typ, self.param, self.self_name) {
+            ParamUse::Unused => Ok(value),
+            ParamUse::Param => Ok(arena.app(span, self.f.clone(), Some(value))),
+            ParamUse::Applied { is_self, arg } => {
+                let map = if is_self { &self.map_ } else { &self.map };
+                let inner =
+                    self.map_field(arena, symbols, depth + 1, ident(span, y.clone()), arg)?;
+                let inner = lambda(arena, symbols, span, Some(y), inner);
+                Ok(arena.app(span, map.clone(), vec![inner, value]))
+            }
+            // Functions are mapped over by mapping their result
+            ParamUse::Function { ret } => {
+                let applied = pos::spanned(
+                    span,
+                    Expr::App {
+                        func: arena.alloc(value),
+                        implicit_args: &mut [],
+                        args: arena.alloc_extend(Some(ident(span, y.clone()))),
+                    },
+                );
+                let inner = self.map_field(arena, symbols, depth + 1, applied, ret)?;
+                Ok(lambda(arena, symbols, span, Some(y), inner))
+            }
+            ParamUse::Unsupported => Err(unsupported_param_use("Functor", self.param)),
+        }
+    }
+}
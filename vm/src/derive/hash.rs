@@ -0,0 +1,116 @@
+use crate::base::{
+    ast::{self, Expr, Literal, TypeBinding, ValueBinding},
+    pos,
+    symbol::{Symbol, Symbols},
+    types::{remove_forall, Type, TypeContext},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate<'ast>(
+    mut arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let x = Symbol::from("x");
+    let hasher = Symbol::from("hasher");
+
+    let hash_with = symbols.simple_symbol("hash_with");
+    let write_int = symbols.simple_symbol("write_int");
+    let hash_with_ = symbols.simple_symbol("hash_with_");
+
+    let mut self_type = {
+        let mut arena = arena;
+        move || bind.alias.value.self_type(&mut arena)
+    };
+
+    // Constructors are distinguished by hashing their index before their arguments
+    let is_variant = matches!(
+        **remove_forall(bind.alias.value.unresolved_type()),
+        Type::Variant(_)
+    );
+    let mut tag = 0;
+    let hash_expr = match_fields(
+        arena,
+        span,
+        bind,
+        ident(span, x.clone()),
+        "Hash",
+        &mut |_, fields| {
+            let init = if is_variant {
+                arena.app(
+                    span,
+                    write_int.clone(),
+                    vec![
+                        pos::spanned(span, Expr::Literal(Literal::Int(tag))),
+                        ident(span, hasher.clone()),
+                    ],
+                )
+            } else {
+                ident(span, hasher.clone())
+            };
+            tag += 1;
+
+            Ok(fields.iter().fold(init, |acc, field| {
+                let hash_fn = if is_self_type(&bind.alias.value.name, field.typ) {
+                    hash_with_.clone()
+                } else {
+                    hash_with.clone()
+                };
+                arena.app(span, hash_fn, vec![ident(span, field.var.clone()), acc])
+            }))
+        },
+    )?;
+
+    let hash_import = arena.generate_import_(
+        span,
+        symbols,
+        &[],
+        &["hash_with", "write_int"],
+        true,
+        "std.hash",
+    );
+
+    let hash_record_expr = Expr::rec_let_bindings(
+        arena,
+        vec![function_binding(
+            arena,
+            span,
+            &hash_with_,
+            &[x, hasher],
+            Some(
+                arena
+                    .clone()
+                    .function(vec![self_type(), arena.hole()], arena.hole()),
+            ),
+            hash_expr,
+        )],
+        record(
+            arena,
+            symbols,
+            span,
+            vec![("hash_with", ident(span, hash_with_.clone()))],
+        ),
+    );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "hash", bind))),
+        ),
+        args: &mut [],
+        expr: let_bindings(
+            arena,
+            span,
+            vec![hash_import],
+            pos::spanned(span, hash_record_expr),
+        ),
+        metadata: Default::default(),
+        typ: Some(binding_type(arena, symbols, "Hash", self_type(), bind)),
+        resolved_type: Type::hole(),
+    })
+}
@@ -1,20 +1,28 @@
 use crate::base::{
     ast::{
-        self, Argument, AstAlloc, AstType, Expr, Lambda, Literal, Pattern, PatternField,
-        SpannedExpr, SpannedPattern, TypeBinding, TypedIdent, ValueBinding,
+        self, Alternative, Argument, AstAlloc, AstType, Expr, ExprField, Lambda, Literal, Pattern,
+        PatternField, SpannedExpr, SpannedPattern, TypeBinding, TypedIdent, ValueBinding,
     },
     metadata::Attribute,
     pos::{self, BytePos, Span},
     symbol::{Symbol, Symbols},
-    types::{row_iter, KindedIdent, Type, TypeContext},
+    types::{
+        ctor_args, remove_forall, row_iter, walk_type, ArgType, KindedIdent, Type, TypeContext,
+    },
 };
 
 use crate::macros::Error;
 
+mod default;
 mod deserialize;
 mod eq;
+mod foldable;
+mod functor;
+mod hash;
+mod ord;
 mod serialize;
 mod show;
+mod traversable;
 
 pub fn generate<'ast>(
     arena: ast::ArenaRef<'_, 'ast, Symbol>,
@@ -23,24 +31,41 @@ pub fn generate<'ast>(
     bind: &TypeBinding<'ast, Symbol>,
 ) -> Result<Vec<ValueBinding<'ast, Symbol>>, Error> {
     match derive.arguments {
-        Some(ref args) => args
-            .split(',')
-            .map(|s| s.trim())
-            .map(|arg| {
-                Ok(match arg {
-                    "Eq" => eq::generate(arena, symbols, bind),
-                    "Show" => show::generate(arena, symbols, bind),
-                    "Deserialize" => deserialize::generate(arena, symbols, bind),
-                    "Serialize" => serialize::generate(arena, symbols, bind),
-                    _ => {
-                        return Err(Error::message(format!(
-                            "`{}` is not a type that can be derived",
-                            arg
-                        )));
-                    }
+        Some(ref args) => {
+            let args: Vec<_> = args.split(',').map(|s| s.trim()).collect();
+            // Instances which contain other instances reuse those that are derived alongside
+            // them, since two instances of the same type in scope make implicit resolution
+            // ambiguous
+            let derived = |name| args.contains(&name);
+            args.iter()
+                .map(|&arg| {
+                    Ok(match arg {
+                        "Eq" => eq::generate(arena, symbols, bind),
+                        "Show" => show::generate(arena, symbols, bind),
+                        "Deserialize" => deserialize::generate(arena, symbols, bind),
+                        "Serialize" => serialize::generate(arena, symbols, bind),
+                        "Hash" => hash::generate(arena, symbols, bind),
+                        "Ord" => ord::generate(arena, symbols, bind, derived("Eq")),
+                        "Functor" => functor::generate(arena, symbols, bind),
+                        "Foldable" => foldable::generate(arena, symbols, bind),
+                        "Traversable" => traversable::generate(
+                            arena,
+                            symbols,
+                            bind,
+                            derived("Functor"),
+                            derived("Foldable"),
+                        ),
+                        "Default" => default::generate(arena, symbols, bind),
+                        _ => {
+                            return Err(Error::message(format!(
+                                "`{}` is not a type that can be derived",
+                                arg
+                            )));
+                        }
+                    })
                 })
-            })
-            .collect::<Result<_, _>>()?,
+                .collect::<Result<_, _>>()?
+        }
         _ => Err(Error::message("Invalid `derive` attribute")),
     }
 }
@@ -307,3 +332,316 @@ fn binding_type<'ast>(
         ),
     )
 }
+
+/// A constructor argument or record field bound by `match_fields`
+struct BoundField<'a, 'ast> {
+    /// The name of the record field, `None` for constructor arguments
+    name: Option<&'a Symbol>,
+    var: Symbol,
+    typ: &'a AstType<'ast, Symbol>,
+}
+
+/// Generates a `match` on `scrutinee` with one alternative per constructor (or a single record
+/// pattern) which binds every field. The body of each alternative is generated by `body` which
+/// is passed the constructor (`None` for records) and the bound fields.
+fn match_fields<'a, 'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    span: Span<BytePos>,
+    bind: &'a TypeBinding<'ast, Symbol>,
+    scrutinee: SpannedExpr<'ast, Symbol>,
+    derive_name: &str,
+    body: &mut dyn FnMut(
+        Option<&'a Symbol>,
+        &[BoundField<'a, 'ast>],
+    ) -> Result<SpannedExpr<'ast, Symbol>, Error>,
+) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+    let alts = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Variant(ref row) => row_iter(row)
+            .map(|variant| {
+                let fields: Vec<_> = ctor_args(&variant.typ)
+                    .enumerate()
+                    .map(|(i, typ)| BoundField {
+                        name: None,
+                        var: Symbol::from(format!("arg_{}", i)),
+                        typ,
+                    })
+                    .collect();
+                Ok(Alternative {
+                    pattern: ctor_pattern(arena, span, &variant.name, &fields),
+                    guard: None,
+                    expr: body(Some(&variant.name), &fields)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?,
+        Type::Record(ref row) => {
+            let fields: Vec<_> = row_iter(row)
+                .enumerate()
+                .map(|(i, field)| BoundField {
+                    name: Some(&field.name),
+                    var: Symbol::from(format!("arg_{}", i)),
+                    typ: &field.typ,
+                })
+                .collect();
+            vec![Alternative {
+                pattern: arena.generate_record_pattern(
+                    span,
+                    row,
+                    fields
+                        .iter()
+                        .map(|field| TypedIdent::new(field.var.clone())),
+                ),
+                guard: None,
+                expr: body(None, &fields)?,
+            }]
+        }
+        _ => {
+            return Err(Error::message(format!(
+                "Unable to derive {} for this type",
+                derive_name
+            )))
+        }
+    };
+    Ok(pos::spanned(
+        span,
+        Expr::Match(arena.alloc(scrutinee), arena.alloc_extend(alts)),
+    ))
+}
+
+fn ctor_pattern<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    span: Span<BytePos>,
+    ctor: &Symbol,
+    fields: &[BoundField<'_, 'ast>],
+) -> SpannedPattern<'ast, Symbol> {
+    pos::spanned(
+        span,
+        Pattern::Constructor(
+            TypedIdent::new(ctor.clone()),
+            arena.alloc_extend(fields.iter().map(|field| {
+                pos::spanned(span, Pattern::Ident(TypedIdent::new(field.var.clone())))
+            })),
+        ),
+    )
+}
+
+/// Rebuilds the value matched by `match_fields` from new field values
+fn construct<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    span: Span<BytePos>,
+    ctor: Option<&Symbol>,
+    fields: &[BoundField<'_, 'ast>],
+    values: Vec<SpannedExpr<'ast, Symbol>>,
+) -> SpannedExpr<'ast, Symbol> {
+    match ctor {
+        Some(ctor) => arena.app(span, ctor.clone(), values),
+        None => pos::spanned(
+            span,
+            Expr::Record {
+                typ: Type::hole(),
+                types: &mut [],
+                exprs: arena.alloc_extend(fields.iter().zip(values).map(|(field, value)| {
+                    ExprField {
+                        metadata: Default::default(),
+                        name: pos::spanned(span, field.name.expect("Record field").clone()),
+                        value: Some(value),
+                    }
+                })),
+                base: None,
+            },
+        ),
+    }
+}
+
+fn lambda<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    span: Span<BytePos>,
+    args: impl IntoIterator<Item = Symbol>,
+    body: SpannedExpr<'ast, Symbol>,
+) -> SpannedExpr<'ast, Symbol> {
+    pos::spanned(
+        span,
+        Expr::Lambda(Lambda {
+            args: arena.alloc_extend(
+                args.into_iter()
+                    .map(|arg| Argument::explicit(pos::spanned(span, TypedIdent::new(arg)))),
+            ),
+            body: arena.alloc(body),
+            id: TypedIdent::new(symbols.simple_symbol("lambda")),
+        }),
+    )
+}
+
+/// Binds `name` to a (possibly recursive) function
+fn function_binding<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    span: Span<BytePos>,
+    name: &Symbol,
+    args: &[Symbol],
+    typ: Option<AstType<'ast, Symbol>>,
+    expr: SpannedExpr<'ast, Symbol>,
+) -> ValueBinding<'ast, Symbol> {
+    ValueBinding {
+        name: pos::spanned(span, Pattern::Ident(TypedIdent::new(name.clone()))),
+        args: arena.alloc_extend(
+            args.iter()
+                .map(|arg| Argument::explicit(pos::spanned(span, TypedIdent::new(arg.clone())))),
+        ),
+        expr,
+        metadata: Default::default(),
+        typ,
+        resolved_type: Type::hole(),
+    }
+}
+
+fn record<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    span: Span<BytePos>,
+    fields: Vec<(&str, SpannedExpr<'ast, Symbol>)>,
+) -> SpannedExpr<'ast, Symbol> {
+    pos::spanned(
+        span,
+        Expr::Record {
+            typ: Type::hole(),
+            types: &mut [],
+            exprs: arena.alloc_extend(fields.into_iter().map(|(name, value)| ExprField {
+                metadata: Default::default(),
+                name: pos::spanned(span, symbols.simple_symbol(name)),
+                value: Some(value),
+            })),
+            base: None,
+        },
+    )
+}
+
+/// Wraps `expr` in the `let` bindings of `binds`, in order
+fn let_bindings<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    span: Span<BytePos>,
+    binds: Vec<ValueBinding<'ast, Symbol>>,
+    expr: SpannedExpr<'ast, Symbol>,
+) -> SpannedExpr<'ast, Symbol> {
+    binds.into_iter().rev().fold(expr, |expr, bind| {
+        pos::spanned(span, Expr::let_binding(arena, bind, expr))
+    })
+}
+
+fn derived_name(symbols: &mut Symbols, prefix: &str, bind: &TypeBinding<Symbol>) -> Symbol {
+    symbols.simple_symbol(format!(
+        "{}_{}",
+        prefix,
+        bind.alias.value.name.declared_name()
+    ))
+}
+
+fn contains_generic(typ: &AstType<Symbol>, generic: &Symbol) -> bool {
+    let mut found = false;
+    walk_type(typ, |typ: &AstType<Symbol>| {
+        if let Type::Generic(ref gen) = **typ {
+            found |= gen.id == *generic;
+        }
+    });
+    found
+}
+
+/// Returns the parameter which `Functor`, `Foldable` and `Traversable` are derived over and the
+/// type they are derived for (the type applied to every other parameter)
+fn last_param_type<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    derive_name: &str,
+    bind: &TypeBinding<'ast, Symbol>,
+) -> Result<(Symbol, AstType<'ast, Symbol>), Error> {
+    let alias = &bind.alias.value;
+    let (last, init) = alias.params().split_last().ok_or_else(|| {
+        Error::message(format!(
+            "`{}` can only be derived for types with at least one type parameter",
+            derive_name
+        ))
+    })?;
+    let f = arena.clone().ident(KindedIdent::new(alias.name.clone()));
+    let typ = if init.is_empty() {
+        f
+    } else {
+        TypeContext::app(
+            &mut arena.clone(),
+            f,
+            arena
+                .clone()
+                .alloc_extend(init.iter().cloned().map(|g| arena.clone().generic(g))),
+        )
+    };
+    Ok((last.id.clone(), typ))
+}
+
+/// The type of an instance derived for a type constructor, `Functor (T a)` for `type T a b`
+fn constructor_binding_type<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    derive_type_name: &str,
+    self_type: AstType<'ast, Symbol>,
+) -> AstType<'ast, Symbol> {
+    let derive_type = arena
+        .clone()
+        .ident(KindedIdent::new(symbols.simple_symbol(derive_type_name)));
+    TypeContext::app(
+        &mut arena.clone(),
+        derive_type,
+        arena.clone().alloc_extend(Some(self_type)),
+    )
+}
+
+/// How the type of a field uses the type parameter an instance is derived over
+enum ParamUse<'a, 'ast> {
+    /// The field does not contain the parameter
+    Unused,
+    /// The field is the parameter itself
+    Param,
+    /// The field is `F .. t` where only `t` contains the parameter. `is_self` is set if `F` is the
+    /// type the instance is derived for.
+    Applied {
+        is_self: bool,
+        arg: &'a AstType<'ast, Symbol>,
+    },
+    /// The field is a function which only contains the parameter in its return type
+    Function { ret: &'a AstType<'ast, Symbol> },
+    /// The parameter is used in a way which can't be derived
+    Unsupported,
+}
+
+impl<'a, 'ast> ParamUse<'a, 'ast> {
+    fn new(typ: &'a AstType<'ast, Symbol>, param: &Symbol, self_name: &Symbol) -> Self {
+        if !contains_generic(typ, param) {
+            return ParamUse::Unused;
+        }
+        match **typ {
+            Type::Generic(_) => ParamUse::Param,
+            Type::App(ref f, ref args) => match args.split_last() {
+                Some((last, init))
+                    if !contains_generic(f, param)
+                        && !init.iter().any(|arg| contains_generic(arg, param)) =>
+                {
+                    ParamUse::Applied {
+                        is_self: is_self_type(self_name, typ),
+                        arg: last,
+                    }
+                }
+                _ => ParamUse::Unsupported,
+            },
+            Type::Function(ArgType::Explicit, ref arg, ref ret)
+                if !contains_generic(arg, param) =>
+            {
+                ParamUse::Function { ret }
+            }
+            _ => ParamUse::Unsupported,
+        }
+    }
+}
+
+fn unsupported_param_use(derive_name: &str, param: &Symbol) -> Error {
+    Error::message(format!(
+        "Unable to derive {} as `{}` is used in an unsupported position",
+        derive_name,
+        param.declared_name()
+    ))
+}
@@ -0,0 +1,301 @@
+use crate::base::{
+    ast::{self, Alternative, Expr, Literal, Pattern, TypeBinding, TypedIdent, ValueBinding},
+    pos,
+    symbol::{Symbol, Symbols},
+    types::{ctor_args, remove_forall, row_iter, Type, TypeContext},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate<'ast>(
+    mut arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+    derived_eq: bool,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let compare = symbols.simple_symbol("compare");
+    let compare_ = symbols.simple_symbol("compare_");
+    let tag = Symbol::from("tag");
+    let l = Symbol::from("l");
+    let r = Symbol::from("r");
+    let x = Symbol::from("x");
+
+    let mut self_type = {
+        let mut arena = arena;
+        move || bind.alias.value.self_type(&mut arena)
+    };
+
+    let ctor = |symbols: &mut Symbols, name: &str| {
+        pos::spanned(
+            span,
+            Pattern::Constructor(TypedIdent::new(symbols.simple_symbol(name)), &mut []),
+        )
+    };
+
+    // Compares each pair of fields in order, stopping at the first pair which is not equal
+    let generate_comparison_chain = |symbols: &mut Symbols, fields: &[(bool, Symbol, Symbol)]| {
+        fields
+            .iter()
+            .rev()
+            .fold(None, |acc, (self_type, l, r)| {
+                let compare_fn = if *self_type {
+                    compare_.clone()
+                } else {
+                    compare.clone()
+                };
+                let comparison = arena.app(
+                    span,
+                    compare_fn,
+                    vec![ident(span, l.clone()), ident(span, r.clone())],
+                );
+                Some(match acc {
+                    Some(rest) => {
+                        let ordering = Symbol::from("ordering");
+                        pos::spanned(
+                            span,
+                            Expr::Match(
+                                arena.alloc(comparison),
+                                arena.alloc_extend(vec![
+                                    Alternative {
+                                        pattern: ctor(symbols, "EQ"),
+                                        guard: None,
+                                        expr: rest,
+                                    },
+                                    Alternative {
+                                        pattern: pos::spanned(
+                                            span,
+                                            Pattern::Ident(TypedIdent::new(ordering.clone())),
+                                        ),
+                                        guard: None,
+                                        expr: ident(span, ordering),
+                                    },
+                                ]),
+                            ),
+                        )
+                    }
+                    None => comparison,
+                })
+            })
+            .unwrap_or_else(|| ident(span, symbols.simple_symbol("EQ")))
+    };
+
+    let matcher = arena.alloc(pos::spanned(
+        span,
+        Expr::Tuple {
+            typ: Type::hole(),
+            elems: arena.alloc_extend(vec![ident(span, l.clone()), ident(span, r.clone())]),
+        },
+    ));
+
+    let tuple_pattern = |l_pattern, r_pattern| {
+        pos::spanned(
+            span,
+            Pattern::Tuple {
+                typ: Type::hole(),
+                elems: arena.alloc_extend(vec![l_pattern, r_pattern]),
+            },
+        )
+    };
+
+    let mut tag_binding = None;
+    let comparison_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Variant(ref variants) => {
+            let mut alts = Vec::new();
+            for variant in row_iter(variants) {
+                let fields: Vec<_> = ctor_args(&variant.typ)
+                    .enumerate()
+                    .map(|(i, typ)| {
+                        (
+                            is_self_type(&bind.alias.value.name, typ),
+                            Symbol::from(format!("arg_l_{}", i)),
+                            Symbol::from(format!("arg_r_{}", i)),
+                        )
+                    })
+                    .collect();
+
+                let ctor_pattern = |args: &mut dyn Iterator<Item = &Symbol>| {
+                    pos::spanned(
+                        span,
+                        Pattern::Constructor(
+                            TypedIdent::new(variant.name.clone()),
+                            arena.alloc_extend(args.map(|arg| {
+                                pos::spanned(span, Pattern::Ident(TypedIdent::new(arg.clone())))
+                            })),
+                        ),
+                    )
+                };
+                alts.push(Alternative {
+                    pattern: tuple_pattern(
+                        ctor_pattern(&mut fields.iter().map(|field| &field.1)),
+                        ctor_pattern(&mut fields.iter().map(|field| &field.2)),
+                    ),
+                    guard: None,
+                    expr: generate_comparison_chain(symbols, &fields),
+                });
+            }
+
+            // Different constructors are ordered by the order they are declared in
+            if alts.len() > 1 {
+                let mut index = 0;
+                let tag_expr = match_fields(
+                    arena,
+                    span,
+                    bind,
+                    ident(span, x.clone()),
+                    "Ord",
+                    &mut |_, _| {
+                        index += 1;
+                        Ok(pos::spanned(span, Expr::Literal(Literal::Int(index - 1))))
+                    },
+                )?;
+                tag_binding = Some(function_binding(arena, span, &tag, &[x], None, tag_expr));
+
+                let tag_of =
+                    |s: &Symbol| arena.app(span, tag.clone(), Some(ident(span, s.clone())));
+                alts.push(Alternative {
+                    pattern: pos::spanned(
+                        span,
+                        Pattern::Ident(TypedIdent::new(symbols.simple_symbol("_"))),
+                    ),
+                    guard: None,
+                    expr: pos::spanned(
+                        span,
+                        Expr::IfElse(
+                            arena.alloc(arena.infix(
+                                span,
+                                tag_of(&l),
+                                symbols.simple_symbol("#Int<"),
+                                tag_of(&r),
+                            )),
+                            arena.alloc(ident(span, symbols.simple_symbol("LT"))),
+                            arena.alloc(ident(span, symbols.simple_symbol("GT"))),
+                        ),
+                    ),
+                });
+            }
+
+            Expr::Match(matcher, arena.alloc_extend(alts))
+        }
+        Type::Record(ref row) => {
+            let fields: Vec<_> = row_iter(row)
+                .map(|field| {
+                    (
+                        is_self_type(&bind.alias.value.name, &field.typ),
+                        Symbol::from(format!("{}_l", field.name.declared_name())),
+                        Symbol::from(format!("{}_r", field.name.declared_name())),
+                    )
+                })
+                .collect();
+
+            let expr = generate_comparison_chain(symbols, &fields);
+            Expr::Match(
+                matcher,
+                arena.alloc_extend(vec![Alternative {
+                    pattern: tuple_pattern(
+                        arena.generate_record_pattern(
+                            span,
+                            row,
+                            fields.iter().map(|field| TypedIdent::new(field.1.clone())),
+                        ),
+                        arena.generate_record_pattern(
+                            span,
+                            row,
+                            fields.iter().map(|field| TypedIdent::new(field.2.clone())),
+                        ),
+                    ),
+                    guard: None,
+                    expr,
+                }]),
+            )
+        }
+        _ => return Err(Error::message("Unable to derive Ord for this type")),
+    };
+
+    let compare_binding = function_binding(
+        arena,
+        span,
+        &compare_,
+        &[l.clone(), r.clone()],
+        Some(
+            arena
+                .clone()
+                .function(vec![self_type(), self_type()], arena.hole()),
+        ),
+        pos::spanned(span, comparison_expr),
+    );
+
+    // `l == r` exactly when `compare_ l r` returns `EQ`
+    let eq_expr = if derived_eq {
+        ident(span, derived_name(symbols, "eq", bind))
+    } else {
+        let (l, r) = (Symbol::from("l"), Symbol::from("r"));
+        let comparison = arena.app(
+            span,
+            compare_.clone(),
+            vec![ident(span, l.clone()), ident(span, r.clone())],
+        );
+        let is_equal = pos::spanned(
+            span,
+            Expr::Match(
+                arena.alloc(comparison),
+                arena.alloc_extend(vec![
+                    Alternative {
+                        pattern: ctor(symbols, "EQ"),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("True")),
+                    },
+                    Alternative {
+                        pattern: pos::spanned(
+                            span,
+                            Pattern::Ident(TypedIdent::new(symbols.simple_symbol("_"))),
+                        ),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("False")),
+                    },
+                ]),
+            ),
+        );
+        let eq_fn = lambda(arena, symbols, span, vec![l, r], is_equal);
+        record(arena, symbols, span, vec![("==", eq_fn)])
+    };
+
+    let ord_record_expr = Expr::rec_let_bindings(
+        arena,
+        vec![compare_binding],
+        record(
+            arena,
+            symbols,
+            span,
+            vec![("eq", eq_expr), ("compare", ident(span, compare_.clone()))],
+        ),
+    );
+
+    let cmp_import = arena.generate_import(
+        span,
+        symbols,
+        &["Bool", "Ordering"],
+        &["compare"],
+        "std.cmp",
+    );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "ord", bind))),
+        ),
+        args: &mut [],
+        expr: let_bindings(
+            arena,
+            span,
+            Some(cmp_import).into_iter().chain(tag_binding).collect(),
+            pos::spanned(span, ord_record_expr),
+        ),
+        metadata: Default::default(),
+        typ: Some(binding_type(arena, symbols, "Ord", self_type(), bind)),
+        resolved_type: Type::hole(),
+    })
+}
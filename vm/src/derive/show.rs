@@ -91,6 +91,7 @@ pub fn generate<'ast>(
                     };
                     Alternative {
                         pattern: ctor_pattern(pattern_args.into_iter().map(|t| t.1).collect()),
+                        guard: None,
                         expr,
                     }
                 })
@@ -153,6 +154,7 @@ pub fn generate<'ast>(
                 arena.alloc(ident(span, x.clone())),
                 arena.alloc_extend(Some(Alternative {
                     pattern: arena.generate_record_pattern(span, row, field_symbols),
+                    guard: None,
                     expr,
                 })),
             )
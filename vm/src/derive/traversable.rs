@@ -0,0 +1,231 @@
+use crate::base::{
+    ast::{self, AstType, Expr, SpannedExpr, TypeBinding, ValueBinding},
+    kind::Kind,
+    pos::{self, BytePos, Span},
+    symbol::{Symbol, Symbols},
+    types::{Generic, KindedIdent, Type, TypeContext},
+};
+
+use crate::macros::Error;
+
+use crate::derive::{foldable::foldable_expr, functor::functor_expr, *};
+
+pub fn generate<'ast>(
+    mut arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    bind: &TypeBinding<'ast, Symbol>,
+    derived_functor: bool,
+    derived_foldable: bool,
+) -> Result<ValueBinding<'ast, Symbol>, Error> {
+    let span = bind.name.span;
+
+    let (param, self_type) = last_param_type(arena, "Traversable", bind)?;
+
+    let app = Symbol::from("app");
+    let f = Symbol::from("f");
+    let x = Symbol::from("x");
+    let traverse_ = symbols.simple_symbol("traverse_");
+
+    let traverser = Traverser {
+        span,
+        param: &param,
+        self_name: &bind.alias.value.name,
+        app: app.clone(),
+        f: f.clone(),
+        traverse: symbols.simple_symbol("traverse"),
+        traverse_: traverse_.clone(),
+    };
+    let traverse_expr = match_fields(
+        arena,
+        span,
+        bind,
+        ident(span, x.clone()),
+        "Traversable",
+        &mut |ctor, fields| {
+            // The fields which do not contain `param` are passed directly to the constructor while
+            // the rest are combined with `apply`
+            let mut args = Vec::new();
+            let mut actions = Vec::new();
+            let mut values = Vec::new();
+            for (i, field) in fields.iter().enumerate() {
+                let value = ident(span, field.var.clone());
+                match ParamUse::new(field.typ, &param, &bind.alias.value.name) {
+                    ParamUse::Unused => values.push(value),
+                    _ => {
+                        let arg = Symbol::from(format!("field_{}", i));
+                        values.push(ident(span, arg.clone()));
+                        args.push(arg);
+                        actions
+                            .push(traverser.traverse_field(arena, symbols, 0, value, field.typ)?);
+                    }
+                }
+            }
+
+            let constructed = construct(arena, span, ctor, fields, values);
+            let wrapped = if args.is_empty() {
+                traverser.applicative_app(arena, symbols, "wrap", vec![constructed])
+            } else {
+                let constructor = lambda(arena, symbols, span, args, constructed);
+                traverser.applicative_app(arena, symbols, "wrap", vec![constructor])
+            };
+            Ok(actions.into_iter().fold(wrapped, |acc, action| {
+                traverser.applicative_app(arena, symbols, "apply", vec![acc, action])
+            }))
+        },
+    )?;
+
+    // `m` must be rigid, otherwise any applicative in scope could be used to resolve the implicit
+    // arguments of `traverse`
+    let m = Generic::new(Symbol::from("m"), Kind::hole());
+    let applicative_type = TypeContext::app(
+        &mut arena.clone(),
+        arena
+            .clone()
+            .ident(KindedIdent::new(symbols.simple_symbol("Applicative"))),
+        arena
+            .clone()
+            .alloc_extend(Some(arena.clone().generic(m.clone()))),
+    );
+    let traverse_type = arena.clone().function(
+        vec![applicative_type, arena.hole(), arena.hole()],
+        arena.hole(),
+    );
+    let traverse_binding = function_binding(
+        arena,
+        span,
+        &traverse_,
+        &[app, f, x],
+        Some(
+            arena
+                .clone()
+                .forall(arena.alloc_extend(Some(m)), traverse_type),
+        ),
+        traverse_expr,
+    );
+
+    let functor = if derived_functor {
+        ident(span, derived_name(symbols, "functor", bind))
+    } else {
+        functor_expr(arena, symbols, bind, &param)?
+    };
+    let foldable = if derived_foldable {
+        ident(span, derived_name(symbols, "foldable", bind))
+    } else {
+        foldable_expr(arena, symbols, bind, &param)?
+    };
+    let traversable_record_expr = Expr::rec_let_bindings(
+        arena,
+        vec![traverse_binding],
+        record(
+            arena,
+            symbols,
+            span,
+            vec![
+                ("functor", functor),
+                ("foldable", foldable),
+                ("traverse", ident(span, traverse_.clone())),
+            ],
+        ),
+    );
+
+    let applicative_import =
+        arena.generate_import(span, symbols, &["Applicative"], &[], "std.applicative");
+    let traversable_import =
+        arena.generate_import(span, symbols, &[], &["traverse"], "std.traversable");
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_name(symbols, "traversable", bind))),
+        ),
+        args: &mut [],
+        expr: let_bindings(
+            arena,
+            span,
+            vec![applicative_import, traversable_import],
+            pos::spanned(span, traversable_record_expr),
+        ),
+        metadata: Default::default(),
+        typ: Some(constructor_binding_type(
+            arena,
+            symbols,
+            "Traversable",
+            self_type,
+        )),
+        resolved_type: Type::hole(),
+    })
+}
+
+struct Traverser<'a> {
+    span: Span<BytePos>,
+    param: &'a Symbol,
+    self_name: &'a Symbol,
+    app: Symbol,
+    f: Symbol,
+    traverse: Symbol,
+    traverse_: Symbol,
+}
+
+impl Traverser<'_> {
+    /// Generates `app.<name> args..`
+    fn applicative_app<'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        symbols: &mut Symbols,
+        name: &str,
+        args: Vec<SpannedExpr<'ast, Symbol>>,
+    ) -> SpannedExpr<'ast, Symbol> {
+        let span = self.span;
+        let func = pos::spanned(
+            span,
+            Expr::Projection(
+                arena.alloc(ident(span, self.app.clone())),
+                symbols.simple_symbol(name),
+                Type::hole(),
+            ),
+        );
+        pos::spanned(
+            span,
+            Expr::App {
+                func: arena.alloc(func),
+                implicit_args: &mut [],
+                args: arena.alloc_extend(args),
+            },
+        )
+    }
+
+    /// Generates an action which applies `f` to every `param` inside `value`
+    fn traverse_field<'ast>(
+        &self,
+        arena: ast::ArenaRef<'_, 'ast, Symbol>,
+        symbols: &mut Symbols,
+        depth: usize,
+        value: SpannedExpr<'ast, Symbol>,
+        typ: &AstType<'ast, Symbol>,
+    ) -> Result<SpannedExpr<'ast, Symbol>, Error> {
+        let span = self.span;
+        match ParamUse::new(typ, self.param, self.self_name) {
+            ParamUse::Unused => Ok(self.applicative_app(arena, symbols, "wrap", vec![value])),
+            ParamUse::Param => Ok(arena.app(span, self.f.clone(), Some(value))),
+            ParamUse::Applied { is_self, arg } => {
+                let y = Symbol::from(format!("y_{}", depth));
+                let inner =
+                    self.traverse_field(arena, symbols, depth + 1, ident(span, y.clone()), arg)?;
+                let inner = lambda(arena, symbols, span, Some(y), inner);
+                Ok(if is_self {
+                    arena.app(
+                        span,
+                        self.traverse_.clone(),
+                        vec![ident(span, self.app.clone()), inner, value],
+                    )
+                } else {
+                    // The applicative is resolved implicitly from `app`
+                    arena.app(span, self.traverse.clone(), vec![inner, value])
+                })
+            }
+            ParamUse::Function { .. } | ParamUse::Unsupported => {
+                Err(unsupported_param_use("Traversable", self.param))
+            }
+        }
+    }
+}
@@ -231,6 +231,10 @@ pub struct Gc {
     values: Option<AllocPtr>,
     /// How many bytes which is currently allocated
     allocated_memory: usize,
+    /// How many bytes which has been allocated in total (including memory which has since been
+    /// collected)
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    total_allocated_memory: u64,
     /// How many bytes this garbage collector can allocate before a collection is run
     collect_limit: usize,
     /// The maximum number of bytes this garbage collector may contain
@@ -1062,6 +1066,7 @@ impl Gc {
         Gc {
             values: None,
             allocated_memory: 0,
+            total_allocated_memory: 0,
             collect_limit: 100,
             memory_limit: memory_limit,
             type_infos: FnvMap::default(),
@@ -1076,6 +1081,12 @@ impl Gc {
         self.allocated_memory
     }
 
+    /// Returns the number of bytes allocated since this garbage collector was created, including
+    /// memory which has since been collected
+    pub fn total_allocated_memory(&self) -> u64 {
+        self.total_allocated_memory
+    }
+
     pub fn set_memory_limit(&mut self, memory_limit: usize) {
         self.memory_limit = memory_limit;
     }
@@ -1250,6 +1261,7 @@ impl Gc {
         let mut ptr = AllocPtr::new::<D::Value>(type_info, size);
         ptr.next = self.values.take();
         self.allocated_memory += ptr.size();
+        self.total_allocated_memory += ptr.size() as u64;
         unsafe {
             let p: *mut D::Value = D::Value::make_ptr(&def, ptr.value());
             let ret: *const D::Value = &*def.initialize(WriteOnly::new(p));
@@ -7,6 +7,8 @@ extern crate collect_mac;
 #[doc(hidden)]
 pub extern crate frunk_core;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 #[macro_use]
 extern crate quick_error;
@@ -49,6 +51,7 @@ pub mod gc;
 
 #[macro_use]
 pub mod api;
+pub mod buffer;
 pub mod channel;
 pub mod compiler;
 pub mod core;
@@ -57,7 +60,9 @@ pub mod dynamic;
 pub mod lazy;
 pub mod macros;
 pub mod primitives;
+pub mod profiler;
 pub mod reference;
+pub mod sandbox;
 pub mod stack;
 pub mod thread;
 pub mod types;
@@ -186,6 +191,9 @@ quick_error! {
         Interrupted {
             display("Thread was interrupted")
         }
+        OutOfFuel {
+            display("Thread ran out of fuel")
+        }
         Panic(err: String, stacktrace: Option<Stacktrace>) {
             display("{}", Panic { err, stacktrace })
         }
@@ -16,15 +16,17 @@ use {
 use gluon_codegen::Trace;
 
 use crate::base::{
-    ast::{self, Expr, MutVisitor, SpannedExpr},
+    ast::{self, Expr, InterpolationPart, MutVisitor, SpannedExpr},
     error::{AsDiagnostic, Errors as BaseErrors},
     fnv::FnvMap,
     pos,
-    pos::{BytePos, Spanned},
+    pos::{BytePos, Span, Spanned},
     symbol::{Symbol, Symbols},
+    types::Type,
 };
 
 use crate::{
+    derive::ArenaExt,
     gc::Trace,
     thread::{RootedThread, Thread},
 };
@@ -396,7 +398,11 @@ pub struct MacroExpander<'a> {
     pub errors: Errors,
     pub userdata: &'a (dyn MacroUserdata + 'a),
     pub spawn: Option<&'a (dyn Spawn + Send + Sync + 'a)>,
+    /// The symbols of the expression which is currently being expanded. Only set while the
+    /// macros found by `run_once` are expanded.
+    pub symbols: Symbols,
     macros: &'a MacroEnv,
+    local_macros: FnvMap<String, Arc<dyn Macro>>,
 }
 
 impl<'a> MacroExpander<'a> {
@@ -408,7 +414,9 @@ impl<'a> MacroExpander<'a> {
         MacroExpander {
             vm,
             state: FnvMap::default(),
+            symbols: Symbols::default(),
             macros: vm.get_macros(),
+            local_macros: FnvMap::default(),
             userdata,
             spawn,
             errors: Errors::new(),
@@ -419,13 +427,28 @@ impl<'a> MacroExpander<'a> {
         MacroExpander {
             vm: self.vm,
             state: FnvMap::default(),
+            symbols: Symbols::default(),
             macros: self.macros,
+            local_macros: FnvMap::default(),
             userdata: self.userdata,
             spawn: self.spawn,
             errors: Errors::new(),
         }
     }
 
+    /// Inserts a macro which is only available in the expression being expanded by this
+    /// `MacroExpander`. Macros from the `MacroEnv` take precedence over local macros.
+    pub fn insert_local(&mut self, name: String, mac: Arc<dyn Macro>) {
+        self.local_macros.insert(name, mac);
+    }
+
+    /// Retrieves the macro bound to `name`, looking in both the `MacroEnv` and the local macros
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Macro>> {
+        self.macros
+            .get(name)
+            .or_else(|| self.local_macros.get(name).cloned())
+    }
+
     pub fn finish(self) -> Result<(), Errors> {
         if self.errors.has_errors() {
             Err(self.errors)
@@ -449,24 +472,65 @@ impl<'a> MacroExpander<'a> {
         arena: &mut ast::OwnedArena<'ast, Symbol>,
         expr: &mut SpannedExpr<'ast, Symbol>,
     ) {
-        let mut visitor = MacroVisitor {
-            expander: self,
-            symbols,
-            arena,
-            exprs: Vec::new(),
-        };
-        visitor.visit_expr(expr);
-        let MacroVisitor { exprs, .. } = visitor;
-        self.expand(arena, exprs).await
+        let mut pending = vec![expr];
+        while !pending.is_empty() {
+            let mut visitor = MacroVisitor {
+                expander: self,
+                symbols,
+                arena,
+                exprs: Vec::new(),
+            };
+            for expr in pending.drain(..) {
+                visitor.visit_expr(expr);
+            }
+            let MacroVisitor { exprs, .. } = visitor;
+            mem::swap(&mut self.symbols, symbols);
+            pending = self.expand(arena, exprs).await;
+            mem::swap(&mut self.symbols, symbols);
+        }
     }
 
-    async fn expand<'ast>(
+    /// Runs `run_once` on `expr` with the symbols of the expression currently being expanded.
+    /// Intended to be called from `Macro::expand` on the expressions that a macro returns.
+    pub async fn run_nested<'ast>(
         &mut self,
         arena: &mut ast::OwnedArena<'ast, Symbol>,
-        mut exprs: Vec<(&'_ mut SpannedExpr<'ast, Symbol>, Arc<dyn Macro>)>,
+        expr: &mut SpannedExpr<'ast, Symbol>,
     ) {
+        let mut symbols = mem::take(&mut self.symbols);
+        self.run_once(&mut symbols, arena, expr).await;
+        self.symbols = symbols;
+    }
+
+    /// Expands the macros in `exprs`, returning the arguments of the applications which turned out
+    /// to not be macros as they still need to be expanded
+    async fn expand<'e, 'ast>(
+        &mut self,
+        arena: &mut ast::OwnedArena<'ast, Symbol>,
+        mut exprs: Vec<(&'e mut SpannedExpr<'ast, Symbol>, Option<Arc<dyn Macro>>)>,
+    ) -> Vec<&'e mut SpannedExpr<'ast, Symbol>> {
+        let mut pending = Vec::new();
         let mut futures = Vec::with_capacity(exprs.len());
         for (expr, mac) in exprs.drain(..) {
+            let mac = mac.or_else(|| match &expr.value {
+                Expr::App { func, .. } => match &func.value {
+                    Expr::Ident(id) => {
+                        let name = id.name.as_ref();
+                        self.local_macros.get(&name[..name.len() - 1]).cloned()
+                    }
+                    _ => None,
+                },
+                _ => None,
+            });
+            let mac = match mac {
+                Some(mac) => mac,
+                None => {
+                    if let Expr::App { args, .. } = &mut expr.value {
+                        pending.extend(args.iter_mut());
+                    }
+                    continue;
+                }
+            };
             let result = match &mut expr.value {
                 Expr::App { args, .. } => mac.expand(self, arena, args).await,
                 _ => unreachable!("{:?}", expr),
@@ -495,6 +559,8 @@ impl<'a> MacroExpander<'a> {
 
             replace_expr(arena, expr, new_expr);
         }
+
+        pending
     }
 }
 
@@ -514,11 +580,31 @@ fn replace_expr<'ast>(
     );
 }
 
+/// Returns the expression `(import! <module>).<field>`
+fn import_field<'ast>(
+    arena: ast::ArenaRef<'_, 'ast, Symbol>,
+    symbols: &mut Symbols,
+    span: Span<BytePos>,
+    module: &str,
+    field: &str,
+) -> SpannedExpr<'ast, Symbol> {
+    let module = arena.project(span, symbols, module);
+    let import = arena.app(span, symbols.simple_symbol("import!"), Some(module));
+    pos::spanned(
+        span,
+        Expr::Projection(
+            arena.alloc(import),
+            symbols.simple_symbol(field),
+            Type::hole(),
+        ),
+    )
+}
+
 struct MacroVisitor<'a: 'b, 'b, 'c, 'd, 'e, 'ast> {
     expander: &'b mut MacroExpander<'a>,
     symbols: &'c mut Symbols,
     arena: &'d mut ast::OwnedArena<'ast, Symbol>,
-    exprs: Vec<(&'e mut SpannedExpr<'ast, Symbol>, Arc<dyn Macro>)>,
+    exprs: Vec<(&'e mut SpannedExpr<'ast, Symbol>, Option<Arc<dyn Macro>>)>,
 }
 
 impl<'a, 'b, 'c, 'e, 'ast> MutVisitor<'e, 'ast> for MacroVisitor<'a, 'b, 'c, '_, 'e, 'ast> {
@@ -540,11 +626,11 @@ impl<'a, 'b, 'c, 'e, 'ast> MutVisitor<'e, 'ast> for MacroVisitor<'a, 'b, 'c, '_,
                     }
 
                     let name = id.name.as_ref();
-                    match self.expander.macros.get(&name[..name.len() - 1]) {
-                        // FIXME Avoid cloning args
-                        Some(m) => Some(m.clone()),
-                        None => None,
-                    }
+                    let name = &name[..name.len() - 1];
+                    // FIXME Avoid cloning args
+                    // Local macros are only known once the macros preceding them have been
+                    // expanded so looking them up is deferred until then
+                    Some(self.expander.macros.get(name))
                 }
                 _ => None,
             },
@@ -581,6 +667,25 @@ impl<'a, 'b, 'c, 'e, 'ast> MutVisitor<'e, 'ast> for MacroVisitor<'a, 'b, 'c, '_,
                 }
                 None
             }
+            Expr::Interpolation(interpolation) => {
+                // `show` and `++` are imported explicitly instead of being looked up in the scope
+                // of the string, so local bindings can't change what they refer to
+                let arena = self.arena.borrow();
+                if interpolation.append_id.is_none() {
+                    let append = import_field(arena, self.symbols, expr.span, "std.string", "++");
+                    interpolation.append_id = Some(arena.alloc(append));
+                }
+                for part in &mut *interpolation.parts {
+                    if let InterpolationPart::Expr { expr, show_id } = part {
+                        if show_id.is_none() {
+                            let show =
+                                import_field(arena, self.symbols, expr.span, "std.show", "show");
+                            *show_id = Some(arena.alloc(show));
+                        }
+                    }
+                }
+                None
+            }
             _ => None,
         };
         if let Some(future) = replacement {
@@ -1,5 +1,6 @@
 //! Module containing functions for interacting with gluon's primitive types.
 use crate::real_std::{
+    convert::TryFrom,
     ffi::OsStr,
     fs, io,
     marker::PhantomData,
@@ -19,7 +20,9 @@ use crate::{
         VmType, WithVM, IO,
     },
     gc::{DataDef, Trace, WriteOnly},
+    sandbox::Capability,
     stack::{ExternState, StackFrame},
+    thread::{overflow_check, zero_check},
     types::VmInt,
     value::{GcStr, Repr, ValueArray},
     vm::{Status, Thread},
@@ -538,19 +541,31 @@ pub fn load_int(vm: &Thread) -> Result<ExternModule> {
             to_le => primitive!(1, std::int::prim::to_le),
             pow => primitive!(2, std::int::prim::pow),
             abs => primitive!(1, std::int::prim::abs),
+            checked_add => primitive!(2, std::int::prim::checked_add),
+            checked_sub => primitive!(2, std::int::prim::checked_sub),
+            checked_mul => primitive!(2, std::int::prim::checked_mul),
+            checked_div => primitive!(2, std::int::prim::checked_div),
             saturating_add => primitive!(2, std::int::prim::saturating_add),
             saturating_sub => primitive!(2, std::int::prim::saturating_sub),
             saturating_mul => primitive!(2, std::int::prim::saturating_mul),
             wrapping_add => primitive!(2, std::int::prim::wrapping_add),
             wrapping_sub => primitive!(2, std::int::prim::wrapping_sub),
             wrapping_mul => primitive!(2, std::int::prim::wrapping_mul),
-            wrapping_div => primitive!(2, std::int::prim::wrapping_div),
+            wrapping_div => primitive!(
+                2,
+                "std.int.prim.wrapping_div",
+                |l: VmInt, r: VmInt| RuntimeResult::from(zero_check(r).map(|()| l.wrapping_div(r)))
+            ),
             wrapping_abs => primitive!(1, std::int::prim::wrapping_abs),
             wrapping_negate => primitive!(1, "std.int.prim.wrapping_negate", std::int::prim::wrapping_neg),
             overflowing_add => primitive!(2, std::int::prim::overflowing_add),
             overflowing_sub => primitive!(2, std::int::prim::overflowing_sub),
             overflowing_mul => primitive!(2, std::int::prim::overflowing_mul),
-            overflowing_div => primitive!(2, std::int::prim::overflowing_div),
+            overflowing_div => primitive!(
+                2,
+                "std.int.prim.overflowing_div",
+                |l: VmInt, r: VmInt| RuntimeResult::from(zero_check(r).map(|()| l.overflowing_div(r)))
+            ),
             overflowing_abs => primitive!(1, std::int::prim::overflowing_abs),
             overflowing_negate => primitive!(1, "std.int.prim.overflowing_negate", std::int::prim::overflowing_neg),
             signum => primitive!(1, std::int::prim::signum),
@@ -563,6 +578,273 @@ pub fn load_int(vm: &Thread) -> Result<ExternModule> {
     )
 }
 
+macro_rules! sized_int_module {
+    ($load: ident, $module: literal, $typ: ident($repr: ident) { $($extra: tt)* }) => {
+        #[allow(non_camel_case_types)]
+        pub fn $load(vm: &Thread) -> Result<ExternModule> {
+            use crate::api::$typ;
+
+            ExternModule::new(
+                vm,
+                record! {
+                    min_value => $typ($repr::min_value()),
+                    max_value => $typ($repr::max_value()),
+                    add => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.add"),
+                        |l: $typ, r: $typ| {
+                            RuntimeResult::from(overflow_check(
+                                l.0.checked_add(r.0).map($typ),
+                                "add",
+                            ))
+                        }
+                    ),
+                    sub => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.sub"),
+                        |l: $typ, r: $typ| {
+                            RuntimeResult::from(overflow_check(
+                                l.0.checked_sub(r.0).map($typ),
+                                "subtract",
+                            ))
+                        }
+                    ),
+                    mul => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.mul"),
+                        |l: $typ, r: $typ| {
+                            RuntimeResult::from(overflow_check(
+                                l.0.checked_mul(r.0).map($typ),
+                                "multiply",
+                            ))
+                        }
+                    ),
+                    div => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.div"),
+                        |l: $typ, r: $typ| RuntimeResult::from(zero_check(r.0).and_then(|()| {
+                            overflow_check(l.0.checked_div(r.0).map($typ), "divide")
+                        }))
+                    ),
+                    rem => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.rem"),
+                        |l: $typ, r: $typ| RuntimeResult::from(zero_check(r.0).and_then(|()| {
+                            overflow_check(l.0.checked_rem(r.0).map($typ), "take the remainder")
+                        }))
+                    ),
+                    negate => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.negate"),
+                        |x: $typ| {
+                            RuntimeResult::from(overflow_check(x.0.checked_neg().map($typ), "negate"))
+                        }
+                    ),
+                    wrapping_add => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.wrapping_add"),
+                        |l: $typ, r: $typ| $typ(l.0.wrapping_add(r.0))
+                    ),
+                    wrapping_sub => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.wrapping_sub"),
+                        |l: $typ, r: $typ| $typ(l.0.wrapping_sub(r.0))
+                    ),
+                    wrapping_mul => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.wrapping_mul"),
+                        |l: $typ, r: $typ| $typ(l.0.wrapping_mul(r.0))
+                    ),
+                    wrapping_div => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.wrapping_div"),
+                        |l: $typ, r: $typ| {
+                            RuntimeResult::from(zero_check(r.0).map(|()| $typ(l.0.wrapping_div(r.0))))
+                        }
+                    ),
+                    wrapping_negate => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.wrapping_negate"),
+                        |x: $typ| $typ(x.0.wrapping_neg())
+                    ),
+                    checked_add => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.checked_add"),
+                        |l: $typ, r: $typ| l.0.checked_add(r.0).map($typ)
+                    ),
+                    checked_sub => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.checked_sub"),
+                        |l: $typ, r: $typ| l.0.checked_sub(r.0).map($typ)
+                    ),
+                    checked_mul => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.checked_mul"),
+                        |l: $typ, r: $typ| l.0.checked_mul(r.0).map($typ)
+                    ),
+                    checked_div => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.checked_div"),
+                        |l: $typ, r: $typ| l.0.checked_div(r.0).map($typ)
+                    ),
+                    saturating_add => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.saturating_add"),
+                        |l: $typ, r: $typ| $typ(l.0.saturating_add(r.0))
+                    ),
+                    saturating_sub => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.saturating_sub"),
+                        |l: $typ, r: $typ| $typ(l.0.saturating_sub(r.0))
+                    ),
+                    saturating_mul => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.saturating_mul"),
+                        |l: $typ, r: $typ| $typ(l.0.saturating_mul(r.0))
+                    ),
+                    eq => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.eq"),
+                        |l: $typ, r: $typ| l == r
+                    ),
+                    compare => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.compare"),
+                        |l: $typ, r: $typ| l.cmp(&r)
+                    ),
+                    bitand => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.bitand"),
+                        |l: $typ, r: $typ| $typ(l.0 & r.0)
+                    ),
+                    bitor => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.bitor"),
+                        |l: $typ, r: $typ| $typ(l.0 | r.0)
+                    ),
+                    bitxor => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.bitxor"),
+                        |l: $typ, r: $typ| $typ(l.0 ^ r.0)
+                    ),
+                    shl => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.shl"),
+                        |l: $typ, r: u32| $typ(l.0.wrapping_shl(r))
+                    ),
+                    shr => primitive!(
+                        2,
+                        concat!("std.", $module, ".prim.shr"),
+                        |l: $typ, r: u32| $typ(l.0.wrapping_shr(r))
+                    ),
+                    count_ones => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.count_ones"),
+                        |x: $typ| x.0.count_ones()
+                    ),
+                    leading_zeros => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.leading_zeros"),
+                        |x: $typ| x.0.leading_zeros()
+                    ),
+                    trailing_zeros => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.trailing_zeros"),
+                        |x: $typ| x.0.trailing_zeros()
+                    ),
+                    swap_bytes => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.swap_bytes"),
+                        |x: $typ| $typ(x.0.swap_bytes())
+                    ),
+                    from_be => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.from_be"),
+                        |x: $typ| $typ($repr::from_be(x.0))
+                    ),
+                    from_le => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.from_le"),
+                        |x: $typ| $typ($repr::from_le(x.0))
+                    ),
+                    to_be => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.to_be"),
+                        |x: $typ| $typ(x.0.to_be())
+                    ),
+                    to_le => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.to_le"),
+                        |x: $typ| $typ(x.0.to_le())
+                    ),
+                    from_int => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.from_int"),
+                        |i: VmInt| $repr::try_from(i).ok().map($typ)
+                    ),
+                    wrapping_from_int => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.wrapping_from_int"),
+                        |i: VmInt| $typ(i as $repr)
+                    ),
+                    show => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.show"),
+                        |x: $typ| x.0.to_string()
+                    ),
+                    parse => primitive!(
+                        1,
+                        concat!("std.", $module, ".prim.parse"),
+                        |s: &str| parse::<$repr>(s).map($typ)
+                    ),
+                    $($extra)*
+                },
+            )
+        }
+    };
+}
+
+sized_int_module! {
+    load_int8, "int8", Int8(i8) {
+        to_int => primitive!(1, "std.int8.prim.to_int", |x: Int8| VmInt::from(x.0)),
+    }
+}
+
+sized_int_module! {
+    load_int16, "int16", Int16(i16) {
+        to_int => primitive!(1, "std.int16.prim.to_int", |x: Int16| VmInt::from(x.0)),
+    }
+}
+
+sized_int_module! {
+    load_int32, "int32", Int32(i32) {
+        to_int => primitive!(1, "std.int32.prim.to_int", |x: Int32| VmInt::from(x.0)),
+    }
+}
+
+sized_int_module! {
+    load_uint16, "uint16", UInt16(u16) {
+        to_int => primitive!(1, "std.uint16.prim.to_int", |x: UInt16| VmInt::from(x.0)),
+    }
+}
+
+sized_int_module! {
+    load_uint32, "uint32", UInt32(u32) {
+        to_int => primitive!(1, "std.uint32.prim.to_int", |x: UInt32| VmInt::from(x.0)),
+    }
+}
+
+sized_int_module! {
+    load_uint64, "uint64", UInt64(u64) {
+        to_int => primitive!(1, "std.uint64.prim.to_int", |x: UInt64| VmInt::try_from(x.0).ok()),
+        wrapping_to_int => primitive!(
+            1,
+            "std.uint64.prim.wrapping_to_int",
+            |x: UInt64| x.0 as VmInt
+        ),
+    }
+}
+
 #[allow(non_camel_case_types)]
 pub fn load_array(vm: &Thread) -> Result<ExternModule> {
     ExternModule::new(
@@ -646,8 +928,35 @@ unsafe impl Trace for DirEntry {
     impl_trace! { self, _gc, { } }
 }
 
+/// Runs `f` if the sandbox of `vm` allows the file system to be accessed at `path`
+fn fs_access<T>(vm: &Thread, path: &Path, f: impl FnOnce(&Path) -> io::Result<T>) -> IO<T> {
+    let result = match vm.global_env().sandbox() {
+        Some(sandbox) => sandbox.check_path(Capability::Fs, path),
+        None => Ok(()),
+    };
+    IO::from(result.and_then(|()| f(path)))
+}
+
+/// Returns `false` if the sandbox of `vm` does not allow the file system to be accessed at
+/// `path`, just as the queries on `Path` do for paths which can't be accessed
+fn fs_query(vm: &Thread, path: &Path, f: impl FnOnce(&Path) -> bool) -> IO<bool> {
+    let allowed = vm.global_env().sandbox().map_or(true, |sandbox| {
+        sandbox.check_path(Capability::Fs, path).is_ok()
+    });
+    IO::Value(allowed && f(path))
+}
+
+/// `std.path.prim` and `std.fs.prim` both use `Metadata`, but `std.fs.prim` can't be imported
+/// under a sandbox without `Capability::Fs`, so whichever module is loaded first registers it
+fn register_metadata(vm: &Thread) -> Result<()> {
+    if vm.get_type::<Metadata>().is_none() {
+        vm.register_type::<Metadata>("std.fs.Metadata", &[])?;
+    }
+    Ok(())
+}
+
 pub fn load_fs(vm: &Thread) -> Result<ExternModule> {
-    vm.register_type::<Metadata>("std.fs.Metadata", &[])?;
+    register_metadata(vm)?;
     vm.register_type::<DirEntry>("std.fs.DirEntry", &[])?;
 
     ExternModule::new(
@@ -656,8 +965,8 @@ pub fn load_fs(vm: &Thread) -> Result<ExternModule> {
             type Metadata => Metadata,
             type DirEntry => DirEntry,
 
-            read_dir => primitive!(1, "std.fs.prim.read_dir", |p: &Path| {
-                IO::from(fs::read_dir(p).and_then(|iter| iter.map(|result| result.map(DirEntry)).collect::<io::Result<Vec<_>>>()))
+            read_dir => primitive!(1, "std.fs.prim.read_dir", |WithVM { vm, value: p }: WithVM<&Path>| {
+                fs_access(vm, p, |p| fs::read_dir(p).and_then(|iter| iter.map(|result| result.map(DirEntry)).collect::<io::Result<Vec<_>>>()))
             }),
 
             dir_entry => record! {
@@ -676,6 +985,8 @@ pub fn load_fs(vm: &Thread) -> Result<ExternModule> {
 }
 
 pub fn load_path(vm: &Thread) -> Result<ExternModule> {
+    register_metadata(vm)?;
+
     ExternModule::new(
         vm,
         record! {
@@ -704,22 +1015,23 @@ pub fn load_path(vm: &Thread) -> Result<ExternModule> {
                     })
                     .collect::<Vec<_>>()
             }),
-            metadata => primitive!(1, "std.path.prim.metadata", |p: &Path| IO::from(p.metadata().map(Metadata))),
-            symlink_metadata => primitive!(1, "std.path.prim.symlink_metadata", |p: &Path| IO::from(p.symlink_metadata().map(Metadata))),
-            canonicalize => primitive!(1, "std.path.prim.canonicalize", |p: &Path| IO::from(p.canonicalize())),
-            read_link => primitive!(1, "std.path.prim.read_link", |p: &Path| IO::from(p.read_link())),
+            metadata => primitive!(1, "std.path.prim.metadata", |WithVM { vm, value: p }: WithVM<&Path>| fs_access(vm, p, |p| p.metadata().map(Metadata))),
+            symlink_metadata => primitive!(1, "std.path.prim.symlink_metadata", |WithVM { vm, value: p }: WithVM<&Path>| fs_access(vm, p, |p| p.symlink_metadata().map(Metadata))),
+            canonicalize => primitive!(1, "std.path.prim.canonicalize", |WithVM { vm, value: p }: WithVM<&Path>| fs_access(vm, p, |p| p.canonicalize())),
+            read_link => primitive!(1, "std.path.prim.read_link", |WithVM { vm, value: p }: WithVM<&Path>| fs_access(vm, p, |p| p.read_link())),
             read_dir => primitive!(
                 1,
                 "std.path.prim.read_dir",
-                |p: &Path| IO::from(
-                        p.read_dir()
+                |WithVM { vm, value: p }: WithVM<&Path>| fs_access(
+                        vm,
+                        p,
+                        |p| p.read_dir()
                             .and_then(|iter| iter.map(|result| Ok(result?.path())).collect::<StdResult<Vec<_>, _>>())
-                            .map_err(|err| Error::Message(err.to_string()))
                     )
             ),
-            exists => primitive!(1, "std.path.prim.exists", |p: &Path| IO::Value(p.exists())),
-            is_file => primitive!(1, "std.path.prim.is_file", |p: &Path| IO::Value(p.is_file())),
-            is_dir => primitive!(1, "std.path.prim.is_file", |p: &Path| IO::Value(p.is_dir())),
+            exists => primitive!(1, "std.path.prim.exists", |WithVM { vm, value: p }: WithVM<&Path>| fs_query(vm, p, Path::exists)),
+            is_file => primitive!(1, "std.path.prim.is_file", |WithVM { vm, value: p }: WithVM<&Path>| fs_query(vm, p, Path::is_file)),
+            is_dir => primitive!(1, "std.path.prim.is_dir", |WithVM { vm, value: p }: WithVM<&Path>| fs_query(vm, p, Path::is_dir)),
         },
     )
 }
@@ -746,6 +1058,59 @@ pub fn load_char(vm: &Thread) -> Result<ExternModule> {
     )
 }
 
+/// FNV-1a, operating on the hash state stored in an `Int`
+mod hash {
+    use super::*;
+
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) const INITIAL: VmInt = OFFSET_BASIS as VmInt;
+
+    fn write_bytes(hasher: VmInt, bytes: &[u8]) -> VmInt {
+        bytes.iter().fold(hasher as u64, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        }) as VmInt
+    }
+
+    pub(crate) fn write_int(i: VmInt, hasher: VmInt) -> VmInt {
+        write_bytes(hasher, &i.to_le_bytes())
+    }
+
+    pub(crate) fn write_float(f: f64, hasher: VmInt) -> VmInt {
+        // `0.0` and `-0.0` compare equal so they must hash the same
+        let f = if f == 0.0 { 0.0 } else { f };
+        write_bytes(hasher, &f.to_bits().to_le_bytes())
+    }
+
+    pub(crate) fn write_byte(b: u8, hasher: VmInt) -> VmInt {
+        write_bytes(hasher, &[b])
+    }
+
+    pub(crate) fn write_char(c: char, hasher: VmInt) -> VmInt {
+        write_bytes(hasher, &(c as u32).to_le_bytes())
+    }
+
+    pub(crate) fn write_string(s: &str, hasher: VmInt) -> VmInt {
+        // Terminate the string so that `("ab", "c")` and `("a", "bc")` hash differently
+        write_bytes(write_bytes(hasher, s.as_bytes()), &[0xff])
+    }
+}
+
+pub fn load_hash(vm: &Thread) -> Result<ExternModule> {
+    ExternModule::new(
+        vm,
+        record! {
+            initial => hash::INITIAL,
+            write_int => primitive!(2, "std.hash.prim.write_int", hash::write_int),
+            write_float => primitive!(2, "std.hash.prim.write_float", hash::write_float),
+            write_byte => primitive!(2, "std.hash.prim.write_byte", hash::write_byte),
+            write_char => primitive!(2, "std.hash.prim.write_char", hash::write_char),
+            write_string => primitive!(2, "std.hash.prim.write_string", hash::write_string),
+        },
+    )
+}
+
 pub mod st_string {
     use super::*;
 
@@ -803,6 +1168,20 @@ pub fn load<'vm>(vm: &'vm Thread) -> Result<ExternModule> {
         primitive!(2, "@string_eq", <str as PartialEq>::eq),
     )?;
 
+    // Used when matching on array patterns
+    vm.define_global(
+        "array_length",
+        primitive!(1, "@array_length", std::array::prim::len),
+    )?;
+    vm.define_global(
+        "array_index",
+        primitive!(2, "@array_index", std::array::prim::index),
+    )?;
+    vm.define_global(
+        "array_slice",
+        primitive!(3, "@array_slice", std::array::prim::slice),
+    )?;
+
     ExternModule::new(
         vm,
         record! {
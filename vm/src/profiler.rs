@@ -0,0 +1,395 @@
+//! Profiler for gluon programs built on top of the `CALL` and `LINE` hooks.
+//!
+//! The profiler runs in one of two modes (see `ProfilerMode`).
+//!
+//! When instrumenting, the stack is recorded each time a hook runs, together with the current time
+//! and the number of bytes the thread has allocated. Everything that happens between two hook
+//! events is attributed to the stack recorded at the first of them. Since hooks only run while the
+//! interpreter is executing, time spent outside of it (such as when compiling modules which the
+//! program imports) is attributed to the function which ran last. Recording on every call and line
+//! slows execution down considerably, so times should be compared relative to each other rather
+//! than read as absolute numbers.
+//!
+//! When sampling, a background thread asks the hook to record the stack once per interval and the
+//! time and allocations since the previous sample are attributed to the sampled stack. The stack of
+//! a running thread can only be read from inside its own hooks so the hook still runs on every call
+//! and line, but it only does a cheap check unless a sample is due.
+//!
+//! ```rust,ignore
+//! let profiler = thread.start_profiling();
+//! // or `thread.start_sampling_profiler(Duration::from_millis(1))`
+//! thread.run_expr::<()>("example", "...")?;
+//! let profile = profiler.finish();
+//! profile.write_summary(&mut std::io::stderr())?;
+//! ```
+use std::{
+    fmt, io, mem,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc, Mutex,
+    },
+    task::Poll,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::base::{fnv::FnvMap, symbol::Symbol};
+
+use crate::thread::{DebugInfo, HookFlags, HookFn, RootedThread, ThreadInternal};
+
+/// Per function statistics collected by the profiler
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FunctionProfile {
+    /// The name of the function
+    pub name: String,
+    /// The number of times the function were called
+    pub calls: u64,
+    /// Time spent executing the function, including the time spent in functions it called
+    pub inclusive_time: Duration,
+    /// Time spent executing the function itself
+    pub exclusive_time: Duration,
+    /// Number of bytes allocated while executing the function itself
+    pub allocated_bytes: u64,
+}
+
+/// The result of profiling a thread
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    functions: Vec<FunctionProfile>,
+    stacks: Vec<(Vec<String>, Duration)>,
+    total_time: Duration,
+    total_allocated_bytes: u64,
+}
+
+impl Profile {
+    /// Returns the statistics for each function that executed, sorted by their exclusive time
+    /// (largest first)
+    pub fn functions(&self) -> &[FunctionProfile] {
+        &self.functions
+    }
+
+    /// Returns the statistics of the function named `name`
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Returns each distinct stack (outermost function first) and the time spent executing it
+    pub fn stacks(&self) -> &[(Vec<String>, Duration)] {
+        &self.stacks
+    }
+
+    /// The total time spent executing while the profiler were active
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// The total number of bytes allocated while the profiler were active
+    pub fn total_allocated_bytes(&self) -> u64 {
+        self.total_allocated_bytes
+    }
+
+    /// Writes the profile as folded stacks (one `outer;inner MICROSECONDS` line per stack) which
+    /// can be turned into a flamegraph by tools such as `flamegraph.pl` or `inferno`
+    pub fn write_folded<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        for (stack, time) in &self.stacks {
+            let micros = time.as_micros();
+            if micros != 0 {
+                writeln!(writer, "{} {}", stack.join(";"), micros)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a table of the statistics for each function
+    pub fn write_summary<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        write!(writer, "{}", self)
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Total time: {:.3}ms, allocated: {} bytes",
+            millis(self.total_time),
+            self.total_allocated_bytes
+        )?;
+        writeln!(
+            f,
+            "{:>10} {:>15} {:>15} {:>15}  {}",
+            "Calls", "Inclusive (ms)", "Exclusive (ms)", "Allocated (B)", "Function"
+        )?;
+        for function in &self.functions {
+            writeln!(
+                f,
+                "{:>10} {:>15.3} {:>15.3} {:>15}  {}",
+                function.calls,
+                millis(function.inclusive_time),
+                millis(function.exclusive_time),
+                function.allocated_bytes,
+                function.name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.
+}
+
+struct Sample {
+    stack: Vec<Symbol>,
+    time: Instant,
+    allocated_memory: u64,
+}
+
+#[derive(Default)]
+struct Recorder {
+    previous: Option<Sample>,
+    functions: FnvMap<Symbol, FunctionProfile>,
+    stacks: FnvMap<Vec<Symbol>, Duration>,
+    total_time: Duration,
+    total_allocated_bytes: u64,
+}
+
+impl Recorder {
+    fn stack(info: &DebugInfo) -> Vec<Symbol> {
+        info.stacktrace()
+            .frames
+            .into_iter()
+            .filter_map(|frame| frame.map(|frame| frame.name))
+            .collect()
+    }
+
+    fn record(&mut self, info: &DebugInfo) {
+        let time = Instant::now();
+        let allocated_memory = info.allocated_memory();
+        let stack = Self::stack(info);
+
+        if let Some(previous) = self.previous.take() {
+            let elapsed = time - previous.time;
+            let allocated_bytes = allocated_memory.saturating_sub(previous.allocated_memory);
+            self.add_interval(previous.stack, elapsed, allocated_bytes);
+        }
+
+        if info.state().contains(HookFlags::CALL_FLAG) {
+            if let Some(function) = stack.last() {
+                self.function(function).calls += 1;
+            }
+        }
+
+        self.previous = Some(Sample {
+            stack,
+            time,
+            allocated_memory,
+        });
+    }
+
+    /// Records a sample, attributing everything since the previous sample to the current stack
+    fn record_sample(&mut self, info: &DebugInfo) {
+        let time = Instant::now();
+        let allocated_memory = info.allocated_memory();
+        let stack = Self::stack(info);
+
+        if let Some(previous) = self.previous.take() {
+            let elapsed = time - previous.time;
+            let allocated_bytes = allocated_memory.saturating_sub(previous.allocated_memory);
+            self.add_interval(stack.clone(), elapsed, allocated_bytes);
+        }
+
+        self.previous = Some(Sample {
+            stack,
+            time,
+            allocated_memory,
+        });
+    }
+
+    fn add_interval(&mut self, stack: Vec<Symbol>, elapsed: Duration, allocated_bytes: u64) {
+        self.total_time += elapsed;
+        self.total_allocated_bytes += allocated_bytes;
+
+        for (i, function) in stack.iter().enumerate() {
+            // Recursive functions appear multiple times in the stack but should only count the
+            // time once
+            if !stack[..i].contains(function) {
+                self.function(function).inclusive_time += elapsed;
+            }
+        }
+        if let Some(function) = stack.last() {
+            let profile = self.function(function);
+            profile.exclusive_time += elapsed;
+            profile.allocated_bytes += allocated_bytes;
+        }
+
+        *self.stacks.entry(stack).or_default() += elapsed;
+    }
+
+    fn function(&mut self, function: &Symbol) -> &mut FunctionProfile {
+        self.functions
+            .entry(function.clone())
+            .or_insert_with(|| FunctionProfile {
+                name: function.declared_name().to_string(),
+                ..FunctionProfile::default()
+            })
+    }
+
+    fn into_profile(self) -> Profile {
+        let mut functions: Vec<_> = self.functions.into_iter().map(|(_, f)| f).collect();
+        functions.sort_by(|l, r| {
+            r.exclusive_time
+                .cmp(&l.exclusive_time)
+                .then_with(|| l.name.cmp(&r.name))
+        });
+
+        let mut stacks: Vec<_> = self
+            .stacks
+            .into_iter()
+            .map(|(stack, time)| {
+                let names = stack
+                    .iter()
+                    .map(|name| name.declared_name().to_string())
+                    .collect();
+                (names, time)
+            })
+            .collect();
+        stacks.sort();
+
+        Profile {
+            functions,
+            stacks,
+            total_time: self.total_time,
+            total_allocated_bytes: self.total_allocated_bytes,
+        }
+    }
+}
+
+/// How a `Profiler` decides when to record the stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProfilerMode {
+    /// Records the stack on every call and line. Gives exact call counts but slows down execution
+    /// considerably.
+    Instrumenting,
+    /// Records the stack about once every `interval`, attributing the time since the previous
+    /// sample to the sampled stack. Call counts are not recorded in this mode.
+    Sampling(Duration),
+}
+
+/// Requests a sample from the hook once every interval
+struct Sampler {
+    running: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl Sampler {
+    fn start(interval: Duration, sample: Arc<AtomicBool>) -> io::Result<Sampler> {
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("gluon-profiler".to_string())
+                .spawn(move || {
+                    while running.load(atomic::Ordering::Relaxed) {
+                        thread::sleep(interval);
+                        sample.store(true, atomic::Ordering::Relaxed);
+                    }
+                })?
+        };
+        Ok(Sampler { running, handle })
+    }
+
+    fn stop(self) {
+        self.running.store(false, atomic::Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// Profiles the code executed on a thread. Created with `RootedThread::start_profiling` or
+/// `RootedThread::start_sampling_profiler`.
+///
+/// The profiler replaces any hook set on the thread while it is active, the previous hook is
+/// restored when the profiler is finished or dropped.
+pub struct Profiler {
+    thread: RootedThread,
+    recorder: Arc<Mutex<Recorder>>,
+    sampler: Option<Sampler>,
+    previous_hook: Option<HookFn>,
+    previous_flags: HookFlags,
+    stopped: bool,
+}
+
+impl Profiler {
+    pub(crate) fn start(thread: RootedThread, mode: ProfilerMode) -> Profiler {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        let sample = Arc::new(AtomicBool::new(false));
+        let sampler = match mode {
+            ProfilerMode::Instrumenting => None,
+            ProfilerMode::Sampling(interval) => match Sampler::start(interval, sample.clone()) {
+                Ok(sampler) => Some(sampler),
+                Err(err) => {
+                    warn!(
+                        "Unable to start the sampling thread, instrumenting instead: {}",
+                        err
+                    );
+                    None
+                }
+            },
+        };
+        let sampling = sampler.is_some();
+        let (previous_hook, previous_flags) = {
+            let hook_recorder = recorder.clone();
+            let mut context = thread.context();
+            let previous_flags = context.hook_mask();
+            let previous_hook = context.set_hook(Some(Box::new(move |_, info| {
+                if !sampling {
+                    hook_recorder.lock().unwrap().record(&info);
+                } else if sample.swap(false, atomic::Ordering::Relaxed) {
+                    hook_recorder.lock().unwrap().record_sample(&info);
+                }
+                Poll::Ready(Ok(()))
+            })));
+            context.set_hook_mask(HookFlags::CALL_FLAG | HookFlags::LINE_FLAG);
+            (previous_hook, previous_flags)
+        };
+        Profiler {
+            thread,
+            recorder,
+            sampler,
+            previous_hook,
+            previous_flags,
+            stopped: false,
+        }
+    }
+
+    /// Stops profiling and returns the collected profile
+    pub fn finish(mut self) -> Profile {
+        self.stop();
+        let recorder = mem::take(&mut *self.recorder.lock().unwrap());
+        recorder.into_profile()
+    }
+
+    fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.stopped = true;
+        if let Some(sampler) = self.sampler.take() {
+            sampler.stop();
+        }
+        let mut context = self.thread.context();
+        context.set_hook(self.previous_hook.take());
+        context.set_hook_mask(self.previous_flags);
+    }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
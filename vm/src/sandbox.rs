@@ -0,0 +1,162 @@
+//! Restricting what the code running in a vm has access to.
+//!
+//! A `Sandbox` is set when creating the vm and lists the capabilities the scripts are allowed to
+//! use. Importing a module which needs a capability that is not allowed fails when the importing
+//! code is compiled. Capabilities which are part of modules that can always be imported, such as
+//! opening files through `std.io`, instead fail with an IO error when used.
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::base::fnv::FnvSet;
+
+/// A capability which scripts can be given access to
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Capability {
+    /// The `std.fs` modules, along with the functions in `std.path` which inspect the file system.
+    /// Without it `exists`, `is_file` and `is_dir` return `False` for every path.
+    Fs,
+    /// The `std.process` module
+    Process,
+    /// The `std.env` module
+    Env,
+    /// Opening files with `std.io`
+    FileIo,
+    /// The `std.http` modules
+    Http,
+    /// Compiling and running code with `std.io.run_expr` and `std.io.load_script`
+    Eval,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Capability::Fs => "fs",
+            Capability::Process => "process",
+            Capability::Env => "env",
+            Capability::FileIo => "file_io",
+            Capability::Http => "http",
+            Capability::Eval => "eval",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Capability {
+    /// Returns the capability needed to import `module`, if any
+    pub fn of_module(module: &str) -> Option<Capability> {
+        let is_module = |name: &str| {
+            module == name || (module.starts_with(name) && module[name.len()..].starts_with('.'))
+        };
+        if is_module("std.fs") {
+            Some(Capability::Fs)
+        } else if is_module("std.process") {
+            Some(Capability::Process)
+        } else if is_module("std.env") {
+            Some(Capability::Env)
+        } else if is_module("std.http") {
+            Some(Capability::Http)
+        } else {
+            None
+        }
+    }
+}
+
+/// The capabilities available to the code running in a vm. A new sandbox allows none of them.
+#[derive(Clone, Debug, Default)]
+pub struct Sandbox {
+    capabilities: FnvSet<Capability>,
+    fs_roots: Vec<PathBuf>,
+}
+
+impl Sandbox {
+    pub fn new() -> Sandbox {
+        Sandbox::default()
+    }
+
+    /// Allows scripts to use `capability`
+    pub fn allow(mut self, capability: Capability) -> Self {
+        self.capabilities.insert(capability);
+        self
+    }
+
+    /// Restricts `Capability::Fs` and `Capability::FileIo` to the files and directories inside
+    /// `root`. If no roots are given every path may be accessed.
+    pub fn fs_root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.fs_roots.push(root.into());
+        self
+    }
+
+    pub fn is_allowed(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Returns an error if `capability` is not allowed
+    pub fn check(&self, capability: Capability) -> io::Result<()> {
+        if self.is_allowed(capability) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "The `{}` capability is not allowed by the sandbox",
+                    capability
+                ),
+            ))
+        }
+    }
+
+    /// Returns an error if `capability` is not allowed or if `path` is outside of the file system
+    /// roots
+    pub fn check_path(&self, capability: Capability, path: &Path) -> io::Result<()> {
+        self.check(capability)?;
+        if self.fs_roots.is_empty() {
+            return Ok(());
+        }
+
+        let path = canonicalize_existing_prefix(path)?;
+        let in_root = self.fs_roots.iter().any(|root| {
+            root.canonicalize()
+                .map_or(false, |root| path.starts_with(root))
+        });
+        if in_root {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "Access to `{}` is not allowed by the sandbox",
+                    path.display()
+                ),
+            ))
+        }
+    }
+}
+
+/// Canonicalizes the longest prefix of `path` which exists, so that paths to files which are
+/// about to be created can be checked as well. `file_name` is `None` for paths ending in `..`,
+/// so a `..` is never appended after the canonical prefix.
+fn canonicalize_existing_prefix(path: &Path) -> io::Result<PathBuf> {
+    let mut rest = Vec::new();
+    let mut existing = path;
+    loop {
+        match existing.canonicalize() {
+            Ok(mut canonical) => {
+                canonical.extend(rest.iter().rev());
+                return Ok(canonical);
+            }
+            Err(err) => match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(file_name)) => {
+                    rest.push(file_name.to_owned());
+                    existing = if parent == Path::new("") {
+                        Path::new(".")
+                    } else {
+                        parent
+                    };
+                }
+                _ => return Err(err),
+            },
+        }
+    }
+}
@@ -46,6 +46,13 @@ impl<'de, 'gc> DeSeed<'gc> {
         }
     }
 
+    /// Reuses the symbols in `symbols` for any deserialized symbol with the same name, letting
+    /// deserialized types refer to the same aliases as types which are already loaded
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.base_seed = self.base_seed.with_symbols(symbols);
+        self
+    }
+
     pub fn deserialize<D, T>(mut self, deserializer: D) -> Result<T, D::Error>
     where
         D: Deserializer<'de>,
@@ -10,11 +10,13 @@ use std::{
     ptr,
     result::Result as StdResult,
     slice,
+    string::String as StdString,
     sync::{
         self,
-        atomic::{self, AtomicBool},
+        atomic::{self, AtomicBool, AtomicU64},
         Arc, Mutex, MutexGuard, RwLock,
     },
+    time::Duration,
     usize,
 };
 
@@ -40,7 +42,8 @@ use crate::{
     gc::{self, CloneUnrooted, DataDef, Gc, GcPtr, GcRef, Generation, Move},
     interner::InternedStr,
     macros::MacroEnv,
-    source_map::LocalIter,
+    profiler::{Profiler, ProfilerMode},
+    source_map::{Local, LocalIter},
     stack::{
         ClosureState, ExternCallState, ExternState, Frame, Lock, Stack, StackFrame, StackState,
         State,
@@ -715,6 +718,18 @@ impl RootedThread {
             .fetch_add(1, atomic::Ordering::Relaxed);
     }
 
+    /// Starts profiling the code executed on this thread by recording every call and line. Call
+    /// `Profiler::finish` to stop the profiler and retrieve the results.
+    pub fn start_profiling(&self) -> Profiler {
+        Profiler::start(self.clone(), ProfilerMode::Instrumenting)
+    }
+
+    /// Starts profiling the code executed on this thread by sampling its stack about once every
+    /// `interval`. Call `Profiler::finish` to stop the profiler and retrieve the results.
+    pub fn start_sampling_profiler(&self, interval: Duration) -> Profiler {
+        Profiler::start(self.clone(), ProfilerMode::Sampling(interval))
+    }
+
     fn unroot_(&mut self) -> bool {
         let root_count = {
             if !self.rooted {
@@ -740,7 +755,7 @@ impl Thread {
         let vm = Thread {
             global_state: self.global_state.clone(),
             parent: Some(unsafe { GcPtr::from_raw(self) }),
-            context: Mutex::new(Context::new(self.owned_context().gc.new_child_gc())),
+            context: Mutex::new(self.owned_context().new_child_context()),
             rooted_values: RwLock::new(Vec::new()),
             child_threads: Default::default(),
             interrupt: AtomicBool::new(false),
@@ -949,6 +964,29 @@ impl Thread {
         self.owned_context().gc.set_memory_limit(memory_limit)
     }
 
+    /// Limits the number of instructions this thread may execute. Once the fuel runs out
+    /// execution fails with `Error::OutOfFuel`, leaving the stack intact so that execution can be
+    /// resumed (with `Execute::new(thread)`) after more fuel has been added.
+    /// `None` removes the limit (the default).
+    ///
+    /// Threads spawned after the limit is set share the fuel of this thread, so spawning threads
+    /// can't be used to execute more instructions than the limit allows.
+    pub fn set_fuel(&self, fuel: Option<u64>) {
+        self.owned_context().set_fuel(fuel)
+    }
+
+    /// Adds `fuel` to the remaining fuel of this thread. Does nothing if the fuel is unlimited.
+    pub fn add_fuel(&self, fuel: u64) {
+        if let Some(ref shared) = self.owned_context().fuel {
+            update_fuel(shared, |remaining| Some(remaining.saturating_add(fuel)));
+        }
+    }
+
+    /// Returns the remaining fuel of this thread or `None` if the fuel is unlimited
+    pub fn fuel(&self) -> Option<u64> {
+        self.owned_context().fuel()
+    }
+
     pub fn interrupt(&self) {
         self.interrupt.store(true, atomic::Ordering::Relaxed)
     }
@@ -1334,6 +1372,7 @@ pub type HookFn = Box<dyn FnMut(&Thread, DebugInfo) -> Poll<Result<()>> + Send +
 
 pub struct DebugInfo<'a> {
     stack: &'a Stack,
+    gc: &'a Gc,
     state: HookFlags,
 }
 
@@ -1374,6 +1413,16 @@ impl<'a> DebugInfo<'a> {
     pub fn stack_info_len(&self) -> usize {
         self.stack.get_frames().len()
     }
+
+    /// Returns a stacktrace of all frames on the stack, starting with the bottom frame
+    pub fn stacktrace(&self) -> crate::stack::Stacktrace {
+        self.stack.stacktrace(0)
+    }
+
+    /// Returns the total number of bytes that the thread has allocated so far
+    pub fn allocated_memory(&self) -> u64 {
+        self.gc.total_allocated_memory()
+    }
 }
 
 pub struct StackInfo<'a> {
@@ -1468,6 +1517,35 @@ impl<'a> StackInfo<'a> {
             _ => &[],
         }
     }
+
+    /// Returns the value of `local` (as returned by `locals`) in this frame
+    pub fn local_value(&self, local: &Local) -> Option<Variants<'a>> {
+        let stack: &'a Stack = self.info.stack;
+        let frames = stack.get_frames();
+        let values = stack.get_values();
+        let frame = &frames[self.index];
+        // The locals of a frame end where the next frame starts
+        let end = frames
+            .get(self.index + 1)
+            .map_or(values.len(), |next| next.offset as usize);
+        let index = (frame.offset + local.index) as usize;
+        if index < end {
+            values.get(index).map(Variants::new)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of the upvar at `index` (as described by `upvars`)
+    pub fn upvar_value(&self, index: usize) -> Option<Variants<'a>> {
+        let stack: &'a Stack = self.info.stack;
+        match stack.get_frames()[self.index].state {
+            State::Closure(ClosureState { ref closure, .. }) => {
+                closure.upvars.get(index).map(Variants::new)
+            }
+            _ => None,
+        }
+    }
 }
 
 bitflags::bitflags! {
@@ -1522,6 +1600,10 @@ pub struct Context {
     #[cfg_attr(feature = "serde_derive", serde(skip))]
     hook: Hook,
     max_stack_size: VmIndex,
+    /// The number of instructions this context may execute before failing with
+    /// `Error::OutOfFuel`, `None` if there is no limit. Shared with the contexts of child threads.
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    fuel: Option<Arc<AtomicU64>>,
 
     /// Stack of polling functions used for extern functions returning futures
     #[cfg_attr(feature = "serde_derive", serde(skip))]
@@ -1539,10 +1621,18 @@ impl Context {
                 previous_instruction_index: usize::max_value(),
             },
             max_stack_size: VmIndex::max_value(),
+            fuel: None,
             poll_fns: Vec::new(),
         }
     }
 
+    /// Creates the context of a child thread, sharing the fuel of this context
+    fn new_child_context(&self) -> Context {
+        let mut context = Context::new(self.gc.new_child_gc());
+        context.fuel = self.fuel.clone();
+        context
+    }
+
     pub fn push_new_data(
         &mut self,
         thread: &Thread,
@@ -1613,10 +1703,32 @@ impl Context {
         self.hook.flags = flags;
     }
 
+    pub fn hook_mask(&self) -> HookFlags {
+        self.hook.flags
+    }
+
     pub fn set_max_stack_size(&mut self, limit: VmIndex) {
         self.max_stack_size = limit;
     }
 
+    /// Limits the number of instructions that may be executed before execution fails with
+    /// `Error::OutOfFuel`. `None` removes the limit.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        match (fuel, &self.fuel) {
+            // Update the existing fuel in place so that the new amount is seen by child threads
+            (Some(fuel), Some(shared)) => shared.store(fuel, atomic::Ordering::Relaxed),
+            (Some(fuel), None) => self.fuel = Some(Arc::new(AtomicU64::new(fuel))),
+            (None, _) => self.fuel = None,
+        }
+    }
+
+    /// Returns the remaining fuel or `None` if the fuel is unlimited
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+            .as_ref()
+            .map(|fuel| fuel.load(atomic::Ordering::Relaxed))
+    }
+
     pub fn stacktrace(&self, frame_level: usize) -> crate::stack::Stacktrace {
         self.stack.stacktrace(frame_level)
     }
@@ -1679,6 +1791,7 @@ impl<'b> OwnedContext<'b> {
     pub fn debug_info(&self) -> DebugInfo {
         DebugInfo {
             stack: &self.stack,
+            gc: &self.gc,
             state: HookFlags::empty(),
         }
     }
@@ -1783,6 +1896,7 @@ impl<'b> OwnedContext<'b> {
                         if let Some(ref mut hook) = context.hook.function {
                             let info = DebugInfo {
                                 stack: &context.stack.stack(),
+                                gc: &context.gc,
                                 state: HookFlags::CALL_FLAG,
                             };
                             ready!(hook(thread, info))?
@@ -2013,11 +2127,33 @@ impl<'b> OwnedContext<'b> {
             stack: StackFrame::current(&mut context.stack),
             hook: &mut context.hook,
             max_stack_size: context.max_stack_size,
+            fuel: context.fuel.as_deref(),
             poll_fns: &context.poll_fns,
         }
     }
 }
 
+/// Atomically replaces the fuel with the result of `f`. Returns `false` (leaving the fuel
+/// unchanged) if `f` returns `None`.
+fn update_fuel(fuel: &AtomicU64, mut f: impl FnMut(u64) -> Option<u64>) -> bool {
+    let mut current = fuel.load(atomic::Ordering::Relaxed);
+    loop {
+        let new = match f(current) {
+            Some(new) => new,
+            None => return false,
+        };
+        match fuel.compare_exchange_weak(
+            current,
+            new,
+            atomic::Ordering::Relaxed,
+            atomic::Ordering::Relaxed,
+        ) {
+            Ok(_) => return true,
+            Err(actual) => current = actual,
+        }
+    }
+}
+
 unsafe fn lock_gc<'gc>(gc: &'gc Gc, value: &Value) -> Variants<'gc> {
     Variants::with_root(value, gc)
 }
@@ -2048,6 +2184,7 @@ pub struct ExecuteContext<'b, 'gc, S: StackState = ClosureState> {
     pub gc: &'gc mut Gc,
     hook: &'b mut Hook,
     max_stack_size: VmIndex,
+    fuel: Option<&'b AtomicU64>,
     poll_fns: &'b [PollFn],
 }
 
@@ -2103,6 +2240,40 @@ where
         Ok(value)
     }
 
+    /// Pops the top `len` values of the stack and pushes an array containing them. Returns an
+    /// error if there are fewer than `len` values on the stack or if the values do not all have
+    /// the same representation.
+    pub fn push_new_array(&mut self, len: usize) -> Result<Variants> {
+        use crate::value::{ArrayDef, Repr};
+
+        if len > self.stack.len() as usize {
+            return Err(Error::Message(format!(
+                "Expected at least {} values on the stack",
+                len
+            )));
+        }
+        let value = {
+            let elems = &self.stack[self.stack.len() - len as VmIndex..];
+            if let Some(first) = elems.first() {
+                let repr = Repr::from_value(first);
+                if elems.iter().any(|elem| Repr::from_value(elem) != repr) {
+                    return Err(Error::Message(
+                        "The elements of an array must all have the same type".into(),
+                    ));
+                }
+            }
+            Variants::from(alloc(
+                &mut self.gc,
+                self.thread,
+                &self.stack.stack(),
+                ArrayDef(elems),
+            )?)
+        };
+        self.stack.pop_many(len as u32);
+        self.stack.push(value.clone());
+        Ok(value)
+    }
+
     pub fn push_new_alloc<D>(&mut self, def: D) -> Result<Variants>
     where
         D: DataDef + Trace,
@@ -2135,6 +2306,16 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
             // that we know exists since we could construct a `ProgramCounter`
             let instr = unsafe { program_counter.instruction() };
             let instruction_index = program_counter.instruction_index;
+
+            if let Some(fuel) = self.fuel {
+                if !update_fuel(fuel, |remaining| remaining.checked_sub(1)) {
+                    // Store the index so that execution can resume at this instruction once the
+                    // fuel has been refilled
+                    self.stack.set_instruction_index(instruction_index);
+                    return Err(Error::OutOfFuel).into();
+                }
+            }
+
             program_counter.step();
 
             debug_instruction(&self.stack, instruction_index, instr);
@@ -2485,17 +2666,81 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                     let v = transfer!(self, self.stack.get_upvar(i).get_value());
                     self.stack.push(v);
                 }
-                AddInt => binop_int(self.thread, &mut self.stack, VmInt::checked_add)?,
-                SubtractInt => binop_int(self.thread, &mut self.stack, VmInt::checked_sub)?,
-                MultiplyInt => binop_int(self.thread, &mut self.stack, VmInt::checked_mul)?,
-                DivideInt => binop_int(self.thread, &mut self.stack, VmInt::checked_div)?,
+                AddInt => binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                    overflow_check(VmInt::checked_add(l, r), "add")
+                })?,
+                SubtractInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        overflow_check(VmInt::checked_sub(l, r), "subtract")
+                    })?
+                }
+                MultiplyInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        overflow_check(VmInt::checked_mul(l, r), "multiply")
+                    })?
+                }
+                DivideInt => binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                    zero_check(r)?;
+                    overflow_check(VmInt::checked_div(l, r), "divide")
+                })?,
+                WrappingAddInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(VmInt::wrapping_add(l, r))
+                    })?
+                }
+                WrappingSubtractInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(VmInt::wrapping_sub(l, r))
+                    })?
+                }
+                WrappingMultiplyInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(VmInt::wrapping_mul(l, r))
+                    })?
+                }
+                WrappingDivideInt => {
+                    binop_int(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        zero_check(r)?;
+                        Ok(VmInt::wrapping_div(l, r))
+                    })?
+                }
                 IntLT => binop_bool(self.thread, &mut self.stack, |l: VmInt, r| l < r)?,
                 IntEQ => binop_bool(self.thread, &mut self.stack, |l: VmInt, r| l == r)?,
 
-                AddByte => binop_byte(self.thread, &mut self.stack, u8::checked_add)?,
-                SubtractByte => binop_byte(self.thread, &mut self.stack, u8::checked_sub)?,
-                MultiplyByte => binop_byte(self.thread, &mut self.stack, u8::checked_mul)?,
-                DivideByte => binop_byte(self.thread, &mut self.stack, u8::checked_div)?,
+                AddByte => binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                    overflow_check(u8::checked_add(l, r), "add")
+                })?,
+                SubtractByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        overflow_check(u8::checked_sub(l, r), "subtract")
+                    })?
+                }
+                MultiplyByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        overflow_check(u8::checked_mul(l, r), "multiply")
+                    })?
+                }
+                DivideByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        zero_check(r)?;
+                        overflow_check(u8::checked_div(l, r), "divide")
+                    })?
+                }
+                WrappingAddByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(u8::wrapping_add(l, r))
+                    })?
+                }
+                WrappingSubtractByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(u8::wrapping_sub(l, r))
+                    })?
+                }
+                WrappingMultiplyByte => {
+                    binop_byte(self.thread, &mut self.stack, instruction_index, |l, r| {
+                        Ok(u8::wrapping_mul(l, r))
+                    })?
+                }
                 ByteLT => binop_bool(self.thread, &mut self.stack, |l: u8, r| l < r)?,
                 ByteEQ => binop_bool(self.thread, &mut self.stack, |l: u8, r| l == r)?,
 
@@ -2565,6 +2810,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                 self.stack.frame_mut().state.instruction_index = index;
                 let info = DebugInfo {
                     stack: &self.stack.stack(),
+                    gc: &self.gc,
                     state: HookFlags::LINE_FLAG,
                 };
                 ready!(hook(self.thread, info))?
@@ -2585,6 +2831,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc, State> {
             gc: self.gc,
             hook: self.hook,
             max_stack_size: self.max_stack_size,
+            fuel: self.fuel,
             poll_fns: self.poll_fns,
         }
     }
@@ -2601,6 +2848,7 @@ where
             gc: self.gc,
             hook: self.hook,
             max_stack_size: self.max_stack_size,
+            fuel: self.fuel,
             poll_fns: self.poll_fns,
         }
     }
@@ -2617,6 +2865,7 @@ where
             gc: self.gc,
             hook: self.hook,
             max_stack_size: self.max_stack_size,
+            fuel: self.fuel,
             poll_fns: self.poll_fns,
         }
     }
@@ -2640,6 +2889,7 @@ where
                     gc: self.gc,
                     hook: self.hook,
                     max_stack_size: self.max_stack_size,
+                    fuel: self.fuel,
                     poll_fns: self.poll_fns,
                 })
             }
@@ -2649,6 +2899,7 @@ where
                 gc: self.gc,
                 hook: self.hook,
                 max_stack_size: self.max_stack_size,
+                fuel: self.fuel,
                 poll_fns: self.poll_fns,
             }),
         }
@@ -2779,20 +3030,52 @@ where
     }
 }
 
+/// Returns the result of a checked integer operation or an error if the operation overflowed
+pub(crate) fn overflow_check<T>(value: Option<T>, op: &str) -> StdResult<T, StdString> {
+    value.ok_or_else(|| format!("attempt to {} with overflow", op))
+}
+
+/// Returns an error if `divisor` is zero
+pub(crate) fn zero_check<T>(divisor: T) -> StdResult<(), StdString>
+where
+    T: Default + PartialEq,
+{
+    if divisor == T::default() {
+        Err("attempt to divide by zero".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Turns a failed arithmetic operation into a panic with a stacktrace pointing at the instruction
+/// at `instruction_index`
+fn arithmetic_panic(
+    stack: &mut StackFrame<ClosureState>,
+    instruction_index: usize,
+    msg: StdString,
+) -> Error {
+    stack.set_instruction_index(instruction_index);
+    Error::Panic(msg, Some(stack.stack().stacktrace(0)))
+}
+
 #[inline(always)]
-fn binop_int<'b, 'c, F, T>(
+fn binop_int<'b, 'c, F>(
     vm: &'b Thread,
     stack: &'b mut StackFrame<'c, ClosureState>,
+    instruction_index: usize,
     f: F,
 ) -> Result<()>
 where
-    F: FnOnce(T, T) -> Option<VmInt>,
-    T: for<'d, 'e> Getable<'d, 'e> + fmt::Debug,
+    F: FnOnce(VmInt, VmInt) -> StdResult<VmInt, StdString>,
 {
     binop(vm, stack, |l, r| {
-        Ok(ValueRepr::Int(f(l, r).ok_or_else(|| {
-            Error::Message("Arithmetic overflow".into())
-        })?))
+        f(l, r)
+            .map(ValueRepr::Int)
+            .map_err(|msg| Error::Panic(msg, None))
+    })
+    .map_err(|err| match err {
+        Error::Panic(msg, None) => arithmetic_panic(stack, instruction_index, msg),
+        err => err,
     })
 }
 
@@ -2810,19 +3093,23 @@ where
 }
 
 #[inline(always)]
-fn binop_byte<'b, 'c, F, T>(
+fn binop_byte<'b, 'c, F>(
     vm: &'b Thread,
     stack: &'b mut StackFrame<'c, ClosureState>,
+    instruction_index: usize,
     f: F,
 ) -> Result<()>
 where
-    F: FnOnce(T, T) -> Option<u8>,
-    T: for<'d, 'e> Getable<'d, 'e> + fmt::Debug,
+    F: FnOnce(u8, u8) -> StdResult<u8, StdString>,
 {
     binop(vm, stack, |l, r| {
-        Ok(ValueRepr::Byte(f(l, r).ok_or_else(|| {
-            Error::Message("Arithmetic overflow".into())
-        })?))
+        f(l, r)
+            .map(ValueRepr::Byte)
+            .map_err(|msg| Error::Panic(msg, None))
+    })
+    .map_err(|err| match err {
+        Error::Panic(msg, None) => arithmetic_panic(stack, instruction_index, msg),
+        err => err,
     })
 }
 
@@ -2933,6 +3220,7 @@ impl<'vm> ActiveThread<'vm> {
             stack: StackFrame::current(&mut context.stack),
             hook: &mut context.hook,
             max_stack_size: context.max_stack_size,
+            fuel: context.fuel.as_deref(),
             poll_fns: &context.poll_fns,
         }
     }
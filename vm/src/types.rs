@@ -159,10 +159,16 @@ pub enum Instruction {
     /// Fills the previously allocated closure with `n` upvariables.
     CloseClosure(VmIndex),
 
+    /// Integer arithmetic which panics on overflow and division by zero
     AddInt,
     SubtractInt,
     MultiplyInt,
     DivideInt,
+    /// Integer arithmetic which wraps around on overflow. Division by zero still panics.
+    WrappingAddInt,
+    WrappingSubtractInt,
+    WrappingMultiplyInt,
+    WrappingDivideInt,
     IntLT,
     IntEQ,
 
@@ -170,6 +176,10 @@ pub enum Instruction {
     SubtractByte,
     MultiplyByte,
     DivideByte,
+    /// Byte arithmetic which wraps around on overflow
+    WrappingAddByte,
+    WrappingSubtractByte,
+    WrappingMultiplyByte,
     ByteLT,
     ByteEQ,
 
@@ -210,8 +220,10 @@ impl Instruction {
             NewClosure { .. } => 1,
             CloseClosure(_) => -1,
             PushUpVar(_) => 1,
-            AddInt | SubtractInt | MultiplyInt | DivideInt | IntLT | IntEQ | AddFloat | AddByte
-            | SubtractByte | MultiplyByte | DivideByte | ByteLT | ByteEQ | SubtractFloat
+            AddInt | SubtractInt | MultiplyInt | DivideInt | WrappingAddInt
+            | WrappingSubtractInt | WrappingMultiplyInt | WrappingDivideInt | IntLT | IntEQ
+            | AddFloat | AddByte | SubtractByte | MultiplyByte | DivideByte | WrappingAddByte
+            | WrappingSubtractByte | WrappingMultiplyByte | ByteLT | ByteEQ | SubtractFloat
             | MultiplyFloat | DivideFloat | FloatLT | FloatEQ => -1,
             Return => 0,
         }
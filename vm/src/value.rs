@@ -748,6 +748,7 @@ impl<'a, 't> InternalPrinter<'a, 't> {
                 use crate::base::types::BuiltinType;
                 match **self.typ {
                     Type::Builtin(BuiltinType::Int) => arena.text(format!("{}", i)),
+                    Type::Builtin(BuiltinType::UInt64) => arena.text(format!("{}", i as u64)),
                     Type::Builtin(BuiltinType::Char) => match ::std::char::from_u32(i as u32) {
                         Some('"') => arena.text(format!("'{}'", '"')),
                         Some(c) => arena.text(format!("'{}'", c.escape_default())),
@@ -1201,7 +1202,7 @@ unsafe impl<'a> DataDef for &'a str {
 }
 
 impl Repr {
-    fn from_value(value: &Value) -> Repr {
+    pub(crate) fn from_value(value: &Value) -> Repr {
         match value.get_repr() {
             ValueRepr::Byte(_) => Repr::Byte,
             ValueRepr::Int(_) => Repr::Int,
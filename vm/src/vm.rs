@@ -30,6 +30,7 @@ use crate::{
     interner::{InternedStr, Interner},
     lazy::Lazy,
     macros::MacroEnv,
+    sandbox::Sandbox,
     thread::ThreadInternal,
     types::*,
     value::{BytecodeFunction, ClosureData, ClosureDataDef, Value},
@@ -245,6 +246,9 @@ pub struct GlobalVmState {
 
     #[cfg_attr(feature = "serde_derive", serde(skip))]
     spawner: Option<Box<dyn futures::task::Spawn + Send + Sync>>,
+
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    sandbox: Option<Sandbox>,
 }
 
 unsafe impl Trace for GlobalVmState {
@@ -539,6 +543,7 @@ impl<'a> VmEnvInstance<'a> {
 #[derive(Default)]
 pub struct GlobalVmStateBuilder {
     spawner: Option<Box<dyn futures::task::Spawn + Send + Sync>>,
+    sandbox: Option<Sandbox>,
 }
 
 impl GlobalVmStateBuilder {
@@ -551,6 +556,12 @@ impl GlobalVmStateBuilder {
         self
     }
 
+    /// Restricts the capabilities of the code running in the vm (default: None)
+    pub fn sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
     pub fn build(self) -> GlobalVmState {
         let mut vm = GlobalVmState {
             env: Default::default(),
@@ -564,6 +575,7 @@ impl GlobalVmStateBuilder {
             debug_level: RwLock::new(DebugLevel::default()),
             thread_reference_count: Default::default(),
             spawner: self.spawner,
+            sandbox: self.sandbox,
         };
         vm.add_types().unwrap();
         vm
@@ -577,7 +589,7 @@ impl GlobalVmState {
 
     fn add_types(&mut self) -> StdResult<(), (TypeId, ArcType)> {
         use crate::api::generic::A;
-        use crate::api::Generic;
+        use crate::api::{Generic, Int16, Int32, Int8, UInt16, UInt32, UInt64};
         use crate::base::types::BuiltinType;
         fn add_builtin_type<T: Any>(self_: &mut GlobalVmState, b: BuiltinType) {
             add_builtin_type_(self_, b, TypeId::of::<T>())
@@ -606,6 +618,12 @@ impl GlobalVmState {
             add_type(self, "()", unit, TypeId::of::<()>());
             add_builtin_type::<VmInt>(self, BuiltinType::Int);
             add_builtin_type::<u8>(self, BuiltinType::Byte);
+            add_builtin_type::<Int8>(self, BuiltinType::Int8);
+            add_builtin_type::<Int16>(self, BuiltinType::Int16);
+            add_builtin_type::<Int32>(self, BuiltinType::Int32);
+            add_builtin_type::<UInt16>(self, BuiltinType::UInt16);
+            add_builtin_type::<UInt32>(self, BuiltinType::UInt32);
+            add_builtin_type::<UInt64>(self, BuiltinType::UInt64);
             add_builtin_type::<f32>(self, BuiltinType::Float);
             add_builtin_type::<f64>(self, BuiltinType::Float);
             add_builtin_type::<::std::string::String>(self, BuiltinType::String);
@@ -791,4 +809,8 @@ impl GlobalVmState {
     pub fn spawner(&self) -> Option<&(dyn futures::task::Spawn + Send + Sync)> {
         self.spawner.as_ref().map(|s| &**s)
     }
+
+    pub fn sandbox(&self) -> Option<&Sandbox> {
+        self.sandbox.as_ref()
+    }
 }